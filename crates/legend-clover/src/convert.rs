@@ -0,0 +1,79 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use legend_engine::engine::atomic_file::write_atomic;
+use legend_engine::engine::graphics::{Image, Palette, RleImage, Vector2};
+use legend_engine::engine::palette_file::{palette_from_bytes, palette_to_bytes, PaletteFormat};
+use crate::extract::{write_sprite, SpriteMetadata};
+
+fn extension_of(path: &Path) -> String {
+    path.extension().map(|extension| extension.to_string_lossy().to_lowercase()).unwrap_or_default()
+}
+
+// the json extract and convert write next to the png, without one the offset is 0, 0
+fn read_metadata(png: &Path) -> Result<Option<SpriteMetadata>, Box<dyn Error>> {
+    let path = png.with_extension("json");
+    if !path.is_file() {
+        return Ok(None);
+    }
+
+    Ok(Some(serde_json::from_str(&fs::read_to_string(path)?)?))
+}
+
+fn rle_to_png(input: &Path, output: &Path, palette: Option<&(String, Palette)>) -> Result<(), Box<dyn Error>> {
+    let sprite = RleImage::from_bytes(&fs::read(input)?)?;
+    write_sprite(&sprite, &input.display().to_string(), output, palette)
+}
+
+// transparent pixels stay transparent, every other one becomes the closest palette color,
+// or its gray level when the png was extracted without a palette. of a color the palette has twice the first index is taken
+fn png_to_rle(input: &Path, output: &Path, palette: Option<&(String, Palette)>) -> Result<(), Box<dyn Error>> {
+    let metadata = read_metadata(input)?;
+
+    if let (Some(SpriteMetadata { palette: Some(name), .. }), None) = (&metadata, palette) {
+        return Err(format!("{} was drawn with the palette {}, pass that file with --palette", input.display(), name).into());
+    }
+
+    let image = Image::load(&input.to_string_lossy())?;
+    if image.size.x > u16::MAX as u32 || image.size.y > u16::MAX as u32 {
+        return Err(format!("{} is {}x{}, too big for a sprite", input.display(), image.size.x, image.size.y).into());
+    }
+
+    let pixels: Vec<Option<u8>> = image.data.iter().map(|color| match (color.a, palette) {
+        (0, _) => None,
+        (_, Some((_, palette))) => Some(palette.closest_index(color)),
+        (_, None) => Some(color.r)
+    }).collect();
+
+    let offset = metadata.map(|metadata| Vector2::new(metadata.offset_x, metadata.offset_y)).unwrap_or(Vector2::new(0, 0));
+    let sprite = RleImage::encode(Vector2::new(image.size.x as u16, image.size.y as u16), offset, &pixels)?;
+    write_atomic(output, sprite.to_bytes())?;
+    Ok(())
+}
+
+// between the palette of the game and the .act of photoshop or .gpl of gimp, any way around
+fn convert_palette(input: &Path, from: PaletteFormat, output: &Path, to: PaletteFormat) -> Result<(), Box<dyn Error>> {
+    let palette = palette_from_bytes(&fs::read(input)?, from)?;
+    let name = input.file_stem().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    write_atomic(output, palette_to_bytes(&palette, to, &name))?;
+    Ok(())
+}
+
+// an .rle sprite to a png with a json of its offset, or such a png back to an .rle, picked by the extension of input.
+// palette is a .pal or .col file of the original game. palettes convert by the extensions of input and output
+pub fn convert(input: &Path, output: &Path, palette_path: Option<&Path>) -> Result<(), Box<dyn Error>> {
+    if let (Some(from), Some(to)) = (PaletteFormat::from_path(input), PaletteFormat::from_path(output)) {
+        return convert_palette(input, from, output, to);
+    }
+
+    let palette = match palette_path {
+        Some(path) => Some((path.display().to_string(), Palette::from_bytes(&fs::read(path)?)?)),
+        None => None
+    };
+
+    match extension_of(input).as_str() {
+        "rle" => rle_to_png(input, output, palette.as_ref()),
+        "png" => png_to_rle(input, output, palette.as_ref()),
+        _ => Err(format!("can not convert {} to {}, only .rle and .png files or palettes", input.display(), output.display()).into())
+    }
+}
@@ -0,0 +1,88 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use include_dir::{include_dir, Dir};
+use legend_engine::engine::error::EngineResult;
+use legend_engine::engine::vfs::Vfs;
+
+// the scripts folder of the repository as it was at build time
+static SCRIPTS: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/../../scripts");
+
+// paths of the files are relative to the scripts folder, whatever folder they are in
+fn write_files(directory: &Dir, target: &Path) -> io::Result<()> {
+    for file in directory.files() {
+        let path = target.join(file.path());
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, file.contents())?;
+    }
+
+    for child in directory.dirs() {
+        write_files(child, target)?;
+    }
+
+    Ok(())
+}
+
+// clover compiles and includes files from disk, so the built in scripts are written out before they run.
+// the folder is per version and written again on every launch, so an edited copy never runs
+pub fn unpack() -> EngineResult<PathBuf> {
+    let directory = dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("legend-clover")
+        .join(format!("scripts-{}", env!("CARGO_PKG_VERSION")));
+
+    write_files(&SCRIPTS, &directory)?;
+    Ok(directory)
+}
+
+fn copy_files(source: &Path, target: &Path) -> io::Result<()> {
+    fs::create_dir_all(target)?;
+    for entry in fs::read_dir(source)?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            copy_files(&path, &target.join(entry.file_name()))?;
+        } else {
+            fs::copy(&path, target.join(entry.file_name()))?;
+        }
+    }
+
+    Ok(())
+}
+
+// a --mod with a scripts folder replaces the scripts of the same name next to the one that starts,
+// so the folder is copied and the replacements written over the copy, the script itself when no mod has any
+pub fn with_mods(vfs: &Vfs, script: &Path) -> EngineResult<PathBuf> {
+    let replaced: Vec<String> = vfs.files()?.into_iter().filter(|name| name.to_lowercase().starts_with("scripts/")).collect();
+    let file_name = match script.file_name() {
+        Some(file_name) if !replaced.is_empty() => file_name,
+        _ => return Ok(script.to_path_buf())
+    };
+
+    let directory = dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("legend-clover")
+        .join("mod-scripts");
+    // nothing of the previous launch or reload is left to include
+    if directory.exists() {
+        fs::remove_dir_all(&directory)?;
+    }
+
+    let source = script.parent().filter(|directory| !directory.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    copy_files(source, &directory)?;
+    for name in replaced.iter() {
+        let path = directory.join(&name["scripts/".len()..]);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, vfs.read(name)?)?;
+    }
+
+    Ok(directory.join(file_name))
+}
+
+// a data file of the scripts folder, like releases.toml, read straight from the executable
+pub fn text(path: &str) -> Option<&'static str> {
+    SCRIPTS.get_file(path).and_then(|file| file.contents_utf8())
+}
@@ -0,0 +1,200 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use legend_engine::engine::assets::{is_sprite_archive, SpriteArchive};
+use legend_engine::engine::atomic_file::write_atomic;
+use legend_engine::engine::graphics::{Color, Image, Palette, RleImage};
+use legend_engine::engine::palette_file::{to_act, to_gpl};
+use legend_engine::engine::vfs::Vfs;
+
+const SPRITE_EXTENSIONS: [&str; 1] = ["rle"];
+const PALETTE_EXTENSIONS: [&str; 2] = ["pal", "col"];
+
+// every palette color is a square of this size in the exported swatch
+const SWATCH_CELL: u32 = 8;
+
+// the json next to every sprite png, convert reads the offset back from it
+#[derive(Serialize, Deserialize)]
+pub struct SpriteMetadata {
+    pub source: String,
+    pub width: u16,
+    pub height: u16,
+    pub offset_x: i16,
+    pub offset_y: i16,
+    // the palette the png was drawn with
+    pub palette: Option<String>
+}
+
+#[derive(Serialize)]
+struct PaletteMetadata {
+    source: String,
+    colors: Vec<[u8; 3]>
+}
+
+#[derive(Default)]
+pub struct ExtractReport {
+    pub sprites: usize,
+    pub palettes: usize,
+    // files with a known extension that could not be decoded, with the reason
+    pub failed: Vec<(String, String)>
+}
+
+// the original files are dos names, extensions come in any case
+fn has_extension(name: &str, extensions: &[&str]) -> bool {
+    Path::new(name).extension()
+        .map(|extension| extensions.iter().any(|known| extension.to_string_lossy().eq_ignore_ascii_case(known)))
+        .unwrap_or(false)
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), Box<dyn Error>> {
+    write_atomic(path, serde_json::to_string_pretty(value)?)?;
+    Ok(())
+}
+
+fn palette_swatch(palette: &Palette) -> Image {
+    let mut image = Image::new(16 * SWATCH_CELL, 16 * SWATCH_CELL);
+
+    for index in 0..=255u8 {
+        let (x, y) = ((index % 16) as u32 * SWATCH_CELL, (index / 16) as u32 * SWATCH_CELL);
+        image.fill_rect(x as i32, y as i32, SWATCH_CELL as i32, SWATCH_CELL as i32, &palette.get_color(index));
+    }
+
+    image
+}
+
+fn extract_palette(vfs: &Vfs, source: &str, target: &Path) -> Result<Palette, Box<dyn Error>> {
+    let palette = Palette::load(vfs, source)?;
+
+    palette_swatch(&palette).save(&target.with_extension("png").to_string_lossy())?;
+    // the same colors for paint programs
+    let name = target.file_stem().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    write_atomic(target.with_extension("act"), to_act(&palette))?;
+    write_atomic(target.with_extension("gpl"), to_gpl(&palette, &name))?;
+    write_json(&target.with_extension("json"), &PaletteMetadata {
+        source: source.to_string(),
+        colors: (0..=255u8).map(|index| {
+            let color = palette.get_color(index);
+            [color.r, color.g, color.b]
+        }).collect()
+    })?;
+
+    Ok(palette)
+}
+
+fn extract_sprite(vfs: &Vfs, source: &str, target: &Path, palette: Option<&(String, Palette)>) -> Result<(), Box<dyn Error>> {
+    write_sprite(&RleImage::load(vfs, source)?, source, target, palette)
+}
+
+// a folder named after the archive with one png and json per frame, returns the frame count
+fn extract_sprite_archive(vfs: &Vfs, source: &str, target: &Path, palette: Option<&(String, Palette)>) -> Result<usize, Box<dyn Error>> {
+    let archive = SpriteArchive::load(vfs, source)?;
+    let directory = target.with_extension("");
+    fs::create_dir_all(&directory)?;
+
+    for (index, frame) in archive.frames().iter().enumerate() {
+        write_sprite(frame, &format!("{}#{}", source, index), &directory.join(format!("{:03}", index)), palette)?;
+    }
+
+    Ok(archive.len())
+}
+
+// target with a png and a json extension, convert writes single sprites through here as well
+pub fn write_sprite(sprite: &RleImage, source: &str, target: &Path, palette: Option<&(String, Palette)>) -> Result<(), Box<dyn Error>> {
+
+    // without any palette the indices are written as gray levels
+    let image = match palette {
+        Some((_, palette)) => sprite.decode(palette),
+        None => {
+            let mut image = Image::new(sprite.size.x as u32, sprite.size.y as u32);
+            for (pixel, index) in image.data.iter_mut().zip(sprite.decode_indexed()) {
+                if let Some(index) = index {
+                    *pixel = Color::new(index, index, index, 255);
+                }
+            }
+            image
+        }
+    };
+
+    image.save(&target.with_extension("png").to_string_lossy())?;
+    write_json(&target.with_extension("json"), &SpriteMetadata {
+        source: source.to_string(),
+        width: sprite.size.x,
+        height: sprite.size.y,
+        offset_x: sprite.offset.x,
+        offset_y: sprite.offset.y,
+        palette: palette.map(|(name, _)| name.clone())
+    })?;
+
+    Ok(())
+}
+
+// the first palette of the game data that loads, what sprites are drawn with when none is named
+pub fn first_palette(vfs: &Vfs) -> Result<Option<(String, Palette)>, Box<dyn Error>> {
+    for name in vfs.files()?.into_iter().filter(|name| has_extension(name, &PALETTE_EXTENSIONS)) {
+        if let Ok(palette) = Palette::load(vfs, &name) {
+            return Ok(Some((name, palette)));
+        }
+    }
+
+    Ok(None)
+}
+
+// writes every palette and sprite of the game data as png plus json into out, keeping the folder layout.
+// sprites are drawn with the given palette, or the first one found. archives get a folder with a png per frame
+pub fn extract(vfs: &Vfs, out: &Path, palette_name: Option<&str>) -> Result<ExtractReport, Box<dyn Error>> {
+    let files = vfs.files()?;
+
+    let mut report = ExtractReport::default();
+    let mut sprite_palette: Option<(String, Palette)> = None;
+
+    let target_of = |name: &str| -> Result<PathBuf, Box<dyn Error>> {
+        let target = out.join(name);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        Ok(target)
+    };
+
+    // palettes go first, the sprites need one
+    for source in files.iter().filter(|name| has_extension(name, &PALETTE_EXTENSIONS)) {
+        let target = target_of(source)?;
+
+        match extract_palette(vfs, source, &target) {
+            Ok(palette) => {
+                report.palettes += 1;
+
+                let wanted = palette_name.map(|name| source.eq_ignore_ascii_case(name)).unwrap_or(sprite_palette.is_none());
+                if wanted {
+                    sprite_palette = Some((source.clone(), palette));
+                }
+            },
+            Err(error) => report.failed.push((source.clone(), error.to_string()))
+        }
+    }
+
+    if let (Some(name), None) = (palette_name, &sprite_palette) {
+        return Err(format!("palette {} not found in {}", name, vfs.describe()).into());
+    }
+
+    for source in files.iter().filter(|name| has_extension(name, &SPRITE_EXTENSIONS)) {
+        let target = target_of(source)?;
+
+        match extract_sprite(vfs, source, &target, sprite_palette.as_ref()) {
+            Ok(()) => report.sprites += 1,
+            Err(error) => report.failed.push((source.clone(), error.to_string()))
+        }
+    }
+
+    for source in files.iter().filter(|name| is_sprite_archive(name)) {
+        let target = target_of(source)?;
+
+        match extract_sprite_archive(vfs, source, &target, sprite_palette.as_ref()) {
+            Ok(frames) => report.sprites += frames,
+            Err(error) => report.failed.push((source.clone(), error.to_string()))
+        }
+    }
+
+    Ok(report)
+}
@@ -0,0 +1,112 @@
+use std::error::Error;
+use std::fs;
+use clap::Subcommand;
+use legend_engine::engine::font_sheet::FontSheet;
+use legend_engine::engine::graphics::{big5_codes, Font, Image};
+
+// the whole big5 range of the chinese font
+const FIRST_BIG5: usize = 0xa140;
+const LAST_BIG5: usize = 0xf9fe;
+
+#[derive(Subcommand, Debug)]
+pub enum FontCommand {
+    /// write a range of glyphs to a png sheet
+    Export {
+        /// a font file of the game, the chinese one unless --english is given
+        #[clap(value_parser)]
+        font: String,
+
+        /// png file to write
+        #[clap(value_parser)]
+        sheet: String,
+
+        /// the font is the 8x16 english one, indexed by character code instead of big5
+        #[clap(long)]
+        english: bool,
+
+        /// first code in hex, like a440, a140 for the chinese font and 00 for the english one by default
+        #[clap(long, value_parser = parse_code)]
+        first: Option<usize>,
+
+        /// last code in hex, f9fe for the chinese font and the last glyph of the english one by default
+        #[clap(long, value_parser = parse_code)]
+        last: Option<usize>,
+
+        /// glyphs per row
+        #[clap(long, value_parser, default_value_t = 32)]
+        columns: usize
+    },
+    /// replace glyphs from an edited png sheet
+    Import {
+        /// a font file of the game, the chinese one unless --english is given
+        #[clap(value_parser)]
+        font: String,
+
+        /// png sheet written by export
+        #[clap(value_parser)]
+        sheet: String,
+
+        /// the font is the 8x16 english one, must match the export
+        #[clap(long)]
+        english: bool,
+
+        /// first code in hex, must match the export
+        #[clap(long, value_parser = parse_code)]
+        first: Option<usize>,
+
+        /// last code in hex, must match the export
+        #[clap(long, value_parser = parse_code)]
+        last: Option<usize>,
+
+        /// glyphs per row, must match the export
+        #[clap(long, value_parser, default_value_t = 32)]
+        columns: usize,
+
+        /// write the patched font here instead of over the original
+        #[clap(long, value_parser)]
+        out: Option<String>
+    }
+}
+
+fn parse_code(value: &str) -> Result<usize, String> {
+    usize::from_str_radix(value.trim_start_matches("0x"), 16).map_err(|error| format!("{} is not a hex code: {}", value, error))
+}
+
+fn load_font(filename: &str, english: bool) -> Result<Font, Box<dyn Error>> {
+    let data = fs::read(filename).map_err(|error| format!("can not read font {}: {}", filename, error))?;
+    let width = if english { 8 } else { 16 };
+    Font::from_bytes(data, width, 16).map_err(|error| format!("can not read font {}: {}", filename, error).into())
+}
+
+// the english font has a glyph for every code, the chinese one only for valid big5 codes
+fn characters(font: &Font, english: bool, first: Option<usize>, last: Option<usize>) -> Vec<usize> {
+    match english {
+        true => (first.unwrap_or(0)..=last.unwrap_or(font.glyph_count().saturating_sub(1))).collect(),
+        false => big5_codes(first.unwrap_or(FIRST_BIG5), last.unwrap_or(LAST_BIG5))
+    }
+}
+
+pub fn run(command: FontCommand) -> Result<(), Box<dyn Error>> {
+    match command {
+        FontCommand::Export { font, sheet, english, first, last, columns } => {
+            let font_data = load_font(&font, english)?;
+            let characters = characters(&font_data, english, first, last);
+
+            FontSheet::new(columns).export(&font_data, &characters).save(&sheet)?;
+            println!("exported {} glyphs to {}", characters.len(), sheet);
+        },
+        FontCommand::Import { font, sheet, english, first, last, columns, out } => {
+            let mut font_data = load_font(&font, english)?;
+            let characters = characters(&font_data, english, first, last);
+            let image = Image::load(&sheet)?;
+
+            let count = FontSheet::new(columns).import(&mut font_data, &characters, &image);
+
+            let out = out.unwrap_or(font);
+            font_data.save(&out)?;
+            println!("imported {} glyphs into {}", count, out);
+        }
+    }
+
+    Ok(())
+}
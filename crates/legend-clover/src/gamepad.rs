@@ -0,0 +1,114 @@
+use gilrs::{Axis, Button, EventType, Gilrs};
+use legend_engine::engine::gamepad::{GamepadMapping, STICK_THRESHOLD};
+use legend_engine::engine::input::Input;
+
+fn button_name(button: Button) -> Option<&'static str> {
+    Some(match button {
+        Button::South => "south",
+        Button::East => "east",
+        Button::North => "north",
+        Button::West => "west",
+        Button::LeftTrigger => "left_trigger",
+        Button::LeftTrigger2 => "left_trigger2",
+        Button::RightTrigger => "right_trigger",
+        Button::RightTrigger2 => "right_trigger2",
+        Button::Select => "select",
+        Button::Start => "start",
+        Button::Mode => "mode",
+        Button::LeftThumb => "left_thumb",
+        Button::RightThumb => "right_thumb",
+        Button::DPadUp => "dpad_up",
+        Button::DPadDown => "dpad_down",
+        Button::DPadLeft => "dpad_left",
+        Button::DPadRight => "dpad_right",
+        _ => return None
+    })
+}
+
+fn axis_name(axis: Axis) -> Option<&'static str> {
+    Some(match axis {
+        Axis::LeftStickX => "left_x",
+        Axis::LeftStickY => "left_y",
+        Axis::RightStickX => "right_x",
+        Axis::RightStickY => "right_y",
+        _ => return None
+    })
+}
+
+// feeds every connected pad into the same Input the keyboard uses
+pub struct Gamepads {
+    gilrs: Gilrs,
+    mapping: GamepadMapping
+}
+
+impl Gamepads {
+    // None when the platform has no gamepad support, the keyboard keeps working
+    pub fn new(mapping: GamepadMapping) -> Option<Self> {
+        match Gilrs::new() {
+            Ok(gilrs) => Some(Gamepads { gilrs, mapping }),
+            Err(error) => {
+                eprintln!("gamepads are not available: {}", error);
+                None
+            }
+        }
+    }
+
+    fn set_button(&self, input: &mut Input, button: &str, down: bool) {
+        if input.is_button_down(button) == down {
+            return;
+        }
+
+        input.set_button(button, down);
+        if let Some(key) = self.mapping.key_for(button) {
+            if down {
+                input.press(key);
+            } else {
+                input.release(key);
+            }
+        }
+    }
+
+    // the left stick also works as a dpad through the stick_ buttons
+    fn update_stick(&self, input: &mut Input) {
+        let (x, y) = (input.axis("left_x"), input.axis("left_y"));
+
+        self.set_button(input, "stick_left", x < -STICK_THRESHOLD);
+        self.set_button(input, "stick_right", x > STICK_THRESHOLD);
+        self.set_button(input, "stick_up", y < -STICK_THRESHOLD);
+        self.set_button(input, "stick_down", y > STICK_THRESHOLD);
+    }
+
+    pub fn poll(&mut self, input: &mut Input) {
+        while let Some(event) = self.gilrs.next_event() {
+            match event.event {
+                EventType::ButtonPressed(button, _) => {
+                    if let Some(name) = button_name(button) {
+                        self.set_button(input, name, true);
+                    }
+                },
+                EventType::ButtonReleased(button, _) => {
+                    if let Some(name) = button_name(button) {
+                        self.set_button(input, name, false);
+                    }
+                },
+                EventType::AxisChanged(axis, value, _) => {
+                    if let Some(name) = axis_name(axis) {
+                        // gilrs points y up, the screen points it down
+                        let value = if name.ends_with("_y") { -value } else { value };
+                        input.set_axis(name, value);
+                        self.update_stick(input);
+                    }
+                },
+                EventType::Disconnected => {
+                    for button in input.held_buttons() {
+                        self.set_button(input, &button, false);
+                    }
+                    input.clear_gamepad();
+                },
+                _ => {}
+            }
+        }
+
+        input.gamepad_connected = self.gilrs.gamepads().next().is_some();
+    }
+}
@@ -0,0 +1,37 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use legend_engine::engine::input_replay::InputReplay;
+use crate::{render_scripts, update_scripts, GameScript, ScriptContext};
+
+pub struct FrameExport<'a> {
+    pub frames: u32,
+    pub out: &'a Path,
+    // every frame is upscaled by this before saving, 1 keeps the original 320x200
+    pub scale: u32,
+    // key presses to play back, without one the game runs without input
+    pub replay: Option<InputReplay>
+}
+
+// every frame advances by exactly one update, so two runs of the same script and replay write the same pngs
+pub fn run_headless(context: &ScriptContext, script: &mut GameScript, mut export: FrameExport, delta: f64) -> Result<(), Box<dyn Error>> {
+    let graphics = &context.graphics;
+    fs::create_dir_all(export.out)?;
+
+    for frame in 0..export.frames {
+        if let Some(replay) = export.replay.as_mut() {
+            replay.apply(&mut context.input.borrow_mut());
+        }
+
+        // no time is left over between updates, so nothing is blended
+        update_scripts(script, context, delta)?;
+        render_scripts(script, context, delta, 0.0)?;
+
+        let filename = export.out.join(format!("frame_{:05}.png", frame));
+        graphics.borrow().render_image()?.upscale(export.scale).save(&filename.to_string_lossy())?;
+        graphics.borrow_mut().end_frame();
+    }
+
+    println!("rendered {} frames to {}", export.frames, export.out.display());
+    Ok(())
+}
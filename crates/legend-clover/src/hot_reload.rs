@@ -0,0 +1,80 @@
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver};
+use std::time::Duration;
+use notify::{watcher, DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
+use legend_engine::bindings::input::take_rebind_callback;
+use legend_engine::engine::error::EngineResult;
+use crate::{game_function, init_script, GameScript, ScriptContext};
+
+// editors save in bursts of writes and renames, they are collected into one reload
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+pub struct ScriptWatcher {
+    // dropping the watcher stops the events
+    _watcher: RecommendedWatcher,
+    events: Receiver<DebouncedEvent>
+}
+
+fn is_script(path: &Path) -> bool {
+    path.extension().map(|extension| extension == "luck").unwrap_or(false)
+}
+
+impl ScriptWatcher {
+    pub fn new(directory: &Path) -> notify::Result<Self> {
+        let (sender, events) = channel();
+        let mut watcher = watcher(sender, DEBOUNCE)?;
+        watcher.watch(directory, RecursiveMode::Recursive)?;
+
+        Ok(ScriptWatcher { _watcher: watcher, events })
+    }
+
+    // whether any script changed since the last call
+    pub fn changed(&self) -> bool {
+        let mut changed = false;
+
+        for event in self.events.try_iter() {
+            changed |= match event {
+                DebouncedEvent::Write(path) | DebouncedEvent::Create(path) => is_script(&path),
+                DebouncedEvent::Rename(_, path) => is_script(&path),
+                _ => false
+            };
+        }
+
+        changed
+    }
+}
+
+// compiles the changed scripts into a new state, the engine side is kept as it is.
+// unless reset, game.on_reload_save() may hand plain data like arrays and numbers
+// over to game.on_reload_restore(data) of the new script
+pub fn reload(context: &ScriptContext, current: &mut GameScript, reset: bool) -> EngineResult<GameScript> {
+    let saved = match game_function(&mut current.state, &current.game, "on_reload_save") {
+        Some(save_function) if !reset => Some(current.state.execute_by_object(save_function, &[])?),
+        _ => None
+    };
+
+    // the new script registers its scenes while it starts, the old ones stay if it does not compile
+    let previous_scenes = std::mem::take(&mut *context.scenes.borrow_mut());
+    let mut script = match init_script(context, &current.path) {
+        Ok(script) => script,
+        Err(error) => {
+            *context.scenes.borrow_mut() = previous_scenes;
+            return Err(error);
+        }
+    };
+
+    // functions of a running mini game belong to the old state
+    context.mini_games.borrow_mut().clear();
+    // so do the menus that pushed input contexts and a rebind still waiting for its key
+    context.input.borrow_mut().clear_contexts();
+    context.input.borrow_mut().cancel_rebind();
+    take_rebind_callback();
+
+    if let Some(saved) = saved {
+        if let Some(restore_function) = game_function(&mut script.state, &script.game, "on_reload_restore") {
+            script.state.execute_by_object(restore_function, &[ saved ])?;
+        }
+    }
+
+    Ok(script)
+}
@@ -0,0 +1,160 @@
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use legend_engine::engine::assets::{is_sprite_archive, SpriteArchive};
+use legend_engine::engine::decode::{read_u16, read_u32};
+use legend_engine::engine::graphics::{Font, Palette, RleImage, FONT_CANDIDATES};
+use legend_engine::engine::world_map::WorldMap;
+
+// bytes shown of a file whose format is not known
+const HEX_DUMP_BYTES: usize = 128;
+// archive frames listed before the rest are summed up
+const MAX_LISTED_FRAMES: usize = 64;
+
+fn extension_of(path: &Path) -> String {
+    path.extension().map(|extension| extension.to_string_lossy().to_lowercase()).unwrap_or_default()
+}
+
+fn hex_dump(bytes: &[u8]) -> Vec<String> {
+    bytes.chunks(16).enumerate().map(|(row, chunk)| {
+        let hex = chunk.iter().map(|byte| format!("{:02x}", byte)).collect::<Vec<String>>().join(" ");
+        let text = chunk.iter().map(|&byte| if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' }).collect::<String>();
+        format!("  {:08x}  {:<47}  {}", row * 16, hex, text)
+    }).collect()
+}
+
+// 16 colors a row, with a block of the color itself when the output is a terminal that can show it
+fn inspect_palette(bytes: &[u8], color: bool) -> Result<Vec<String>, Box<dyn Error>> {
+    let palette = Palette::from_bytes(bytes)?;
+    let mut lines = vec![ "palette, 256 colors of 6 bit vga values, shown as 8 bit".to_string() ];
+
+    for row in 0..16u8 {
+        let cells = (0..16u8).map(|column| {
+            let value = palette.get_color(row * 16 + column);
+            match color {
+                true => format!("\x1b[48;2;{};{};{}m  \x1b[0m", value.r, value.g, value.b),
+                false => format!("{:02x}{:02x}{:02x}", value.r, value.g, value.b)
+            }
+        }).collect::<Vec<String>>();
+
+        lines.push(format!("  {:3}  {}", row as usize * 16, cells.join(" ")));
+    }
+
+    Ok(lines)
+}
+
+fn sprite_lines(sprite: &RleImage) -> Vec<String> {
+    let pixels = sprite.decode_indexed();
+    let indices: BTreeSet<u8> = pixels.iter().flatten().copied().collect();
+
+    vec![
+        format!("  size     {}x{}", sprite.size.x, sprite.size.y),
+        format!("  offset   {}, {}", sprite.offset.x, sprite.offset.y),
+        format!("  opaque   {} of {} pixels", pixels.iter().filter(|pixel| pixel.is_some()).count(), pixels.len()),
+        match (indices.first(), indices.last()) {
+            (Some(first), Some(last)) => format!("  indices  {} used, {} to {}", indices.len(), first, last),
+            _ => "  indices  none used".to_string()
+        }
+    ]
+}
+
+fn inspect_sprite(bytes: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
+    let sprite = RleImage::from_bytes(bytes)?;
+    let mut lines = vec![ format!("rle sprite, {} bytes of lines after the 8 byte header", bytes.len().saturating_sub(8)) ];
+    lines.extend(sprite_lines(&sprite));
+    Ok(lines)
+}
+
+fn inspect_sprite_archive(bytes: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
+    let archive = SpriteArchive::from_bytes(bytes)?;
+    let mut lines = vec![ format!("sprite archive, {} frames, table of {} bytes", archive.len(), 2 + archive.len() * 4) ];
+
+    // the table is read again for the offsets, the archive only keeps the frames
+    let offsets = (0..archive.len()).map(|index| read_u32(bytes, 2 + index * 4).map(|offset| offset as usize)).collect::<Result<Vec<usize>, _>>()?;
+    let ends = offsets.iter().skip(1).copied().chain(std::iter::once(bytes.len()));
+
+    lines.push("  frame  offset    bytes  size     offset".to_string());
+    for (index, ((offset, end), frame)) in offsets.iter().zip(ends).zip(archive.frames()).enumerate().take(MAX_LISTED_FRAMES) {
+        lines.push(match frame.is_empty() {
+            true => format!("  {:5}  {:08x}  {:5}  skipped", index, offset, end - offset),
+            false => format!("  {:5}  {:08x}  {:5}  {:>7}  {}, {}", index, offset, end - offset, format!("{}x{}", frame.size.x, frame.size.y), frame.offset.x, frame.offset.y)
+        });
+    }
+    if archive.len() > MAX_LISTED_FRAMES {
+        lines.push(format!("  and {} more frames", archive.len() - MAX_LISTED_FRAMES));
+    }
+
+    Ok(lines)
+}
+
+fn inspect_map(bytes: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
+    let map = WorldMap::from_bytes(bytes)?;
+    let tiles: BTreeSet<u16> = (0..map.size.y).flat_map(|y| (0..map.size.x).map(move |x| (x, y)))
+        .filter_map(|(x, y)| map.get_tile(x, y))
+        .collect();
+
+    let mut lines = vec![
+        "map".to_string(),
+        format!("  size     {}x{} tiles, {} bytes", map.size.x, map.size.y, 4 + map.size.x * map.size.y * 2)
+    ];
+    lines.push(match (tiles.first(), tiles.last()) {
+        (Some(first), Some(last)) => format!("  tiles    {} kinds, {} to {}", tiles.len(), first, last),
+        _ => "  tiles    none".to_string()
+    });
+
+    let trailing = bytes.len().saturating_sub(4 + (map.size.x * map.size.y * 2) as usize);
+    if trailing > 0 {
+        lines.push(format!("  trailing {} bytes after the tiles", trailing));
+    }
+
+    Ok(lines)
+}
+
+fn inspect_font(bytes: &[u8], width: usize, height: usize) -> Result<Vec<String>, Box<dyn Error>> {
+    let font = Font::from_bytes(bytes.to_vec(), width, height)?;
+
+    Ok(vec![
+        "font, one bit per pixel".to_string(),
+        format!("  glyphs   {} of {}x{}, {} bytes each", font.glyph_count(), width, height, font.glyph_bytes())
+    ])
+}
+
+// the english and chinese font files of every release, by name since most of them have no extension to go by
+fn font_size(name: &str) -> Option<(usize, usize)> {
+    FONT_CANDIDATES.iter().find_map(|(_, english, chinese)| {
+        if name.eq_ignore_ascii_case(english) {
+            Some((8, 16))
+        } else if name.eq_ignore_ascii_case(chinese) {
+            Some((16, 16))
+        } else {
+            None
+        }
+    })
+}
+
+// what the engine makes of the file, picked by its name like the loaders do. anything else gets the start of a hex dump
+pub fn inspect(path: &Path, color: bool) -> Result<Vec<String>, Box<dyn Error>> {
+    let bytes = fs::read(path)?;
+    let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+
+    let mut lines = vec![ format!("{}, {} bytes", path.display(), bytes.len()) ];
+    let details = match (extension_of(path).as_str(), font_size(&name)) {
+        (_, Some((width, height))) => inspect_font(&bytes, width, height)?,
+        ("pal", _) | ("col", _) => inspect_palette(&bytes, color)?,
+        ("rle", _) => inspect_sprite(&bytes)?,
+        ("map", _) => inspect_map(&bytes)?,
+        _ if is_sprite_archive(&name) => inspect_sprite_archive(&bytes)?,
+        _ => {
+            let mut lines = vec![ "unknown format".to_string() ];
+            if let Ok(first) = read_u16(&bytes, 0) {
+                lines.push(format!("  first u16 {}, first u32 {}", first, read_u32(&bytes, 0).map(|value| value.to_string()).unwrap_or_default()));
+            }
+            lines.extend(hex_dump(&bytes[..bytes.len().min(HEX_DUMP_BYTES)]));
+            lines
+        }
+    };
+
+    lines.extend(details);
+    Ok(lines)
+}
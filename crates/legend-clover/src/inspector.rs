@@ -0,0 +1,45 @@
+use pixels::{Pixels, SurfaceTexture};
+use winit::dpi::LogicalSize;
+use winit::event_loop::EventLoop;
+use winit::window::{Window, WindowBuilder, WindowId};
+use legend_engine::engine::debug_view::{DebugViews, DEBUG_VIEW_HEIGHT, DEBUG_VIEW_WIDTH};
+use legend_engine::engine::error::{EngineError, EngineResult};
+use legend_engine::engine::graphics::GameFont;
+
+// a second window for the debug views, so they never cover the game
+pub struct Inspector {
+    window: Window,
+    pixels: Pixels
+}
+
+impl Inspector {
+    pub fn new(event_loop: &EventLoop<()>, scale: u32) -> EngineResult<Self> {
+        let window = WindowBuilder::new()
+            .with_title("Legend Clover Inspector")
+            .with_inner_size(LogicalSize::new(DEBUG_VIEW_WIDTH * scale, DEBUG_VIEW_HEIGHT * scale))
+            .with_min_inner_size(LogicalSize::new(DEBUG_VIEW_WIDTH, DEBUG_VIEW_HEIGHT))
+            .build(event_loop)
+            .map_err(|error| EngineError::Graphics(error.to_string()))?;
+
+        let pixels = {
+            let window_size = window.inner_size();
+            let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
+            Pixels::new(DEBUG_VIEW_WIDTH, DEBUG_VIEW_HEIGHT, surface_texture).map_err(|error| EngineError::Graphics(error.to_string()))?
+        };
+
+        Ok(Inspector { window, pixels })
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        self.pixels.resize_surface(width, height);
+    }
+
+    pub fn render(&mut self, debug_views: &DebugViews, game_font: Option<&GameFont>) -> EngineResult<()> {
+        debug_views.render(game_font).copy_to(self.pixels.get_frame());
+        self.pixels.render().map_err(|error| EngineError::Graphics(error.to_string()))
+    }
+}
@@ -0,0 +1,45 @@
+use winit::event::{MouseButton, VirtualKeyCode};
+
+// the names scripts use for keys, keys the game has no use for are left out
+pub fn key_name(key: VirtualKeyCode) -> Option<&'static str> {
+    use VirtualKeyCode::*;
+
+    Some(match key {
+        Up => "up",
+        Down => "down",
+        Left => "left",
+        Right => "right",
+        Return | NumpadEnter => "enter",
+        Escape => "escape",
+        Space => "space",
+        Tab => "tab",
+        Back => "backspace",
+        Delete => "delete",
+        Home => "home",
+        End => "end",
+        PageUp => "page_up",
+        PageDown => "page_down",
+        LShift | RShift => "shift",
+        LControl | RControl => "control",
+        LAlt | RAlt => "alt",
+        A => "a", B => "b", C => "c", D => "d", E => "e", F => "f", G => "g",
+        H => "h", I => "i", J => "j", K => "k", L => "l", M => "m", N => "n",
+        O => "o", P => "p", Q => "q", R => "r", S => "s", T => "t", U => "u",
+        V => "v", W => "w", X => "x", Y => "y", Z => "z",
+        Key0 | Numpad0 => "0", Key1 | Numpad1 => "1", Key2 | Numpad2 => "2", Key3 | Numpad3 => "3", Key4 | Numpad4 => "4",
+        Key5 | Numpad5 => "5", Key6 | Numpad6 => "6", Key7 | Numpad7 => "7", Key8 | Numpad8 => "8", Key9 | Numpad9 => "9",
+        F1 => "f1", F2 => "f2", F3 => "f3", F4 => "f4", F5 => "f5", F6 => "f6",
+        F7 => "f7", F8 => "f8", F9 => "f9", F10 => "f10", F11 => "f11", F12 => "f12",
+        _ => return None
+    })
+}
+
+// mouse buttons are keys too, so actions can be bound to them
+pub fn mouse_button_name(button: MouseButton) -> Option<&'static str> {
+    match button {
+        MouseButton::Left => Some("mouse_left"),
+        MouseButton::Right => Some("mouse_right"),
+        MouseButton::Middle => Some("mouse_middle"),
+        MouseButton::Other(_) => None
+    }
+}
@@ -1,107 +1,1290 @@
 use std::error::Error;
 use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::io::{self, IsTerminal};
 use std::process::exit;
-use clap::Parser;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use clap::{Args as ClapArgs, Parser, Subcommand};
 use pixels::{Pixels, SurfaceTexture};
-use clover::{Clover, Object, Program, State};
+use clover::{Clover, Object, Program, Reference, State};
 use clover::helper::make_reference;
 use clover_std::clover_std_inject_to;
 
 use winit::{
-    event::{Event, WindowEvent},
+    event::{ElementState, Event, KeyboardInput, ModifiersState, VirtualKeyCode, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
     dpi::LogicalSize,
-    window::WindowBuilder,
+    window::{Fullscreen, Window, WindowBuilder},
 };
-use legend_engine::engine::graphics::{Color, Graphics};
+use legend_engine::engine::audio::config::AudioConfig;
+use legend_engine::engine::audio::mixer::AudioChannel;
+use legend_engine::engine::audio::player::Audio;
+use legend_engine::engine::audio::sound::PlayOptions;
+use legend_engine::engine::clock::{FixedStep, FrameTimer, GameTime, Steps, UpdateRate, UPDATE_RATES};
+use legend_engine::engine::compression::Compression;
+use legend_engine::engine::error::{EngineError, EngineResult};
+use legend_engine::engine::frame_stats::FrameStats;
+use legend_engine::engine::graphics::{Color, GameFont, Graphics};
+use legend_engine::engine::debug_overlay::FrameSample;
+use legend_engine::engine::debug_view::DebugViews;
+use legend_engine::engine::dialog::{DialogBox, SeenTextStore};
+use legend_engine::engine::field::Field;
+use legend_engine::engine::movement::MovementRules;
+use legend_engine::engine::status::StatusRegistry;
+use legend_engine::engine::encounter::{EncounterSystem, EncounterTables};
+use legend_engine::engine::vfs::{DirectoryMount, Vfs};
+use legend_engine::engine::data_path::install_locations;
+use legend_engine::engine::install_check::RequiredFiles;
+use legend_engine::engine::manifest::{Manifests, Preloader};
+use legend_engine::engine::release_check::Releases;
+use legend_engine::engine::save::SaveStore;
+use legend_engine::engine::settings::{Settings, SettingsFile, SettingsOverrides};
+use legend_engine::engine::options_menu::OptionsMenu;
+use legend_engine::engine::photo_mode::PhotoMode;
+use legend_engine::engine::screenshot::Screenshots;
+use legend_engine::engine::error_screen::ErrorScreen;
+use legend_engine::engine::accessibility::Announcer;
+use legend_engine::engine::gamepad::GamepadMapping;
+use legend_engine::engine::input_replay::InputReplay;
+use legend_engine::engine::input::{Input, KeyEvent};
+use legend_engine::engine::party::Party;
+use legend_engine::engine::presence::{Presence, PresenceManager};
+use legend_engine::bindings::bestiary::BestiaryModel;
+use legend_engine::bindings::audio::AudioModel;
+use legend_engine::bindings::color::GraphicsModel;
+use legend_engine::bindings::image::ImageModel;
+use legend_engine::bindings::debug::DebugModel;
+use legend_engine::bindings::dialog::{DialogModel, ScriptDialog};
+use legend_engine::bindings::engine::EngineModel;
+use legend_engine::bindings::field::{FieldModel, ScriptField};
+use legend_engine::bindings::world_map::WorldMapModel;
+use legend_engine::bindings::movement::{MovementModel, ScriptMovement};
+use legend_engine::bindings::encounter::{encounter_step, EncountersModel, ScriptEncounters};
+use legend_engine::bindings::input::{take_rebind_callback, InputModel};
+use legend_engine::bindings::minigame::MiniGames;
+use legend_engine::bindings::palette::PaletteModel;
+use legend_engine::bindings::presence::PresenceModel;
+use legend_engine::bindings::resources::ResourcesModel;
+use legend_engine::bindings::inventory::InventoryModel;
+use legend_engine::bindings::equipment::{EquipmentDatabaseModel, EquipmentModel};
+use legend_engine::bindings::skill::SkillsModel;
+use legend_engine::bindings::level::{LevelHooks, LevelsModel, ScriptLevels};
+use legend_engine::bindings::status::{ScriptStatuses, StatusSetModel, StatusesModel};
+use legend_engine::bindings::save::{SaveModel, SaveTableModel};
+use legend_engine::bindings::settings::SettingsModel;
+use legend_engine::bindings::sprite::SpriteModel;
+use legend_engine::bindings::options::OptionsModel;
+use legend_engine::bindings::accessibility::AccessibilityModel;
+use legend_engine::bindings::animation::AnimationModel;
+use legend_engine::bindings::scene::{SceneHook, Scenes, ScenesModel};
+use crate::fonts::FontCommand;
+use crate::gamepad::Gamepads;
+use crate::headless::FrameExport;
+use crate::hot_reload::ScriptWatcher;
+use crate::inspector::Inspector;
+use crate::keys::{key_name, mouse_button_name};
+
+mod convert;
+mod embedded_scripts;
+mod extract;
+mod fonts;
+mod headless;
+mod gamepad;
+mod hot_reload;
+mod inspect;
+mod inspector;
+mod keys;
+mod render_map;
+mod test_runner;
 
 const WIDTH: u32 = 320;
 const HEIGHT: u32 = 200;
+// one toml file per scene with the files it needs
+const MANIFEST_DIRECTORY: &str = "./scripts/manifests";
+// the files the game data must have, the built in copy when it is missing
+const REQUIRED_FILES: &str = "./scripts/required_files.toml";
+// what runs without --script or a script in the settings, the built in copy when it is missing
+const MAIN_SCRIPT: &str = "./scripts/main.luck";
+// the messages the player has read, next to the save slots
+const SEEN_TEXT_FILE: &str = "seen_text.bin";
+// random encounters start from the same seed every run, so a --replay meets the same battles
+const ENCOUNTER_SEED: u32 = 0x5eed;
+// crc32 of the files of every known release, for verify, the built in copy when it is missing
+const RELEASES: &str = "./scripts/releases.toml";
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// play the game, what runs when no command is given
+    Run(RunArgs),
+    /// run every script in the test folder without opening a window
+    Test {
+        /// folder with the test scripts
+        #[clap(long, value_parser, default_value = "./scripts/tests")]
+        directory: String,
+
+        /// original game folder, only needed by tests that draw text
+        #[clap(long, value_parser)]
+        data_path: Option<String>,
+    },
+    /// write the sprites and palettes of the original game as png and json, palettes also as .act and .gpl
+    Extract {
+        /// folder which contain the original Legend game install path or CD, an .iso or .cue image of the CD or a .zip of the game data
+        #[clap(value_parser)]
+        data_path: String,
+
+        /// folder the extracted files are written to, keeping the folder layout of the game
+        #[clap(value_parser)]
+        out_dir: String,
+
+        /// palette file to draw the sprites with, relative to data_path, the first one found by default
+        #[clap(long, value_parser)]
+        palette: Option<String>,
+    },
+    /// print what the engine reads from an original data file, its entries, offsets, sizes and colors
+    Inspect {
+        /// palette, sprite, sprite archive, map or font file, anything else is shown as hex
+        #[clap(value_parser)]
+        file: String,
+    },
+    /// turn an .rle sprite into a png with its offset in a json next to it, or such a png back into an .rle,
+    /// or a .pal or .col palette into a photoshop .act or gimp .gpl and back
+    Convert {
+        /// .rle, .png or palette file, a png keeps the offset of the json with the same name
+        #[clap(value_parser)]
+        input: String,
+
+        /// file to write, the png gets a json next to it and a palette is written in the format of its extension
+        #[clap(value_parser)]
+        output: String,
+
+        /// .pal or .col file to draw with or match colors against, gray levels are palette indices without it
+        #[clap(long, value_parser)]
+        palette: Option<String>,
+    },
+    /// draw the english or big5 font of the game into a png glyph sheet, or read an edited sheet back into the font
+    Font {
+        #[clap(subcommand)]
+        command: FontCommand,
+    },
+    /// draw a whole map of the game into one png, for reference maps
+    RenderMap {
+        /// folder which contain the original Legend game install path or CD, an .iso or .cue image of the CD or a .zip of the game data
+        #[clap(value_parser)]
+        data_path: String,
+
+        /// map file, relative to data_path
+        #[clap(value_parser)]
+        map: String,
 
+        /// png file to write
+        #[clap(value_parser)]
+        out: String,
+
+        /// sprite archive with a frame for every tile value, relative to data_path, colored squares without it
+        #[clap(long, value_parser)]
+        tiles: Option<String>,
+
+        /// palette file to draw with, relative to data_path, the first one found by default
+        #[clap(long, value_parser)]
+        palette: Option<String>,
+    },
+    /// compare the game data to the checksums of the known releases and tell which one it is
+    Verify {
+        /// folder which contain the original Legend game install path or CD, an .iso or .cue image of the CD or a .zip of the game data
+        #[clap(value_parser)]
+        data_path: String,
+
+        /// toml file with the crc32 of the files of every release
+        #[clap(long, value_parser, default_value = RELEASES)]
+        releases: String,
+    },
+}
+
+// legend-clover [options] [data_path] is legend-clover run [options] [data_path]
 #[derive(Parser, Debug)]
-#[clap(version)]
+#[clap(version, args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
-    /// window scale
-    #[clap(short, long, value_parser = clap::value_parser!(u32).range(1...10), default_value_t = 2)]
-    scale: u32,
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    #[clap(flatten)]
+    run: RunArgs,
+}
+
+#[derive(ClapArgs, Debug)]
+struct RunArgs {
+    /// settings file, settings.toml in the config folder of the platform by default
+    #[clap(long, value_parser)]
+    config: Option<String>,
+
+    /// window scale, also the scale --export-frames writes at, 2 unless the settings say otherwise
+    #[clap(short, long, value_parser = clap::value_parser!(u32).range(1...10))]
+    scale: Option<u32>,
+
+    /// start fullscreen on the current monitor, alt+enter switches back and forth
+    #[clap(long)]
+    fullscreen: bool,
+
+    /// fixed game updates per second whatever the display rate, 30 matches the original game and 120 is smoother, 60 unless the settings say otherwise
+    #[clap(long, value_parser = parse_update_rate)]
+    update_rate: Option<u32>,
+
+    /// outline every draw and tint overdraw hotspots
+    #[clap(long)]
+    debug_draw: bool,
+
+    /// show fps, update and render times and draw counts in the corner, f3 switches it on and off
+    #[clap(long)]
+    debug_overlay: bool,
+
+    /// open a second window with the palette, inspected objects and log the scripts send through Debug()
+    #[clap(long)]
+    inspector: bool,
+
+    /// write per frame timing to a csv file when the game exits
+    #[clap(long, value_parser)]
+    frame_stats: Option<String>,
+
+    /// audio output device name, the system default unless the settings name one
+    #[clap(long, value_parser)]
+    audio_device: Option<String>,
+
+    /// audio output sample rate, overrides the settings
+    #[clap(long, value_parser)]
+    sample_rate: Option<u32>,
+
+    /// audio frames per output callback, lower is less latency but may crackle, overrides the settings
+    #[clap(long, value_parser)]
+    audio_buffer: Option<u32>,
+
+    /// show the current chapter and location in discord, needs the discord application id
+    #[cfg(feature = "discord")]
+    #[clap(long, value_parser)]
+    discord_client_id: Option<String>,
+
+    /// script to start instead of the main one, like a test scene or a mod, the settings can name one as well
+    #[clap(long, value_parser)]
+    script: Option<String>,
+
+    /// reload the scripts whenever a file in the folder of the script changes
+    #[clap(long)]
+    hot_reload: bool,
+
+    /// start reloaded scripts from scratch instead of handing over game.on_reload_save()
+    #[clap(long, requires = "hot-reload")]
+    hot_reload_reset: bool,
 
-    /// folder which contain the original Legend game install path or CD
+    /// run without a window and save every frame as a png, for machines without a display
+    #[clap(long, requires = "out")]
+    headless: bool,
+
+    /// frames to render in headless mode or for --export-frames
+    #[clap(long, value_parser, default_value_t = 60)]
+    frames: u32,
+
+    /// folder the headless frames are written to
+    #[clap(long, value_parser, requires = "headless")]
+    out: Option<String>,
+
+    /// render --frames frames without a window into this folder at --scale, for lossless trailer footage
+    #[clap(long, value_parser, conflicts_with = "headless")]
+    export_frames: Option<String>,
+
+    /// play back the key presses of a file written by --record-input
+    #[clap(long, value_parser)]
+    replay: Option<String>,
+
+    /// write every key press and release to this file when the game exits, to be played back with --replay
+    #[clap(long, value_parser)]
+    record_input: Option<String>,
+
+    /// toml file with a [buttons] table mapping gamepad buttons to keys, like south = "enter"
+    #[clap(long, value_parser)]
+    gamepad_config: Option<String>,
+
+    /// map a gamepad button to a key as button=key, can be given more than once, an empty key unmaps it
+    #[clap(long, value_parser, multiple_occurrences = true)]
+    gamepad_map: Vec<String>,
+
+    /// check the required files and every file the scene manifests list against the game data, then exit
+    #[clap(long)]
+    check: bool,
+
+    /// log every game data read with the folder it came from, its size and time, and sum them up at exit
+    #[clap(long)]
+    trace_resources: bool,
+
+    /// folder of files that replace the game data files of the same name and, in a scripts folder, the scripts, can be given more than once and later ones win
+    #[clap(long = "mod", value_parser, multiple_occurrences = true)]
+    mods: Vec<String>,
+
+    /// folder for save slots, by default the user data folder of the platform
+    #[clap(long, value_parser)]
+    save_dir: Option<String>,
+
+    /// folder f12 writes screenshots to, by default next to the save slots
+    #[clap(long, value_parser)]
+    screenshot_dir: Option<String>,
+
+    /// folder which contain the original Legend game install path or CD, an .iso or .cue image of the CD or a .zip of the game data, can be left out once it is in the settings or when the game is installed in a usual place
     #[clap(value_parser)]
-    data_path: String,
+    data_path: Option<String>,
 }
 
-fn init_script() -> Result<(State, Object, Object), Box<dyn Error>> {
-    let clover = Clover::new();
+fn parse_update_rate(value: &str) -> Result<u32, String> {
+    let rate = value.parse::<u32>().map_err(|error| format!("{} is not a number: {}", value, error))?;
+    match UPDATE_RATES.contains(&rate) {
+        true => Ok(rate),
+        false => Err(format!("{} is not one of {:?}", rate, UPDATE_RATES))
+    }
+}
 
-    let program = clover.compile_file("./scripts/main.luck")?;
+// engine objects the scripts reach through the native models
+pub struct ScriptContext {
+    pub graphics: Reference<Graphics>,
+    pub mini_games: Reference<MiniGames>,
+    pub scenes: Reference<Scenes>,
+    pub presence: Reference<Presence>,
+    pub audio: Reference<Audio>,
+    pub input: Reference<Input>,
+    pub debug_views: Reference<DebugViews>,
+    pub saves: Reference<SaveStore>,
+    pub settings: Reference<SettingsFile>,
+    pub preloader: Reference<Preloader>,
+    pub time: Reference<GameTime>,
+    pub options: Reference<OptionsMenu>,
+    pub speech: Reference<Announcer>,
+    pub dialog: Reference<DialogBox>,
+    pub field: Reference<Field>,
+    pub movement: Reference<MovementRules>,
+    pub statuses: Reference<StatusRegistry>,
+    pub levels: Reference<LevelHooks>,
+    pub photo_mode: Reference<PhotoMode>,
+    pub encounters: Reference<EncounterSystem>,
+    pub vfs: Arc<Vfs>
+}
 
-    let mut state: State = program.into();
-    clover_std_inject_to(&mut state);
+pub fn inject_models(state: &mut State, context: &ScriptContext) {
+    clover_std_inject_to(state);
 
     state.add_native_model("Color", make_reference(Color::new(0, 0, 0, 0)));
+    state.add_native_model("Graphics", make_reference(GraphicsModel::new(context.graphics.clone())));
+    state.add_native_model("Image", make_reference(ImageModel::new(context.graphics.borrow().shared_game_font(), context.vfs.clone())));
+    state.add_native_model("Party", make_reference(Party::default()));
+    state.add_native_model("Inventory", make_reference(InventoryModel));
+    state.add_native_model("EquipmentDatabase", make_reference(EquipmentDatabaseModel::new(context.vfs.clone())));
+    state.add_native_model("Equipment", make_reference(EquipmentModel));
+    state.add_native_model("Statuses", make_reference(StatusesModel::new(make_reference(ScriptStatuses::new(context.statuses.clone())))));
+    state.add_native_model("Levels", make_reference(LevelsModel::new(make_reference(ScriptLevels::new(context.levels.clone(), context.vfs.clone())))));
+    state.add_native_model("Skills", make_reference(SkillsModel::new(context.vfs.clone())));
+    state.add_native_model("StatusSet", make_reference(StatusSetModel::new(context.statuses.clone(), context.graphics.clone())));
+    state.add_native_model("Bestiary", make_reference(BestiaryModel::new(context.saves.clone())));
+    state.add_native_model("MiniGame", context.mini_games.clone());
+    state.add_native_model("Presence", make_reference(PresenceModel::new(context.presence.clone())));
+    state.add_native_model("Audio", make_reference(AudioModel::new(context.audio.clone())));
+    state.add_native_model("Palette", make_reference(PaletteModel::new(context.vfs.clone())));
+    state.add_native_model("Sprite", make_reference(SpriteModel::new(context.vfs.clone())));
+    state.add_native_model("Input", make_reference(InputModel::new(context.input.clone())));
+    state.add_native_model("Debug", make_reference(DebugModel::new(context.debug_views.clone())));
+    state.add_native_model("Save", make_reference(SaveModel::new(context.saves.clone())));
+    state.add_native_model("SaveTable", make_reference(SaveTableModel));
+    state.add_native_model("Settings", make_reference(SettingsModel::new(context.settings.clone())));
+    state.add_native_model("Engine", make_reference(EngineModel::new(context.time.clone())));
+    state.add_native_model("Resources", make_reference(ResourcesModel::new(context.preloader.clone())));
+    state.add_native_model("Options", make_reference(OptionsModel::new(context.options.clone())));
+    state.add_native_model("Accessibility", make_reference(AccessibilityModel::new(context.speech.clone())));
+    state.add_native_model("Animation", make_reference(AnimationModel));
+    state.add_native_model("Scenes", make_reference(ScenesModel::new(context.scenes.clone())));
+    state.add_native_model("Dialog", make_reference(DialogModel::new(make_reference(ScriptDialog::new(context.dialog.clone(), context.settings.clone(), context.vfs.clone())))));
+    state.add_native_model("Field", make_reference(FieldModel::new(make_reference(ScriptField::new(context.field.clone(), context.graphics.clone(), context.audio.clone(), context.dialog.clone(), context.settings.clone(), context.vfs.clone())))));
+    state.add_native_model("Encounters", make_reference(EncountersModel::new(make_reference(ScriptEncounters::new(context.encounters.clone(), context.scenes.clone(), context.vfs.clone())))));
+    state.add_native_model("WorldMap", make_reference(WorldMapModel::new(context.graphics.clone(), context.scenes.clone(), context.encounters.clone(), context.movement.clone(), context.vfs.clone())));
+    state.add_native_model("Movement", make_reference(MovementModel::new(make_reference(ScriptMovement::new(context.movement.clone())))));
+}
+
+// the compiled main script and the game object it returned
+pub struct GameScript {
+    // the entry script, a reload compiles it again
+    pub path: PathBuf,
+    pub state: State,
+    pub game: Object,
+    pub update_function: Object,
+    pub render_function: Object,
+    pub on_key_down: Option<Object>,
+    pub on_key_up: Option<Object>,
+    pub on_key_action: Option<Object>
+}
+
+// an optional game function, missing ones are skipped
+pub fn game_function(state: &mut State, game: &Object, name: &str) -> Option<Object> {
+    match state.get_object_property_by_name(game.clone(), name) {
+        Ok(Object::Null) | Err(_) => None,
+        Ok(function) => Some(function)
+    }
+}
+
+fn init_script(context: &ScriptContext, path: &Path) -> EngineResult<GameScript> {
+    if !path.is_file() {
+        return Err(EngineError::NotFound(format!("script {}", path.display())));
+    }
+
+    // hot reload watches the folder of path, what compiles has the scripts of any --mod in it
+    let compiled_path = embedded_scripts::with_mods(&context.vfs, path)?;
+    let clover = Clover::new();
+    let program = clover.compile_file(&compiled_path.to_string_lossy()).map_err(|error| EngineError::Script(error.to_string()))?;
+
+    let mut state: State = program.into();
+    inject_models(&mut state, context);
 
     let game = state.execute()?;
     let update_function = state.get_object_property_by_name(game.clone(), "update")?;
     let render_function = state.get_object_property_by_name(game.clone(), "render")?;
+    let on_key_down = game_function(&mut state, &game, "on_key_down");
+    let on_key_up = game_function(&mut state, &game, "on_key_up");
+    let on_key_action = game_function(&mut state, &game, "on_key_action");
 
-    Ok((state, update_function, render_function))
+    Ok(GameScript { path: path.to_path_buf(), state, game, update_function, render_function, on_key_down, on_key_up, on_key_action })
 }
 
-fn init_engine() -> Result<(Graphics), Box<dyn Error>> {
+fn init_engine() -> EngineResult<(Graphics)> {
     Ok((Graphics::new(WIDTH, HEIGHT)?))
 }
 
-fn run_frame(graphics: &mut Graphics, state: &mut State, update_function: &Object, render_function: &Object, pixels: &mut Pixels) -> Result<(), Box<dyn Error>> {
-    let update_result = state.execute_by_object(update_function.clone(), &[ Object::Float(0.0) ])?;
-    let render_result = state.execute_by_object(render_function.clone(), &[ Object::Float(0.0) ])?;
+fn load_game_font(graphics: &mut Graphics, vfs: &Vfs) {
+    match GameFont::discover(vfs) {
+        Ok(game_font) => graphics.set_game_font(game_font),
+        Err(error) => eprintln!("{}", error)
+    }
+}
+
+// key events since the last frame go to game.on_key_down(key, context), game.on_key_up(key, context)
+// and game.on_key_action(action, key, context) when the game has them,
+// context names the input context that owns the key and action is "repeat", "double_tap" or "hold"
+fn dispatch_key_events(script: &mut GameScript, input: &Reference<Input>) -> EngineResult<()> {
+    let events = input.borrow_mut().take_events();
+
+    for event in events {
+        let (callback, parameters) = match event {
+            KeyEvent::Down { key, context } => (script.on_key_down.clone(), vec![ key, context ]),
+            KeyEvent::Up { key, context } => (script.on_key_up.clone(), vec![ key, context ]),
+            KeyEvent::Action { action, key, context } => (script.on_key_action.clone(), vec![ action.name().to_string(), key, context ]),
+            KeyEvent::Rebound { action, key } => (take_rebind_callback(), vec![ action, key ])
+        };
+
+        if let Some(callback) = callback {
+            let parameters: Vec<Object> = parameters.into_iter().map(|parameter| Object::String(make_reference(parameter))).collect();
+            script.state.execute_by_object(callback, &parameters)?;
+        }
+    }
+
+    Ok(())
+}
+
+// the real time of a frame and the fixed updates it is due
+#[derive(Copy, Clone, Debug)]
+struct FrameTiming {
+    delta: f64,
+    update_delta: f64,
+    steps: Steps
+}
+
+fn update_photo_mode(context: &ScriptContext, delta: f64) {
+    let input = context.input.borrow();
+    let direction = |negative: &str, positive: &str| input.is_down(positive) as i32 - input.is_down(negative) as i32;
+
+    let mut graphics = context.graphics.borrow_mut();
+    // without bounds the camera goes as far as it likes
+    let (width, height) = graphics.camera().bounds().unwrap_or((i32::MAX, i32::MAX));
+    let mut photo_mode = context.photo_mode.borrow_mut();
+    photo_mode.move_camera(direction("left", "right"), direction("up", "down"), delta, width.saturating_sub(WIDTH as i32), height.saturating_sub(HEIGHT as i32));
+
+    let camera = photo_mode.camera();
+    graphics.camera_mut().set_position(camera.x as f64, camera.y as f64);
+}
+
+// on_level_up(character, level_up) for every level Growth.gain reached during the update
+fn level_ups(script: &mut GameScript, context: &ScriptContext) -> EngineResult<()> {
+    let level_ups = context.levels.borrow_mut().take_level_ups();
+    for (hook, character, level_up) in level_ups {
+        script.state.execute_by_object(hook, &[ character, level_up ])?;
+    }
+
+    Ok(())
+}
+
+// one fixed step of game logic, a running mini game replaces the main update until it returns a result.
+// scripts get the dt scaled by the time scale, key timing stays in real time
+fn update_scripts(script: &mut GameScript, context: &ScriptContext, delta: f64) -> EngineResult<()> {
+    context.input.borrow_mut().update(delta);
+    context.preloader.borrow_mut().update();
+
+    // photo mode pauses the game too, the arrows move its camera over the map
+    if context.photo_mode.borrow().pauses_game() {
+        update_photo_mode(context, delta);
+        return Ok(());
+    }
+
+    // the options menu takes the keys and the game stays paused behind it
+    if context.options.borrow().is_open() {
+        update_options(context);
+        return Ok(());
+    }
+
+    dispatch_key_events(script, &context.input)?;
+
+    let delta = context.time.borrow_mut().advance(delta);
+    context.graphics.borrow_mut().update_palette_blends(delta);
+    context.dialog.borrow_mut().update(delta);
+    context.field.borrow_mut().update(delta);
+    field_encounters(script, context)?;
+    map_transitions(script, context, delta)?;
+
+    change_scenes(script, context)?;
+
+    let mini_games = &context.mini_games;
+    let state = &mut script.state;
+    let active_functions = mini_games.borrow().active_functions();
+    let scene_functions = context.scenes.borrow().active_functions();
+
+    match active_functions {
+        Some((mini_update_function, _)) => {
+            let result = state.execute_by_object(mini_update_function, &[ Object::Float(delta) ])?;
+
+            if !matches!(result, Object::Null) {
+                let finish_function = mini_games.borrow_mut().finish();
+                if let Some(finish_function) = finish_function {
+                    state.execute_by_object(finish_function, &[ result ])?;
+                }
+            }
+        },
+        None => {
+            let update_function = scene_functions.map(|(scene_update_function, _)| scene_update_function).unwrap_or_else(|| script.update_function.clone());
+            state.execute_by_object(update_function, &[ Object::Float(delta) ])?;
+        }
+    }
+
+    level_ups(script, context)
+}
+
+// every tile the field leader walked counts towards an encounter on the field map
+fn field_encounters(script: &GameScript, context: &ScriptContext) -> EngineResult<()> {
+    let (map, steps) = {
+        let mut field = context.field.borrow_mut();
+        (field.name().map(str::to_string), field.take_leader_steps())
+    };
+
+    if let Some(map) = map {
+        for _ in 0..steps {
+            encounter_step(&mut context.encounters.borrow_mut(), &mut context.scenes.borrow_mut(), &map, &script.state)?;
+        }
+    }
+
+    Ok(())
+}
+
+// a door the leader stepped on starts a map change, the switch it queues runs in change_scenes right after
+fn map_transitions(script: &GameScript, context: &ScriptContext, delta: f64) -> EngineResult<()> {
+    let door = context.field.borrow_mut().take_transition();
+    let mut scenes = context.scenes.borrow_mut();
+    if let Some(door) = door {
+        scenes.start_transition(&door);
+    }
+
+    for sound in scenes.update_transition(delta, &script.state)? {
+        context.audio.borrow_mut().play_sound(&sound, PlayOptions::default())?;
+    }
+
+    Ok(())
+}
+
+// the scene changes scripts asked for since the last update, a scene that is left takes its effect buffers along
+fn change_scenes(script: &mut GameScript, context: &ScriptContext) -> EngineResult<()> {
+    if !context.scenes.borrow().has_changes() {
+        return Ok(());
+    }
+
+    let hooks = context.scenes.borrow_mut().take_changes();
+    for hook in hooks {
+        match hook {
+            SceneHook::Exit(exit_function) => {
+                script.state.execute_by_object(exit_function, &[])?;
+                context.graphics.borrow_mut().end_scene();
+            },
+            SceneHook::Enter(enter_function, value) => {
+                script.state.execute_by_object(enter_function, &[ value ])?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn update_options(context: &ScriptContext) {
+    let mut settings = context.settings.borrow_mut();
+    let changes = context.options.borrow_mut().update(&mut context.input.borrow_mut(), &mut settings.settings);
+
+    if changes.volumes {
+        let mut audio = context.audio.borrow_mut();
+        audio.set_volume(AudioChannel::Master, settings.settings.master_volume);
+        audio.set_volume(AudioChannel::Music, settings.settings.music_volume);
+        audio.set_volume(AudioChannel::Sound, settings.settings.sound_volume);
+    }
+
+    let mut speech = context.speech.borrow_mut();
+    if changes.speech {
+        *speech = init_speech(settings.settings.screen_reader);
+    }
+    if let Some(text) = context.options.borrow().selected_text(&settings.settings, &context.input.borrow()) {
+        speech.select(&text);
+    }
+
+    // what the player changed in the menu, the command line flags of this launch stay out of the file
+    if changes.closed {
+        if let Err(error) = settings.save() {
+            eprintln!("can not write the settings to {}: {}", settings.path().display(), error);
+        }
+    }
+}
+
+// render(delta, alpha) once per displayed frame, delta is the real frame time
+fn render_scripts(script: &mut GameScript, context: &ScriptContext, delta: f64, alpha: f64) -> EngineResult<()> {
+    let render_function = match (context.mini_games.borrow().active_functions(), context.scenes.borrow().active_functions()) {
+        (Some((_, mini_render_function)), _) => mini_render_function,
+        (None, Some((_, scene_render_function))) => scene_render_function,
+        (None, None) => script.render_function.clone()
+    };
+    context.field.borrow_mut().alpha = alpha;
+
+    script.state.execute_by_object(render_function, &[ Object::Float(delta), Object::Float(alpha) ])?;
+
+    // photos are of the map alone
+    if context.photo_mode.borrow().hides_ui() {
+        return Ok(());
+    }
+
+    // what the engine draws on top is in screen coordinates
+    context.graphics.borrow_mut().set_camera_enabled(false);
+    if let Some(transition) = context.scenes.borrow().transition() {
+        transition.draw(&mut context.graphics.borrow_mut());
+    }
+    context.dialog.borrow().draw(&mut context.graphics.borrow_mut());
+    context.input.borrow().pointer.draw(&mut context.graphics.borrow_mut());
+
+    let options = context.options.borrow();
+    if options.is_open() {
+        options.draw(&mut context.graphics.borrow_mut(), &context.settings.borrow().settings, &context.input.borrow());
+    }
+    Ok(())
+}
+
+fn present(graphics: &Graphics, pixels: &mut Pixels) -> EngineResult<()> {
+    graphics.render_to(pixels.get_frame())?;
+    pixels.render().map_err(|error| EngineError::Graphics(error.to_string()))?;
+    Ok(())
+}
+
+// the updates due since the last frame and then one render
+fn run_frame(context: &ScriptContext, script: &mut GameScript, pixels: &mut Pixels, replay: Option<&mut InputReplay>, frame: FrameTiming, frame_stats: &mut FrameStats) -> EngineResult<()> {
+    let graphics = &context.graphics;
+    let update_start = Instant::now();
+
+    let mut replay = replay;
+    for _ in 0..frame.steps.updates {
+        // recorded presses are tied to update counts, so they go in between updates
+        if let Some(replay) = replay.as_mut() {
+            replay.apply(&mut context.input.borrow_mut());
+        }
 
-    let frame_buffer = pixels.get_frame();
+        update_scripts(script, context, frame.update_delta)?;
+    }
 
-    graphics.render_to(frame_buffer)?;
+    let render_start = Instant::now();
+    render_scripts(script, context, frame.delta, frame.steps.alpha)?;
+    let update_time = render_start - update_start;
+    let script_time = update_time + render_start.elapsed();
 
-    pixels.render()?;
+    present(&graphics.borrow(), pixels)?;
+
+    let now = Instant::now();
+    frame_stats.record_present(now, script_time);
+    let (dropped_frames, script_spike) = frame_stats.last().map(|record| (record.dropped_frames, record.script_spike)).unwrap_or_default();
+
+    let mut graphics = graphics.borrow_mut();
+    let blits = graphics.draw_count();
+    graphics.record_frame(FrameSample {
+        interval: Duration::from_secs_f64(frame.delta),
+        update_time,
+        render_time: now - render_start,
+        script_time,
+        updates: frame.steps.updates,
+        blits,
+        dropped_frames,
+        script_spike
+    });
+    graphics.end_frame();
 
 
     Ok(())
 }
 
+#[cfg(feature = "discord")]
+fn init_presence(args: &RunArgs) -> PresenceManager {
+    use legend_engine::engine::presence::discord::DiscordPresence;
+
+    match &args.discord_client_id {
+        Some(client_id) => match DiscordPresence::connect(client_id) {
+            Ok(discord) => PresenceManager::new(Box::new(discord)),
+            Err(error) => {
+                eprintln!("can not connect to discord: {}", error);
+                PresenceManager::disabled()
+            }
+        },
+        None => PresenceManager::disabled()
+    }
+}
+
+#[cfg(not(feature = "discord"))]
+fn init_presence(_args: &RunArgs) -> PresenceManager {
+    PresenceManager::disabled()
+}
+
+#[cfg(feature = "tts")]
+fn init_speech(screen_reader: bool) -> Announcer {
+    use legend_engine::engine::accessibility::platform::PlatformSpeech;
+
+    if !screen_reader {
+        return Announcer::disabled();
+    }
+
+    match PlatformSpeech::connect() {
+        Ok(speech) => Announcer::new(Box::new(speech)),
+        Err(error) => {
+            eprintln!("can not start text to speech: {}", error);
+            Announcer::disabled()
+        }
+    }
+}
+
+#[cfg(not(feature = "tts"))]
+fn init_speech(screen_reader: bool) -> Announcer {
+    if screen_reader {
+        eprintln!("screen_reader is set but this build has no text to speech, build with --features tts");
+    }
+    Announcer::disabled()
+}
+
+// settings.toml first, then whatever was given on the command line on top of it
+fn init_settings(args: &RunArgs) -> EngineResult<SettingsFile> {
+    let path = args.config.clone().map(PathBuf::from).unwrap_or_else(SettingsFile::default_path);
+    // the flags only last for this launch, saving the settings leaves them out
+    let overrides = SettingsOverrides {
+        scale: args.scale,
+        fullscreen: args.fullscreen.then_some(true),
+        update_rate: args.update_rate,
+        audio_device: args.audio_device.clone(),
+        sample_rate: args.sample_rate,
+        audio_buffer: args.audio_buffer,
+        data_path: args.data_path.clone()
+    };
+
+    Ok(SettingsFile::load(&path)?.with_overrides(overrides))
+}
+
+// the config file first, then every --gamepad-map on top of it
+fn init_gamepad_mapping(args: &RunArgs) -> EngineResult<GamepadMapping> {
+    let mut mapping = match &args.gamepad_config {
+        Some(filename) => GamepadMapping::load(filename)?,
+        None => GamepadMapping::default()
+    };
+
+    for value in args.gamepad_map.iter() {
+        mapping.set_from_str(value)?;
+    }
+
+    Ok(mapping)
+}
+
+// borderless on the monitor the window is on, pixels keeps the picture at whole multiples and centered
+// after the options menu changed the display settings
+fn apply_display(window: &Window, settings: &Settings) {
+    if window.fullscreen().is_some() != settings.fullscreen {
+        toggle_fullscreen(window);
+    }
+    if !settings.fullscreen {
+        window.set_inner_size(LogicalSize::new(WIDTH * settings.scale, HEIGHT * settings.scale));
+    }
+}
+
+// a file of the scripts folder that is not next to the executable is taken from the built in scripts
+fn embedded_text(path: &str) -> Option<&'static str> {
+    match Path::new(path).is_file() {
+        true => None,
+        false => embedded_scripts::text(path.strip_prefix("./scripts/")?)
+    }
+}
+
+// the first install location that has every required file, for a launch without data_path
+fn detect_data_path(required_files: &RequiredFiles) -> EngineResult<Option<String>> {
+    for location in install_locations() {
+        if required_files.check(&Vfs::directory(&location))?.is_complete() {
+            let location = location.display().to_string();
+            eprintln!("using the game data found in {}, pass a folder or set data_path in the settings to use another", location);
+            return Ok(Some(location));
+        }
+    }
+
+    Ok(None)
+}
+
+fn print_resource_trace(vfs: &Vfs) {
+    if let Some(summary) = vfs.trace_summary() {
+        for line in summary {
+            eprintln!("{}", line);
+        }
+    }
+}
+
+fn toggle_fullscreen(window: &Window) {
+    if window.fullscreen().is_some() {
+        window.set_fullscreen(None);
+    } else {
+        window.set_fullscreen(Some(Fullscreen::Borderless(window.current_monitor())));
+    }
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Args::parse();
 
+    let args = match args.command {
+        Some(Command::Run(run)) => run,
+        Some(Command::Test { directory, data_path }) => {
+            let passed = test_runner::run_tests(Path::new(&directory), data_path.as_deref())?;
+            exit(if passed { 0 } else { 1 });
+        },
+        Some(Command::Extract { data_path, out_dir, palette }) => {
+            let report = extract::extract(&Vfs::open(Path::new(&data_path))?, Path::new(&out_dir), palette.as_deref())?;
+            for (name, error) in report.failed.iter() {
+                eprintln!("can not extract {}: {}", name, error);
+            }
+
+            println!("extracted {} sprites and {} palettes to {}", report.sprites, report.palettes, out_dir);
+            return Ok(());
+        },
+        Some(Command::Inspect { file }) => {
+            for line in inspect::inspect(Path::new(&file), io::stdout().is_terminal())? {
+                println!("{}", line);
+            }
+            return Ok(());
+        },
+        Some(Command::Convert { input, output, palette }) => {
+            convert::convert(Path::new(&input), Path::new(&output), palette.as_deref().map(Path::new))?;
+            println!("converted {} to {}", input, output);
+            return Ok(());
+        },
+        Some(Command::Font { command }) => {
+            fonts::run(command)?;
+            return Ok(());
+        },
+        Some(Command::RenderMap { data_path, map, out, tiles, palette }) => {
+            let image = render_map::render_map(&Vfs::open(Path::new(&data_path))?, &map, tiles.as_deref(), palette.as_deref())?;
+            image.save(&out)?;
+            println!("rendered {} at {}x{} to {}", map, image.size.x, image.size.y, out);
+            return Ok(());
+        },
+        Some(Command::Verify { data_path, releases }) => {
+            let releases = match embedded_text(&releases) {
+                Some(source) => Releases::from_toml(source)?,
+                None => Releases::load(Path::new(&releases))?
+            };
+            let report = releases.verify(&Vfs::open(Path::new(&data_path))?)?;
+            for line in report.lines() {
+                println!("{}", line);
+            }
+            exit(if report.detected().is_some() { 0 } else { 1 });
+        },
+        None => args.run
+    };
+
+    let settings = init_settings(&args)?;
+    let required_files = match embedded_text(REQUIRED_FILES) {
+        Some(source) => RequiredFiles::from_toml(source)?,
+        None => RequiredFiles::load(Path::new(REQUIRED_FILES))?
+    };
+    let data_path = match settings.settings.data_path.clone() {
+        Some(data_path) => data_path,
+        None => detect_data_path(&required_files)?
+            .ok_or_else(|| EngineError::NotFound(format!("data path, pass the game folder or set data_path in {}", settings.path().display())))?
+    };
+    let scale = settings.settings.scale;
+    if !Path::new(&data_path).exists() {
+        return Err(EngineError::NotFound(format!("data path {}, pass the game folder or fix data_path in {}", data_path, settings.path().display())).into());
+    }
+    let mut vfs = Vfs::open(Path::new(&data_path))?;
+    for mod_path in args.mods.iter() {
+        if !Path::new(mod_path).is_dir() {
+            return Err(EngineError::NotFound(format!("mod folder {}", mod_path)).into());
+        }
+        vfs.mount(Box::new(DirectoryMount::new(Path::new(mod_path))));
+    }
+    if args.trace_resources {
+        vfs.enable_trace();
+    }
+    let vfs = Arc::new(vfs);
+    let manifests = Manifests::load(Path::new(MANIFEST_DIRECTORY))?;
+
+    // a wrong folder is reported here with every file it lacks, not by the first loader that trips over one
+    let install_report = required_files.check(&vfs)?;
+    if args.check || !install_report.is_complete() {
+        for line in install_report.lines() {
+            eprintln!("{}", line);
+        }
+    }
+
+    if args.check {
+        let problems = manifests.check(&vfs);
+        for problem in problems.iter() {
+            eprintln!("{}: {}", problem.scene, problem.message);
+        }
+
+        println!("checked {} scenes, {} problems", manifests.scenes().len(), problems.len());
+        exit(if problems.is_empty() && install_report.is_complete() { 0 } else { 1 });
+    }
+
+    if !install_report.is_complete() {
+        return Err(EngineError::NotFound(format!("{} required files in {}", install_report.problem_count(), data_path)).into());
+    }
+
     let (mut graphics) = init_engine()?;
-    let (mut state, update_function, render_function) = init_script()?;
+    graphics.set_draw_debug(args.debug_draw);
+    graphics.set_debug_overlay(args.debug_overlay);
+    load_game_font(&mut graphics, &vfs);
+
+    // shared with the scripts, which draw through it during render
+    let graphics = make_reference(graphics);
+
+    let mini_games = make_reference(MiniGames::new());
+    let presence = make_reference(Presence::new());
+    let mut presence_manager = init_presence(&args);
+    let audio_config = AudioConfig::new(settings.settings.audio_device.clone(), settings.settings.sample_rate, settings.settings.audio_buffer);
+    let audio = if args.headless || args.export_frames.is_some() {
+        make_reference(Audio::silent(&audio_config, vfs.clone()))
+    } else {
+        make_reference(Audio::open(&audio_config, vfs.clone()))
+    };
+    {
+        let mut audio = audio.borrow_mut();
+        audio.set_volume(AudioChannel::Master, settings.settings.master_volume);
+        audio.set_volume(AudioChannel::Music, settings.settings.music_volume);
+        audio.set_volume(AudioChannel::Sound, settings.settings.sound_volume);
+    }
+    let fullscreen = settings.settings.fullscreen;
+    let update_rate = UpdateRate::new(settings.settings.update_rate);
+    let save_directory = args.save_dir.clone().map(PathBuf::from).unwrap_or_else(SaveStore::default_directory);
+    let screenshots = Screenshots::new(&args.screenshot_dir.clone().map(PathBuf::from).unwrap_or_else(Screenshots::default_directory));
+    let save_compression = if settings.settings.compress_saves { Compression::Deflate } else { Compression::None };
+    let mut input = Input::new();
+    input.pointer.set_bounds(WIDTH, HEIGHT);
+    settings.settings.bind_actions(&mut input.actions);
+    let speech = init_speech(settings.settings.screen_reader);
+    let seen_text_filename = save_directory.join(SEEN_TEXT_FILE).to_string_lossy().to_string();
+    let mut dialog = DialogBox::new(WIDTH as i32, HEIGHT as i32);
+    dialog.seen = SeenTextStore::load(&seen_text_filename)?;
+    let script_path = match args.script.clone().or_else(|| settings.settings.script.clone()) {
+        Some(script) => PathBuf::from(script),
+        // without a scripts folder next to it the game runs on the scripts built into the executable
+        None if !Path::new(MAIN_SCRIPT).is_file() => embedded_scripts::unpack()?.join("main.luck"),
+        None => PathBuf::from(MAIN_SCRIPT)
+    };
+    let movement = make_reference(MovementRules::new());
+    let context = ScriptContext { graphics: graphics.clone(), mini_games: mini_games.clone(), scenes: make_reference(Scenes::new()), presence: presence.clone(), audio, input: make_reference(input), debug_views: make_reference(DebugViews::new()), saves: make_reference(SaveStore::new(&save_directory).with_compression(save_compression)), settings: make_reference(settings), preloader: make_reference(Preloader::new(manifests, vfs.clone())), time: make_reference(GameTime::new()), options: make_reference(OptionsMenu::new()), speech: make_reference(speech), dialog: make_reference(dialog), field: make_reference(Field::new(movement.clone())), movement, statuses: make_reference(StatusRegistry::with_defaults()), levels: make_reference(LevelHooks::new()), photo_mode: make_reference(PhotoMode::new()), encounters: make_reference(EncounterSystem::new(EncounterTables::new(), ENCOUNTER_SEED)), vfs };
+    let mut script = init_script(&context, &script_path)?;
+    // the folder of the script is watched, a bare file name is in the working folder
+    let script_directory = script_path.parent().filter(|directory| !directory.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let script_watcher = if args.hot_reload { Some(ScriptWatcher::new(script_directory)?) } else { None };
+    let hot_reload_reset = args.hot_reload_reset;
+    let frame_stats_filename = args.frame_stats.clone();
+    // every frame is kept for the csv only when one is written, the overlay needs the last one alone
+    let mut frame_stats = FrameStats::new(frame_stats_filename.is_some());
+
+    let mut replay = match &args.replay {
+        Some(filename) => Some(InputReplay::load(filename)?),
+        None => None
+    };
+
+    // --headless and --out always come together, exported frames are scaled
+    let export = match (&args.out, &args.export_frames) {
+        (Some(out), _) => Some((out, 1)),
+        (None, Some(out)) => Some((out, scale)),
+        (None, None) => None
+    };
+    if let Some((out, scale)) = export {
+        let export = FrameExport { frames: args.frames, out: Path::new(out), scale, replay };
+        let result = headless::run_headless(&context, &mut script, export, update_rate.delta());
+        print_resource_trace(&context.vfs);
+        return result;
+    }
+
+    let record_input_filename = args.record_input.clone();
+    if record_input_filename.is_some() {
+        context.input.borrow_mut().start_recording();
+    }
+
+    let mut gamepads = Gamepads::new(init_gamepad_mapping(&args)?);
 
     let event_loop = EventLoop::new();
     let window = {
-        let scale = args.scale;
-
         let size = LogicalSize::new(WIDTH * scale, HEIGHT * scale);
-        WindowBuilder::new()
+        let window = WindowBuilder::new()
             .with_title("Legend Clover")
             .with_inner_size(size)
-            .with_resizable(false)
-            .build(&event_loop).unwrap()
+            .with_min_inner_size(LogicalSize::new(WIDTH, HEIGHT))
+            .build(&event_loop).unwrap();
+
+        if fullscreen {
+            toggle_fullscreen(&window);
+        }
+        window
     };
 
+    let mut inspector = if args.inspector { Some(Inspector::new(&event_loop, scale)?) } else { None };
+
     let mut pixels = {
         let window_size = window.inner_size();
         let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
         Pixels::new(WIDTH, HEIGHT, surface_texture)?
     };
 
-    event_loop.run(move |event, _, control_flow| {
-        *control_flow = ControlFlow::Wait;
+    let mut fixed_step = FixedStep::new(update_rate);
+    let mut frame_timer = FrameTimer::new(Instant::now());
+    let mut modifiers = ModifiersState::empty();
+    let mut error_screen: Option<ErrorScreen> = None;
 
+    event_loop.run(move |event, _, control_flow| {
         match event {
             Event::WindowEvent {
                 event: WindowEvent::CloseRequested,
                 window_id,
             } if window_id == window.id() => *control_flow = ControlFlow::Exit,
-            _ => (),
-        }
+            // closing the inspector leaves the game running
+            Event::WindowEvent {
+                event: WindowEvent::CloseRequested,
+                window_id,
+            } if inspector.as_ref().map(|inspector| inspector.id() == window_id).unwrap_or(false) => inspector = None,
+            Event::WindowEvent {
+                event: WindowEvent::Resized(size),
+                window_id,
+            } if inspector.as_ref().map(|inspector| inspector.id() == window_id).unwrap_or(false) => {
+                if let Some(inspector) = inspector.as_mut() {
+                    inspector.resize(size.width, size.height);
+                }
+            },
+            Event::WindowEvent {
+                event: WindowEvent::Resized(size),
+                window_id,
+            } if window_id == window.id() => pixels.resize_surface(size.width, size.height),
+            Event::WindowEvent {
+                event: WindowEvent::ScaleFactorChanged { new_inner_size, .. },
+                window_id,
+            } if window_id == window.id() => pixels.resize_surface(new_inner_size.width, new_inner_size.height),
+            Event::WindowEvent {
+                event: WindowEvent::ModifiersChanged(state),
+                window_id,
+            } if window_id == window.id() => modifiers = state,
+            // alt+enter belongs to the window, the game never sees that enter
+            Event::WindowEvent {
+                event: WindowEvent::KeyboardInput { input: KeyboardInput { virtual_keycode: Some(VirtualKeyCode::Return), state: ElementState::Pressed, .. }, .. },
+                window_id,
+            } if window_id == window.id() && modifiers.alt() => toggle_fullscreen(&window),
+            // so is f3, which shows the debug overlay
+            Event::WindowEvent {
+                event: WindowEvent::KeyboardInput { input: KeyboardInput { virtual_keycode: Some(VirtualKeyCode::F3), state: ElementState::Pressed, .. }, .. },
+                window_id,
+            } if window_id == window.id() => {
+                let mut graphics = context.graphics.borrow_mut();
+                let enabled = graphics.is_debug_overlay();
+                graphics.set_debug_overlay(!enabled);
+            },
+            // f9, which enters photo mode from where the camera is and leaves it again
+            Event::WindowEvent {
+                event: WindowEvent::KeyboardInput { input: KeyboardInput { virtual_keycode: Some(VirtualKeyCode::F9), state: ElementState::Pressed, .. }, .. },
+                window_id,
+            } if window_id == window.id() && error_screen.is_none() => {
+                let mut photo_mode = context.photo_mode.borrow_mut();
+                if photo_mode.is_active() {
+                    photo_mode.leave();
+                } else {
+                    let (x, y) = context.graphics.borrow().camera().position();
+                    photo_mode.enter(x, y);
+                }
+            },
+            // tab, which changes the photo filter while in photo mode
+            Event::WindowEvent {
+                event: WindowEvent::KeyboardInput { input: KeyboardInput { virtual_keycode: Some(VirtualKeyCode::Tab), state: ElementState::Pressed, .. }, .. },
+                window_id,
+            } if window_id == window.id() && context.photo_mode.borrow().is_active() => {
+                let mut photo_mode = context.photo_mode.borrow_mut();
+                photo_mode.filter = photo_mode.filter.next();
+                println!("photo filter {}", photo_mode.filter.name());
+            },
+            // and f12, which takes a screenshot, in photo mode a photo with its filter and scale
+            Event::WindowEvent {
+                event: WindowEvent::KeyboardInput { input: KeyboardInput { virtual_keycode: Some(VirtualKeyCode::F12), state: ElementState::Pressed, .. }, .. },
+                window_id,
+            } if window_id == window.id() => {
+                let mut graphics = context.graphics.borrow_mut();
+                let photo_mode = context.photo_mode.borrow();
+                let saved = if photo_mode.is_active() {
+                    screenshots.next_path().and_then(|path| {
+                        photo_mode.save_photo(graphics.frame_buffer(), &path.to_string_lossy())?;
+                        Ok(path)
+                    })
+                } else {
+                    screenshots.take(&graphics)
+                };
 
-        if run_frame(&mut graphics, &mut state, &update_function, &render_function, &mut pixels).is_err() {
-            *control_flow = ControlFlow::Exit;
+                match saved {
+                    Ok(path) => {
+                        graphics.flash();
+                        println!("screenshot saved to {}", path.display());
+                    },
+                    Err(error) => eprintln!("can not save a screenshot to {}: {}", screenshots.directory().display(), error)
+                }
+            },
+            // the error screen stays until any other key
+            Event::WindowEvent {
+                event: WindowEvent::KeyboardInput { input: KeyboardInput { state: ElementState::Pressed, .. }, .. },
+                window_id,
+            } if window_id == window.id() && error_screen.is_some() => *control_flow = ControlFlow::Exit,
+            // held keys are tracked by name, keys without one are left out
+            Event::WindowEvent {
+                event: WindowEvent::KeyboardInput { input: KeyboardInput { virtual_keycode: Some(key), state, .. }, .. },
+                window_id,
+            } if window_id == window.id() => {
+                if let Some(name) = key_name(key) {
+                    match state {
+                        ElementState::Pressed => context.input.borrow_mut().press(name),
+                        ElementState::Released => context.input.borrow_mut().release(name)
+                    }
+                }
+            },
+            // in frame buffer pixels, outside of the game image it sticks to the nearest edge
+            Event::WindowEvent {
+                event: WindowEvent::CursorMoved { position, .. },
+                window_id,
+            } if window_id == window.id() => {
+                let (x, y) = match pixels.window_pos_to_pixel((position.x as f32, position.y as f32)) {
+                    Ok((x, y)) => (x as f64, y as f64),
+                    Err((x, y)) => (x as f64, y as f64)
+                };
+                context.input.borrow_mut().pointer.move_to(x, y);
+            },
+            Event::WindowEvent {
+                event: WindowEvent::MouseInput { button, state, .. },
+                window_id,
+            } if window_id == window.id() && error_screen.is_none() => {
+                if let Some(name) = mouse_button_name(button) {
+                    match state {
+                        ElementState::Pressed => context.input.borrow_mut().press(name),
+                        ElementState::Released => context.input.borrow_mut().release(name)
+                    }
+                }
+            },
+            // releases never arrive while another window has focus
+            Event::WindowEvent {
+                event: WindowEvent::Focused(false),
+                window_id,
+            } if window_id == window.id() => context.input.borrow_mut().release_all(),
+            Event::LoopDestroyed => {
+                print_resource_trace(&context.vfs);
+
+                if let (Some(filename), Some(recording)) = (&record_input_filename, context.input.borrow_mut().take_recording()) {
+                    if let Err(error) = recording.save(filename) {
+                        eprintln!("can not write the input recording to {}: {}", filename, error);
+                    }
+                }
+
+                if let Err(error) = context.dialog.borrow().seen.save(&seen_text_filename) {
+                    eprintln!("can not write the read messages to {}: {}", seen_text_filename, error);
+                }
+
+                if let Err(error) = presence_manager.clear() {
+                    eprintln!("can not clear presence: {}", error);
+                }
+
+                if let Some(filename) = &frame_stats_filename {
+                    let summary = frame_stats.summary();
+                    println!("{} frames, average {:.2}ms, worst {:.2}ms, {} dropped, {} script spikes",
+                        summary.frame_count,
+                        summary.average_interval.as_secs_f64() * 1000.0,
+                        summary.worst_interval.as_secs_f64() * 1000.0,
+                        summary.dropped_frames,
+                        summary.script_spikes);
+
+                    if let Err(error) = frame_stats.write_csv(filename) {
+                        eprintln!("can not write frame stats to {}: {}", filename, error);
+                    }
+                }
+                return;
+            },
+            // redraw at the display rate, presenting waits for vsync so this does not spin
+            Event::MainEventsCleared => {
+                if let Some(gamepads) = gamepads.as_mut() {
+                    gamepads.poll(&mut context.input.borrow_mut());
+                }
+
+                window.request_redraw();
+            },
+            Event::RedrawRequested(window_id) if window_id == window.id() => {
+                let delta = frame_timer.tick(Instant::now());
+
+                // a script that does not compile yet keeps the old one running
+                if script_watcher.as_ref().map(|script_watcher| script_watcher.changed()).unwrap_or(false) {
+                    match hot_reload::reload(&context, &mut script, hot_reload_reset) {
+                        Ok(reloaded) => {
+                            script = reloaded;
+                            // a fixed script picks up where the error stopped it
+                            error_screen = None;
+                            println!("scripts reloaded");
+                        },
+                        Err(error) => eprintln!("can not reload scripts: {}", error)
+                    }
+                }
+
+                if error_screen.is_some() {
+                    if let Err(error) = present(&context.graphics.borrow(), &mut pixels) {
+                        eprintln!("{}", error);
+                        *control_flow = ControlFlow::Exit;
+                    }
+                } else {
+                    let frame = FrameTiming { delta, update_delta: fixed_step.delta(), steps: fixed_step.advance(delta) };
+                    if let Err(error) = run_frame(&context, &mut script, &mut pixels, replay.as_mut(), frame, &mut frame_stats) {
+                        eprintln!("{}", error);
+                        let screen = ErrorScreen::new(&error);
+                        screen.draw(&mut context.graphics.borrow_mut());
+                        error_screen = Some(screen);
+                    }
+                }
+
+                if context.options.borrow_mut().take_display_change() {
+                    apply_display(&window, &context.settings.borrow().settings);
+                }
+
+                if let Some(inspector) = inspector.as_mut() {
+                    let game_font = context.graphics.borrow().shared_game_font();
+                    if let Err(error) = inspector.render(&context.debug_views.borrow(), game_font.as_deref()) {
+                        eprintln!("can not draw the inspector: {}", error);
+                    }
+                }
+
+                presence.borrow_mut().add_playtime(delta);
+                // a lost discord connection should not stop the game
+                if let Err(error) = presence_manager.update(&presence.borrow(), Instant::now()) {
+                    eprintln!("can not update presence: {}", error);
+                    presence_manager = PresenceManager::disabled();
+                }
+            },
+            _ => (),
         }
     });
 }
\ No newline at end of file
@@ -0,0 +1,56 @@
+use std::error::Error;
+use legend_engine::engine::assets::SpriteArchive;
+use legend_engine::engine::atlas::AtlasSet;
+use legend_engine::engine::graphics::{Image, Palette};
+use legend_engine::engine::movement::TILE_SIZE;
+use legend_engine::engine::vfs::Vfs;
+use legend_engine::engine::world_map::WorldMap;
+use crate::extract::first_palette;
+
+// a side of the png, a map bigger than this is more than any image viewer opens
+const MAX_IMAGE_SIDE: i32 = 32768;
+// the tile frames are small, a page holds a whole archive of them
+const ATLAS_PAGE_SIDE: u32 = 1024;
+
+// every tile value is a frame of the tile archive drawn on the movement grid, values without a frame stay transparent.
+// without an archive each tile is a square of the palette color of its low byte, enough to see the layout
+pub fn render_map(vfs: &Vfs, map_name: &str, tiles_name: Option<&str>, palette_name: Option<&str>) -> Result<Image, Box<dyn Error>> {
+    let map = WorldMap::load(vfs, map_name)?;
+    let (width, height) = (map.size.x * TILE_SIZE, map.size.y * TILE_SIZE);
+    if width > MAX_IMAGE_SIDE || height > MAX_IMAGE_SIDE {
+        return Err(format!("{} would be {}x{} pixels, more than {} a side", map_name, width, height, MAX_IMAGE_SIDE).into());
+    }
+
+    let palette = match palette_name {
+        Some(name) => Palette::load(vfs, name)?,
+        None => first_palette(vfs)?.ok_or_else(|| format!("no palette in {}, pass one with --palette", vfs.describe()))?.1
+    };
+    let tiles = match tiles_name {
+        Some(name) => Some(SpriteArchive::load(vfs, name)?),
+        None => None
+    };
+    // a tile is drawn hundreds of times, so its frame is unpacked into an atlas page once instead of on every blit
+    let atlas = tiles.as_ref().map(|tiles| {
+        let frames: Vec<(usize, _)> = tiles.frames().iter().enumerate().collect();
+        AtlasSet::pack(ATLAS_PAGE_SIDE, ATLAS_PAGE_SIDE, &frames)
+    });
+
+    let mut image = Image::new(width as u32, height as u32);
+    for y in 0..map.size.y {
+        for x in 0..map.size.x {
+            let tile = map.get_tile(x, y).unwrap_or(0);
+
+            match (&tiles, &atlas) {
+                (Some(tiles), Some(atlas)) => if !atlas.blit(&mut image, tile as usize, x * TILE_SIZE, y * TILE_SIZE, &palette) {
+                    // a frame bigger than a page is not in the atlas, empty ones draw nothing anyway
+                    if let Some(frame) = tiles.frame(tile as usize).filter(|frame| !frame.is_empty()) {
+                        image.blit(frame, x * TILE_SIZE, y * TILE_SIZE, &palette);
+                    }
+                },
+                _ => image.fill_rect(x * TILE_SIZE, y * TILE_SIZE, TILE_SIZE, TILE_SIZE, &palette.get_color(tile as u8))
+            }
+        }
+    }
+
+    Ok(image)
+}
@@ -0,0 +1,109 @@
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::process;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use clover::{Clover, State};
+use clover::helper::make_reference;
+use legend_engine::bindings::level::LevelHooks;
+use legend_engine::bindings::minigame::MiniGames;
+use legend_engine::bindings::scene::Scenes;
+use legend_engine::bindings::testing::{AssertEq, AssertImageMatches};
+use legend_engine::engine::accessibility::Announcer;
+use legend_engine::engine::audio::config::AudioConfig;
+use legend_engine::engine::audio::player::Audio;
+use legend_engine::engine::clock::GameTime;
+use legend_engine::engine::debug_view::DebugViews;
+use legend_engine::engine::dialog::DialogBox;
+use legend_engine::engine::encounter::{EncounterSystem, EncounterTables};
+use legend_engine::engine::field::Field;
+use legend_engine::engine::movement::MovementRules;
+use legend_engine::engine::photo_mode::PhotoMode;
+use legend_engine::engine::status::StatusRegistry;
+use legend_engine::engine::input::Input;
+use legend_engine::engine::manifest::{Manifests, Preloader};
+use legend_engine::engine::options_menu::OptionsMenu;
+use legend_engine::engine::presence::Presence;
+use legend_engine::engine::save::SaveStore;
+use legend_engine::engine::settings::{Settings, SettingsFile};
+use legend_engine::engine::vfs::Vfs;
+use crate::{init_engine, inject_models, load_game_font, ScriptContext, ENCOUNTER_SEED, HEIGHT, WIDTH};
+
+fn find_tests(directory: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut tests: Vec<PathBuf> = fs::read_dir(directory)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().map(|extension| extension == "luck").unwrap_or(false))
+        .collect();
+
+    tests.sort();
+    Ok(tests)
+}
+
+// every test file gets a fresh screen and script state, a failed assertion is a runtime error
+fn run_test(path: &Path, data_path: Option<&str>) -> Result<(), Box<dyn Error>> {
+    let vfs = Arc::new(Vfs::open(Path::new(data_path.unwrap_or(".")))?);
+    let mut graphics = init_engine()?;
+    if data_path.is_some() {
+        load_game_font(&mut graphics, &vfs);
+    }
+
+    let movement = make_reference(MovementRules::new());
+    let context = ScriptContext {
+        graphics: make_reference(graphics),
+        mini_games: make_reference(MiniGames::new()),
+        scenes: make_reference(Scenes::new()),
+        presence: make_reference(Presence::new()),
+        audio: make_reference(Audio::silent(&AudioConfig::default(), vfs.clone())),
+        input: make_reference(Input::new()),
+        debug_views: make_reference(DebugViews::new()),
+        // never the player's own saves
+        saves: make_reference(SaveStore::new(&env::temp_dir().join(format!("legend-clover-test-saves-{}", process::id())))),
+        settings: make_reference(SettingsFile::new(&env::temp_dir().join(format!("legend-clover-test-settings-{}.toml", process::id())), Settings::default())),
+        preloader: make_reference(Preloader::new(Manifests::new(), vfs.clone())),
+        time: make_reference(GameTime::new()),
+        options: make_reference(OptionsMenu::new()),
+        speech: make_reference(Announcer::disabled()),
+        dialog: make_reference(DialogBox::new(WIDTH as i32, HEIGHT as i32)),
+        field: make_reference(Field::new(movement.clone())),
+        movement,
+        statuses: make_reference(StatusRegistry::with_defaults()),
+        levels: make_reference(LevelHooks::new()),
+        photo_mode: make_reference(PhotoMode::new()),
+        encounters: make_reference(EncounterSystem::new(EncounterTables::new(), ENCOUNTER_SEED)),
+        vfs
+    };
+
+    let program = Clover::new().compile_file(&path.to_string_lossy())?;
+
+    let mut state: State = program.into();
+    inject_models(&mut state, &context);
+    state.add_native_model("assert_eq", make_reference(AssertEq));
+    state.add_native_model("assert_image_matches", make_reference(AssertImageMatches::new(context.graphics.clone())));
+
+    state.execute()?;
+    Ok(())
+}
+
+// prints one line per test, returns whether all of them passed
+pub fn run_tests(directory: &Path, data_path: Option<&str>) -> Result<bool, Box<dyn Error>> {
+    let tests = find_tests(directory)?;
+    let mut failed = 0;
+
+    for test in tests.iter() {
+        let name = test.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+
+        match run_test(test, data_path) {
+            Ok(()) => println!("test {} ... ok", name),
+            Err(error) => {
+                println!("test {} ... FAILED\n    {}", name, error);
+                failed += 1;
+            }
+        }
+    }
+
+    println!("\n{} passed, {} failed", tests.len() - failed, failed);
+
+    Ok(failed == 0)
+}
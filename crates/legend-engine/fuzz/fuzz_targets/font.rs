@@ -0,0 +1,18 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use legend_engine::engine::graphics::Font;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 4 {
+        return;
+    }
+
+    let character = u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    for (width, height) in [(8, 16), (16, 16), (16, 15)] {
+        if let Ok(mut font) = Font::from_bytes(data[4..].to_vec(), width, height) {
+            if let Some(glyph) = font.get_glyph(character).map(|glyph| glyph.to_vec()) {
+                font.set_glyph(character, &glyph);
+            }
+        }
+    }
+});
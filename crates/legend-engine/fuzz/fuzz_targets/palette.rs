@@ -0,0 +1,11 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use legend_engine::engine::graphics::Palette;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(mut palette) = Palette::from_bytes(data) {
+        if let (Some(&index), Some(&count)) = (data.first(), data.get(1)) {
+            palette.animate(index, count);
+        }
+    }
+});
@@ -0,0 +1,16 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use legend_engine::engine::graphics::{Image, Palette, RleImage};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(rle_image) = RleImage::from_bytes(data) {
+        let palette = Palette::empty();
+        rle_image.decode_indexed();
+        rle_image.decode(&palette);
+
+        // drawing partly outside the target exercises the clipping
+        let mut target = Image::new(64, 48);
+        target.blit(&rle_image, -7, 5, &palette);
+        target.blit(&rle_image, 60, 40, &palette);
+    }
+});
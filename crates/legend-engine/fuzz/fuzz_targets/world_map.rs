@@ -0,0 +1,12 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use legend_engine::engine::world_map::WorldMap;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(world_map) = WorldMap::from_bytes(data) {
+        for y in -1..=world_map.size.y {
+            world_map.get_tile(-1, y);
+            world_map.get_tile(world_map.size.x, y);
+        }
+    }
+});
@@ -0,0 +1,30 @@
+use clover::{NativeModelInstance, Object, Reference, State};
+use clover::debug::RuntimeError;
+use crate::bindings::script_object::{script_object, singleton_model, ScriptObject};
+use crate::engine::accessibility::Announcer;
+
+// Accessibility().announce(line) when dialogue is shown, select(name) when the cursor moves,
+// both do nothing unless the screen reader is turned on
+singleton_model!(AccessibilityModel, Announcer);
+
+impl ScriptObject for Announcer {
+    const NAME: &'static str = "Accessibility";
+    // scripts can skip work like describing a whole screen when nobody listens
+    const PROPERTIES: &'static [&'static str] = &["enabled"];
+    const METHODS: &'static [(&'static str, usize)] = &[("announce", 1), ("select", 1), ("stop", 0)];
+
+    fn get_property(&self, _key: &str) -> Result<Object, RuntimeError> {
+        Ok(Object::Boolean(self.is_enabled()))
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, _state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        match key {
+            "announce" => self.announce(&parameters[0].string_value()?),
+            "select" => self.select(&parameters[0].string_value()?),
+            _ => self.stop()
+        }
+        Ok(Object::Null)
+    }
+}
+
+script_object!(Announcer);
@@ -0,0 +1,90 @@
+use clover::{NativeModel, NativeModelInstance, Object, Reference, State};
+use clover::debug::{Position, RuntimeError};
+use clover::helper::{ensure_parameters_length, make_reference};
+use crate::bindings::color::{float_parameter, integer_parameter};
+use crate::bindings::script_object::{script_object, ScriptObject};
+use crate::engine::animation::{Animation, AnimationFrame, LoopMode};
+
+fn mode_from(object: &Object) -> Result<LoopMode, RuntimeError> {
+    let name = object.string_value()?;
+    LoopMode::from_name(name.as_str()).ok_or_else(|| RuntimeError::new(&format!("unknown loop mode {}, it is once, loop or ping_pong", name), Position::none()))
+}
+
+fn array_from(object: &Object, state: &State) -> Result<Vec<Object>, RuntimeError> {
+    match object {
+        Object::Array(values) => Ok(values.borrow().clone()),
+        _ => Err(RuntimeError::new("frames are an array", state.last_position()))
+    }
+}
+
+// Animation(frames, duration, mode) plays the frames, indices into the sprite's RleImage list, for duration seconds each.
+// duration is one number for every frame or an array with one per frame, mode is "loop" when left out
+pub struct AnimationModel;
+
+impl NativeModel for AnimationModel {
+    fn call(&mut self, state: &mut State, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        ensure_parameters_length(parameters, 2)?;
+
+        let indices = array_from(&parameters[0], state)?.iter()
+            .map(|index| Ok(index.integer_value()?.max(0) as usize))
+            .collect::<Result<Vec<usize>, RuntimeError>>()?;
+        let durations = match &parameters[1] {
+            Object::Array(_) => array_from(&parameters[1], state)?.iter().map(|duration| duration.float_value()).collect::<Result<Vec<f64>, RuntimeError>>()?,
+            _ => vec![ float_parameter(parameters, 1)?; indices.len() ]
+        };
+        if durations.len() != indices.len() {
+            return Err(RuntimeError::new(&format!("{} frames but {} durations", indices.len(), durations.len()), state.last_position()));
+        }
+
+        let mode = if parameters.len() > 2 { mode_from(&parameters[2])? } else { LoopMode::Loop };
+        let frames = indices.into_iter().zip(durations).map(|(index, duration)| AnimationFrame { index, duration }).collect();
+
+        Ok(Object::NativeInstance(make_reference(Animation::new(frames, mode))))
+    }
+}
+
+impl ScriptObject for Animation {
+    const NAME: &'static str = "Animation";
+    const PROPERTIES: &'static [&'static str] = &["frame", "position", "length", "mode", "playing", "finished"];
+    const METHODS: &'static [(&'static str, usize)] = &[("update", 1), ("play", 0), ("pause", 0), ("restart", 0), ("seek", 1)];
+
+    fn get_property(&self, key: &str) -> Result<Object, RuntimeError> {
+        Ok(match key {
+            // null for an animation without frames
+            "frame" => self.frame().map(|index| Object::Integer(index as i64)).unwrap_or(Object::Null),
+            "position" => Object::Integer(self.position() as i64),
+            "length" => Object::Integer(self.len() as i64),
+            "mode" => Object::String(make_reference(self.mode().name().to_string())),
+            "playing" => Object::Boolean(self.is_playing()),
+            _ => Object::Boolean(self.is_finished())
+        })
+    }
+
+    fn set_property(&mut self, key: &str, value: Object) -> Result<bool, RuntimeError> {
+        if key != "mode" {
+            return Ok(false);
+        }
+
+        self.set_mode(mode_from(&value)?);
+        Ok(true)
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, _state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        match key {
+            // update(delta) returns the events of the step, "loop" for each time round and "finish" when a once animation ends
+            "update" => {
+                self.update(float_parameter(parameters, 0)?);
+                let events = self.drain_events().iter().map(|event| Object::String(make_reference(event.name().to_string()))).collect();
+                return Ok(Object::Array(make_reference(events)));
+            },
+            "play" => self.play(),
+            "pause" => self.pause(),
+            "restart" => self.restart(),
+            // seek(position) shows that frame of the sequence from its start
+            _ => self.seek(integer_parameter(parameters, 0)?.max(0) as usize)
+        }
+        Ok(Object::Null)
+    }
+}
+
+script_object!(Animation);
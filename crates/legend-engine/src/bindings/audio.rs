@@ -0,0 +1,99 @@
+use clover::{NativeModelInstance, Object, Reference, State};
+use clover::debug::RuntimeError;
+use clover::helper::make_reference;
+use crate::bindings::script_object::{script_object, singleton_model, ScriptObject};
+use crate::engine::audio::environment::Environment;
+use crate::engine::audio::mixer::AudioChannel;
+use crate::engine::audio::player::Audio;
+use crate::engine::audio::sound::PlayOptions;
+
+// Audio() hands out the engine audio, there is only one output
+singleton_model!(AudioModel, Audio);
+
+fn channel_from(object: &Object, state: &State) -> Result<AudioChannel, RuntimeError> {
+    let name = object.string_value()?;
+    AudioChannel::from_name(name.as_str()).ok_or_else(|| RuntimeError::new(&format!("unknown audio channel {}", name), state.last_position()))
+}
+
+fn float_or(parameters: &[Object], index: usize, default: f64) -> Result<f64, RuntimeError> {
+    match parameters.get(index) {
+        Some(Object::Integer(value)) => Ok(*value as f64),
+        Some(value) => value.float_value(),
+        None => Ok(default)
+    }
+}
+
+impl ScriptObject for Audio {
+    const NAME: &'static str = "Audio";
+    const PROPERTIES: &'static [&'static str] = &["enabled", "music", "environment"];
+    const METHODS: &'static [(&'static str, usize)] = &[
+        ("play_sound", 1), ("stop_sounds", 0), ("play_music", 1), ("stop_music", 0),
+        ("set_volume", 2), ("get_volume", 1), ("set_environment", 1), ("music_events", 0)
+    ];
+
+    fn get_property(&self, key: &str) -> Result<Object, RuntimeError> {
+        Ok(match key {
+            "enabled" => Object::Boolean(self.is_enabled()),
+            "music" => self.music().map(|music| Object::String(make_reference(music.to_string()))).unwrap_or(Object::Null),
+            _ => Object::String(make_reference(self.environment().name().to_string()))
+        })
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        match key {
+            // play_sound(name, volume, pitch, pitch_variance)
+            "play_sound" => {
+                let name = parameters[0].string_value()?;
+                let options = PlayOptions {
+                    volume: float_or(parameters, 1, 1.0)? as f32,
+                    pitch: float_or(parameters, 2, 1.0)? as f32,
+                    pitch_variance: float_or(parameters, 3, 0.0)? as f32
+                };
+
+                self.play_sound(name.as_str(), options).map_err(|error| RuntimeError::new(&error.to_string(), state.last_position()))?;
+                Ok(Object::Null)
+            },
+            "stop_sounds" => {
+                self.stop_sounds();
+                Ok(Object::Null)
+            },
+            // play_music(name, loop)
+            "play_music" => {
+                let name = parameters[0].string_value()?;
+                let looping = match parameters.get(1) {
+                    Some(Object::Boolean(looping)) => *looping,
+                    _ => true
+                };
+
+                self.play_music(name.as_str(), looping).map_err(|error| RuntimeError::new(&error.to_string(), state.last_position()))?;
+                Ok(Object::Null)
+            },
+            "stop_music" => {
+                self.stop_music();
+                Ok(Object::Null)
+            },
+            "set_volume" => {
+                let channel = channel_from(&parameters[0], state)?;
+                self.set_volume(channel, float_or(parameters, 1, 1.0)? as f32);
+                Ok(Object::Null)
+            },
+            "get_volume" => {
+                let channel = channel_from(&parameters[0], state)?;
+                Ok(Object::Float(self.get_volume(channel) as f64))
+            },
+            "set_environment" => {
+                let name = parameters[0].string_value()?;
+                let environment = Environment::from_name(name.as_str()).ok_or_else(|| RuntimeError::new(&format!("unknown environment {}", name), state.last_position()))?;
+                self.set_environment(environment);
+                Ok(Object::Null)
+            },
+            // names of beats, bars, loop points and track ends since the last call
+            _ => {
+                let events = self.drain_music_events().iter().map(|event| Object::String(make_reference(event.name().to_string()))).collect();
+                Ok(Object::Array(make_reference(events)))
+            }
+        }
+    }
+}
+
+script_object!(Audio);
@@ -0,0 +1,98 @@
+use std::fs;
+use std::path::Path;
+use clover::{NativeModel, NativeModelInstance, Object, Reference, State};
+use clover::debug::RuntimeError;
+use clover::helper::make_reference;
+use crate::bindings::script_object::{script_object, ScriptObject};
+use crate::engine::bestiary::Bestiary;
+use crate::engine::save::SaveStore;
+
+// a file of the save directory, next to the slots. only the file name counts, so none ends up outside of it
+fn bestiary_path(directory: &Path, filename: &str, state: &State) -> Result<String, RuntimeError> {
+    let file_name = Path::new(filename).file_name().ok_or_else(|| RuntimeError::new(&format!("{} is not a file name", filename), state.last_position()))?;
+    Ok(directory.join(file_name).to_string_lossy().to_string())
+}
+
+// Bestiary(filename) loads what was recorded before from the save directory, Bestiary() starts empty
+pub struct BestiaryModel {
+    saves: Reference<SaveStore>
+}
+
+impl BestiaryModel {
+    pub fn new(saves: Reference<SaveStore>) -> Self {
+        BestiaryModel { saves }
+    }
+}
+
+impl NativeModel for BestiaryModel {
+    fn call(&mut self, state: &mut State, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        let saves = self.saves.clone();
+        let bestiary = if !parameters.is_empty() {
+            let filename = bestiary_path(saves.borrow().directory(), &parameters[0].string_value()?, state)?;
+            Bestiary::load(&filename).map_err(|error| RuntimeError::new(&error.to_string(), state.last_position()))?
+        } else {
+            Bestiary::new()
+        };
+
+        Ok(Object::NativeInstance(make_reference(ScriptBestiary { bestiary, saves })))
+    }
+}
+
+pub struct ScriptBestiary {
+    bestiary: Bestiary,
+    saves: Reference<SaveStore>
+}
+
+impl ScriptObject for ScriptBestiary {
+    const NAME: &'static str = "Bestiary";
+    const PROPERTIES: &'static [&'static str] = &["enemies", "items", "locations"];
+    const METHODS: &'static [(&'static str, usize)] = &[
+        ("encounter_enemy", 1), ("defeat_enemy", 1), ("see_item", 1), ("visit_location", 1),
+        ("encountered_count", 1), ("defeated_count", 1), ("has_seen_item", 1), ("has_visited", 1), ("save", 1)
+    ];
+
+    fn get_property(&self, key: &str) -> Result<Object, RuntimeError> {
+        let bestiary = &self.bestiary;
+        Ok(match key {
+            "enemies" => Object::Array(make_reference(bestiary.enemies().iter().map(|record| Object::Integer(record.enemy as i64)).collect())),
+            "items" => Object::Array(make_reference(bestiary.items().map(|item| Object::Integer(item as i64)).collect())),
+            _ => Object::Array(make_reference(bestiary.locations().map(|location| Object::String(make_reference(location.to_string()))).collect()))
+        })
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        let bestiary = &mut self.bestiary;
+        match key {
+            "encounter_enemy" => {
+                bestiary.encounter_enemy(parameters[0].integer_value()? as u32);
+                Ok(Object::Null)
+            },
+            "defeat_enemy" => {
+                bestiary.defeat_enemy(parameters[0].integer_value()? as u32);
+                Ok(Object::Null)
+            },
+            "see_item" => Ok(Object::Boolean(bestiary.see_item(parameters[0].integer_value()? as u32))),
+            "visit_location" => Ok(Object::Boolean(bestiary.visit_location(&parameters[0].string_value()?))),
+            "encountered_count" => {
+                let enemy = parameters[0].integer_value()? as u32;
+                Ok(Object::Integer(bestiary.enemy(enemy).map(|record| record.encountered).unwrap_or(0) as i64))
+            },
+            "defeated_count" => {
+                let enemy = parameters[0].integer_value()? as u32;
+                Ok(Object::Integer(bestiary.enemy(enemy).map(|record| record.defeated).unwrap_or(0) as i64))
+            },
+            "has_seen_item" => Ok(Object::Boolean(bestiary.has_seen_item(parameters[0].integer_value()? as u32))),
+            "has_visited" => Ok(Object::Boolean(bestiary.has_visited(&parameters[0].string_value()?))),
+            // save(filename) into the save directory, made when missing
+            _ => {
+                let saves = self.saves.borrow();
+                let filename = bestiary_path(saves.directory(), &parameters[0].string_value()?, state)?;
+                fs::create_dir_all(saves.directory()).map_err(|error| RuntimeError::new(&error.to_string(), state.last_position()))?;
+                self.bestiary.save(&filename).map_err(|error| RuntimeError::new(&error.to_string(), state.last_position()))?;
+                Ok(Object::Null)
+            }
+        }
+    }
+}
+
+script_object!(ScriptBestiary);
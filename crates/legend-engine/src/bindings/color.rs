@@ -1,7 +1,14 @@
 use clover::{NativeModel, NativeModelInstance, Object, Reference, State};
 use clover::debug::{Position, RuntimeError};
 use clover::helper::{ensure_parameters_length, make_reference};
-use crate::engine::graphics::{Color, Graphics};
+use crate::engine::camera::DEFAULT_FOLLOW_SPEED;
+use crate::engine::graphics::{BlendMode, Color, GradientDirection, Graphics, TextAlign, TextDirection};
+use crate::bindings::image::{image_from, ScriptImage};
+use crate::bindings::palette::{index_parameter, palette_from};
+use crate::bindings::script_object::{script_object, singleton_model, ScriptObject};
+use crate::bindings::sprite::sprite_from;
+use crate::engine::panel::PanelStyle;
+use crate::engine::text::encode_big5;
 
 impl NativeModel for Color {
     fn call(&mut self, _state: &mut State, parameters: &[Object]) -> Result<Object, RuntimeError> {
@@ -24,55 +31,36 @@ impl From<Reference<dyn NativeModelInstance>> for Color {
     }
 }
 
-impl NativeModelInstance for Color {
-    fn index_get(&self, this: Reference<dyn NativeModelInstance>, index: &Object) -> Result<Object, RuntimeError> {
-        self.instance_get(this, index.string_value()?.as_str())
-    }
+impl ScriptObject for Color {
+    const NAME: &'static str = "Color";
+    const PROPERTIES: &'static [&'static str] = &["r", "g", "b", "a"];
+    const METHODS: &'static [(&'static str, usize)] = &[("blend", 1), ("alpha_blend", 2)];
 
-    fn index_set(&mut self, this: Reference<dyn NativeModelInstance>, index: &Object, value: Object) -> Result<(), RuntimeError> {
-        self.instance_set(this, index.string_value()?.as_str(), value)
+    fn get_property(&self, key: &str) -> Result<Object, RuntimeError> {
+        Ok(Object::Integer(self.raw_integer(key).unwrap_or(0)))
     }
 
-    fn instance_get(&self, this: Reference<dyn NativeModelInstance>, key: &str) -> Result<Object, RuntimeError> {
+    fn set_property(&mut self, key: &str, value: Object) -> Result<bool, RuntimeError> {
+        let value = value.integer_value()? as u8;
         match key {
-            "r" => Ok(Object::Integer(self.r as i64)),
-            "g" => Ok(Object::Integer(self.g as i64)),
-            "b" => Ok(Object::Integer(self.b as i64)),
-            "a" => Ok(Object::Integer(self.a as i64)),
-            "blend" | "alpha_blend" => Ok(Object::InstanceNativeFunction(this, key.to_string())),
-            _ => Err(RuntimeError::new("index not exists", Position::none()))
+            "r" => self.r = value,
+            "g" => self.g = value,
+            "b" => self.b = value,
+            _ => self.a = value
         }
+        Ok(true)
     }
 
-    fn instance_set(&mut self, _this: Reference<dyn NativeModelInstance>, key: &str, value: Object) -> Result<(), RuntimeError> {
-        match key {
-            "r" => self.r = value.integer_value()? as u8,
-            "g" => self.g = value.integer_value()? as u8,
-            "b" => self.b = value.integer_value()? as u8,
-            "a" => self.a = value.integer_value()? as u8,
-            _ => return Err(RuntimeError::new(&format!("can not set {}", key), Position::none()))
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, _state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        let color = color_parameter(parameters, 0)?;
+        let blended = match key {
+            "blend" => self.blend(&color),
+            _ => self.alpha_blend(&color, float_parameter(parameters, 1)?)
         };
-        Ok(())
-    }
-
-    fn call(&mut self, this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
-        match key {
-            "blend" => {
-                ensure_parameters_length(parameters, 1)?;
-                let color: Color = Color::from(parameters[0].native_instance_value()?);
-                Ok(Object::NativeInstance(make_reference(self.blend(&color))))
-            },
-            "alpha_blend" => {
-                ensure_parameters_length(parameters, 1)?;
-                let color: Color = Color::from(parameters[0].native_instance_value()?);
-                let alpha = parameters[1].float_value()?;
-                Ok(Object::NativeInstance(make_reference(self.alpha_blend(&color,alpha))))
-            }
-            _ => Err(RuntimeError::new(&format!("can not call {}", key), state.last_position()))
-        }
+        Ok(Object::NativeInstance(make_reference(blended)))
     }
 
-    fn raw_get_integer(&self, key: &str) -> Option<i64> {
+    fn raw_integer(&self, key: &str) -> Option<i64> {
         match key {
             "r" => Some(self.r as i64),
             "g" => Some(self.g as i64),
@@ -83,25 +71,284 @@ impl NativeModelInstance for Color {
     }
 }
 
+script_object!(Color);
+
+// Graphics() hands out the screen the engine presents
+singleton_model!(GraphicsModel, Graphics);
 
-impl NativeModelInstance for Graphics {
-    fn index_get(&self, this: Reference<dyn NativeModelInstance>, index: &Object) -> Result<Object, RuntimeError> {
-        todo!()
+pub(crate) fn color_parameter(parameters: &[Object], index: usize) -> Result<Color, RuntimeError> {
+    Ok(Color::from(parameters[index].native_instance_value()?))
+}
+
+pub(crate) fn integer_parameter(parameters: &[Object], index: usize) -> Result<i32, RuntimeError> {
+    Ok(parameters[index].integer_value()? as i32)
+}
+
+pub(crate) fn float_parameter(parameters: &[Object], index: usize) -> Result<f64, RuntimeError> {
+    match &parameters[index] {
+        Object::Integer(value) => Ok(*value as f64),
+        value => value.float_value()
     }
+}
 
-    fn index_set(&mut self, this: Reference<dyn NativeModelInstance>, index: &Object, value: Object) -> Result<(), RuntimeError> {
-        todo!()
+pub(crate) fn name_parameter<T>(parameters: &[Object], index: usize, default: T, from_name: fn(&str) -> Option<T>, state: &State) -> Result<T, RuntimeError> {
+    match parameters.get(index) {
+        Some(Object::Null) | None => Ok(default),
+        Some(value) => {
+            let name = value.string_value()?;
+            from_name(name.as_str()).ok_or_else(|| RuntimeError::new(&format!("unknown option {}", name), state.last_position()))
+        }
     }
+}
 
-    fn instance_get(&self, this: Reference<dyn NativeModelInstance>, key: &str) -> Result<Object, RuntimeError> {
-        todo!()
+pub(crate) fn text_parameter(parameters: &[Object], index: usize) -> Result<Vec<usize>, RuntimeError> {
+    Ok(encode_big5(parameters[index].string_value()?.as_str()))
+}
+
+impl ScriptObject for Graphics {
+    const NAME: &'static str = "Graphics";
+    const PROPERTIES: &'static [&'static str] = &["width", "height", "blend_mode", "has_font", "camera", "camera_x", "camera_y"];
+    const METHODS: &'static [(&'static str, usize)] = &[
+        ("clear", 0), ("set_pixel", 3), ("get_pixel", 2), ("fill_rect", 5), ("draw_rect", 5), ("fill_gradient", 6), ("fill_palette_ramp", 7), ("draw_panel", 4),
+        ("draw_text", 4), ("draw_text_center", 6), ("text_width", 1), ("text_height", 1),
+        ("blit", 3), ("draw_sprite", 5), ("draw_icons", 5), ("capture", 4), ("mosaic", 1), ("set_wave", 3), ("clear_wave", 0),
+        ("effect_buffer", 3), ("persist_effect_buffer", 1), ("free_effect_buffer", 1), ("end_scene", 0),
+        ("set_camera", 2), ("center_camera", 2), ("follow_camera", 3), ("clear_camera", 0), ("set_camera_bounds", 0),
+        ("blend_palette", 3), ("stop_palette_blend", 1), ("palette_blending", 1)
+    ];
+
+    fn get_property(&self, key: &str) -> Result<Object, RuntimeError> {
+        Ok(match key {
+            "width" => Object::Integer(self.get_width() as i64),
+            "height" => Object::Integer(self.get_height() as i64),
+            "blend_mode" => Object::String(make_reference(self.get_blend_mode().name().to_string())),
+            "has_font" => Object::Boolean(self.get_game_font().is_some()),
+            "camera" => Object::Boolean(self.is_camera_enabled()),
+            "camera_x" => Object::Integer(self.camera().position().0 as i64),
+            _ => Object::Integer(self.camera().position().1 as i64)
+        })
     }
 
-    fn instance_set(&mut self, this: Reference<dyn NativeModelInstance>, key: &str, value: Object) -> Result<(), RuntimeError> {
-        todo!()
+    fn set_property(&mut self, key: &str, value: Object) -> Result<bool, RuntimeError> {
+        match key {
+            "blend_mode" => {
+                let name = value.string_value()?;
+                let blend_mode = BlendMode::from_name(name.as_str()).ok_or_else(|| RuntimeError::new(&format!("unknown blend mode {}", name), Position::none()))?;
+                self.set_blend_mode(blend_mode);
+            },
+            // false draws the ui over the world in screen coordinates
+            "camera" => match value {
+                Object::Boolean(enabled) => self.set_camera_enabled(enabled),
+                _ => return Err(RuntimeError::new("camera is true or false", Position::none()))
+            },
+            _ => return Ok(false)
+        }
+        Ok(true)
     }
 
-    fn call(&mut self, this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
-        todo!()
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        match key {
+            // clear(color), transparent black without a color
+            "clear" => {
+                let color = if parameters.len() > 0 { color_parameter(parameters, 0)? } else { Color::new(0, 0, 0, 0) };
+                self.clear(&color);
+                Ok(Object::Null)
+            },
+            "set_pixel" => {
+                self.set_pixel(integer_parameter(parameters, 0)?, integer_parameter(parameters, 1)?, &color_parameter(parameters, 2)?);
+                Ok(Object::Null)
+            },
+            "get_pixel" => {
+                Ok(match self.get_pixel(integer_parameter(parameters, 0)?, integer_parameter(parameters, 1)?) {
+                    Some(color) => Object::NativeInstance(make_reference(color)),
+                    None => Object::Null
+                })
+            },
+            // fill_rect(x, y, width, height, color, blend_mode) and draw_rect without the blend mode,
+            // "alpha" or "dither" draws this one rect that way, leaving it out uses Graphics.blend_mode
+            "fill_rect" | "draw_rect" => {
+                let (x, y) = (integer_parameter(parameters, 0)?, integer_parameter(parameters, 1)?);
+                let (width, height) = (integer_parameter(parameters, 2)?, integer_parameter(parameters, 3)?);
+                let color = color_parameter(parameters, 4)?;
+
+                if key == "fill_rect" {
+                    let mode = name_parameter(parameters, 5, self.get_blend_mode(), BlendMode::from_name, state)?;
+                    self.fill_rect_with_mode(x, y, width, height, &color, mode);
+                } else {
+                    self.draw_rect(x, y, width, height, &color);
+                }
+                Ok(Object::Null)
+            },
+            // draw_panel(x, y, width, height, backdrop, opacity, blur, dim, border), null or missing keeps the default
+            "draw_panel" => {
+                let mut style = PanelStyle::default();
+
+                let is_set = |index: usize| !matches!(parameters.get(index), None | Some(Object::Null));
+                if is_set(4) { style.backdrop = color_parameter(parameters, 4)?; }
+                if is_set(5) { style.opacity = float_parameter(parameters, 5)?; }
+                if is_set(6) { style.blur = integer_parameter(parameters, 6)?.max(0) as u32; }
+                if is_set(7) { style.dim = float_parameter(parameters, 7)?; }
+                if is_set(8) { style.border = Some(color_parameter(parameters, 8)?); }
+
+                self.draw_panel(integer_parameter(parameters, 0)?, integer_parameter(parameters, 1)?, integer_parameter(parameters, 2)?, integer_parameter(parameters, 3)?, &style);
+                Ok(Object::Null)
+            },
+            // fill_gradient(x, y, width, height, from, to, "vertical" or "horizontal")
+            "fill_gradient" => {
+                let direction = name_parameter(parameters, 6, GradientDirection::Vertical, GradientDirection::from_name, state)?;
+                self.fill_gradient_rect(
+                    integer_parameter(parameters, 0)?, integer_parameter(parameters, 1)?,
+                    integer_parameter(parameters, 2)?, integer_parameter(parameters, 3)?,
+                    &color_parameter(parameters, 4)?, &color_parameter(parameters, 5)?, direction);
+                Ok(Object::Null)
+            },
+            // fill_palette_ramp(x, y, width, height, palette, start, end, "vertical" or "horizontal") steps through
+            // the palette colors from start to end in even bands
+            "fill_palette_ramp" => {
+                let direction = name_parameter(parameters, 7, GradientDirection::Vertical, GradientDirection::from_name, state)?;
+                let palette = palette_from(&parameters[4])?;
+                self.fill_palette_ramp_rect(
+                    integer_parameter(parameters, 0)?, integer_parameter(parameters, 1)?,
+                    integer_parameter(parameters, 2)?, integer_parameter(parameters, 3)?,
+                    &palette.borrow(), index_parameter(parameters, 5, state)?, index_parameter(parameters, 6, state)?, direction);
+                Ok(Object::Null)
+            },
+            // draw_text(text, x, y, color, width, align, direction), the last three are optional
+            "draw_text" => {
+                let width = if parameters.len() > 4 { integer_parameter(parameters, 4)? } else { 0 };
+                let align = name_parameter(parameters, 5, TextAlign::Left, TextAlign::from_name, state)?;
+                let direction = name_parameter(parameters, 6, TextDirection::Horizontal, TextDirection::from_name, state)?;
+
+                let drawn = self.draw_text(&text_parameter(parameters, 0)?, integer_parameter(parameters, 1)?, integer_parameter(parameters, 2)?, width, align, direction, &color_parameter(parameters, 3)?);
+                Ok(Object::Boolean(drawn))
+            },
+            // draw_text_center(text, x, y, width, height, color)
+            "draw_text_center" => {
+                let drawn = self.draw_text_center(&text_parameter(parameters, 0)?,
+                    integer_parameter(parameters, 1)?, integer_parameter(parameters, 2)?,
+                    integer_parameter(parameters, 3)?, integer_parameter(parameters, 4)?, &color_parameter(parameters, 5)?);
+                Ok(Object::Boolean(drawn))
+            },
+            "text_width" | "text_height" => {
+                let (width, height) = self.text_size(&text_parameter(parameters, 0)?).unwrap_or((0, 0));
+                Ok(Object::Integer(if key == "text_width" { width } else { height } as i64))
+            },
+            // blit(image, x, y, alpha, blend_mode), the blend mode is Graphics.blend_mode when left out
+            "blit" => {
+                let source = image_from(&parameters[0])?;
+                let alpha = if parameters.len() > 3 { float_parameter(parameters, 3)? } else { 1.0 };
+                let mode = name_parameter(parameters, 4, self.get_blend_mode(), BlendMode::from_name, state)?;
+                self.draw_image_with_mode(&source.borrow(), integer_parameter(parameters, 1)?, integer_parameter(parameters, 2)?, alpha, mode);
+                Ok(Object::Null)
+            },
+            // draw_sprite(sprite, frame, x, y, palette) at the offset of the frame, false for a frame the sprite does not have.
+            // a frame is decoded once and drawn from the cache until the palette changes
+            "draw_sprite" => {
+                let (archive, palette) = (sprite_from(&parameters[0])?, palette_from(&parameters[4])?);
+                let drawn = match usize::try_from(parameters[1].integer_value()?) {
+                    Ok(index) => self.draw_sprite(&archive.borrow(), index, integer_parameter(parameters, 2)?, integer_parameter(parameters, 3)?, &palette.borrow()),
+                    Err(_) => false
+                };
+                Ok(Object::Boolean(drawn))
+            },
+            // draw_icons(sprite, [frames], x, y, palette) in a row one pixel apart, like status icons next to a hp bar,
+            // the width they took
+            "draw_icons" => {
+                let (archive, palette) = (sprite_from(&parameters[0])?, palette_from(&parameters[4])?);
+                let frames = match &parameters[1] {
+                    Object::Array(frames) => frames.borrow().iter().map(|frame| Ok(frame.integer_value()?.max(0) as usize)).collect::<Result<Vec<usize>, RuntimeError>>()?,
+                    _ => return Err(RuntimeError::new("draw_icons takes an array of frames", state.last_position()))
+                };
+                let width = self.draw_icons(&archive.borrow(), &frames, integer_parameter(parameters, 2)?, integer_parameter(parameters, 3)?, 1, &palette.borrow());
+                Ok(Object::Integer(width as i64))
+            },
+            // capture(x, y, width, height) copies part of the screen into a new Image
+            "capture" => {
+                let image = self.capture(
+                    integer_parameter(parameters, 0)?, integer_parameter(parameters, 1)?,
+                    integer_parameter(parameters, 2)?.max(0) as u32, integer_parameter(parameters, 3)?.max(0) as u32);
+                Ok(Object::NativeInstance(make_reference(ScriptImage::new(image, self.shared_game_font()))))
+            },
+            // effect_buffer(name, width, height) is the same image every call until freed or the scene ends
+            "effect_buffer" => {
+                let image = self.effect_buffer(&parameters[0].string_value()?, integer_parameter(parameters, 1)?.max(0) as u32, integer_parameter(parameters, 2)?.max(0) as u32);
+                Ok(Object::NativeInstance(make_reference(ScriptImage::shared(image, self.shared_game_font()))))
+            },
+            "persist_effect_buffer" => {
+                Ok(Object::Boolean(self.persist_effect_buffer(&parameters[0].string_value()?)))
+            },
+            "free_effect_buffer" => {
+                Ok(Object::Boolean(self.free_effect_buffer(&parameters[0].string_value()?)))
+            },
+            "end_scene" => {
+                self.end_scene();
+                Ok(Object::Null)
+            },
+            // blend_palette(palette, target, seconds) moves the colors of palette to those of target over game time,
+            // a Palette(Color(255, 255, 255)) target flashes to white and blending back fades it out again
+            "blend_palette" => {
+                let target = palette_from(&parameters[1])?.borrow().clone();
+                self.blend_palette(palette_from(&parameters[0])?, target, float_parameter(parameters, 2)?);
+                Ok(Object::Null)
+            },
+            // stop_palette_blend(palette) leaves the colors where the blend got to
+            "stop_palette_blend" => {
+                Ok(Object::Boolean(self.stop_palette_blend(&palette_from(&parameters[0])?)))
+            },
+            "palette_blending" => {
+                Ok(Object::Boolean(self.is_palette_blending(&palette_from(&parameters[0])?)))
+            },
+            // set_camera(x, y) moves the top left of the screen there, every draw after it is in world coordinates
+            // until clear_camera() or the end of the frame. center_camera(x, y) puts x and y in the middle instead
+            "set_camera" | "center_camera" => {
+                let (x, y) = (float_parameter(parameters, 0)?, float_parameter(parameters, 1)?);
+                if key == "set_camera" {
+                    self.camera_mut().set_position(x, y);
+                } else {
+                    self.camera_mut().center_on(x, y);
+                }
+                self.set_camera_enabled(true);
+                Ok(Object::Null)
+            },
+            // follow_camera(x, y, delta, speed) eases toward centering on x and y, speed is optional
+            "follow_camera" => {
+                let speed = if parameters.len() > 3 { float_parameter(parameters, 3)? } else { DEFAULT_FOLLOW_SPEED };
+                self.camera_mut().follow(float_parameter(parameters, 0)?, float_parameter(parameters, 1)?, speed, float_parameter(parameters, 2)?);
+                self.set_camera_enabled(true);
+                Ok(Object::Null)
+            },
+            "clear_camera" => {
+                self.set_camera_enabled(false);
+                Ok(Object::Null)
+            },
+            // set_camera_bounds(map_width, map_height) keeps the camera inside the map, set_camera_bounds() lets it go anywhere
+            "set_camera_bounds" => {
+                let bounds = match parameters {
+                    [] | [Object::Null, ..] => None,
+                    _ => {
+                        ensure_parameters_length(parameters, 2)?;
+                        Some((integer_parameter(parameters, 0)?, integer_parameter(parameters, 1)?))
+                    }
+                };
+                self.camera_mut().set_bounds(bounds);
+                Ok(Object::Null)
+            },
+            "mosaic" => {
+                self.mosaic(parameters[0].integer_value()?.max(0) as u32);
+                Ok(Object::Null)
+            },
+            // set_wave(amplitude, frequency, phase)
+            "set_wave" => {
+                self.set_wave(float_parameter(parameters, 0)?, float_parameter(parameters, 1)?, float_parameter(parameters, 2)?);
+                Ok(Object::Null)
+            },
+            // clear_wave()
+            _ => {
+                self.clear_wave();
+                Ok(Object::Null)
+            }
+        }
     }
-}
\ No newline at end of file
+}
+
+script_object!(Graphics);
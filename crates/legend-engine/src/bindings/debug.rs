@@ -0,0 +1,62 @@
+use clover::{NativeModelInstance, Object, Reference, State};
+use clover::debug::RuntimeError;
+use crate::bindings::script_object::{script_object, singleton_model, ScriptObject};
+use crate::bindings::testing::describe;
+use crate::engine::debug_view::DebugViews;
+use crate::engine::graphics::Color;
+
+// Debug() hands out what the inspector window shows
+singleton_model!(DebugModel, DebugViews);
+
+// plain text for strings, everything else like assert_eq prints it
+fn display(object: &Object) -> String {
+    match object {
+        Object::String(value) => value.borrow().clone(),
+        _ => describe(object)
+    }
+}
+
+impl ScriptObject for DebugViews {
+    const NAME: &'static str = "Debug";
+    const PROPERTIES: &'static [&'static str] = &[];
+    const METHODS: &'static [(&'static str, usize)] = &[("inspect", 3), ("forget", 1), ("log", 0), ("show_palette", 1)];
+
+    fn get_property(&self, _key: &str) -> Result<Object, RuntimeError> {
+        Ok(Object::Null)
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        match key {
+            // inspect("hero", "x", hero.x) shows the field under the hero section until forget("hero")
+            "inspect" => {
+                self.inspect(parameters[0].string_value()?.as_str(), parameters[1].string_value()?.as_str(), display(&parameters[2]));
+                Ok(Object::Null)
+            },
+            "forget" => {
+                self.forget(parameters[0].string_value()?.as_str());
+                Ok(Object::Null)
+            },
+            // log(value, ...) joins the values with spaces like print
+            "log" => {
+                self.log(parameters.iter().map(display).collect::<Vec<String>>().join(" "));
+                Ok(Object::Null)
+            },
+            // show_palette(palette) shows the colors of a Palette in the inspector
+            _ => {
+                let palette = parameters[0].native_instance_value()?;
+                let palette = palette.try_borrow().map_err(|_| RuntimeError::new("palette is in use", state.last_position()))?;
+
+                let colors = (0..256).map(|index| {
+                    let packed = palette.raw_get_integer(&format!("color_{}", index))
+                        .ok_or_else(|| RuntimeError::new("not a palette", state.last_position()))?;
+                    Ok(Color::new((packed >> 16) as u8, (packed >> 8) as u8, packed as u8, 255))
+                }).collect::<Result<Vec<Color>, RuntimeError>>()?;
+
+                self.set_palette(colors);
+                Ok(Object::Null)
+            }
+        }
+    }
+}
+
+script_object!(DebugViews);
@@ -0,0 +1,135 @@
+use std::sync::Arc;
+use clover::{NativeModelInstance, Object, Reference, State};
+use clover::debug::RuntimeError;
+use clover::helper::make_reference;
+use crate::bindings::color::{float_parameter, integer_parameter, name_parameter, text_parameter};
+use crate::bindings::palette::palette_from;
+use crate::bindings::script_object::{script_object, singleton_model, ScriptObject};
+use crate::bindings::sprite::sprite_from;
+use crate::engine::assets::SpriteArchive;
+use crate::engine::dialog::{DialogBox, PortraitSlot, TextSpeed};
+use crate::engine::settings::SettingsFile;
+use crate::engine::vfs::Vfs;
+
+// the message window the engine draws, types at the text speed of the settings
+pub struct ScriptDialog {
+    dialog: Reference<DialogBox>,
+    settings: Reference<SettingsFile>,
+    // face sprites can be named by file, mods replace them like any other game data
+    vfs: Arc<Vfs>
+}
+
+impl ScriptDialog {
+    pub fn new(dialog: Reference<DialogBox>, settings: Reference<SettingsFile>, vfs: Arc<Vfs>) -> Self {
+        ScriptDialog { dialog, settings, vfs }
+    }
+}
+
+fn slot_parameter(parameters: &[Object], index: usize, state: &State) -> Result<PortraitSlot, RuntimeError> {
+    let name = parameters[index].string_value()?;
+    PortraitSlot::from_name(&name).ok_or_else(|| RuntimeError::new(&format!("unknown portrait slot {}, it is left or right", name), state.last_position()))
+}
+
+// one frame index or an array of them: eyes open, half closed and closed
+fn frames_parameter(parameters: &[Object], index: usize, state: &State) -> Result<Vec<usize>, RuntimeError> {
+    let values = match &parameters[index] {
+        Object::Array(values) => values.borrow().iter().map(|value| value.integer_value()).collect::<Result<Vec<i64>, RuntimeError>>()?,
+        value => vec![ value.integer_value()? ]
+    };
+
+    values.into_iter()
+        .map(|value| usize::try_from(value).map_err(|_| RuntimeError::new(&format!("{} is not a face frame", value), state.last_position())))
+        .collect()
+}
+
+// Dialog() is the one message window of the game
+singleton_model!(DialogModel, ScriptDialog);
+
+impl ScriptObject for ScriptDialog {
+    const NAME: &'static str = "Dialog";
+    const PROPERTIES: &'static [&'static str] = &["open", "complete", "pending", "seen", "auto_advance", "auto_delay", "skip_seen", "x", "y", "width", "height"];
+    const METHODS: &'static [(&'static str, usize)] = &[("say", 1), ("confirm", 0), ("close", 0), ("faces", 2), ("show_portrait", 2), ("hide_portrait", 1)];
+
+    fn get_property(&self, key: &str) -> Result<Object, RuntimeError> {
+        let dialog = self.dialog.borrow();
+
+        Ok(match key {
+            "open" => Object::Boolean(dialog.is_open()),
+            "complete" => Object::Boolean(dialog.is_complete()),
+            "pending" => Object::Integer(dialog.pending() as i64),
+            // the message on screen was read before, in this play or an earlier one
+            "seen" => Object::Boolean(dialog.text().map(|text| dialog.seen.is_seen(text)).unwrap_or(false)),
+            "auto_advance" => Object::Boolean(dialog.advance.auto_advance),
+            "auto_delay" => Object::Float(dialog.advance.auto_delay),
+            "skip_seen" => Object::Boolean(dialog.advance.skip_seen),
+            "x" => Object::Integer(dialog.x as i64),
+            "y" => Object::Integer(dialog.y as i64),
+            "width" => Object::Integer(dialog.width as i64),
+            _ => Object::Integer(dialog.height as i64)
+        })
+    }
+
+    // auto_advance closes a finished message after auto_delay seconds, skip_seen closes read ones at once,
+    // the rest is where the window sits on screen
+    fn set_property(&mut self, key: &str, value: Object) -> Result<bool, RuntimeError> {
+        let mut dialog = self.dialog.borrow_mut();
+        match (key, value) {
+            ("auto_advance", Object::Boolean(value)) => dialog.advance.auto_advance = value,
+            ("skip_seen", Object::Boolean(value)) => dialog.advance.skip_seen = value,
+            ("auto_delay", value) => dialog.advance.auto_delay = float_parameter(&[ value ], 0)?.max(0.0),
+            ("x", value) => dialog.x = integer_parameter(&[ value ], 0)?,
+            ("y", value) => dialog.y = integer_parameter(&[ value ], 0)?,
+            ("width", value) => dialog.width = integer_parameter(&[ value ], 0)?,
+            ("height", value) => dialog.height = integer_parameter(&[ value ], 0)?,
+            _ => return Ok(false)
+        }
+
+        Ok(true)
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        match key {
+            // say(text, speed) shows the text once the messages before it are closed,
+            // speed is "slow", "normal", "fast" or "instant" for this message only and the setting when left out
+            "say" => {
+                let message_speed = name_parameter(parameters, 1, None, |name| TextSpeed::from_name(name).map(Some), state)?;
+                let global_speed = self.settings.borrow().settings.text_speed();
+                self.dialog.borrow_mut().say(text_parameter(parameters, 0)?, global_speed, message_speed);
+                Ok(Object::Null)
+            },
+            // confirm() for the confirm key, finishes the typing first and then closes the message, true once it closed
+            "confirm" => Ok(Object::Boolean(self.dialog.borrow_mut().confirm())),
+            // faces(sprite, palette) sets the sprite the portrait frames are taken from, a Sprite or the name of a sprite archive
+            "faces" => {
+                let faces = match &parameters[0] {
+                    Object::String(filename) => {
+                        let filename = filename.borrow();
+                        let archive = SpriteArchive::load(&self.vfs, &filename).map_err(|error| RuntimeError::new(&format!("can not load faces {}: {}", filename, error), state.last_position()))?;
+                        make_reference(archive)
+                    },
+                    sprite => sprite_from(sprite)?
+                };
+                self.dialog.borrow_mut().set_faces(faces, palette_from(&parameters[1])?);
+                Ok(Object::Null)
+            },
+            // show_portrait(slot, frames) slides a face in on the left or right of the window, pushing out the one that was there
+            "show_portrait" => {
+                let (slot, frames) = (slot_parameter(parameters, 0, state)?, frames_parameter(parameters, 1, state)?);
+                self.dialog.borrow_mut().portraits.show(slot, frames);
+                Ok(Object::Null)
+            },
+            "hide_portrait" => {
+                let slot = slot_parameter(parameters, 0, state)?;
+                self.dialog.borrow_mut().portraits.hide(slot);
+                Ok(Object::Null)
+            },
+            // close() also slides the faces out
+            _ => {
+                self.dialog.borrow_mut().close();
+                Ok(Object::Null)
+            }
+        }
+    }
+}
+
+script_object!(ScriptDialog);
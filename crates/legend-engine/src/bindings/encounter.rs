@@ -0,0 +1,102 @@
+use std::sync::Arc;
+use clover::{NativeModelInstance, Object, Reference, State};
+use clover::debug::RuntimeError;
+use clover::helper::make_reference;
+use crate::bindings::scene::Scenes;
+use crate::bindings::script_object::{script_object, singleton_model, ScriptObject};
+use crate::engine::encounter::{EncounterSystem, EncounterTables};
+use crate::engine::error::EngineResult;
+use crate::engine::vfs::Vfs;
+
+// counts one step on map, an encounter pushes battle_scene with the battle id when the scripts set one.
+// world map travel and the field leader both go through here
+pub fn encounter_step(encounters: &mut EncounterSystem, scenes: &mut Scenes, map: &str, state: &State) -> Result<Option<u32>, RuntimeError> {
+    let group = match encounters.step(map) {
+        Some(group) => group,
+        None => return Ok(None)
+    };
+
+    if let Some(scene) = encounters.battle_scene.clone() {
+        scenes.push(scene, Object::Integer(group.battle as i64), state)?;
+    }
+
+    Ok(Some(group.battle))
+}
+
+fn load_tables(vfs: &Vfs, filename: &str) -> EngineResult<EncounterTables> {
+    EncounterTables::from_toml(&String::from_utf8_lossy(&vfs.read(filename)?))
+}
+
+// the random encounters of every map, Encounters().enabled = false for story sequences
+pub struct ScriptEncounters {
+    encounters: Reference<EncounterSystem>,
+    scenes: Reference<Scenes>,
+    vfs: Arc<Vfs>
+}
+
+impl ScriptEncounters {
+    pub fn new(encounters: Reference<EncounterSystem>, scenes: Reference<Scenes>, vfs: Arc<Vfs>) -> Self {
+        ScriptEncounters { encounters, scenes, vfs }
+    }
+}
+
+singleton_model!(EncountersModel, ScriptEncounters);
+
+impl ScriptObject for ScriptEncounters {
+    const NAME: &'static str = "Encounters";
+    const PROPERTIES: &'static [&'static str] = &["enabled", "battle_scene"];
+    const METHODS: &'static [(&'static str, usize)] = &[("load", 1), ("set_enabled", 1), ("reset", 0), ("step", 1)];
+
+    fn get_property(&self, key: &str) -> Result<Object, RuntimeError> {
+        let encounters = self.encounters.borrow();
+
+        Ok(match key {
+            "enabled" => Object::Boolean(encounters.enabled),
+            // null when scripts start battles themselves from the battle id step returns
+            _ => encounters.battle_scene.as_ref().map(|scene| Object::String(make_reference(scene.clone()))).unwrap_or(Object::Null)
+        })
+    }
+
+    fn set_property(&mut self, key: &str, value: Object) -> Result<bool, RuntimeError> {
+        let mut encounters = self.encounters.borrow_mut();
+        match (key, value) {
+            ("enabled", Object::Boolean(enabled)) => encounters.set_enabled(enabled),
+            ("battle_scene", Object::Null) => encounters.battle_scene = None,
+            ("battle_scene", value) => encounters.battle_scene = Some(value.string_value()?),
+            _ => return Ok(false)
+        }
+
+        Ok(true)
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        match key {
+            // load(filename) reads the [[table]] file through the vfs, replacing the tables loaded before
+            "load" => {
+                let filename = parameters[0].string_value()?;
+                let tables = load_tables(&self.vfs, &filename).map_err(|error| RuntimeError::new(&format!("can not load encounters {}: {}", filename, error), state.last_position()))?;
+                self.encounters.borrow_mut().set_tables(tables);
+                Ok(Object::Null)
+            },
+            "set_enabled" => match parameters[0] {
+                Object::Boolean(enabled) => {
+                    self.encounters.borrow_mut().set_enabled(enabled);
+                    Ok(Object::Null)
+                },
+                _ => Err(RuntimeError::new("set_enabled takes true or false", state.last_position()))
+            },
+            "reset" => {
+                self.encounters.borrow_mut().reset();
+                Ok(Object::Null)
+            },
+            // step(map) for movement the engine does not see, the battle id of an encounter or null
+            _ => {
+                let map = parameters[0].string_value()?;
+                let battle = encounter_step(&mut self.encounters.borrow_mut(), &mut self.scenes.borrow_mut(), &map, state)?;
+                Ok(battle.map(|battle| Object::Integer(battle as i64)).unwrap_or(Object::Null))
+            }
+        }
+    }
+}
+
+script_object!(ScriptEncounters);
@@ -0,0 +1,40 @@
+use clover::{NativeModelInstance, Object, Reference, State};
+use clover::debug::RuntimeError;
+use crate::bindings::color::float_parameter;
+use crate::bindings::script_object::{script_object, singleton_model, ScriptObject};
+use crate::engine::clock::GameTime;
+
+// Engine() hands out the game time, Engine().set_time_scale(0.5) is slow motion for everything that updates
+singleton_model!(EngineModel, GameTime);
+
+impl ScriptObject for GameTime {
+    const NAME: &'static str = "Engine";
+    // real_delta is the update dt before scaling, for menus that should not slow down
+    const PROPERTIES: &'static [&'static str] = &["time_scale", "real_delta", "time", "real_time"];
+    const METHODS: &'static [(&'static str, usize)] = &[("set_time_scale", 1)];
+
+    fn get_property(&self, key: &str) -> Result<Object, RuntimeError> {
+        Ok(Object::Float(match key {
+            "time_scale" => self.time_scale(),
+            "real_delta" => self.real_delta(),
+            "time" => self.elapsed(),
+            _ => self.real_elapsed()
+        }))
+    }
+
+    fn set_property(&mut self, key: &str, value: Object) -> Result<bool, RuntimeError> {
+        if key != "time_scale" {
+            return Ok(false);
+        }
+
+        self.set_time_scale(float_parameter(&[ value ], 0)?);
+        Ok(true)
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, _state: &mut State, _key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        self.set_time_scale(float_parameter(parameters, 0)?);
+        Ok(Object::Null)
+    }
+}
+
+script_object!(GameTime);
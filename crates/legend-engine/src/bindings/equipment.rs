@@ -0,0 +1,182 @@
+use std::sync::Arc;
+use clover::{NativeModel, NativeModelInstance, Object, Reference, State};
+use clover::debug::RuntimeError;
+use clover::helper::{ensure_parameters_length, make_reference};
+use crate::bindings::inventory::{inventory_from, item_parameter};
+use crate::bindings::party::member_parameter;
+use crate::bindings::save::{save_table, to_save_value};
+use crate::bindings::script_object::{lookup, script_object, Registered, Registry, ScriptObject};
+use crate::engine::equipment::{EquipSlot, Equipment, EquipmentDatabase};
+use crate::engine::save::SaveValue;
+use crate::engine::stats::Stats;
+use crate::engine::vfs::Vfs;
+
+thread_local! {
+    static DATABASES: Registry<EquipmentDatabase> = Registry::new();
+}
+
+pub(crate) const STAT_NAMES: &[&str] = &["hp", "max_hp", "mp", "max_mp", "attack", "defence", "speed"];
+
+// a SaveTable of every stat, for menus and saves alike
+pub(crate) fn stats_table(stats: &Stats) -> Object {
+    save_table(SaveValue::Table(STAT_NAMES.iter().map(|name| (name.to_string(), SaveValue::Integer(stats.get(name).unwrap_or(0) as i64))).collect()))
+}
+
+fn slot_parameter(parameters: &[Object], index: usize, state: &State) -> Result<EquipSlot, RuntimeError> {
+    let name = parameters[index].string_value()?;
+    EquipSlot::from_name(&name).ok_or_else(|| RuntimeError::new(&format!("unknown equipment slot {}, it is weapon, armor or accessory", name), state.last_position()))
+}
+
+fn item_object(item: Option<u32>) -> Object {
+    item.map(|item| Object::Integer(item as i64)).unwrap_or(Object::Null)
+}
+
+// EquipmentDatabase(filename) reads the [[equipment]] file through the vfs
+pub struct EquipmentDatabaseModel {
+    vfs: Arc<Vfs>
+}
+
+impl EquipmentDatabaseModel {
+    pub fn new(vfs: Arc<Vfs>) -> Self {
+        EquipmentDatabaseModel { vfs }
+    }
+}
+
+impl NativeModel for EquipmentDatabaseModel {
+    fn call(&mut self, state: &mut State, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        ensure_parameters_length(parameters, 1)?;
+
+        let filename = parameters[0].string_value()?;
+        let database = self.vfs.read(&filename)
+            .and_then(|source| EquipmentDatabase::from_toml(&String::from_utf8_lossy(&source)))
+            .map_err(|error| RuntimeError::new(&format!("can not load equipment {}: {}", filename, error), state.last_position()))?;
+
+        let database = make_reference(database);
+        Ok(Object::NativeInstance(make_reference(ScriptEquipmentDatabase { registered: Registered::new(&DATABASES, &database), database })))
+    }
+}
+
+pub struct ScriptEquipmentDatabase {
+    registered: Registered<EquipmentDatabase>,
+    database: Reference<EquipmentDatabase>
+}
+
+impl ScriptObject for ScriptEquipmentDatabase {
+    const NAME: &'static str = "EquipmentDatabase";
+    const PROPERTIES: &'static [&'static str] = &[];
+    const METHODS: &'static [(&'static str, usize)] = &[("name", 1), ("bonus", 1)];
+
+    fn get_property(&self, _key: &str) -> Result<Object, RuntimeError> {
+        Ok(Object::Null)
+    }
+
+    // name(item) and bonus(item) are null for items that can not be equipped
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        let item = item_parameter(parameters, 0, state)?;
+        let database = self.database.borrow();
+        let equipment = match database.get(item) {
+            Some(equipment) => equipment,
+            None => return Ok(Object::Null)
+        };
+
+        Ok(match key {
+            "name" => Object::String(make_reference(equipment.name.clone())),
+            _ => stats_table(&equipment.bonus)
+        })
+    }
+
+    fn raw_integer(&self, key: &str) -> Option<i64> {
+        match key {
+            "equipment_database_id" => Some(self.registered.id()),
+            _ => None
+        }
+    }
+}
+
+script_object!(ScriptEquipmentDatabase);
+
+// Equipment(database) is what one character wears, nothing at first
+pub struct EquipmentModel;
+
+impl NativeModel for EquipmentModel {
+    fn call(&mut self, _state: &mut State, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        ensure_parameters_length(parameters, 1)?;
+
+        let database = lookup(&DATABASES, &parameters[0], "equipment_database_id", "an equipment database")?;
+        Ok(Object::NativeInstance(make_reference(ScriptEquipment { equipment: Equipment::new(), database })))
+    }
+}
+
+pub struct ScriptEquipment {
+    equipment: Equipment,
+    database: Reference<EquipmentDatabase>
+}
+
+impl ScriptObject for ScriptEquipment {
+    const NAME: &'static str = "Equipment";
+    const PROPERTIES: &'static [&'static str] = &["weapon", "armor", "accessory", "bonus"];
+    const METHODS: &'static [(&'static str, usize)] = &[("equip", 4), ("unequip", 2), ("compare", 1), ("stats", 1)];
+
+    // the item in each slot, null when it is empty
+    fn get_property(&self, key: &str) -> Result<Object, RuntimeError> {
+        Ok(match key {
+            "weapon" => item_object(self.equipment.get(EquipSlot::Weapon)),
+            "armor" => item_object(self.equipment.get(EquipSlot::Armor)),
+            "accessory" => item_object(self.equipment.get(EquipSlot::Accessory)),
+            // what everything worn adds up to
+            _ => stats_table(&self.equipment.bonus(&self.database.borrow()))
+        })
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        match key {
+            // equip(inventory, item, character, class) takes the item out of the inventory and puts back the one it replaces,
+            // the replaced item or null
+            "equip" => {
+                let (inventory, item) = (inventory_from(&parameters[0])?, item_parameter(parameters, 1, state)?);
+                let (character, class) = (member_parameter(parameters, 2, state)?, parameters[3].string_value()?);
+                let previous = self.equipment.equip(&self.database.borrow(), &mut inventory.borrow_mut(), item, character, &class)
+                    .map_err(|error| RuntimeError::new(&error.to_string(), state.last_position()))?;
+                Ok(item_object(previous))
+            },
+            // unequip(inventory, slot) puts the item back in the inventory, null when the slot was empty
+            "unequip" => {
+                let (inventory, slot) = (inventory_from(&parameters[0])?, slot_parameter(parameters, 1, state)?);
+                let item = self.equipment.unequip(&mut inventory.borrow_mut(), slot);
+                Ok(item_object(item))
+            },
+            // compare(item) is how every stat would change if item replaced what is in its slot, null for items that can not be equipped
+            "compare" => {
+                let item = item_parameter(parameters, 0, state)?;
+                Ok(self.equipment.compare(&self.database.borrow(), item).map(|change| stats_table(&change)).unwrap_or(Object::Null))
+            },
+            // stats(base) is a table of base stats plus everything worn
+            _ => {
+                let base = stats_parameter(&parameters[0], state)?;
+                Ok(stats_table(&self.equipment.derived_stats(&self.database.borrow(), &base)))
+            }
+        }
+    }
+}
+
+script_object!(ScriptEquipment);
+
+// a table with any of the stats, the ones left out are 0 and other keys like a name are left alone
+pub(crate) fn stats_parameter(object: &Object, state: &State) -> Result<Stats, RuntimeError> {
+    let mut stats = Stats::default();
+    let table = match to_save_value(object)? {
+        SaveValue::Table(table) => table,
+        _ => return Err(RuntimeError::new("stats are a SaveTable of hp, max_hp, mp, max_mp, attack, defence and speed", state.last_position()))
+    };
+
+    for (name, value) in table.into_iter().filter(|(name, _)| Stats::default().get(name).is_some()) {
+        let value = match value {
+            SaveValue::Integer(value) => value as i32,
+            SaveValue::Float(value) => value as i32,
+            _ => return Err(RuntimeError::new(&format!("stat {} is not a number", name), state.last_position()))
+        };
+        stats.set(&name, value);
+    }
+
+    Ok(stats)
+}
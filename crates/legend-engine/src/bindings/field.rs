@@ -0,0 +1,379 @@
+use std::sync::Arc;
+use clover::{NativeModelInstance, Object, Reference, State};
+use clover::debug::{Position, RuntimeError};
+use clover::helper::make_reference;
+use crate::bindings::color::{float_parameter, integer_parameter};
+use crate::bindings::inventory::inventory_from;
+use crate::bindings::palette::palette_from;
+use crate::bindings::party::party_from;
+use crate::bindings::save::{save_table, to_save_value};
+use crate::bindings::script_object::{script_object, singleton_model, ScriptObject};
+use crate::bindings::sprite::sprite_from;
+use crate::engine::audio::player::Audio;
+use crate::engine::audio::sound::PlayOptions;
+use crate::engine::clock::GameClock;
+use crate::engine::dialog::DialogBox;
+use crate::engine::entity::{Entity, Schedule, ScheduleEntry};
+use crate::engine::field::Field;
+use crate::engine::graphics::{Graphics, Vector2};
+use crate::engine::movement::Direction;
+use crate::engine::pickup::{OpenedPickups, PickupEvent, Pickups};
+use crate::engine::transition::Transitions;
+use crate::engine::save::SaveValue;
+use crate::engine::settings::SettingsFile;
+use crate::engine::text::encode_big5;
+use crate::engine::vfs::Vfs;
+
+pub(crate) fn direction_from(object: &Object) -> Result<Direction, RuntimeError> {
+    let name = object.string_value()?;
+    Direction::from_name(&name).ok_or_else(|| RuntimeError::new(&format!("unknown direction {}, it is up, right, left or down", name), Position::none()))
+}
+
+fn id_parameter(parameters: &[Object], index: usize, state: &State) -> Result<u32, RuntimeError> {
+    let id = parameters[index].integer_value()?;
+    u32::try_from(id).map_err(|_| RuntimeError::new(&format!("{} is not an entity id", id), state.last_position()))
+}
+
+fn position_parameter(parameters: &[Object], index: usize) -> Result<Vector2<i32>, RuntimeError> {
+    Ok(Vector2::new(integer_parameter(parameters, index)?, integer_parameter(parameters, index + 1)?))
+}
+
+// [start_minute, x, y, facing, behavior] for every entry
+fn schedule_parameter(parameters: &[Object], index: usize, state: &State) -> Result<Schedule, RuntimeError> {
+    let entries = match &parameters[index] {
+        Object::Array(entries) => entries.borrow().clone(),
+        _ => return Err(RuntimeError::new("a schedule is an array of [start_minute, x, y, facing, behavior]", state.last_position()))
+    };
+
+    let entries = entries.iter().map(|entry| {
+        let values = match entry {
+            Object::Array(values) if values.borrow().len() >= 5 => values.borrow().clone(),
+            _ => return Err(RuntimeError::new("a schedule entry is [start_minute, x, y, facing, behavior]", state.last_position()))
+        };
+
+        Ok(ScheduleEntry {
+            start_minute: values[0].integer_value()?.max(0) as u32,
+            position: position_parameter(&values, 1)?,
+            facing: direction_from(&values[3])?,
+            behavior: values[4].string_value()?
+        })
+    }).collect::<Result<Vec<ScheduleEntry>, RuntimeError>>()?;
+
+    Ok(Schedule::new(entries))
+}
+
+// { id, items: [[item, count], ...], gold } of what was just opened
+fn pickup_table(event: &PickupEvent) -> Object {
+    let items = event.items.iter().map(|&(item, count)| SaveValue::Array(vec![ SaveValue::Integer(item as i64), SaveValue::Integer(count as i64) ])).collect();
+    save_table(SaveValue::Table([
+        ("id".to_string(), SaveValue::String(event.id.clone())),
+        ("items".to_string(), SaveValue::Array(items)),
+        ("gold".to_string(), SaveValue::Integer(event.gold as i64))
+    ].into_iter().collect()))
+}
+
+fn entity_object(field: &Reference<Field>, id: u32) -> Object {
+    Object::NativeInstance(make_reference(ScriptEntity { field: field.clone(), id }))
+}
+
+// the map the party walks on, drawn by draw(tiles, sprites, palette) and updated by the engine every step
+pub struct ScriptField {
+    field: Reference<Field>,
+    graphics: Reference<Graphics>,
+    // opening a chest plays its sound and shows its message
+    audio: Reference<Audio>,
+    dialog: Reference<DialogBox>,
+    settings: Reference<SettingsFile>,
+    vfs: Arc<Vfs>
+}
+
+impl ScriptField {
+    pub fn new(field: Reference<Field>, graphics: Reference<Graphics>, audio: Reference<Audio>, dialog: Reference<DialogBox>, settings: Reference<SettingsFile>, vfs: Arc<Vfs>) -> Self {
+        ScriptField { field, graphics, audio, dialog, settings, vfs }
+    }
+}
+
+// Field() is the one local map of the game
+singleton_model!(FieldModel, ScriptField);
+
+impl ScriptObject for ScriptField {
+    const NAME: &'static str = "Field";
+    const PROPERTIES: &'static [&'static str] = &["map", "width", "height", "day", "hour", "minute", "clock_speed", "entities", "leader", "opened"];
+    const METHODS: &'static [(&'static str, usize)] = &[
+        ("load", 2), ("tile", 2), ("set_time", 2), ("add_entity", 4), ("remove_entity", 1), ("entity", 1), ("entity_at", 2), ("set_followers", 2), ("set_party", 1), ("load_pickups", 1), ("load_transitions", 1), ("interact", 1), ("draw", 3)
+    ];
+
+    fn get_property(&self, key: &str) -> Result<Object, RuntimeError> {
+        let field = self.field.borrow();
+        let size = field.map().map(|map| map.size).unwrap_or(Vector2::new(0, 0));
+
+        Ok(match key {
+            // null before the first load
+            "map" => field.name().map(|name| Object::String(make_reference(name.to_string()))).unwrap_or(Object::Null),
+            "width" => Object::Integer(size.x as i64),
+            "height" => Object::Integer(size.y as i64),
+            "day" => Object::Integer(field.clock.day() as i64),
+            "hour" => Object::Integer(field.clock.hour() as i64),
+            "minute" => Object::Integer(field.clock.minute_of_day() as i64),
+            "clock_speed" => Object::Float(field.clock.speed),
+            // the id of the entity the player walks, its steps count towards random encounters
+            "leader" => field.leader.map(|leader| Object::Integer(leader as i64)).unwrap_or(Object::Null),
+            // a SaveTable of the pickups taken so far, kept in the save and set back after reading it
+            "opened" => save_table(SaveValue::from_data(&field.pickups.opened)?),
+            // ids of everyone on the map
+            _ => Object::Array(make_reference(field.entities.iter().map(|entity| Object::Integer(entity.id as i64)).collect()))
+        })
+    }
+
+    // clock_speed is in game minutes per real second, 0 stops the time of day,
+    // opened takes the table the property gave before the game was saved
+    fn set_property(&mut self, key: &str, value: Object) -> Result<bool, RuntimeError> {
+        let mut field = self.field.borrow_mut();
+        match (key, value) {
+            ("clock_speed", value) => field.clock.speed = float_parameter(&[ value ], 0)?.max(0.0),
+            ("leader", Object::Null) => field.leader = None,
+            ("leader", value) => field.leader = Some(u32::try_from(value.integer_value()?).map_err(|_| RuntimeError::new("the leader is an entity id", Position::none()))?),
+            ("opened", value) => field.pickups.opened = to_save_value(&value)?.to_data::<OpenedPickups>()
+                .map_err(|error| RuntimeError::new(&format!("not the opened pickups of a save: {}", error), Position::none()))?,
+            _ => return Ok(false)
+        }
+
+        Ok(true)
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        match key {
+            // load(name, filename) reads the map through the vfs, so mods can replace it, and clears the entities
+            "load" => {
+                let (name, filename) = (parameters[0].string_value()?, parameters[1].string_value()?);
+                self.field.borrow_mut().load(&self.vfs, &name, &filename)
+                    .map_err(|error| RuntimeError::new(&format!("can not load map {}: {}", filename, error), state.last_position()))?;
+                Ok(Object::Null)
+            },
+            // tile(x, y) is null outside of the map
+            "tile" => {
+                let (x, y) = (integer_parameter(parameters, 0)?, integer_parameter(parameters, 1)?);
+                let tile = self.field.borrow().map().and_then(|map| map.get_tile(x, y));
+                Ok(tile.map(|tile| Object::Integer(tile as i64)).unwrap_or(Object::Null))
+            },
+            // set_time(day, minute_of_day) for a new game or a loaded save
+            "set_time" => {
+                let mut field = self.field.borrow_mut();
+                let speed = field.clock.speed;
+                field.clock = GameClock::new(parameters[0].integer_value()?.max(0) as u32, parameters[1].integer_value()?.max(0) as u32, speed);
+                Ok(Object::Null)
+            },
+            // add_entity(id, x, y, sprite) facing down, the id is how scripts find it again
+            "add_entity" => {
+                let id = id_parameter(parameters, 0, state)?;
+                let mut field = self.field.borrow_mut();
+                if field.entities.get(id).is_some() {
+                    return Err(RuntimeError::new(&format!("entity {} is already on the map", id), state.last_position()));
+                }
+
+                let sprite = parameters[3].integer_value()?.max(0) as usize;
+                field.entities.add(Entity::new(id, position_parameter(parameters, 1)?, Direction::Down, sprite));
+                Ok(entity_object(&self.field, id))
+            },
+            "remove_entity" => {
+                let id = id_parameter(parameters, 0, state)?;
+                Ok(Object::Boolean(self.field.borrow_mut().entities.remove(id).is_some()))
+            },
+            // entity(id) and entity_at(x, y) are null when nobody is there
+            "entity" => {
+                let id = id_parameter(parameters, 0, state)?;
+                let found = self.field.borrow().entities.get(id).is_some();
+                Ok(if found { entity_object(&self.field, id) } else { Object::Null })
+            },
+            "entity_at" => {
+                let id = self.field.borrow().entities.entity_at(integer_parameter(parameters, 0)?, integer_parameter(parameters, 1)?).map(|entity| entity.id);
+                Ok(id.map(|id| entity_object(&self.field, id)).unwrap_or(Object::Null))
+            },
+            // set_followers(leader, [ids], spacing) makes the members walk the path of the leader, spacing tiles apart,
+            // one tile when left out and an empty array stops it
+            "set_followers" => {
+                let leader = id_parameter(parameters, 0, state)?;
+                let members = match &parameters[1] {
+                    Object::Array(members) => members.borrow().iter().map(|member| id_parameter(std::slice::from_ref(member), 0, state)).collect::<Result<Vec<u32>, RuntimeError>>()?,
+                    _ => return Err(RuntimeError::new("followers are an array of entity ids", state.last_position()))
+                };
+                let spacing = if parameters.len() > 2 { parameters[2].integer_value()?.max(1) as usize } else { 1 };
+
+                self.field.borrow_mut().set_followers(leader, members, spacing);
+                Ok(Object::Null)
+            },
+            // set_party(party) lets the mode of the party decide where the leader walks and how it looks, null for none
+            "set_party" => {
+                let party = match &parameters[0] {
+                    Object::Null => None,
+                    party => Some(party_from(party)?)
+                };
+                self.field.borrow_mut().party = party;
+                Ok(Object::Null)
+            },
+            // load_pickups(filename) reads the [[pickup]] file of every map through the vfs, what was opened stays opened
+            "load_pickups" => {
+                let filename = parameters[0].string_value()?;
+                let pickups = self.vfs.read(&filename)
+                    .and_then(|source| Pickups::from_toml(&String::from_utf8_lossy(&source)))
+                    .map_err(|error| RuntimeError::new(&format!("can not load pickups {}: {}", filename, error), state.last_position()))?;
+
+                let mut field = self.field.borrow_mut();
+                let opened = std::mem::take(&mut field.pickups.opened);
+                field.pickups = pickups;
+                field.pickups.opened = opened;
+                Ok(Object::Null)
+            },
+            // load_transitions(filename) reads the [[transition]] doors of every map through the vfs,
+            // the leader stepping on one fades to the destination through Scenes().map_scene
+            "load_transitions" => {
+                let filename = parameters[0].string_value()?;
+                let transitions = Transitions::load_vfs(&self.vfs, &filename)
+                    .map_err(|error| RuntimeError::new(&format!("can not load transitions {}: {}", filename, error), state.last_position()))?;
+                self.field.borrow_mut().transitions = transitions;
+                Ok(Object::Null)
+            },
+            // interact(inventory) for the confirm key: opens the pickup the leader faces into the inventory,
+            // playing its sound and saying its message, and is the table of what was in it.
+            // otherwise it is the entity the leader faces, to talk to, or null
+            "interact" => {
+                let inventory = inventory_from(&parameters[0])?;
+                let event = self.field.borrow_mut().open_facing(&mut inventory.borrow_mut());
+
+                if let Some(event) = event {
+                    self.audio.borrow_mut().play_sound(&event.sound, PlayOptions::default())
+                        .map_err(|error| RuntimeError::new(&format!("can not play {}: {}", event.sound, error), state.last_position()))?;
+                    if !event.message.is_empty() {
+                        let speed = self.settings.borrow().settings.text_speed();
+                        self.dialog.borrow_mut().say(encode_big5(&event.message), speed, None);
+                    }
+                    return Ok(pickup_table(&event));
+                }
+
+                let field = self.field.borrow();
+                let id = field.facing_tile().and_then(|tile| field.entities.entity_at(tile.x, tile.y)).map(|entity| entity.id);
+                Ok(id.map(|id| entity_object(&self.field, id)).unwrap_or(Object::Null))
+            },
+            // draw(tiles, sprites, palette) with the camera, tiles and entity sprites are Sprite archives, pickups are entity sprites too
+            _ => {
+                let (tiles, sprites, palette) = (sprite_from(&parameters[0])?, sprite_from(&parameters[1])?, palette_from(&parameters[2])?);
+                self.field.borrow().draw(&mut self.graphics.borrow_mut(), &tiles.borrow(), &sprites.borrow(), &palette.borrow());
+                Ok(Object::Null)
+            }
+        }
+    }
+}
+
+script_object!(ScriptField);
+
+// someone on the field, found by id, so the handle goes stale once the entity is removed or the map changes
+pub struct ScriptEntity {
+    field: Reference<Field>,
+    id: u32
+}
+
+impl ScriptEntity {
+    fn gone(&self) -> RuntimeError {
+        RuntimeError::new(&format!("entity {} is not on the map", self.id), Position::none())
+    }
+
+    fn with<T>(&self, read: impl FnOnce(&Entity) -> T) -> Result<T, RuntimeError> {
+        self.field.borrow().entities.get(self.id).map(read).ok_or_else(|| self.gone())
+    }
+
+    fn with_mut<T>(&self, change: impl FnOnce(&mut Entity) -> T) -> Result<T, RuntimeError> {
+        self.field.borrow_mut().entities.get_mut(self.id).map(change).ok_or_else(|| self.gone())
+    }
+}
+
+impl ScriptObject for ScriptEntity {
+    const NAME: &'static str = "Entity";
+    const PROPERTIES: &'static [&'static str] = &["id", "x", "y", "facing", "sprite", "behavior", "walking", "speed", "pixel_x", "pixel_y"];
+    const METHODS: &'static [(&'static str, usize)] = &[("place", 2), ("walk_to", 2), ("step", 1), ("set_schedule", 1)];
+
+    fn get_property(&self, key: &str) -> Result<Object, RuntimeError> {
+        if key == "id" {
+            return Ok(Object::Integer(self.id as i64));
+        }
+
+        let alpha = self.field.borrow().alpha;
+        self.with(|entity| match key {
+            "x" => Object::Integer(entity.position.x as i64),
+            "y" => Object::Integer(entity.position.y as i64),
+            "facing" => Object::String(make_reference(entity.facing.name().to_string())),
+            "sprite" => Object::Integer(entity.sprite as i64),
+            // what the schedule says it is doing, like "sleep" or "shop"
+            "behavior" => Object::String(make_reference(entity.behavior.clone())),
+            "walking" => Object::Boolean(entity.is_walking()),
+            "speed" => Object::Float(entity.speed()),
+            // where it is drawn this frame, between two tiles while it walks
+            "pixel_x" => Object::Float(entity.interpolated_position(alpha).x),
+            _ => Object::Float(entity.interpolated_position(alpha).y)
+        })
+    }
+
+    // speed is in pixels per second
+    fn set_property(&mut self, key: &str, value: Object) -> Result<bool, RuntimeError> {
+        match key {
+            "facing" => {
+                let facing = direction_from(&value)?;
+                self.with_mut(|entity| entity.facing = facing)?;
+            },
+            "sprite" => {
+                let sprite = value.integer_value()?.max(0) as usize;
+                self.with_mut(|entity| entity.sprite = sprite)?;
+            },
+            "speed" => {
+                let speed = float_parameter(&[ value ], 0)?;
+                self.with_mut(|entity| entity.set_speed(speed))?;
+            },
+            _ => return Ok(false)
+        }
+
+        Ok(true)
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        match key {
+            "place" => {
+                let position = position_parameter(parameters, 0)?;
+                self.with_mut(|entity| entity.place(position))?;
+                Ok(Object::Null)
+            },
+            // walk_to(x, y) goes around whatever is in the way and waits while it is blocked
+            "walk_to" => {
+                let position = position_parameter(parameters, 0)?;
+                self.with_mut(|entity| entity.walk_to(position))?;
+                Ok(Object::Null)
+            },
+            // step(direction) turns and walks one tile, false while still walking or when the tile is blocked
+            "step" => {
+                let direction = direction_from(&parameters[0])?;
+                let mut field = self.field.borrow_mut();
+                let (walking, position) = field.entities.get(self.id).map(|entity| (entity.is_walking(), entity.position)).ok_or_else(|| self.gone())?;
+                if walking {
+                    return Ok(Object::Boolean(false));
+                }
+
+                let offset = direction.offset();
+                let target = Vector2::new(position.x + offset.x, position.y + offset.y);
+                let free = field.can_enter(self.id, target.x, target.y);
+
+                let entity = field.entities.get_mut(self.id).ok_or_else(|| self.gone())?;
+                entity.facing = direction;
+                if free {
+                    entity.walk_to(target);
+                }
+                Ok(Object::Boolean(free))
+            },
+            // set_schedule([[start_minute, x, y, facing, behavior], ...]) walks it to each place at its time of day
+            _ => {
+                let schedule = schedule_parameter(parameters, 0, state)?;
+                self.with_mut(|entity| entity.schedule = Some(schedule))?;
+                Ok(Object::Null)
+            }
+        }
+    }
+}
+
+script_object!(ScriptEntity);
@@ -0,0 +1,230 @@
+use std::rc::Rc;
+use std::sync::Arc;
+use clover::{NativeModel, NativeModelInstance, Object, Reference, State};
+use clover::debug::RuntimeError;
+use clover::helper::{ensure_parameters_length, make_reference};
+use crate::bindings::color::{color_parameter, float_parameter, integer_parameter, name_parameter, text_parameter};
+use crate::bindings::palette::{index_parameter, palette_from};
+use crate::bindings::script_object::{lookup, script_object, Registered, Registry, ScriptObject};
+use crate::engine::graphics::{BlendMode, Color, GameFont, GradientDirection, Image, TextAlign, TextDirection};
+use crate::engine::image_trim::{apply_color_key, content_bounds, trim};
+use crate::engine::vfs::Vfs;
+
+thread_local! {
+    static IMAGES: Registry<Image> = Registry::new();
+}
+
+// an offscreen image owned by a script
+pub struct ScriptImage {
+    registered: Registered<Image>,
+    image: Reference<Image>,
+    game_font: Option<Rc<GameFont>>
+}
+
+impl ScriptImage {
+    pub fn new(image: Image, game_font: Option<Rc<GameFont>>) -> Self {
+        Self::shared(make_reference(image), game_font)
+    }
+
+    // an image the engine keeps as well, drawing on either side shows on both
+    pub fn shared(image: Reference<Image>, game_font: Option<Rc<GameFont>>) -> Self {
+        ScriptImage { registered: Registered::new(&IMAGES, &image), image, game_font }
+    }
+
+    pub fn image(&self) -> Reference<Image> {
+        self.image.clone()
+    }
+}
+
+fn integer_array(values: &[i64]) -> Object {
+    Object::Array(make_reference(values.iter().map(|&value| Object::Integer(value)).collect()))
+}
+
+// the image behind a script Image instance
+pub fn image_from(object: &Object) -> Result<Reference<Image>, RuntimeError> {
+    lookup(&IMAGES, object, "image_id", "an image")
+}
+
+// Image(width, height) makes a transparent image, Image(filename) loads a png,
+// Image(filename, color) makes that color of the png transparent
+pub struct ImageModel {
+    game_font: Option<Rc<GameFont>>,
+    vfs: Arc<Vfs>
+}
+
+impl ImageModel {
+    pub fn new(game_font: Option<Rc<GameFont>>, vfs: Arc<Vfs>) -> Self {
+        ImageModel { game_font, vfs }
+    }
+}
+
+impl NativeModel for ImageModel {
+    fn call(&mut self, state: &mut State, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        ensure_parameters_length(parameters, 1)?;
+
+        let image = match &parameters[0] {
+            Object::String(filename) => {
+                // through the vfs when a mod or the game data has the file, from disk like the scripts folder otherwise
+                let filename = filename.borrow().clone();
+                let name = filename.trim_start_matches("./");
+                let image = if self.vfs.exists(name) { Image::load_vfs(&self.vfs, name) } else { Image::load(&filename) };
+                let mut image = image.map_err(|error| RuntimeError::new(&error.to_string(), state.last_position()))?;
+                if parameters.len() > 1 {
+                    apply_color_key(&mut image, &color_parameter(parameters, 1)?, 0);
+                }
+                image
+            },
+            _ => {
+                ensure_parameters_length(parameters, 2)?;
+                let width = parameters[0].integer_value()?.max(0) as u32;
+                let height = parameters[1].integer_value()?.max(0) as u32;
+                Image::new(width, height)
+            }
+        };
+
+        Ok(Object::NativeInstance(make_reference(ScriptImage::new(image, self.game_font.clone()))))
+    }
+}
+
+impl ScriptObject for ScriptImage {
+    const NAME: &'static str = "Image";
+    const PROPERTIES: &'static [&'static str] = &["width", "height"];
+    const METHODS: &'static [(&'static str, usize)] = &[
+        ("clear", 0), ("set_pixel", 3), ("get_pixel", 2), ("fill_rect", 5), ("draw_rect", 5), ("fill_gradient", 6), ("fill_palette_ramp", 7),
+        ("draw_text", 4), ("blit", 3), ("sub_image", 4), ("mosaic", 1), ("save", 1),
+        ("color_key", 1), ("content_bounds", 0), ("trim", 0)
+    ];
+
+    fn get_property(&self, key: &str) -> Result<Object, RuntimeError> {
+        let size = self.image.borrow().size;
+        Ok(Object::Integer(if key == "width" { size.x } else { size.y } as i64))
+    }
+
+    fn call_method(&mut self, this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        match key {
+            "clear" => {
+                let color = if !parameters.is_empty() { color_parameter(parameters, 0)? } else { Color::new(0, 0, 0, 0) };
+                self.image.borrow_mut().clear_by_color(color);
+                Ok(Object::Null)
+            },
+            "set_pixel" => {
+                self.image.borrow_mut().set_pixel(integer_parameter(parameters, 0)?, integer_parameter(parameters, 1)?, &color_parameter(parameters, 2)?);
+                Ok(Object::Null)
+            },
+            "get_pixel" => {
+                Ok(match self.image.borrow().get_pixel(integer_parameter(parameters, 0)?, integer_parameter(parameters, 1)?) {
+                    Some(color) => Object::NativeInstance(make_reference(color)),
+                    None => Object::Null
+                })
+            },
+            // fill_rect(x, y, width, height, color, blend_mode) and draw_rect without the blend mode
+            "fill_rect" | "draw_rect" => {
+                let (x, y) = (integer_parameter(parameters, 0)?, integer_parameter(parameters, 1)?);
+                let (width, height) = (integer_parameter(parameters, 2)?, integer_parameter(parameters, 3)?);
+                let color = color_parameter(parameters, 4)?;
+
+                if key == "fill_rect" {
+                    let mode = name_parameter(parameters, 5, BlendMode::Alpha, BlendMode::from_name, state)?;
+                    self.image.borrow_mut().fill_rect_with_mode(x, y, width, height, &color, mode);
+                } else {
+                    self.image.borrow_mut().draw_rect(x, y, width, height, &color);
+                }
+                Ok(Object::Null)
+            },
+            "fill_gradient" => {
+                let direction = name_parameter(parameters, 6, GradientDirection::Vertical, GradientDirection::from_name, state)?;
+                self.image.borrow_mut().fill_gradient_rect(
+                    integer_parameter(parameters, 0)?, integer_parameter(parameters, 1)?,
+                    integer_parameter(parameters, 2)?, integer_parameter(parameters, 3)?,
+                    &color_parameter(parameters, 4)?, &color_parameter(parameters, 5)?, direction);
+                Ok(Object::Null)
+            },
+            // same parameters as Graphics.fill_palette_ramp
+            "fill_palette_ramp" => {
+                let direction = name_parameter(parameters, 7, GradientDirection::Vertical, GradientDirection::from_name, state)?;
+                let palette = palette_from(&parameters[4])?;
+                self.image.borrow_mut().fill_palette_ramp_rect(
+                    integer_parameter(parameters, 0)?, integer_parameter(parameters, 1)?,
+                    integer_parameter(parameters, 2)?, integer_parameter(parameters, 3)?,
+                    &palette.borrow(), index_parameter(parameters, 5, state)?, index_parameter(parameters, 6, state)?, direction);
+                Ok(Object::Null)
+            },
+            // same parameters as Graphics.draw_text
+            "draw_text" => {
+                let game_font = match self.game_font.as_ref() {
+                    Some(game_font) => game_font,
+                    None => return Ok(Object::Boolean(false))
+                };
+
+                let width = if parameters.len() > 4 { integer_parameter(parameters, 4)? } else { 0 };
+                let align = name_parameter(parameters, 5, TextAlign::Left, TextAlign::from_name, state)?;
+                let direction = name_parameter(parameters, 6, TextDirection::Horizontal, TextDirection::from_name, state)?;
+                let (text, x, y, color) = (text_parameter(parameters, 0)?, integer_parameter(parameters, 1)?, integer_parameter(parameters, 2)?, color_parameter(parameters, 3)?);
+
+                match direction {
+                    TextDirection::Horizontal => self.image.borrow_mut().draw_game_text_aligned(&text, x, y, width, align, game_font, &color),
+                    TextDirection::Vertical => self.image.borrow_mut().draw_game_text_vertical(&text, x, y, game_font, &color)
+                }
+                Ok(Object::Boolean(true))
+            },
+            // blit(image, x, y, alpha, blend_mode), images blend with "alpha" unless told otherwise
+            "blit" => {
+                let alpha = if parameters.len() > 3 { float_parameter(parameters, 3)? } else { 1.0 };
+                let mode = name_parameter(parameters, 4, BlendMode::Alpha, BlendMode::from_name, state)?;
+                let (x, y) = (integer_parameter(parameters, 1)?, integer_parameter(parameters, 2)?);
+
+                // this instance is borrowed while called, drawing onto itself goes through a copy
+                if Rc::ptr_eq(&parameters[0].native_instance_value()?, &this) {
+                    let copy = self.image.borrow().clone();
+                    self.image.borrow_mut().alpha_blit_with_mode(&copy, x, y, alpha, mode);
+                } else {
+                    let source = image_from(&parameters[0])?;
+                    self.image.borrow_mut().alpha_blit_with_mode(&source.borrow(), x, y, alpha, mode);
+                }
+                Ok(Object::Null)
+            },
+            "sub_image" => {
+                let image = self.image.borrow().sub_image(
+                    integer_parameter(parameters, 0)?, integer_parameter(parameters, 1)?,
+                    integer_parameter(parameters, 2)?.max(0) as u32, integer_parameter(parameters, 3)?.max(0) as u32);
+                Ok(Object::NativeInstance(make_reference(ScriptImage::new(image, self.game_font.clone()))))
+            },
+            "mosaic" => {
+                self.image.borrow_mut().mosaic(parameters[0].integer_value()?.max(0) as u32);
+                Ok(Object::Null)
+            },
+            // color_key(color, tolerance) makes every pixel that close to color transparent and returns how many,
+            // tolerance is per channel and 0 when left out
+            "color_key" => {
+                let tolerance = if parameters.len() > 1 { integer_parameter(parameters, 1)?.clamp(0, 255) as u8 } else { 0 };
+                Ok(Object::Integer(apply_color_key(&mut self.image.borrow_mut(), &color_parameter(parameters, 0)?, tolerance) as i64))
+            },
+            // [x, y, width, height] of the pixels that are not transparent, null when there are none
+            "content_bounds" => Ok(match content_bounds(&self.image.borrow()) {
+                Some(bounds) => integer_array(&[bounds.x as i64, bounds.y as i64, bounds.width as i64, bounds.height as i64]),
+                None => Object::Null
+            }),
+            // trim() cuts off the transparent border and returns [x, y], where the rest was in the image before
+            "trim" => {
+                let trimmed = trim(&self.image.borrow());
+                *self.image.borrow_mut() = trimmed.image;
+                Ok(integer_array(&[trimmed.offset.x as i64, trimmed.offset.y as i64]))
+            },
+            // save(filename) writes a png
+            _ => {
+                let filename = parameters[0].string_value()?;
+                self.image.borrow().save(&filename).map_err(|error| RuntimeError::new(&format!("can not save {}: {}", filename, error), state.last_position()))?;
+                Ok(Object::Null)
+            }
+        }
+    }
+
+    fn raw_integer(&self, key: &str) -> Option<i64> {
+        match key {
+            "image_id" => Some(self.registered.id()),
+            _ => None
+        }
+    }
+}
+
+script_object!(ScriptImage);
@@ -0,0 +1,154 @@
+use std::cell::RefCell;
+use clover::{NativeModelInstance, Object, Reference, State};
+use clover::debug::RuntimeError;
+use clover::helper::make_reference;
+use crate::bindings::color::float_parameter;
+use crate::bindings::script_object::{script_object, singleton_model, ScriptObject};
+use crate::engine::input::{Input, InputContext};
+use crate::engine::pointer::PointerSource;
+
+thread_local! {
+    // the script function waiting for Input.start_rebind to finish, the engine can not hold script objects
+    static REBIND_CALLBACK: RefCell<Option<Object>> = RefCell::new(None);
+}
+
+// called with (action, key) once the rebind is done
+pub fn take_rebind_callback() -> Option<Object> {
+    REBIND_CALLBACK.with(|callback| callback.borrow_mut().take())
+}
+
+fn string_array(values: Vec<String>) -> Object {
+    Object::Array(make_reference(values.into_iter().map(|value| Object::String(make_reference(value))).collect()))
+}
+
+// Input() hands out the keyboard and gamepad state the engine keeps up to date
+singleton_model!(InputModel, Input);
+
+// key queries take an optional context, then a key captured above it counts as up
+fn key_in_context(input: &Input, parameters: &[Object], check: fn(&Input, &str) -> bool) -> Result<Object, RuntimeError> {
+    let key = parameters[0].string_value()?;
+
+    let in_context = match parameters.get(1) {
+        Some(context) => input.owner(&key) == context.string_value()?.as_str(),
+        None => true
+    };
+
+    Ok(Object::Boolean(in_context && check(input, &key)))
+}
+
+impl ScriptObject for Input {
+    const NAME: &'static str = "Input";
+    const PROPERTIES: &'static [&'static str] = &[
+        "held", "buttons", "gamepad_connected", "pointer_x", "pointer_y", "pointer_source", "context", "contexts", "actions", "rebinding",
+        "repeat_delay", "repeat_interval", "double_tap_time", "hold_time"
+    ];
+    const METHODS: &'static [(&'static str, usize)] = &[
+        ("is_down", 1), ("is_held", 1), ("is_double_tapped", 1), ("held_time", 1), ("is_button_down", 1), ("axis", 1),
+        ("push_context", 1), ("pop_context", 1), ("is_action_down", 1), ("get_binding", 1), ("set_binding", 2),
+        ("start_rebind", 1), ("cancel_rebind", 0)
+    ];
+
+    fn get_property(&self, key: &str) -> Result<Object, RuntimeError> {
+        Ok(match key {
+            "held" => string_array(self.held_keys()),
+            "buttons" => string_array(self.held_buttons()),
+            "gamepad_connected" => Object::Boolean(self.gamepad_connected),
+            "pointer_x" => Object::Integer(self.pointer.position().0 as i64),
+            "pointer_y" => Object::Integer(self.pointer.position().1 as i64),
+            // null until the mouse or the right stick moved it
+            "pointer_source" => match self.pointer.source() {
+                Some(PointerSource::Mouse) => Object::String(make_reference("mouse".to_string())),
+                Some(PointerSource::Stick) => Object::String(make_reference("stick".to_string())),
+                None => Object::Null
+            },
+            "context" => Object::String(make_reference(self.context_names().pop().unwrap_or_default())),
+            "contexts" => string_array(self.context_names()),
+            "actions" => string_array(self.actions.actions()),
+            "rebinding" => self.rebinding().map(|action| Object::String(make_reference(action.to_string()))).unwrap_or(Object::Null),
+            "repeat_delay" => Object::Float(self.timing.repeat_delay),
+            "repeat_interval" => Object::Float(self.timing.repeat_interval),
+            "double_tap_time" => Object::Float(self.timing.double_tap_time),
+            _ => Object::Float(self.timing.hold_time)
+        })
+    }
+
+    // the timings are in seconds and never negative
+    fn set_property(&mut self, key: &str, value: Object) -> Result<bool, RuntimeError> {
+        let timing = match key {
+            "repeat_delay" => &mut self.timing.repeat_delay,
+            "repeat_interval" => &mut self.timing.repeat_interval,
+            "double_tap_time" => &mut self.timing.double_tap_time,
+            "hold_time" => &mut self.timing.hold_time,
+            _ => return Ok(false)
+        };
+
+        *timing = float_parameter(&[ value ], 0)?.max(0.0);
+        Ok(true)
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, _state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        match key {
+            // is_down(key) sees every held key, is_down(key, context) only the ones no context above captures
+            "is_down" => key_in_context(self, parameters, Input::is_down),
+            // is_held(key) after the hold time, for running
+            "is_held" => key_in_context(self, parameters, Input::is_held),
+            // is_double_tapped(key) until the second press is let go, for dashing
+            "is_double_tapped" => key_in_context(self, parameters, Input::is_double_tapped),
+            // is_action_down(action, context) like is_down for any key bound to the action
+            "is_action_down" => {
+                let action = parameters[0].string_value()?;
+                let context = match parameters.get(1) {
+                    Some(context) => Some(context.string_value()?),
+                    None => None
+                };
+
+                Ok(Object::Boolean(self.actions.keys(&action).iter().any(|key| {
+                    self.is_down(key) && context.as_ref().map(|context| self.owner(key) == context.as_str()).unwrap_or(true)
+                })))
+            },
+            "get_binding" => Ok(string_array(self.actions.keys(parameters[0].string_value()?.as_str()).to_vec())),
+            // set_binding(action, [ keys ])
+            "set_binding" => {
+                let keys = match &parameters[1] {
+                    Object::Array(keys) => keys.borrow().iter().map(|key| key.string_value()).collect::<Result<Vec<String>, RuntimeError>>()?,
+                    key => vec![ key.string_value()? ]
+                };
+                self.actions.bind(parameters[0].string_value()?.as_str(), keys);
+                Ok(Object::Null)
+            },
+            // start_rebind(action, callback) binds the next pressed key instead of sending it to the game,
+            // then calls callback(action, key)
+            "start_rebind" => {
+                self.start_rebind(parameters[0].string_value()?.as_str());
+                let callback = parameters.get(1).cloned();
+                REBIND_CALLBACK.with(|rebind_callback| *rebind_callback.borrow_mut() = callback);
+                Ok(Object::Null)
+            },
+            "cancel_rebind" => {
+                self.cancel_rebind();
+                take_rebind_callback();
+                Ok(Object::Null)
+            },
+            "held_time" => Ok(Object::Float(self.held_time(parameters[0].string_value()?.as_str()))),
+            // push_context("menu"), push_context("text") or push_context(name, [ keys ])
+            "push_context" => {
+                let name = parameters[0].string_value()?;
+                let context = match parameters.get(1) {
+                    Some(Object::Array(keys)) => {
+                        let keys = keys.borrow().iter().map(|key| key.string_value()).collect::<Result<Vec<String>, RuntimeError>>()?;
+                        InputContext::new(&name, &keys.iter().map(|key| key.as_str()).collect::<Vec<&str>>())
+                    },
+                    _ => InputContext::from_name(&name)
+                };
+                self.push_context(context);
+                Ok(Object::Null)
+            },
+            "pop_context" => Ok(Object::Boolean(self.pop_context(parameters[0].string_value()?.as_str()))),
+            "is_button_down" => Ok(Object::Boolean(self.is_button_down(parameters[0].string_value()?.as_str()))),
+            // axis(name)
+            _ => Ok(Object::Float(self.axis(parameters[0].string_value()?.as_str()) as f64))
+        }
+    }
+}
+
+script_object!(Input);
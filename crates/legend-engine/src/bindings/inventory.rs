@@ -0,0 +1,84 @@
+use clover::{NativeModel, NativeModelInstance, Object, Reference, State};
+use clover::debug::RuntimeError;
+use clover::helper::make_reference;
+use crate::bindings::script_object::{lookup, script_object, Registered, Registry, ScriptObject};
+use crate::engine::inventory::Inventory;
+
+thread_local! {
+    static INVENTORIES: Registry<Inventory> = Registry::new();
+}
+
+pub(crate) fn item_parameter(parameters: &[Object], index: usize, state: &State) -> Result<u32, RuntimeError> {
+    let item = parameters[index].integer_value()?;
+    u32::try_from(item).map_err(|_| RuntimeError::new(&format!("{} is not an item", item), state.last_position()))
+}
+
+fn count_parameter(parameters: &[Object], index: usize, state: &State) -> Result<u32, RuntimeError> {
+    match parameters.get(index) {
+        Some(count) => {
+            let count = count.integer_value()?;
+            u32::try_from(count).map_err(|_| RuntimeError::new(&format!("{} is not an item count", count), state.last_position()))
+        },
+        None => Ok(1)
+    }
+}
+
+// the inventory behind a script Inventory instance, equipment and pickups put items into it
+pub fn inventory_from(object: &Object) -> Result<Reference<Inventory>, RuntimeError> {
+    lookup(&INVENTORIES, object, "inventory_id", "an inventory")
+}
+
+// Inventory() is an empty bag of items
+pub struct InventoryModel;
+
+impl NativeModel for InventoryModel {
+    fn call(&mut self, _state: &mut State, _parameters: &[Object]) -> Result<Object, RuntimeError> {
+        let inventory = make_reference(Inventory::new());
+        Ok(Object::NativeInstance(make_reference(ScriptInventory { registered: Registered::new(&INVENTORIES, &inventory), inventory })))
+    }
+}
+
+pub struct ScriptInventory {
+    registered: Registered<Inventory>,
+    inventory: Reference<Inventory>
+}
+
+impl ScriptObject for ScriptInventory {
+    const NAME: &'static str = "Inventory";
+    const PROPERTIES: &'static [&'static str] = &["items"];
+    const METHODS: &'static [(&'static str, usize)] = &[("add", 1), ("remove", 1), ("count", 1)];
+
+    // [[item, count], ...] in item order
+    fn get_property(&self, _key: &str) -> Result<Object, RuntimeError> {
+        let items = self.inventory.borrow().items()
+            .map(|(item, count)| Object::Array(make_reference(vec![ Object::Integer(item as i64), Object::Integer(count as i64) ])))
+            .collect();
+
+        Ok(Object::Array(make_reference(items)))
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        let item = item_parameter(parameters, 0, state)?;
+        let mut inventory = self.inventory.borrow_mut();
+
+        match key {
+            // add(item, count) and remove(item, count), one when the count is left out
+            "add" => {
+                inventory.add(item, count_parameter(parameters, 1, state)?);
+                Ok(Object::Null)
+            },
+            // false without taking any when there are not enough
+            "remove" => Ok(Object::Boolean(inventory.remove(item, count_parameter(parameters, 1, state)?))),
+            _ => Ok(Object::Integer(inventory.count(item) as i64))
+        }
+    }
+
+    fn raw_integer(&self, key: &str) -> Option<i64> {
+        match key {
+            "inventory_id" => Some(self.registered.id()),
+            _ => None
+        }
+    }
+}
+
+script_object!(ScriptInventory);
@@ -0,0 +1,157 @@
+use std::sync::Arc;
+use clover::{NativeModelInstance, Object, Reference, State};
+use clover::debug::RuntimeError;
+use clover::helper::make_reference;
+use crate::bindings::equipment::{stats_parameter, stats_table, STAT_NAMES};
+use crate::bindings::save::{save_table, table_entries, Entries};
+use crate::bindings::script_object::{script_object, singleton_model, ScriptObject};
+use crate::engine::level::{GrowthTable, LevelUp, Progression};
+use crate::engine::save::SaveValue;
+use crate::engine::vfs::Vfs;
+
+// a missing level or experience is a new character
+fn character_value(entries: &Reference<Entries>, key: &str, default: u32) -> Result<u32, RuntimeError> {
+    match entries.borrow().get(key) {
+        None | Some(Object::Null) => Ok(default),
+        Some(value) => Ok(value.integer_value()?.max(0) as u32)
+    }
+}
+
+fn level_up_object(level_up: &LevelUp) -> Object {
+    let table = stats_table(&level_up.gained);
+    let level_up_table = save_table(SaveValue::Table(Default::default()));
+    if let Some(entries) = table_entries(&level_up_table) {
+        let mut entries = entries.borrow_mut();
+        entries.insert("level".to_string(), Object::Integer(level_up.level as i64));
+        entries.insert("gained".to_string(), table);
+    }
+    level_up_table
+}
+
+// the on_level_up hook and the level ups waiting for it, the engine calls it between two updates like the scene hooks
+pub struct LevelHooks {
+    hook: Option<Object>,
+    pending: Vec<(Object, Object)>
+}
+
+impl LevelHooks {
+    pub fn new() -> Self {
+        LevelHooks { hook: None, pending: Vec::new() }
+    }
+
+    // the hook calls for the levels gained since the last call, with the character and the level up
+    pub fn take_level_ups(&mut self) -> Vec<(Object, Object, Object)> {
+        let pending = std::mem::take(&mut self.pending);
+        match self.hook.clone() {
+            Some(hook) => pending.into_iter().map(|(character, level_up)| (hook.clone(), character, level_up)).collect(),
+            None => Vec::new()
+        }
+    }
+}
+
+impl Default for LevelHooks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Levels() reads growth tables and calls on_level_up(character, level_up) for every level a character gains
+pub struct ScriptLevels {
+    hooks: Reference<LevelHooks>,
+    vfs: Arc<Vfs>
+}
+
+impl ScriptLevels {
+    pub fn new(hooks: Reference<LevelHooks>, vfs: Arc<Vfs>) -> Self {
+        ScriptLevels { hooks, vfs }
+    }
+}
+
+singleton_model!(LevelsModel, ScriptLevels);
+
+impl ScriptObject for ScriptLevels {
+    const NAME: &'static str = "Levels";
+    const PROPERTIES: &'static [&'static str] = &["on_level_up"];
+    const METHODS: &'static [(&'static str, usize)] = &[("load", 1)];
+
+    fn get_property(&self, _key: &str) -> Result<Object, RuntimeError> {
+        Ok(self.hooks.borrow().hook.clone().unwrap_or(Object::Null))
+    }
+
+    // on_level_up is a function of the character table and { level, gained }, null for none
+    fn set_property(&mut self, key: &str, value: Object) -> Result<bool, RuntimeError> {
+        match (key, value) {
+            ("on_level_up", Object::Null) => self.hooks.borrow_mut().hook = None,
+            ("on_level_up", hook) => self.hooks.borrow_mut().hook = Some(hook),
+            _ => return Ok(false)
+        }
+
+        Ok(true)
+    }
+
+    // load(filename) reads a [curve] and [growth] file through the vfs
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, state: &mut State, _key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        let filename = parameters[0].string_value()?;
+        let table = self.vfs.read(&filename)
+            .and_then(|source| GrowthTable::from_toml(&String::from_utf8_lossy(&source)))
+            .map_err(|error| RuntimeError::new(&format!("can not load growth {}: {}", filename, error), state.last_position()))?;
+
+        Ok(Object::NativeInstance(make_reference(ScriptGrowth { table, hooks: self.hooks.clone() })))
+    }
+}
+
+script_object!(ScriptLevels);
+
+// gain(character, amount) levels up a character table of level, experience and stats
+pub struct ScriptGrowth {
+    table: GrowthTable,
+    hooks: Reference<LevelHooks>
+}
+
+impl ScriptObject for ScriptGrowth {
+    const NAME: &'static str = "Growth";
+    const PROPERTIES: &'static [&'static str] = &["max_level"];
+    const METHODS: &'static [(&'static str, usize)] = &[("gain", 2), ("experience_to_next", 1), ("required_for", 1)];
+
+    fn get_property(&self, _key: &str) -> Result<Object, RuntimeError> {
+        Ok(Object::Integer(self.table.curve.max_level() as i64))
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        // required_for(level) is the total experience of the level, null past the max level
+        if key == "required_for" {
+            let level = parameters[0].integer_value()?.max(0) as u32;
+            return Ok(self.table.curve.required_for(level).map(|required| Object::Integer(required as i64)).unwrap_or(Object::Null));
+        }
+
+        let character = &parameters[0];
+        let entries = table_entries(character).ok_or_else(|| RuntimeError::new("a character is a SaveTable of level, experience and stats", state.last_position()))?;
+        let mut progression = Progression::new(character_value(&entries, "level", 1)?, character_value(&entries, "experience", 0)?);
+
+        // experience_to_next(character) is null at the max level
+        if key == "experience_to_next" {
+            return Ok(progression.experience_to_next(&self.table.curve).map(|left| Object::Integer(left as i64)).unwrap_or(Object::Null));
+        }
+
+        // gain(character, amount) adds the experience and the growth of every level it reaches to the table,
+        // the level ups in order and each one for on_level_up after the update
+        let amount = parameters[1].integer_value()?.max(0) as u32;
+        let mut stats = stats_parameter(character, state)?;
+        let level_ups = progression.gain_experience(amount, &self.table, &mut stats);
+
+        {
+            let mut entries = entries.borrow_mut();
+            entries.insert("level".to_string(), Object::Integer(progression.level as i64));
+            entries.insert("experience".to_string(), Object::Integer(progression.experience as i64));
+            for name in STAT_NAMES {
+                entries.insert(name.to_string(), Object::Integer(stats.get(name).unwrap_or(0) as i64));
+            }
+        }
+
+        let level_ups: Vec<Object> = level_ups.iter().map(level_up_object).collect();
+        self.hooks.borrow_mut().pending.extend(level_ups.iter().map(|level_up| (character.clone(), level_up.clone())));
+        Ok(Object::Array(make_reference(level_ups)))
+    }
+}
+
+script_object!(ScriptGrowth);
@@ -0,0 +1,59 @@
+use clover::{NativeModel, Object, State};
+use clover::debug::RuntimeError;
+use clover::helper::ensure_parameters_length;
+
+struct MiniGame {
+    update_function: Object,
+    render_function: Object,
+    finish_function: Object
+}
+
+// MiniGame(game, on_finish) from scripts runs game.update / game.render instead of the main ones,
+// the first non null value update returns is handed to on_finish and the caller takes over again
+pub struct MiniGames {
+    games: Vec<MiniGame>
+}
+
+impl MiniGames {
+    pub fn new() -> Self {
+        MiniGames { games: Vec::new() }
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.games.is_empty()
+    }
+
+    // only the top mini game gets updates and input, the ones below wait for it
+    pub fn active_functions(&self) -> Option<(Object, Object)> {
+        self.games.last().map(|game| (game.update_function.clone(), game.render_function.clone()))
+    }
+
+    // pops the top mini game and returns the function to call with its result
+    pub fn finish(&mut self) -> Option<Object> {
+        self.games.pop().map(|game| game.finish_function)
+    }
+
+    pub fn clear(&mut self) {
+        self.games.clear();
+    }
+}
+
+impl Default for MiniGames {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NativeModel for MiniGames {
+    fn call(&mut self, state: &mut State, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        ensure_parameters_length(parameters, 2)?;
+
+        let game = parameters[0].clone();
+        let update_function = state.get_object_property_by_name(game.clone(), "update")?;
+        let render_function = state.get_object_property_by_name(game, "render")?;
+
+        self.games.push(MiniGame { update_function, render_function, finish_function: parameters[1].clone() });
+
+        Ok(Object::Null)
+    }
+}
@@ -1 +1,31 @@
-pub mod color;
\ No newline at end of file
+pub mod color;
+pub mod party;
+pub mod inventory;
+pub mod equipment;
+pub mod status;
+pub mod skill;
+pub mod level;
+pub mod bestiary;
+pub mod minigame;
+pub mod presence;
+pub mod audio;
+pub mod testing;
+pub mod image;
+pub mod sprite;
+pub mod palette;
+pub mod input;
+pub mod debug;
+pub mod dialog;
+pub mod field;
+pub mod movement;
+pub mod world_map;
+pub mod encounter;
+pub mod save;
+pub mod settings;
+pub mod resources;
+pub mod engine;
+pub mod options;
+pub mod accessibility;
+pub mod script_object;
+pub mod animation;
+pub mod scene;
\ No newline at end of file
@@ -0,0 +1,88 @@
+use clover::{NativeModelInstance, Object, Reference, State};
+use clover::debug::{Position, RuntimeError};
+use clover::helper::make_reference;
+use crate::bindings::color::{float_parameter, integer_parameter};
+use crate::bindings::script_object::{script_object, singleton_model, ScriptObject};
+use crate::engine::movement::{ModeRule, MovementMode, MovementRules};
+
+pub(crate) fn mode_from(object: &Object) -> Result<MovementMode, RuntimeError> {
+    let name = object.string_value()?;
+    MovementMode::from_name(&name).ok_or_else(|| RuntimeError::new(&format!("unknown movement mode {}, it is walking, boat or horse", name), Position::none()))
+}
+
+fn tile_parameter(values: &[Object], index: usize, state: &State) -> Result<u16, RuntimeError> {
+    let tile = values[index].integer_value()?;
+    u16::try_from(tile).map_err(|_| RuntimeError::new(&format!("{} is not a tile", tile), state.last_position()))
+}
+
+// [[first, last], ...] inclusive tile ranges
+fn passable_parameter(parameters: &[Object], index: usize, state: &State) -> Result<Vec<(u16, u16)>, RuntimeError> {
+    let ranges = match &parameters[index] {
+        Object::Array(ranges) => ranges.borrow().clone(),
+        _ => return Err(RuntimeError::new("passable tiles are an array of [first, last]", state.last_position()))
+    };
+
+    ranges.iter().map(|range| match range {
+        Object::Array(values) if values.borrow().len() >= 2 => {
+            let values = values.borrow();
+            Ok((tile_parameter(&values, 0, state)?, tile_parameter(&values, 1, state)?))
+        },
+        _ => Err(RuntimeError::new("a passable range is [first, last]", state.last_position()))
+    }).collect()
+}
+
+// where walking, riding and sailing can go, shared by the field leader and world map travel
+pub struct ScriptMovement {
+    movement: Reference<MovementRules>
+}
+
+impl ScriptMovement {
+    pub fn new(movement: Reference<MovementRules>) -> Self {
+        ScriptMovement { movement }
+    }
+}
+
+singleton_model!(MovementModel, ScriptMovement);
+
+impl ScriptObject for ScriptMovement {
+    const NAME: &'static str = "Movement";
+    const PROPERTIES: &'static [&'static str] = &["modes"];
+    const METHODS: &'static [(&'static str, usize)] = &[("set", 5), ("can_enter", 2)];
+
+    // the modes that have a rule, the others go anywhere
+    fn get_property(&self, _key: &str) -> Result<Object, RuntimeError> {
+        let movement = self.movement.borrow();
+        let modes = [MovementMode::Walking, MovementMode::Boat, MovementMode::Horse].into_iter()
+            .filter(|mode| movement.get(*mode).is_some())
+            .map(|mode| Object::String(make_reference(mode.name().to_string())))
+            .collect();
+
+        Ok(Object::Array(make_reference(modes)))
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        let mode = mode_from(&parameters[0])?;
+
+        match key {
+            // set(mode, [[first, last], ...], sprite_base, frames_per_direction, step_interval),
+            // the sprites are frames_per_direction frames for up, right, left and down from sprite_base
+            "set" => {
+                let rule = ModeRule {
+                    passable: passable_parameter(parameters, 1, state)?,
+                    sprite_base: integer_parameter(parameters, 2)?.max(0) as usize,
+                    frames_per_direction: integer_parameter(parameters, 3)?.max(1) as usize,
+                    step_interval: float_parameter(parameters, 4)?.max(0.0)
+                };
+                self.movement.borrow_mut().set(mode, rule);
+                Ok(Object::Null)
+            },
+            // can_enter(mode, tile)
+            _ => {
+                let tile = tile_parameter(parameters, 1, state)?;
+                Ok(Object::Boolean(self.movement.borrow().allows(mode, tile)))
+            }
+        }
+    }
+}
+
+script_object!(ScriptMovement);
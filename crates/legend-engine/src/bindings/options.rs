@@ -0,0 +1,24 @@
+use clover::{NativeModelInstance, Object, Reference, State};
+use clover::debug::RuntimeError;
+use crate::bindings::script_object::{script_object, singleton_model, ScriptObject};
+use crate::engine::options_menu::OptionsMenu;
+
+// Options().open() shows the engine options over the game, which pauses until it is closed
+singleton_model!(OptionsModel, OptionsMenu);
+
+impl ScriptObject for OptionsMenu {
+    const NAME: &'static str = "Options";
+    const PROPERTIES: &'static [&'static str] = &["is_open"];
+    const METHODS: &'static [(&'static str, usize)] = &[("open", 0)];
+
+    fn get_property(&self, _key: &str) -> Result<Object, RuntimeError> {
+        Ok(Object::Boolean(self.is_open()))
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, _state: &mut State, _key: &str, _parameters: &[Object]) -> Result<Object, RuntimeError> {
+        self.open();
+        Ok(Object::Null)
+    }
+}
+
+script_object!(OptionsMenu);
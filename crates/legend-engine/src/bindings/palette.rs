@@ -0,0 +1,127 @@
+use std::rc::Rc;
+use std::sync::Arc;
+use clover::{NativeModel, NativeModelInstance, Object, Reference, State};
+use clover::debug::RuntimeError;
+use clover::helper::make_reference;
+use crate::bindings::color::{color_parameter, float_parameter};
+use crate::bindings::script_object::{lookup, script_object, Registered, Registry, ScriptObject};
+use crate::engine::error::{EngineError, EngineResult};
+use crate::engine::graphics::Palette;
+use crate::engine::vfs::Vfs;
+
+const PALETTE_BYTES: usize = 256 * 3;
+
+thread_local! {
+    static PALETTES: Registry<Palette> = Registry::new();
+}
+
+// Palette() makes a black palette, Palette(color) one of a single color,
+// Palette(filename, index) reads the index-th palette of a game data file
+pub struct PaletteModel {
+    vfs: Arc<Vfs>
+}
+
+impl PaletteModel {
+    pub fn new(vfs: Arc<Vfs>) -> Self {
+        PaletteModel { vfs }
+    }
+
+    fn load(&self, filename: &str, index: usize) -> EngineResult<Palette> {
+        let bytes = self.vfs.read(filename)?;
+
+        let bytes = bytes.get(index * PALETTE_BYTES..).ok_or_else(|| EngineError::NotFound(format!("palette {} in {}", index, filename)))?;
+        Ok(Palette::from_bytes(bytes)?)
+    }
+}
+
+impl NativeModel for PaletteModel {
+    fn call(&mut self, state: &mut State, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        let palette = match parameters.first() {
+            Some(Object::String(filename)) => {
+                let filename = filename.borrow();
+                let index = if parameters.len() > 1 { parameters[1].integer_value()?.max(0) as usize } else { 0 };
+                self.load(filename.as_str(), index).map_err(|error| RuntimeError::new(&format!("can not load palette {}: {}", filename, error), state.last_position()))?
+            },
+            Some(Object::NativeInstance(_)) => Palette::filled(color_parameter(parameters, 0)?),
+            _ => Palette::empty()
+        };
+
+        Ok(Object::NativeInstance(make_reference(ScriptPalette::new(palette))))
+    }
+}
+
+// a palette owned by a script, the engine keeps a handle while it blends it
+pub struct ScriptPalette {
+    registered: Registered<Palette>,
+    palette: Reference<Palette>
+}
+
+impl ScriptPalette {
+    pub fn new(palette: Palette) -> Self {
+        let palette = make_reference(palette);
+        ScriptPalette { registered: Registered::new(&PALETTES, &palette), palette }
+    }
+}
+
+// the palette behind a script Palette instance
+pub fn palette_from(object: &Object) -> Result<Reference<Palette>, RuntimeError> {
+    lookup(&PALETTES, object, "palette_id", "a palette")
+}
+
+pub(crate) fn index_parameter(parameters: &[Object], index: usize, state: &State) -> Result<u8, RuntimeError> {
+    let value = parameters[index].integer_value()?;
+    u8::try_from(value).map_err(|_| RuntimeError::new(&format!("palette index {} is out of range", value), state.last_position()))
+}
+
+impl ScriptObject for ScriptPalette {
+    const NAME: &'static str = "Palette";
+    const PROPERTIES: &'static [&'static str] = &["generation"];
+    const METHODS: &'static [(&'static str, usize)] = &[
+        ("get_color", 1), ("set_color", 2), ("swap", 2), ("animate", 2), ("lerp", 3), ("copy", 0)
+    ];
+
+    fn get_property(&self, _key: &str) -> Result<Object, RuntimeError> {
+        Ok(Object::Integer(self.palette.borrow().generation() as i64))
+    }
+
+    fn call_method(&mut self, this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        match key {
+            "get_color" => {
+                let color = self.palette.borrow().get_color(index_parameter(parameters, 0, state)?);
+                return Ok(Object::NativeInstance(make_reference(color)));
+            },
+            "set_color" => self.palette.borrow_mut().set_color(index_parameter(parameters, 0, state)?, color_parameter(parameters, 1)?),
+            "swap" => self.palette.borrow_mut().swap(index_parameter(parameters, 0, state)?, index_parameter(parameters, 1, state)?),
+            // animate(index, count) moves the colors from index - count to index up by one and wraps the top one around,
+            // calling it every few frames cycles them
+            "animate" => self.palette.borrow_mut().animate(index_parameter(parameters, 0, state)?, index_parameter(parameters, 1, state)?),
+            // lerp(from, to, t) sets every color t of the way from from to to, for blends a script times itself
+            "lerp" => {
+                // this instance is borrowed while called, p.lerp(p, target, t) reads its own palette directly
+                let palette_of = |object: &Object| match Rc::ptr_eq(&object.native_instance_value()?, &this) {
+                    true => Ok(self.palette.clone()),
+                    false => palette_from(object)
+                };
+                let (from, to) = (palette_of(&parameters[0])?, palette_of(&parameters[1])?);
+                let blended = Palette::lerp(&from.borrow(), &to.borrow(), float_parameter(parameters, 2)?);
+                self.palette.borrow_mut().copy_from(&blended);
+            },
+            // copy() is a new palette with the same colors
+            _ => return Ok(Object::NativeInstance(make_reference(ScriptPalette::new(self.palette.borrow().clone()))))
+        }
+        Ok(Object::Null)
+    }
+
+    // "color_17" is the packed 0xrrggbb of color 17, "palette_id" finds the palette for palette_from
+    fn raw_integer(&self, key: &str) -> Option<i64> {
+        if key == "palette_id" {
+            return Some(self.registered.id());
+        }
+
+        let index = key.strip_prefix("color_")?.parse::<u8>().ok()?;
+        let color = self.palette.borrow().get_color(index);
+        Some(((color.r as i64) << 16) | ((color.g as i64) << 8) | color.b as i64)
+    }
+}
+
+script_object!(ScriptPalette);
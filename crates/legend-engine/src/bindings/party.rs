@@ -0,0 +1,129 @@
+use clover::{NativeModel, NativeModelInstance, Object, Reference, State};
+use clover::debug::RuntimeError;
+use clover::helper::make_reference;
+use crate::bindings::movement::mode_from;
+use crate::bindings::save::{save_table, to_save_value};
+use crate::bindings::script_object::{lookup, script_object, Registered, Registry, ScriptObject};
+use crate::engine::party::{Party, DEFAULT_ACTIVE_SIZE};
+use crate::engine::save::SaveValue;
+
+thread_local! {
+    static PARTIES: Registry<Party> = Registry::new();
+}
+
+pub(crate) fn member_parameter(parameters: &[Object], index: usize, state: &State) -> Result<u32, RuntimeError> {
+    let member = parameters[index].integer_value()?;
+    u32::try_from(member).map_err(|_| RuntimeError::new(&format!("{} is not a party member", member), state.last_position()))
+}
+
+fn to_array(members: &[u32]) -> Object {
+    Object::Array(make_reference(members.iter().map(|&member| Object::Integer(member as i64)).collect()))
+}
+
+// Party(size) makes an empty party with room for size active members,
+// Party(table) restores one from the table to_table() made or a save read back
+impl NativeModel for Party {
+    fn call(&mut self, state: &mut State, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        let max_active = match parameters.first() {
+            Some(Object::NativeInstance(_)) => {
+                let party: Party = to_save_value(&parameters[0])?.to_data()
+                    .map_err(|error| RuntimeError::new(&format!("not a saved party: {}", error), state.last_position()))?;
+                return Ok(Object::NativeInstance(make_reference(ScriptParty::new(party))));
+            },
+            Some(size) => {
+                let size = size.integer_value()?;
+                usize::try_from(size).map_err(|_| RuntimeError::new(&format!("{} is not an active party size", size), state.last_position()))?
+            },
+            None => DEFAULT_ACTIVE_SIZE
+        };
+
+        Ok(Object::NativeInstance(make_reference(ScriptParty::new(Party::new(max_active)))))
+    }
+}
+
+// a party owned by a script, saves and the field find it by its id
+pub struct ScriptParty {
+    registered: Registered<Party>,
+    party: Reference<Party>
+}
+
+impl ScriptParty {
+    pub fn new(party: Party) -> Self {
+        let party = make_reference(party);
+        ScriptParty { registered: Registered::new(&PARTIES, &party), party }
+    }
+}
+
+// the party behind a script Party instance
+pub fn party_from(object: &Object) -> Result<Reference<Party>, RuntimeError> {
+    lookup(&PARTIES, object, "party_id", "a party")
+}
+
+// members, formation and mode as save data, for Save().write and to_table()
+pub fn party_save_value(party: &Party) -> Result<SaveValue, RuntimeError> {
+    Ok(SaveValue::from_data(party)?)
+}
+
+impl ScriptObject for ScriptParty {
+    const NAME: &'static str = "Party";
+    const PROPERTIES: &'static [&'static str] = &["active", "reserve", "mode", "leader"];
+    const METHODS: &'static [(&'static str, usize)] = &[
+        ("add", 1), ("remove", 1), ("swap", 2), ("set_leader", 1), ("contains", 1), ("move_to_reserve", 1), ("move_to_active", 1), ("to_table", 0)
+    ];
+
+    fn get_property(&self, key: &str) -> Result<Object, RuntimeError> {
+        let party = self.party.borrow();
+
+        Ok(match key {
+            "active" => to_array(party.active()),
+            "reserve" => to_array(party.reserve()),
+            "mode" => Object::String(make_reference(party.mode.name().to_string())),
+            _ => party.leader().map(|leader| Object::Integer(leader as i64)).unwrap_or(Object::Null)
+        })
+    }
+
+    fn set_property(&mut self, key: &str, value: Object) -> Result<bool, RuntimeError> {
+        if key != "mode" {
+            return Ok(false);
+        }
+
+        self.party.borrow_mut().mode = mode_from(&value)?;
+        Ok(true)
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        let mut party = self.party.borrow_mut();
+
+        // to_table() is a SaveTable of the party, Party(table) makes it again
+        if key == "to_table" {
+            return Ok(save_table(party_save_value(&party)?));
+        }
+
+        if key == "swap" {
+            let member_a = member_parameter(parameters, 0, state)?;
+            let member_b = member_parameter(parameters, 1, state)?;
+            return Ok(Object::Boolean(party.swap(member_a, member_b)));
+        }
+
+        let member = member_parameter(parameters, 0, state)?;
+        let result = match key {
+            "add" => party.add(member),
+            "remove" => party.remove(member),
+            "set_leader" => party.set_leader(member),
+            "contains" => party.contains(member),
+            "move_to_reserve" => party.move_to_reserve(member),
+            _ => party.move_to_active(member)
+        };
+
+        Ok(Object::Boolean(result))
+    }
+
+    fn raw_integer(&self, key: &str) -> Option<i64> {
+        match key {
+            "party_id" => Some(self.registered.id()),
+            _ => None
+        }
+    }
+}
+
+script_object!(ScriptParty);
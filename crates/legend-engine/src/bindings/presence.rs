@@ -0,0 +1,39 @@
+use clover::{NativeModelInstance, Object, Reference, State};
+use clover::debug::RuntimeError;
+use clover::helper::make_reference;
+use crate::bindings::script_object::{script_object, singleton_model, ScriptObject};
+use crate::engine::presence::Presence;
+
+// Presence() always hands out the one presence the engine reports
+singleton_model!(PresenceModel, Presence);
+
+impl ScriptObject for Presence {
+    const NAME: &'static str = "Presence";
+    const PROPERTIES: &'static [&'static str] = &["chapter", "location", "playtime"];
+    const METHODS: &'static [(&'static str, usize)] = &[];
+
+    fn get_property(&self, key: &str) -> Result<Object, RuntimeError> {
+        Ok(match key {
+            "chapter" => Object::String(make_reference(self.chapter.clone())),
+            "location" => Object::String(make_reference(self.location.clone())),
+            _ => Object::Float(self.playtime)
+        })
+    }
+
+    fn set_property(&mut self, key: &str, value: Object) -> Result<bool, RuntimeError> {
+        match key {
+            "chapter" => self.chapter = value.string_value()?.to_string(),
+            "location" => self.location = value.string_value()?.to_string(),
+            // restored from a save
+            _ => self.playtime = value.float_value()?
+        }
+
+        Ok(true)
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, _state: &mut State, _key: &str, _parameters: &[Object]) -> Result<Object, RuntimeError> {
+        Ok(Object::Null)
+    }
+}
+
+script_object!(Presence);
@@ -0,0 +1,69 @@
+use clover::{NativeModelInstance, Object, Reference, State};
+use clover::debug::RuntimeError;
+use clover::helper::make_reference;
+use crate::bindings::script_object::{script_object, singleton_model, ScriptObject};
+use crate::engine::background_load::BackgroundLoad;
+use crate::engine::manifest::Preloader;
+
+// Resources() reads the next scene ahead while a transition plays
+singleton_model!(ResourcesModel, Preloader);
+
+impl ScriptObject for Preloader {
+    const NAME: &'static str = "Resources";
+    const PROPERTIES: &'static [&'static str] = &["ready", "progress", "scenes"];
+    const METHODS: &'static [(&'static str, usize)] = &[("prefetch", 1), ("preload", 1)];
+
+    fn get_property(&self, key: &str) -> Result<Object, RuntimeError> {
+        Ok(match key {
+            "ready" => Object::Boolean(self.is_ready()),
+            "progress" => Object::Float(self.progress()),
+            _ => Object::Array(make_reference(self.manifests().scenes().into_iter().map(|scene| Object::String(make_reference(scene))).collect()))
+        })
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, _state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        match key {
+            // prefetch("town") when the fade out starts, ready turns true once every file of the town is read
+            "prefetch" => {
+                self.prefetch(parameters[0].string_value()?.as_str())?;
+                Ok(Object::Null)
+            },
+            // preload(["town", "MAPS/CAVE.MAP"]) reads scenes and files on a thread and returns the load to watch,
+            // its progress goes from 0 to 1 while the game keeps drawing
+            _ => {
+                let names = match &parameters[0] {
+                    Object::Array(names) => names.borrow().iter().map(|name| name.string_value()).collect::<Result<Vec<String>, RuntimeError>>()?,
+                    name => vec![ name.string_value()? ]
+                };
+                Ok(Object::NativeInstance(make_reference(self.preload(&names)?)))
+            }
+        }
+    }
+}
+
+script_object!(Preloader);
+
+impl ScriptObject for BackgroundLoad {
+    const NAME: &'static str = "Load";
+    const PROPERTIES: &'static [&'static str] = &["progress", "ready", "loaded", "total", "failed", "cancelled"];
+    const METHODS: &'static [(&'static str, usize)] = &[("cancel", 0)];
+
+    fn get_property(&self, key: &str) -> Result<Object, RuntimeError> {
+        Ok(match key {
+            "progress" => Object::Float(self.progress()),
+            "ready" => Object::Boolean(self.is_ready()),
+            "loaded" => Object::Integer(self.loaded() as i64),
+            "total" => Object::Integer(self.total() as i64),
+            // names that could not be read, the scene can still start and report them where they are used
+            "failed" => Object::Array(make_reference(self.failed().into_iter().map(|name| Object::String(make_reference(name))).collect())),
+            _ => Object::Boolean(self.is_cancelled())
+        })
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, _state: &mut State, _key: &str, _parameters: &[Object]) -> Result<Object, RuntimeError> {
+        self.cancel();
+        Ok(Object::Null)
+    }
+}
+
+script_object!(BackgroundLoad);
@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+use clover::{NativeModel, NativeModelInstance, Object, Reference, State};
+use clover::debug::{Position, RuntimeError};
+use clover::helper::make_reference;
+use crate::bindings::party::{party_from, party_save_value};
+use crate::bindings::script_object::{lookup, script_object, singleton_model, Registered, Registry, ScriptObject};
+use crate::engine::error::EngineError;
+use crate::engine::save::{SaveStore, SaveValue, SlotInfo};
+
+// deeper than this is a table holding itself
+const MAX_DEPTH: usize = 32;
+
+pub(crate) type Entries = BTreeMap<String, Object>;
+
+thread_local! {
+    static TABLES: Registry<Entries> = Registry::new();
+}
+
+// a string keyed table scripts fill with what they want saved, table.gold = 10 or table["gold"] = 10.
+// its keys are whatever the script sets, so it has no fixed names to be a ScriptObject with
+pub struct SaveTable {
+    registered: Registered<Entries>,
+    entries: Reference<Entries>
+}
+
+impl SaveTable {
+    pub fn new(entries: Entries) -> Self {
+        let entries = make_reference(entries);
+        SaveTable { registered: Registered::new(&TABLES, &entries), entries }
+    }
+}
+
+pub(crate) fn table_entries(object: &Object) -> Option<Reference<Entries>> {
+    lookup(&TABLES, object, "table_id", "a table").ok()
+}
+
+// what Save().write stores for a script value, a Party is saved as the table to_table() makes
+pub(crate) fn to_save_value(object: &Object) -> Result<SaveValue, RuntimeError> {
+    save_value(object, 0)
+}
+
+// the script object read back for a save value, tables become SaveTable
+pub(crate) fn save_table(value: SaveValue) -> Object {
+    to_object(value)
+}
+
+fn save_value(object: &Object, depth: usize) -> Result<SaveValue, RuntimeError> {
+    if depth > MAX_DEPTH {
+        return Err(RuntimeError::new("can not save a table that holds itself", Position::none()));
+    }
+
+    Ok(match object {
+        Object::Null => SaveValue::Null,
+        Object::Boolean(value) => SaveValue::Boolean(*value),
+        Object::Integer(value) => SaveValue::Integer(*value),
+        Object::Float(value) => SaveValue::Float(*value),
+        Object::String(value) => SaveValue::String(value.borrow().clone()),
+        Object::Array(values) => SaveValue::Array(values.borrow().iter().map(|value| save_value(value, depth + 1)).collect::<Result<_, _>>()?),
+        _ => match (table_entries(object), party_from(object)) {
+            (Some(entries), _) => {
+                let entries = entries.borrow().clone();
+                SaveValue::Table(entries.iter().map(|(key, value)| Ok((key.clone(), save_value(value, depth + 1)?))).collect::<Result<_, RuntimeError>>()?)
+            },
+            (None, Ok(party)) => party_save_value(&party.borrow())?,
+            (None, Err(_)) => return Err(RuntimeError::new("only null, booleans, numbers, strings, arrays, SaveTable and Party can be saved", Position::none()))
+        }
+    })
+}
+
+fn to_object(value: SaveValue) -> Object {
+    match value {
+        SaveValue::Null => Object::Null,
+        SaveValue::Boolean(value) => Object::Boolean(value),
+        SaveValue::Integer(value) => Object::Integer(value),
+        SaveValue::Float(value) => Object::Float(value),
+        SaveValue::String(value) => Object::String(make_reference(value)),
+        SaveValue::Array(values) => Object::Array(make_reference(values.into_iter().map(to_object).collect())),
+        SaveValue::Table(entries) => Object::NativeInstance(make_reference(SaveTable::new(entries.into_iter().map(|(key, value)| (key, to_object(value))).collect())))
+    }
+}
+
+fn slot_parameter(parameters: &[Object], index: usize) -> Result<u32, RuntimeError> {
+    let slot = parameters[index].integer_value()?;
+    u32::try_from(slot).map_err(|_| RuntimeError::new(&format!("{} is not a save slot", slot), Position::none()))
+}
+
+// a table with slot, version, saved_at and corrupt for the load menu
+fn slot_object(info: SlotInfo) -> Object {
+    let mut entries = BTreeMap::new();
+    entries.insert("slot".to_string(), Object::Integer(info.slot as i64));
+    entries.insert("version".to_string(), info.version.map_or(Object::Null, |version| Object::Integer(version as i64)));
+    entries.insert("saved_at".to_string(), info.saved_at.map_or(Object::Null, |saved_at| Object::Integer(saved_at as i64)));
+    entries.insert("corrupt".to_string(), Object::Boolean(info.corrupt));
+    Object::NativeInstance(make_reference(SaveTable::new(entries)))
+}
+
+// SaveTable() makes an empty table to fill and hand to Save().write
+pub struct SaveTableModel;
+
+impl NativeModel for SaveTableModel {
+    fn call(&mut self, _state: &mut State, _parameters: &[Object]) -> Result<Object, RuntimeError> {
+        Ok(Object::NativeInstance(make_reference(SaveTable::new(BTreeMap::new()))))
+    }
+}
+
+impl NativeModelInstance for SaveTable {
+    fn index_get(&self, this: Reference<dyn NativeModelInstance>, index: &Object) -> Result<Object, RuntimeError> {
+        self.instance_get(this, index.string_value()?.as_str())
+    }
+
+    fn index_set(&mut self, this: Reference<dyn NativeModelInstance>, index: &Object, value: Object) -> Result<(), RuntimeError> {
+        self.instance_set(this, index.string_value()?.as_str(), value)
+    }
+
+    // missing keys read as null, like a fresh game
+    fn instance_get(&self, _this: Reference<dyn NativeModelInstance>, key: &str) -> Result<Object, RuntimeError> {
+        Ok(self.entries.borrow().get(key).cloned().unwrap_or(Object::Null))
+    }
+
+    fn instance_set(&mut self, _this: Reference<dyn NativeModelInstance>, key: &str, value: Object) -> Result<(), RuntimeError> {
+        self.entries.borrow_mut().insert(key.to_string(), value);
+        Ok(())
+    }
+
+    fn call(&mut self, _this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, _parameters: &[Object]) -> Result<Object, RuntimeError> {
+        Err(RuntimeError::new(&format!("can not call {}", key), state.last_position()))
+    }
+
+    fn raw_get_integer(&self, key: &str) -> Option<i64> {
+        match key {
+            "table_id" => Some(self.registered.id()),
+            _ => None
+        }
+    }
+}
+
+// Save() hands out the slots under the save directory
+singleton_model!(SaveModel, SaveStore);
+
+impl ScriptObject for SaveStore {
+    const NAME: &'static str = "Save";
+    const PROPERTIES: &'static [&'static str] = &["directory"];
+    const METHODS: &'static [(&'static str, usize)] = &[("write", 2), ("read", 1), ("exists", 1), ("delete", 1), ("slots", 0)];
+
+    fn get_property(&self, _key: &str) -> Result<Object, RuntimeError> {
+        Ok(Object::String(make_reference(self.directory().display().to_string())))
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, _state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        match key {
+            // write(slot, table) replaces the slot, a crash half way keeps the old one
+            "write" => {
+                let data = to_save_value(&parameters[1])?;
+                self.write(slot_parameter(parameters, 0)?, &data)?;
+                Ok(Object::Null)
+            },
+            // read(slot) is null for an empty slot, a corrupt one is an error
+            "read" => match self.read(slot_parameter(parameters, 0)?) {
+                Ok(save) => Ok(to_object(save.data)),
+                Err(EngineError::NotFound(_)) => Ok(Object::Null),
+                Err(error) => Err(error.into())
+            },
+            "exists" => Ok(Object::Boolean(self.exists(slot_parameter(parameters, 0)?))),
+            "delete" => {
+                self.delete(slot_parameter(parameters, 0)?)?;
+                Ok(Object::Null)
+            },
+            // slots() for the load menu
+            _ => Ok(Object::Array(make_reference(self.slots().into_iter().map(slot_object).collect())))
+        }
+    }
+}
+
+script_object!(SaveStore);
@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use clover::{NativeModelInstance, Object, Reference, State};
+use clover::debug::RuntimeError;
+use clover::helper::make_reference;
+use crate::bindings::script_object::{script_object, singleton_model, ScriptObject};
+use crate::engine::transition::{MapTransition, TransitionDefinition, TransitionStep};
+
+struct SceneHooks {
+    update: Object,
+    render: Object,
+    // called with the value push or switch was given
+    enter: Option<Object>,
+    exit: Option<Object>
+}
+
+// what the engine runs between two updates, a hook can not run while a script is calling Scenes()
+pub enum SceneHook {
+    Enter(Object, Object),
+    Exit(Object)
+}
+
+enum SceneChange {
+    Push(String, Object),
+    Pop,
+    Switch(String, Object)
+}
+
+// Scenes().register(name, scene) takes a scene with update and render and optional enter and exit,
+// push(name, value), pop() and switch(name, value) change the scene at the top of the stack before the next update.
+// only the top scene is updated and rendered, with an empty stack game.update and game.render run as before.
+// a map change covers the screen, switches to map_scene with [map, x, y, facing] and uncovers it again
+#[derive(Default)]
+pub struct Scenes {
+    registered: HashMap<String, SceneHooks>,
+    stack: Vec<String>,
+    pending: Vec<SceneChange>,
+    map_scene: Option<String>,
+    transition: Option<MapTransition>
+}
+
+fn optional_hook(state: &mut State, scene: &Object, name: &str) -> Option<Object> {
+    match state.get_object_property_by_name(scene.clone(), name) {
+        Ok(Object::Null) | Err(_) => None,
+        Ok(function) => Some(function)
+    }
+}
+
+impl Scenes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn current(&self) -> Option<&str> {
+        self.stack.last().map(String::as_str)
+    }
+
+    // update and render of the top scene
+    pub fn active_functions(&self) -> Option<(Object, Object)> {
+        let hooks = self.registered.get(self.current()?)?;
+        Some((hooks.update.clone(), hooks.render.clone()))
+    }
+
+    pub fn has_changes(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    // applies the queued changes to the stack, the caller runs the returned hooks in order
+    pub fn take_changes(&mut self) -> Vec<SceneHook> {
+        let mut hooks = Vec::new();
+
+        for change in std::mem::take(&mut self.pending) {
+            match change {
+                SceneChange::Push(name, value) => {
+                    if let Some(enter) = self.enter_hook(&name) {
+                        hooks.push(SceneHook::Enter(enter, value));
+                    }
+                    self.stack.push(name);
+                },
+                SceneChange::Pop => {
+                    if let Some(name) = self.stack.pop() {
+                        hooks.extend(self.exit_hook(&name).map(SceneHook::Exit));
+                    }
+                },
+                SceneChange::Switch(name, value) => {
+                    if let Some(previous) = self.stack.pop() {
+                        hooks.extend(self.exit_hook(&previous).map(SceneHook::Exit));
+                    }
+                    if let Some(enter) = self.enter_hook(&name) {
+                        hooks.push(SceneHook::Enter(enter, value));
+                    }
+                    self.stack.push(name);
+                }
+            }
+        }
+
+        hooks
+    }
+
+    fn enter_hook(&self, name: &str) -> Option<Object> {
+        self.registered.get(name).and_then(|hooks| hooks.enter.clone())
+    }
+
+    fn exit_hook(&self, name: &str) -> Option<Object> {
+        self.registered.get(name).and_then(|hooks| hooks.exit.clone())
+    }
+
+    // for the engine side, the same as a script calling push(name, value)
+    pub fn push(&mut self, name: String, value: Object, state: &State) -> Result<(), RuntimeError> {
+        self.queue(SceneChange::Push(name, value), state)
+    }
+
+    // and switch(name, value)
+    pub fn switch(&mut self, name: String, value: Object, state: &State) -> Result<(), RuntimeError> {
+        self.queue(SceneChange::Switch(name, value), state)
+    }
+
+    // a door the field leader stepped on, nothing happens while another map change runs
+    pub fn start_transition(&mut self, definition: &TransitionDefinition) {
+        if self.transition.is_none() {
+            self.transition = Some(MapTransition::new(definition));
+        }
+    }
+
+    pub fn transition(&self) -> Option<&MapTransition> {
+        self.transition.as_ref()
+    }
+
+    // runs the map change one update further, in the order of its steps: the sounds to play are returned,
+    // the switch to the destination is queued once the screen is covered
+    pub fn update_transition(&mut self, delta: f64, state: &State) -> Result<Vec<String>, RuntimeError> {
+        let steps = match self.transition.as_mut() {
+            Some(transition) => transition.step(delta),
+            None => return Ok(Vec::new())
+        };
+
+        let mut sounds = Vec::new();
+        for step in steps {
+            match step {
+                TransitionStep::PlaySound(sound) => sounds.push(sound),
+                TransitionStep::LoadMap { map, x, y, facing } => {
+                    let scene = self.map_scene.clone().ok_or_else(|| RuntimeError::new(&format!("Scenes().map_scene is not set for the change to {}", map), state.last_position()))?;
+                    let value = Object::Array(make_reference(vec![
+                        Object::String(make_reference(map)),
+                        Object::Integer(x as i64),
+                        Object::Integer(y as i64),
+                        Object::String(make_reference(facing.name().to_string()))
+                    ]));
+                    self.queue(SceneChange::Switch(scene, value), state)?;
+                },
+                TransitionStep::Finished => self.transition = None
+            }
+        }
+
+        Ok(sounds)
+    }
+
+    fn queue(&mut self, change: SceneChange, state: &State) -> Result<(), RuntimeError> {
+        if let SceneChange::Push(name, _) | SceneChange::Switch(name, _) = &change {
+            if !self.registered.contains_key(name) {
+                return Err(RuntimeError::new(&format!("no scene {} is registered", name), state.last_position()));
+            }
+        }
+
+        self.pending.push(change);
+        Ok(())
+    }
+}
+
+singleton_model!(ScenesModel, Scenes);
+
+impl ScriptObject for Scenes {
+    const NAME: &'static str = "Scenes";
+    const PROPERTIES: &'static [&'static str] = &["current", "depth", "map_scene", "transitioning"];
+    const METHODS: &'static [(&'static str, usize)] = &[("register", 2), ("push", 1), ("pop", 0), ("switch", 1)];
+
+    fn get_property(&self, key: &str) -> Result<Object, RuntimeError> {
+        Ok(match key {
+            // null with no scene on the stack
+            "current" => self.current().map(|name| Object::String(make_reference(name.to_string()))).unwrap_or(Object::Null),
+            "depth" => Object::Integer(self.stack.len() as i64),
+            "map_scene" => self.map_scene.as_ref().map(|name| Object::String(make_reference(name.clone()))).unwrap_or(Object::Null),
+            // true from a door until the new map is uncovered, scenes can hold the player still meanwhile
+            _ => Object::Boolean(self.transition.is_some())
+        })
+    }
+
+    // map_scene is entered with [map, x, y, facing] by map changes, its enter loads the map and places the leader
+    fn set_property(&mut self, key: &str, value: Object) -> Result<bool, RuntimeError> {
+        match (key, value) {
+            ("map_scene", Object::Null) => self.map_scene = None,
+            ("map_scene", value) => self.map_scene = Some(value.string_value()?),
+            _ => return Ok(false)
+        }
+
+        Ok(true)
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        let value = parameters.get(1).cloned().unwrap_or(Object::Null);
+
+        match key {
+            "register" => {
+                let scene = parameters[1].clone();
+                let hooks = SceneHooks {
+                    update: state.get_object_property_by_name(scene.clone(), "update")?,
+                    render: state.get_object_property_by_name(scene.clone(), "render")?,
+                    enter: optional_hook(state, &scene, "enter"),
+                    exit: optional_hook(state, &scene, "exit")
+                };
+                self.registered.insert(parameters[0].string_value()?, hooks);
+            },
+            "push" => self.queue(SceneChange::Push(parameters[0].string_value()?, value), state)?,
+            "pop" => self.queue(SceneChange::Pop, state)?,
+            _ => self.queue(SceneChange::Switch(parameters[0].string_value()?, value), state)?
+        }
+        Ok(Object::Null)
+    }
+}
+
+script_object!(Scenes);
@@ -0,0 +1,187 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+use std::thread::LocalKey;
+use clover::{NativeModelInstance, Object, Reference, State};
+use clover::debug::{Position, RuntimeError};
+
+// an engine type scripts use through a native instance. script_object! turns it into a NativeModelInstance,
+// so a binding only lists its names and handles them, and every binding reports unknown names and missing parameters the same way
+pub trait ScriptObject {
+    // the name in error messages
+    const NAME: &'static str;
+    // readable with instance.name or instance["name"]
+    const PROPERTIES: &'static [&'static str];
+    // callable as instance.name(...), with the least number of parameters each one takes
+    const METHODS: &'static [(&'static str, usize)];
+
+    // only asked for names in PROPERTIES
+    fn get_property(&self, key: &str) -> Result<Object, RuntimeError>;
+
+    // false when the property is read only
+    fn set_property(&mut self, _key: &str, _value: Object) -> Result<bool, RuntimeError> {
+        Ok(false)
+    }
+
+    // only asked for names in METHODS, with at least as many parameters as listed.
+    // this is the instance being called, it is borrowed until the call returns
+    fn call_method(&mut self, this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError>;
+
+    // for the engine side, which can not downcast instances and asks for integers instead
+    fn raw_integer(&self, _key: &str) -> Option<i64> {
+        None
+    }
+}
+
+pub(crate) fn method_arity<T: ScriptObject>(key: &str) -> Option<usize> {
+    T::METHODS.iter().find(|(name, _)| *name == key).map(|(_, arity)| *arity)
+}
+
+pub(crate) fn unknown_index<T: ScriptObject>(key: &str) -> RuntimeError {
+    let names: Vec<&str> = T::PROPERTIES.iter().copied().chain(T::METHODS.iter().map(|(name, _)| *name)).collect();
+    RuntimeError::new(&format!("{} has no {}, it has {}", T::NAME, key, names.join(", ")), Position::none())
+}
+
+pub(crate) fn read_only<T: ScriptObject>(key: &str) -> RuntimeError {
+    RuntimeError::new(&format!("can not set {} of {}", key, T::NAME), Position::none())
+}
+
+pub(crate) fn missing_parameters<T: ScriptObject>(key: &str, arity: usize, state: &State) -> RuntimeError {
+    RuntimeError::new(&format!("{}.{} needs {} parameters", T::NAME, key, arity), state.last_position())
+}
+
+// script objects can not be downcast, so the engine finds what is behind an instance by an id it reads with raw_get_integer.
+// a binding keeps one registry per type in a thread_local
+pub struct Registry<T: 'static> {
+    next_id: Cell<i64>,
+    entries: RefCell<HashMap<i64, Weak<RefCell<T>>>>
+}
+
+impl<T> Registry<T> {
+    pub fn new() -> Self {
+        Registry { next_id: Cell::new(1), entries: RefCell::new(HashMap::new()) }
+    }
+}
+
+impl<T> Default for Registry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// the id of a value in a registry, an instance keeps it and the value is forgotten when the instance is dropped
+pub struct Registered<T: 'static> {
+    id: i64,
+    registry: &'static LocalKey<Registry<T>>
+}
+
+impl<T> Registered<T> {
+    pub fn new(registry: &'static LocalKey<Registry<T>>, value: &Reference<T>) -> Self {
+        let id = registry.with(|registry| {
+            let id = registry.next_id.get();
+            registry.next_id.set(id + 1);
+            registry.entries.borrow_mut().insert(id, Rc::downgrade(value));
+            id
+        });
+
+        Registered { id, registry }
+    }
+
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+}
+
+impl<T> Drop for Registered<T> {
+    fn drop(&mut self) {
+        let id = self.id;
+        let _ = self.registry.try_with(|registry| registry.entries.borrow_mut().remove(&id));
+    }
+}
+
+// the value behind an instance whose raw_get_integer(id_key) is its id, name is what the errors call it
+pub fn lookup<T>(registry: &'static LocalKey<Registry<T>>, object: &Object, id_key: &str, name: &str) -> Result<Reference<T>, RuntimeError> {
+    let instance = object.native_instance_value()?;
+    let id = instance.try_borrow().map_err(|_| RuntimeError::new(&format!("{} is in use", name), Position::none()))?.raw_get_integer(id_key);
+
+    id.and_then(|id| registry.with(|registry| registry.entries.borrow().get(&id).and_then(|value| value.upgrade())))
+        .ok_or_else(|| RuntimeError::new(&format!("not {}", name), Position::none()))
+}
+
+// implements NativeModelInstance for a ScriptObject
+macro_rules! script_object {
+    ($type:ty) => {
+        impl clover::NativeModelInstance for $type {
+            fn index_get(&self, this: clover::Reference<dyn clover::NativeModelInstance>, index: &clover::Object) -> Result<clover::Object, clover::debug::RuntimeError> {
+                self.instance_get(this, index.string_value()?.as_str())
+            }
+
+            fn index_set(&mut self, this: clover::Reference<dyn clover::NativeModelInstance>, index: &clover::Object, value: clover::Object) -> Result<(), clover::debug::RuntimeError> {
+                self.instance_set(this, index.string_value()?.as_str(), value)
+            }
+
+            fn instance_get(&self, this: clover::Reference<dyn clover::NativeModelInstance>, key: &str) -> Result<clover::Object, clover::debug::RuntimeError> {
+                use $crate::bindings::script_object::{method_arity, unknown_index, ScriptObject};
+
+                if method_arity::<$type>(key).is_some() {
+                    Ok(clover::Object::InstanceNativeFunction(this, key.to_string()))
+                } else if <$type as ScriptObject>::PROPERTIES.contains(&key) {
+                    self.get_property(key)
+                } else {
+                    Err(unknown_index::<$type>(key))
+                }
+            }
+
+            fn instance_set(&mut self, _this: clover::Reference<dyn clover::NativeModelInstance>, key: &str, value: clover::Object) -> Result<(), clover::debug::RuntimeError> {
+                use $crate::bindings::script_object::{read_only, unknown_index, ScriptObject};
+
+                if !<$type as ScriptObject>::PROPERTIES.contains(&key) {
+                    return Err(unknown_index::<$type>(key));
+                }
+
+                match self.set_property(key, value)? {
+                    true => Ok(()),
+                    false => Err(read_only::<$type>(key))
+                }
+            }
+
+            fn call(&mut self, this: clover::Reference<dyn clover::NativeModelInstance>, state: &mut clover::State, key: &str, parameters: &[clover::Object]) -> Result<clover::Object, clover::debug::RuntimeError> {
+                use $crate::bindings::script_object::{method_arity, missing_parameters, unknown_index, ScriptObject};
+
+                match method_arity::<$type>(key) {
+                    Some(arity) if parameters.len() < arity => Err(missing_parameters::<$type>(key, arity, state)),
+                    Some(_) => self.call_method(this, state, key, parameters),
+                    None => Err(unknown_index::<$type>(key))
+                }
+            }
+
+            fn raw_get_integer(&self, key: &str) -> Option<i64> {
+                $crate::bindings::script_object::ScriptObject::raw_integer(self, key)
+            }
+        }
+    };
+}
+
+// a model that always hands out the one instance the engine owns, like Input() or Settings()
+macro_rules! singleton_model {
+    ($model:ident, $type:ty) => {
+        pub struct $model {
+            instance: clover::Reference<$type>
+        }
+
+        impl $model {
+            pub fn new(instance: clover::Reference<$type>) -> Self {
+                $model { instance }
+            }
+        }
+
+        impl clover::NativeModel for $model {
+            fn call(&mut self, _state: &mut clover::State, _parameters: &[clover::Object]) -> Result<clover::Object, clover::debug::RuntimeError> {
+                Ok(clover::Object::NativeInstance(self.instance.clone()))
+            }
+        }
+    };
+}
+
+pub(crate) use script_object;
+pub(crate) use singleton_model;
@@ -0,0 +1,76 @@
+use clover::{NativeModelInstance, Object, Reference, State};
+use clover::debug::{Position, RuntimeError};
+use clover::helper::make_reference;
+use crate::bindings::color::float_parameter;
+use crate::bindings::script_object::{script_object, singleton_model, ScriptObject};
+use crate::engine::dialog::TextSpeed;
+use crate::engine::settings::{SettingsFile, MAX_SCALE, MIN_SCALE};
+
+// Settings() hands out what settings.toml and the command line chose, changes last once save() is called
+singleton_model!(SettingsModel, SettingsFile);
+
+fn string_object(value: &str) -> Object {
+    Object::String(make_reference(value.to_string()))
+}
+
+impl ScriptObject for SettingsFile {
+    const NAME: &'static str = "Settings";
+    const PROPERTIES: &'static [&'static str] = &[
+        "scale", "fullscreen", "master_volume", "music_volume", "sound_volume", "data_path", "script",
+        "language", "compress_saves", "text_speed", "screen_reader", "path"
+    ];
+    const METHODS: &'static [(&'static str, usize)] = &[("save", 0)];
+
+    fn get_property(&self, key: &str) -> Result<Object, RuntimeError> {
+        Ok(match key {
+            "scale" => Object::Integer(self.settings.scale as i64),
+            "fullscreen" => Object::Boolean(self.settings.fullscreen),
+            "master_volume" => Object::Float(self.settings.master_volume as f64),
+            "music_volume" => Object::Float(self.settings.music_volume as f64),
+            "sound_volume" => Object::Float(self.settings.sound_volume as f64),
+            "data_path" => self.settings.data_path.as_deref().map_or(Object::Null, string_object),
+            "script" => self.settings.script.as_deref().map_or(Object::Null, string_object),
+            "language" => string_object(&self.settings.language),
+            "compress_saves" => Object::Boolean(self.settings.compress_saves),
+            "text_speed" => string_object(self.settings.text_speed().name()),
+            "screen_reader" => Object::Boolean(self.settings.screen_reader),
+            _ => string_object(&self.path().display().to_string())
+        })
+    }
+
+    fn set_property(&mut self, key: &str, value: Object) -> Result<bool, RuntimeError> {
+        let settings = &mut self.settings;
+        match (key, value) {
+            ("scale", value) => settings.scale = (value.integer_value()?.max(0) as u32).clamp(MIN_SCALE, MAX_SCALE),
+            ("fullscreen", Object::Boolean(value)) => settings.fullscreen = value,
+            ("master_volume", value) => settings.master_volume = float_parameter(&[ value ], 0)?.clamp(0.0, 1.0) as f32,
+            ("music_volume", value) => settings.music_volume = float_parameter(&[ value ], 0)?.clamp(0.0, 1.0) as f32,
+            ("sound_volume", value) => settings.sound_volume = float_parameter(&[ value ], 0)?.clamp(0.0, 1.0) as f32,
+            ("data_path", Object::Null) => settings.data_path = None,
+            ("data_path", value) => settings.data_path = Some(value.string_value()?),
+            ("script", Object::Null) => settings.script = None,
+            ("script", value) => settings.script = Some(value.string_value()?),
+            ("language", value) => settings.language = value.string_value()?,
+            ("compress_saves", Object::Boolean(value)) => settings.compress_saves = value,
+            ("screen_reader", Object::Boolean(value)) => settings.screen_reader = value,
+            ("text_speed", value) => {
+                let name = value.string_value()?;
+                match TextSpeed::from_name(&name) {
+                    Some(speed) => settings.text_speed = speed.name().to_string(),
+                    None => return Err(RuntimeError::new(&format!("unknown text speed {}", name), Position::none()))
+                }
+            },
+            _ => return Ok(false)
+        }
+
+        Ok(true)
+    }
+
+    // save() keeps the changes, scale, fullscreen, data_path, script and compress_saves take effect on the next launch
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, _state: &mut State, _key: &str, _parameters: &[Object]) -> Result<Object, RuntimeError> {
+        self.save()?;
+        Ok(Object::Null)
+    }
+}
+
+script_object!(SettingsFile);
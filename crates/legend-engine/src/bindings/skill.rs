@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use clover::{NativeModel, NativeModelInstance, Object, Reference, State};
+use clover::debug::RuntimeError;
+use clover::helper::{ensure_parameters_length, make_reference};
+use crate::bindings::color::float_parameter;
+use crate::bindings::equipment::stats_parameter;
+use crate::bindings::save::{save_table, table_entries};
+use crate::bindings::script_object::{script_object, ScriptObject};
+use crate::engine::animation::{Animation, LoopMode};
+use crate::engine::save::SaveValue;
+use crate::engine::skill::{execute_skill, DefaultFormula, SkillDatabase, SkillError, SkillOutcome};
+use crate::engine::stats::Stats;
+use crate::engine::vfs::Vfs;
+
+fn skill_parameter(parameters: &[Object], index: usize, state: &State) -> Result<u32, RuntimeError> {
+    let skill = parameters[index].integer_value()?;
+    u32::try_from(skill).map_err(|_| RuntimeError::new(&format!("{} is not a skill", skill), state.last_position()))
+}
+
+// hp and mp are all a skill changes, the rest of the table stays as the script left it
+fn write_back(object: &Object, stats: &Stats) {
+    if let Some(entries) = table_entries(object) {
+        let mut entries = entries.borrow_mut();
+        entries.insert("hp".to_string(), Object::Integer(stats.hp as i64));
+        entries.insert("mp".to_string(), Object::Integer(stats.mp as i64));
+    }
+}
+
+// Skills(filename) reads the [[skill]] file through the vfs
+pub struct SkillsModel {
+    vfs: Arc<Vfs>
+}
+
+impl SkillsModel {
+    pub fn new(vfs: Arc<Vfs>) -> Self {
+        SkillsModel { vfs }
+    }
+}
+
+impl NativeModel for SkillsModel {
+    fn call(&mut self, state: &mut State, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        ensure_parameters_length(parameters, 1)?;
+
+        let filename = parameters[0].string_value()?;
+        let database = self.vfs.read(&filename)
+            .and_then(|source| SkillDatabase::from_toml(&String::from_utf8_lossy(&source)))
+            .map_err(|error| RuntimeError::new(&format!("can not load skills {}: {}", filename, error), state.last_position()))?;
+
+        Ok(Object::NativeInstance(make_reference(ScriptSkills { database, animations: HashMap::new() })))
+    }
+}
+
+// execute(skill, user, targets) runs a skill on stat tables, the battle scene shows what it returns
+pub struct ScriptSkills {
+    database: SkillDatabase,
+    // the battle effect of each animation id skills name, played once on the targets
+    animations: HashMap<u32, Animation>
+}
+
+impl ScriptSkills {
+    // { skill, animation, results: [{ target, amount, kind, status, defeated }, ...] }
+    fn outcome_object(&self, outcome: &SkillOutcome) -> Object {
+        let results = outcome.results.iter().map(|result| SaveValue::Table([
+            ("target".to_string(), SaveValue::Integer(result.target as i64)),
+            ("amount".to_string(), SaveValue::Integer(result.amount as i64)),
+            ("kind".to_string(), SaveValue::String(result.kind.name().to_string())),
+            ("status".to_string(), result.status.clone().map(SaveValue::String).unwrap_or(SaveValue::Null)),
+            ("defeated".to_string(), SaveValue::Boolean(result.defeated))
+        ].into_iter().collect())).collect();
+
+        let table = save_table(SaveValue::Table([
+            ("skill".to_string(), SaveValue::Integer(outcome.skill as i64)),
+            ("results".to_string(), SaveValue::Array(results))
+        ].into_iter().collect()));
+
+        // a fresh copy from its first frame every time, null without an effect set for the id
+        let animation = outcome.animation.and_then(|id| self.animations.get(&id)).map(|animation| {
+            let mut animation = animation.clone();
+            animation.restart();
+            Object::NativeInstance(make_reference(animation))
+        }).unwrap_or(Object::Null);
+
+        if let Some(entries) = table_entries(&table) {
+            entries.borrow_mut().insert("animation".to_string(), animation);
+        }
+
+        table
+    }
+}
+
+impl ScriptObject for ScriptSkills {
+    const NAME: &'static str = "Skills";
+    const PROPERTIES: &'static [&'static str] = &[];
+    const METHODS: &'static [(&'static str, usize)] = &[("set_animation", 3), ("name", 1), ("cost", 1), ("target", 1), ("execute", 3)];
+
+    fn get_property(&self, _key: &str) -> Result<Object, RuntimeError> {
+        Ok(Object::Null)
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        let id = skill_parameter(parameters, 0, state)?;
+
+        match key {
+            // set_animation(animation_id, [frames], duration) is the effect skills with that animation play, each frame for duration seconds
+            "set_animation" => {
+                let frames = match &parameters[1] {
+                    Object::Array(frames) => frames.borrow().iter().map(|frame| Ok(frame.integer_value()?.max(0) as usize)).collect::<Result<Vec<usize>, RuntimeError>>()?,
+                    _ => return Err(RuntimeError::new("animation frames are an array", state.last_position()))
+                };
+                self.animations.insert(id, Animation::uniform(&frames, float_parameter(parameters, 2)?, LoopMode::Once));
+                Ok(Object::Null)
+            },
+            // execute(skill, user, [targets]) takes the mp from the user table and changes the hp of the target tables,
+            // or of the user for skills on the user. the outcome for the battle scene, with the animation to play on the targets,
+            // or null when the user lacks the mp or there is no target
+            "execute" => {
+                let mut user = stats_parameter(&parameters[1], state)?;
+                let target_objects = match &parameters[2] {
+                    Object::Array(targets) => targets.borrow().clone(),
+                    _ => return Err(RuntimeError::new("targets are an array of stat tables", state.last_position()))
+                };
+                let mut targets = target_objects.iter().map(|target| stats_parameter(target, state)).collect::<Result<Vec<Stats>, RuntimeError>>()?;
+
+                let outcome = {
+                    let mut target_stats: Vec<&mut Stats> = targets.iter_mut().collect();
+                    execute_skill(&self.database, id, &mut user, &mut target_stats, &DefaultFormula)
+                };
+
+                let outcome = match outcome {
+                    Ok(outcome) => outcome,
+                    Err(SkillError::NotEnoughMp { .. }) | Err(SkillError::NoTarget) => return Ok(Object::Null),
+                    Err(error) => return Err(RuntimeError::new(&error.to_string(), state.last_position()))
+                };
+
+                write_back(&parameters[1], &user);
+                for (object, stats) in target_objects.iter().zip(targets.iter()) {
+                    write_back(object, stats);
+                }
+
+                Ok(self.outcome_object(&outcome))
+            },
+            // name, cost and target are null for an unknown skill, target is single_enemy, all_enemies, single_ally, all_allies or user
+            _ => {
+                let skill = match self.database.get(id) {
+                    Some(skill) => skill,
+                    None => return Ok(Object::Null)
+                };
+
+                Ok(match key {
+                    "name" => Object::String(make_reference(skill.name.clone())),
+                    "cost" => Object::Integer(skill.cost as i64),
+                    _ => Object::String(make_reference(skill.target.name().to_string()))
+                })
+            }
+        }
+    }
+}
+
+script_object!(ScriptSkills);
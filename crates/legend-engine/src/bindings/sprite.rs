@@ -0,0 +1,88 @@
+use std::sync::Arc;
+use clover::{NativeModel, NativeModelInstance, Object, Reference, State};
+use clover::debug::RuntimeError;
+use clover::helper::{ensure_parameters_length, make_reference};
+use crate::bindings::script_object::{lookup, script_object, Registered, Registry, ScriptObject};
+use crate::engine::assets::SpriteArchive;
+use crate::engine::graphics::RleImage;
+use crate::engine::vfs::Vfs;
+
+thread_local! {
+    static SPRITES: Registry<SpriteArchive> = Registry::new();
+}
+
+// Sprite(filename) reads a sprite archive of the game data, Graphics.draw_sprite draws its frames
+pub struct SpriteModel {
+    vfs: Arc<Vfs>
+}
+
+impl SpriteModel {
+    pub fn new(vfs: Arc<Vfs>) -> Self {
+        SpriteModel { vfs }
+    }
+}
+
+impl NativeModel for SpriteModel {
+    fn call(&mut self, state: &mut State, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        ensure_parameters_length(parameters, 1)?;
+
+        let filename = parameters[0].string_value()?;
+        let archive = SpriteArchive::load(&self.vfs, &filename).map_err(|error| RuntimeError::new(&format!("can not load sprite {}: {}", filename, error), state.last_position()))?;
+        Ok(Object::NativeInstance(make_reference(ScriptSprite::new(archive))))
+    }
+}
+
+pub struct ScriptSprite {
+    registered: Registered<SpriteArchive>,
+    archive: Reference<SpriteArchive>
+}
+
+impl ScriptSprite {
+    pub fn new(archive: SpriteArchive) -> Self {
+        let archive = make_reference(archive);
+        ScriptSprite { registered: Registered::new(&SPRITES, &archive), archive }
+    }
+}
+
+// the archive behind a script Sprite instance
+pub fn sprite_from(object: &Object) -> Result<Reference<SpriteArchive>, RuntimeError> {
+    lookup(&SPRITES, object, "sprite_id", "a sprite")
+}
+
+fn frame_size(archive: &SpriteArchive, index: i64, size: fn(&RleImage) -> i64) -> Object {
+    usize::try_from(index).ok()
+        .and_then(|index| archive.frame(index))
+        .map(|frame| Object::Integer(size(frame)))
+        .unwrap_or(Object::Null)
+}
+
+impl ScriptObject for ScriptSprite {
+    const NAME: &'static str = "Sprite";
+    const PROPERTIES: &'static [&'static str] = &["count"];
+    const METHODS: &'static [(&'static str, usize)] = &[("width", 1), ("height", 1)];
+
+    fn get_property(&self, _key: &str) -> Result<Object, RuntimeError> {
+        Ok(Object::Integer(self.archive.borrow().len() as i64))
+    }
+
+    // width(frame) and height(frame), null for a frame the archive does not have
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, _state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        let index = parameters[0].integer_value()?;
+        let archive = self.archive.borrow();
+
+        Ok(match key {
+            "width" => frame_size(&archive, index, |frame| frame.size.x as i64),
+            // height
+            _ => frame_size(&archive, index, |frame| frame.size.y as i64)
+        })
+    }
+
+    fn raw_integer(&self, key: &str) -> Option<i64> {
+        match key {
+            "sprite_id" => Some(self.registered.id()),
+            _ => None
+        }
+    }
+}
+
+script_object!(ScriptSprite);
@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+use clover::{NativeModel, NativeModelInstance, Object, Reference, State};
+use clover::debug::RuntimeError;
+use clover::helper::make_reference;
+use crate::bindings::color::integer_parameter;
+use crate::bindings::palette::palette_from;
+use crate::bindings::save::{save_table, to_save_value};
+use crate::bindings::script_object::{script_object, singleton_model, ScriptObject};
+use crate::bindings::sprite::sprite_from;
+use crate::engine::graphics::Graphics;
+use crate::engine::save::SaveValue;
+use crate::engine::status::{StackRule, StatusDefinition, StatusRegistry, StatusSet};
+
+// gap between two status icons, the same as Graphics().draw_icons
+const ICON_SPACING: i32 = 1;
+
+fn definition_error(message: &str, state: &State) -> RuntimeError {
+    RuntimeError::new(&format!("{}, an effect is a SaveTable of name, duration, stack, hp_per_turn, modifiers, prevents_action and icon", message), state.last_position())
+}
+
+fn stack_rule(value: Option<&SaveValue>, state: &State) -> Result<StackRule, RuntimeError> {
+    Ok(match value {
+        None | Some(SaveValue::Null) => StackRule::Refresh,
+        Some(SaveValue::Integer(limit)) => StackRule::Stack((*limit).max(1) as u32),
+        Some(SaveValue::String(name)) if name == "refresh" => StackRule::Refresh,
+        Some(SaveValue::String(name)) if name == "extend" => StackRule::Extend,
+        Some(SaveValue::String(name)) if name == "ignore" => StackRule::Ignore,
+        _ => return Err(definition_error("stack is refresh, extend, ignore or the most stacks", state))
+    })
+}
+
+fn integer_entry(entries: &BTreeMap<String, SaveValue>, key: &str, state: &State) -> Result<i64, RuntimeError> {
+    match entries.get(key) {
+        None | Some(SaveValue::Null) => Ok(0),
+        Some(SaveValue::Integer(value)) => Ok(*value),
+        _ => Err(definition_error(&format!("{} is not an integer", key), state))
+    }
+}
+
+// a SaveTable like { name: "burn", duration: 3, stack: "refresh", hp_per_turn: -8, modifiers: { defence: -5 }, icon: 4 }
+fn definition_parameter(parameters: &[Object], index: usize, state: &State) -> Result<StatusDefinition, RuntimeError> {
+    let entries = match to_save_value(&parameters[index])? {
+        SaveValue::Table(entries) => entries,
+        _ => return Err(definition_error("not a table", state))
+    };
+
+    let name = match entries.get("name") {
+        Some(SaveValue::String(name)) => name.clone(),
+        _ => return Err(definition_error("the name is missing", state))
+    };
+
+    let mut definition = StatusDefinition::new(&name, integer_entry(&entries, "duration", state)?.max(0) as u32, stack_rule(entries.get("stack"), state)?);
+    definition.hp_per_turn = integer_entry(&entries, "hp_per_turn", state)? as i32;
+    definition.prevents_action = matches!(entries.get("prevents_action"), Some(SaveValue::Boolean(true)));
+    definition.icon = match entries.get("icon") {
+        None | Some(SaveValue::Null) => None,
+        Some(SaveValue::Integer(icon)) if *icon >= 0 => Some(*icon as usize),
+        _ => return Err(definition_error("icon is a sprite frame", state))
+    };
+    definition.modifiers = match entries.get("modifiers") {
+        None | Some(SaveValue::Null) => Vec::new(),
+        Some(SaveValue::Table(modifiers)) => modifiers.keys().map(|stat| Ok((stat.clone(), integer_entry(modifiers, stat, state)? as i32))).collect::<Result<_, RuntimeError>>()?,
+        _ => return Err(definition_error("modifiers is a table of stat amounts", state))
+    };
+
+    Ok(definition)
+}
+
+// Statuses() knows every effect, the built in poison, sleep, attack_up and defence_up and those scripts register
+pub struct ScriptStatuses {
+    registry: Reference<StatusRegistry>
+}
+
+impl ScriptStatuses {
+    pub fn new(registry: Reference<StatusRegistry>) -> Self {
+        ScriptStatuses { registry }
+    }
+}
+
+singleton_model!(StatusesModel, ScriptStatuses);
+
+impl ScriptObject for ScriptStatuses {
+    const NAME: &'static str = "Statuses";
+    const PROPERTIES: &'static [&'static str] = &[];
+    const METHODS: &'static [(&'static str, usize)] = &[("register", 1), ("known", 1)];
+
+    fn get_property(&self, _key: &str) -> Result<Object, RuntimeError> {
+        Ok(Object::Null)
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        match key {
+            // register(table) adds an effect or replaces the one of the same name,
+            // whatever hp_per_turn and modifiers can not express the script does with the ticks of tick()
+            "register" => {
+                let definition = definition_parameter(parameters, 0, state)?;
+                self.registry.borrow_mut().register(definition);
+                Ok(Object::Null)
+            },
+            _ => Ok(Object::Boolean(self.registry.borrow().get(&parameters[0].string_value()?).is_some()))
+        }
+    }
+}
+
+script_object!(ScriptStatuses);
+
+// StatusSet() is the effects on one character, empty at first
+pub struct StatusSetModel {
+    registry: Reference<StatusRegistry>,
+    graphics: Reference<Graphics>
+}
+
+impl StatusSetModel {
+    pub fn new(registry: Reference<StatusRegistry>, graphics: Reference<Graphics>) -> Self {
+        StatusSetModel { registry, graphics }
+    }
+}
+
+impl NativeModel for StatusSetModel {
+    fn call(&mut self, _state: &mut State, _parameters: &[Object]) -> Result<Object, RuntimeError> {
+        Ok(Object::NativeInstance(make_reference(ScriptStatusSet { status: StatusSet::new(), registry: self.registry.clone(), graphics: self.graphics.clone() })))
+    }
+}
+
+pub struct ScriptStatusSet {
+    status: StatusSet,
+    registry: Reference<StatusRegistry>,
+    graphics: Reference<Graphics>
+}
+
+impl ScriptObject for ScriptStatusSet {
+    const NAME: &'static str = "StatusSet";
+    const PROPERTIES: &'static [&'static str] = &["effects"];
+    const METHODS: &'static [(&'static str, usize)] = &[
+        ("apply", 1), ("remove", 1), ("clear", 0), ("has", 1), ("tick", 0), ("can_act", 0), ("modifier", 1), ("draw_icons", 4)
+    ];
+
+    // [{ name, remaining, stacks }, ...] in the order they were applied, remaining is null for effects that last until removed
+    fn get_property(&self, _key: &str) -> Result<Object, RuntimeError> {
+        let effects = self.status.effects().iter().map(|effect| SaveValue::Table([
+            ("name".to_string(), SaveValue::String(effect.name.clone())),
+            ("remaining".to_string(), effect.remaining.map(|remaining| SaveValue::Integer(remaining as i64)).unwrap_or(SaveValue::Null)),
+            ("stacks".to_string(), SaveValue::Integer(effect.stacks as i64))
+        ].into_iter().collect()));
+
+        Ok(save_table(SaveValue::Array(effects.collect())))
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        let registry = self.registry.borrow();
+
+        match key {
+            // apply(name) is false for an unknown effect or one its stack rule ignores
+            "apply" => Ok(Object::Boolean(self.status.apply(&registry, &parameters[0].string_value()?))),
+            "remove" => Ok(Object::Boolean(self.status.remove(&parameters[0].string_value()?))),
+            "clear" => {
+                self.status.clear();
+                Ok(Object::Null)
+            },
+            "has" => Ok(Object::Boolean(self.status.has(&parameters[0].string_value()?))),
+            // tick() once per turn is [{ name, hp_delta, expired }, ...], the script changes the hp
+            // and runs whatever its own effects do by name
+            "tick" => {
+                let ticks = self.status.tick(&registry).into_iter().map(|tick| SaveValue::Table([
+                    ("name".to_string(), SaveValue::String(tick.name)),
+                    ("hp_delta".to_string(), SaveValue::Integer(tick.hp_delta as i64)),
+                    ("expired".to_string(), SaveValue::Boolean(tick.expired))
+                ].into_iter().collect()));
+                Ok(save_table(SaveValue::Array(ticks.collect())))
+            },
+            "can_act" => Ok(Object::Boolean(self.status.can_act(&registry))),
+            // modifier(stat) is what every effect adds to the stat together
+            "modifier" => Ok(Object::Integer(self.status.modifier(&registry, &parameters[0].string_value()?) as i64)),
+            // draw_icons(icons, x, y, palette) puts the icons of the effects in a row, next to a hp bar, and is the width they took
+            _ => {
+                let (icons, palette) = (sprite_from(&parameters[0])?, palette_from(&parameters[3])?);
+                let frames = self.status.icons(&registry);
+                let width = self.graphics.borrow_mut().draw_icons(&icons.borrow(), &frames, integer_parameter(parameters, 1)?, integer_parameter(parameters, 2)?, ICON_SPACING, &palette.borrow());
+                Ok(Object::Integer(width as i64))
+            }
+        }
+    }
+}
+
+script_object!(ScriptStatusSet);
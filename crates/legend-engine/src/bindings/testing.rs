@@ -0,0 +1,108 @@
+use std::path::Path;
+use clover::{NativeModel, Object, Reference, State};
+use clover::debug::RuntimeError;
+use clover::helper::ensure_parameters_length;
+use crate::engine::graphics::{Graphics, Image};
+
+pub(crate) fn describe(object: &Object) -> String {
+    match object {
+        Object::Null => "null".to_string(),
+        Object::Integer(value) => value.to_string(),
+        Object::Float(value) => value.to_string(),
+        Object::Boolean(value) => value.to_string(),
+        Object::String(value) => format!("\"{}\"", value.borrow()),
+        Object::Array(values) => format!("[{}]", values.borrow().iter().map(describe).collect::<Vec<String>>().join(", ")),
+        _ => "object".to_string()
+    }
+}
+
+// values compare by content, models and instances only when they are the same one
+fn objects_equal(a: &Object, b: &Object) -> bool {
+    match (a, b) {
+        (Object::Null, Object::Null) => true,
+        (Object::Integer(a), Object::Integer(b)) => a == b,
+        (Object::Float(a), Object::Float(b)) => a == b,
+        (Object::Integer(a), Object::Float(b)) | (Object::Float(b), Object::Integer(a)) => *a as f64 == *b,
+        (Object::Boolean(a), Object::Boolean(b)) => a == b,
+        (Object::String(a), Object::String(b)) => *a.borrow() == *b.borrow(),
+        (Object::Array(a), Object::Array(b)) => {
+            let (a, b) = (a.borrow(), b.borrow());
+            a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| objects_equal(a, b))
+        },
+        (Object::NativeInstance(a), Object::NativeInstance(b)) => Reference::ptr_eq(a, b),
+        _ => false
+    }
+}
+
+// assert_eq(actual, expected, message)
+pub struct AssertEq;
+
+impl NativeModel for AssertEq {
+    fn call(&mut self, state: &mut State, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        ensure_parameters_length(parameters, 2)?;
+
+        if objects_equal(&parameters[0], &parameters[1]) {
+            return Ok(Object::Null);
+        }
+
+        let message = match parameters.get(2) {
+            Some(message) => format!("{}: ", message.string_value()?),
+            None => String::new()
+        };
+
+        Err(RuntimeError::new(&format!("{}expected {} but was {}", message, describe(&parameters[1]), describe(&parameters[0])), state.last_position()))
+    }
+}
+
+// assert_image_matches(filename, tolerance) compares the screen with a png,
+// a missing png is written from the current screen so new tests can record their expectation
+pub struct AssertImageMatches {
+    graphics: Reference<Graphics>
+}
+
+impl AssertImageMatches {
+    pub fn new(graphics: Reference<Graphics>) -> Self {
+        AssertImageMatches { graphics }
+    }
+}
+
+fn images_match(actual: &Image, expected: &Image, tolerance: u8) -> bool {
+    if actual.size.x != expected.size.x || actual.size.y != expected.size.y {
+        return false;
+    }
+
+    actual.data.iter().zip(expected.data.iter()).all(|(a, b)| {
+        a.r.abs_diff(b.r) <= tolerance && a.g.abs_diff(b.g) <= tolerance && a.b.abs_diff(b.b) <= tolerance && a.a.abs_diff(b.a) <= tolerance
+    })
+}
+
+impl NativeModel for AssertImageMatches {
+    fn call(&mut self, state: &mut State, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        ensure_parameters_length(parameters, 1)?;
+
+        let filename = parameters[0].string_value()?;
+        let tolerance = if parameters.len() > 1 { parameters[1].integer_value()?.clamp(0, 255) as u8 } else { 0 };
+
+        let actual = {
+            let graphics = self.graphics.borrow();
+            graphics.capture(0, 0, graphics.get_width(), graphics.get_height())
+        };
+
+        if !Path::new(&filename).exists() {
+            actual.save(&filename).map_err(|error| RuntimeError::new(&format!("can not save {}: {}", filename, error), state.last_position()))?;
+            return Ok(Object::Null);
+        }
+
+        let expected = Image::load(&filename).map_err(|error| RuntimeError::new(&format!("can not load {}: {}", filename, error), state.last_position()))?;
+
+        if images_match(&actual, &expected, tolerance) {
+            return Ok(Object::Null);
+        }
+
+        // keep what was drawn next to the expectation for comparing
+        let actual_filename = Path::new(&filename).with_extension("actual.png");
+        actual.save(&actual_filename.to_string_lossy()).map_err(|error| RuntimeError::new(&format!("can not save {}: {}", actual_filename.display(), error), state.last_position()))?;
+
+        Err(RuntimeError::new(&format!("screen does not match {}, saved as {}", filename, actual_filename.display()), state.last_position()))
+    }
+}
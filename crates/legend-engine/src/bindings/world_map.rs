@@ -0,0 +1,218 @@
+use std::sync::Arc;
+use clover::{NativeModel, NativeModelInstance, Object, Reference, State};
+use clover::debug::RuntimeError;
+use clover::helper::{ensure_parameters_length, make_reference};
+use crate::bindings::color::integer_parameter;
+use crate::bindings::encounter::encounter_step;
+use crate::bindings::field::direction_from;
+use crate::bindings::palette::palette_from;
+use crate::bindings::party::party_from;
+use crate::bindings::scene::Scenes;
+use crate::bindings::script_object::{script_object, ScriptObject};
+use crate::bindings::sprite::sprite_from;
+use crate::engine::encounter::EncounterSystem;
+use crate::engine::graphics::{Graphics, Vector2};
+use crate::engine::movement::{Direction, MovementMode, MovementRules, TILE_SIZE};
+use crate::engine::party::Party;
+use crate::engine::vfs::Vfs;
+use crate::engine::world_map::{LocationNode, TravelEvent, WorldMap, WorldMapTravel};
+
+// the encounter table the world map uses unless the script names another
+const WORLD_ENCOUNTERS: &str = "world";
+
+// WorldMap(filename, x, y) reads the world map through the vfs and puts the party on it
+pub struct WorldMapModel {
+    graphics: Reference<Graphics>,
+    scenes: Reference<Scenes>,
+    encounters: Reference<EncounterSystem>,
+    movement: Reference<MovementRules>,
+    vfs: Arc<Vfs>
+}
+
+impl WorldMapModel {
+    pub fn new(graphics: Reference<Graphics>, scenes: Reference<Scenes>, encounters: Reference<EncounterSystem>, movement: Reference<MovementRules>, vfs: Arc<Vfs>) -> Self {
+        WorldMapModel { graphics, scenes, encounters, movement, vfs }
+    }
+}
+
+impl NativeModel for WorldMapModel {
+    fn call(&mut self, state: &mut State, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        ensure_parameters_length(parameters, 3)?;
+
+        let filename = parameters[0].string_value()?;
+        let map = WorldMap::load(&self.vfs, &filename).map_err(|error| RuntimeError::new(&format!("can not load world map {}: {}", filename, error), state.last_position()))?;
+        let travel = WorldMapTravel::new(Vector2::new(integer_parameter(parameters, 1)?, integer_parameter(parameters, 2)?), Direction::Down);
+
+        Ok(Object::NativeInstance(make_reference(ScriptWorldMap {
+            map,
+            travel,
+            graphics: self.graphics.clone(),
+            scenes: self.scenes.clone(),
+            encounters: self.encounters.clone(),
+            movement: self.movement.clone(),
+            party: None,
+            encounter_table: WORLD_ENCOUNTERS.to_string(),
+            location_scene: None,
+            location: None,
+            battle: None
+        })))
+    }
+}
+
+// step(direction) moves the party a tile, counts the step for encounters and enters the location it lands on
+pub struct ScriptWorldMap {
+    map: WorldMap,
+    travel: WorldMapTravel,
+    graphics: Reference<Graphics>,
+    scenes: Reference<Scenes>,
+    encounters: Reference<EncounterSystem>,
+    movement: Reference<MovementRules>,
+    // the party travelling, walking when none is set
+    party: Option<Reference<Party>>,
+    encounter_table: String,
+    // switched to with the map of a location as its value, scripts load the local map in its enter
+    location_scene: Option<String>,
+    // what the last step ran into
+    location: Option<LocationNode>,
+    battle: Option<u32>
+}
+
+impl ScriptWorldMap {
+    // the whole screen in tiles, a tile more so scrolling never shows an empty edge
+    fn view_size(&self) -> (i32, i32) {
+        let graphics = self.graphics.borrow();
+        (graphics.get_width() as i32 / TILE_SIZE + 1, graphics.get_height() as i32 / TILE_SIZE + 1)
+    }
+
+    fn mode(&self) -> MovementMode {
+        self.party.as_ref().map(|party| party.borrow().mode).unwrap_or_default()
+    }
+
+    fn view_origin(&self) -> Vector2<i32> {
+        let (columns, rows) = self.view_size();
+        self.travel.view_origin(&self.map, columns, rows)
+    }
+}
+
+fn string_object(value: &str) -> Object {
+    Object::String(make_reference(value.to_string()))
+}
+
+impl ScriptObject for ScriptWorldMap {
+    const NAME: &'static str = "WorldMap";
+    const PROPERTIES: &'static [&'static str] = &["x", "y", "facing", "mode", "sprite", "steps", "width", "height", "encounter_table", "location_scene", "location", "battle", "view_x", "view_y"];
+    const METHODS: &'static [(&'static str, usize)] = &[("add_location", 4), ("place", 2), ("step", 1), ("tile", 2), ("set_party", 1), ("draw", 2)];
+
+    fn get_property(&self, key: &str) -> Result<Object, RuntimeError> {
+        Ok(match key {
+            "x" => Object::Integer(self.travel.position.x as i64),
+            "y" => Object::Integer(self.travel.position.y as i64),
+            "facing" => string_object(self.travel.facing.name()),
+            "mode" => string_object(self.mode().name()),
+            // the party sprite of the sprite set of its mode, a new frame every step, null when the mode has no rule
+            "sprite" => self.movement.borrow().get(self.mode()).map(|rule| Object::Integer(rule.sprite(self.travel.facing, self.travel.steps() as usize) as i64)).unwrap_or(Object::Null),
+            "steps" => Object::Integer(self.travel.steps() as i64),
+            "width" => Object::Integer(self.map.size.x as i64),
+            "height" => Object::Integer(self.map.size.y as i64),
+            "encounter_table" => string_object(&self.encounter_table),
+            "location_scene" => self.location_scene.as_deref().map(string_object).unwrap_or(Object::Null),
+            // the name of the location the last step entered, null when it entered none
+            "location" => self.location.as_ref().map(|location| string_object(&location.name)).unwrap_or(Object::Null),
+            // the battle id the last step ran into, null without an encounter
+            "battle" => self.battle.map(|battle| Object::Integer(battle as i64)).unwrap_or(Object::Null),
+            // the top left tile draw starts at, the party is drawn at (x - view_x, y - view_y) tiles
+            "view_x" => Object::Integer(self.view_origin().x as i64),
+            _ => Object::Integer(self.view_origin().y as i64)
+        })
+    }
+
+    // encounter_table names the table of the encounter file, location_scene is the scene locations are entered with
+    fn set_property(&mut self, key: &str, value: Object) -> Result<bool, RuntimeError> {
+        match (key, value) {
+            ("encounter_table", value) => self.encounter_table = value.string_value()?,
+            ("location_scene", Object::Null) => self.location_scene = None,
+            ("location_scene", value) => self.location_scene = Some(value.string_value()?),
+            _ => return Ok(false)
+        }
+
+        Ok(true)
+    }
+
+    fn call_method(&mut self, _this: Reference<dyn NativeModelInstance>, state: &mut State, key: &str, parameters: &[Object]) -> Result<Object, RuntimeError> {
+        match key {
+            // add_location(name, x, y, map), stepping on x, y enters the location
+            "add_location" => {
+                self.map.add_location(LocationNode {
+                    name: parameters[0].string_value()?,
+                    position: Vector2::new(integer_parameter(parameters, 1)?, integer_parameter(parameters, 2)?),
+                    map: parameters[3].string_value()?
+                });
+                Ok(Object::Null)
+            },
+            // place(x, y) after leaving a location, without counting a step
+            "place" => {
+                self.travel.position = Vector2::new(integer_parameter(parameters, 0)?, integer_parameter(parameters, 1)?);
+                Ok(Object::Null)
+            },
+            // step(direction) is "blocked", "moved", "encounter" or "location".
+            // an encounter leaves its battle in battle and pushes the battle scene of Encounters(),
+            // a location is switched to with location_scene when one is set
+            "step" => {
+                let direction = direction_from(&parameters[0])?;
+                self.location = None;
+                self.battle = None;
+
+                let (movement, mode) = (self.movement.clone(), self.mode());
+                let event = self.travel.step(&self.map, direction, |tile| movement.borrow().allows(mode, tile));
+                let name = match event {
+                    TravelEvent::Blocked => "blocked",
+                    TravelEvent::Moved(_) => {
+                        self.battle = encounter_step(&mut self.encounters.borrow_mut(), &mut self.scenes.borrow_mut(), &self.encounter_table, state)?;
+                        if self.battle.is_some() { "encounter" } else { "moved" }
+                    },
+                    TravelEvent::EnterLocation(location) => {
+                        // a location is a safe place, the danger starts over when the party leaves it
+                        self.encounters.borrow_mut().reset();
+                        if let Some(scene) = self.location_scene.clone() {
+                            self.scenes.borrow_mut().switch(scene, string_object(&location.map), state)?;
+                        }
+                        self.location = Some(location);
+                        "location"
+                    }
+                };
+                Ok(string_object(name))
+            },
+            // tile(x, y) is null outside of the map
+            "tile" => {
+                let tile = self.map.get_tile(integer_parameter(parameters, 0)?, integer_parameter(parameters, 1)?);
+                Ok(tile.map(|tile| Object::Integer(tile as i64)).unwrap_or(Object::Null))
+            },
+            // set_party(party) moves by the passability of the party mode, over the sea once it boards a boat
+            "set_party" => {
+                self.party = match &parameters[0] {
+                    Object::Null => None,
+                    party => Some(party_from(party)?)
+                };
+                Ok(Object::Null)
+            },
+            // draw(tiles, palette) draws the part of the map around the party that fills the screen
+            _ => {
+                let (tiles, palette) = (sprite_from(&parameters[0])?, palette_from(&parameters[1])?);
+                let (tiles, palette) = (tiles.borrow(), palette.borrow());
+                let ((columns, rows), origin) = (self.view_size(), self.view_origin());
+
+                let mut graphics = self.graphics.borrow_mut();
+                for row in 0..rows {
+                    for column in 0..columns {
+                        if let Some(tile) = self.map.get_tile(origin.x + column, origin.y + row) {
+                            graphics.draw_sprite(&tiles, tile as usize, column * TILE_SIZE, row * TILE_SIZE, &palette);
+                        }
+                    }
+                }
+                Ok(Object::Null)
+            }
+        }
+    }
+}
+
+script_object!(ScriptWorldMap);
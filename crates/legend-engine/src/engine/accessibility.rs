@@ -0,0 +1,107 @@
+use std::error::Error;
+
+pub trait SpeechBackend {
+    // interrupt drops whatever is still being read
+    fn speak(&mut self, text: &str, interrupt: bool) -> Result<(), Box<dyn Error>>;
+    fn stop(&mut self) -> Result<(), Box<dyn Error>>;
+}
+
+// used when speech is turned off, not compiled in or failed to start
+pub struct NullSpeech;
+
+impl SpeechBackend for NullSpeech {
+    fn speak(&mut self, _text: &str, _interrupt: bool) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+// reads dialogue and menu selections aloud for players who can not read the screen.
+// the same text twice in a row is read once, so callers can announce every frame
+pub struct Announcer {
+    backend: Box<dyn SpeechBackend>,
+    enabled: bool,
+    last: Option<String>
+}
+
+impl Announcer {
+    pub fn new(backend: Box<dyn SpeechBackend>) -> Self {
+        Announcer { backend, enabled: true, last: None }
+    }
+
+    pub fn disabled() -> Self {
+        Announcer { backend: Box::new(NullSpeech), enabled: false, last: None }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    // dialogue, read after whatever is still being read
+    pub fn announce(&mut self, text: &str) {
+        self.speak(text, false);
+    }
+
+    // a menu selection, cuts off the previous one so moving through a menu does not build a queue
+    pub fn select(&mut self, text: &str) {
+        self.speak(text, true);
+    }
+
+    pub fn stop(&mut self) {
+        self.last = None;
+        if let Err(error) = self.backend.stop() {
+            self.fail(error);
+        }
+    }
+
+    fn speak(&mut self, text: &str, interrupt: bool) {
+        let text = text.trim();
+        if !self.enabled || text.is_empty() || self.last.as_deref() == Some(text) {
+            return;
+        }
+
+        self.last = Some(text.to_string());
+        if let Err(error) = self.backend.speak(text, interrupt) {
+            self.fail(error);
+        }
+    }
+
+    // a broken speech service should not stop the game
+    fn fail(&mut self, error: Box<dyn Error>) {
+        eprintln!("text to speech error: {}", error);
+        *self = Self::disabled();
+    }
+}
+
+#[cfg(feature = "tts")]
+pub mod platform {
+    use std::error::Error;
+    use tts::Tts;
+    use super::SpeechBackend;
+
+    // sapi or the screen reader on windows, speech dispatcher on linux, av foundation on mac
+    pub struct PlatformSpeech {
+        tts: Tts
+    }
+
+    impl PlatformSpeech {
+        pub fn connect() -> Result<Self, Box<dyn Error>> {
+            Ok(PlatformSpeech { tts: Tts::default()? })
+        }
+    }
+
+    impl SpeechBackend for PlatformSpeech {
+        fn speak(&mut self, text: &str, interrupt: bool) -> Result<(), Box<dyn Error>> {
+            self.tts.speak(text, interrupt)?;
+            Ok(())
+        }
+
+        fn stop(&mut self) -> Result<(), Box<dyn Error>> {
+            self.tts.stop()?;
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use std::collections::BTreeMap;
+
+// which keys trigger each action, scripts ask for actions so players can rebind the keys
+#[derive(Clone, Debug)]
+pub struct ActionMap {
+    actions: BTreeMap<String, Vec<String>>
+}
+
+impl ActionMap {
+    pub fn new() -> Self {
+        ActionMap { actions: BTreeMap::new() }
+    }
+
+    pub fn keys(&self, action: &str) -> &[String] {
+        self.actions.get(action).map(|keys| keys.as_slice()).unwrap_or(&[])
+    }
+
+    // no keys leaves the action unbound but known
+    pub fn bind(&mut self, action: &str, keys: Vec<String>) {
+        self.actions.insert(action.to_string(), keys);
+    }
+
+    pub fn actions(&self) -> Vec<String> {
+        self.actions.keys().cloned().collect()
+    }
+}
+
+// the keys of the original game
+impl Default for ActionMap {
+    fn default() -> Self {
+        let mut map = Self::new();
+
+        for (action, keys) in [
+            ("up", vec!["up"]), ("down", vec!["down"]), ("left", vec!["left"]), ("right", vec!["right"]),
+            ("confirm", vec!["enter", "space"]), ("cancel", vec!["escape"]), ("menu", vec!["escape"])
+        ] {
+            map.bind(action, keys.into_iter().map(|key| key.to_string()).collect());
+        }
+
+        map
+    }
+}
@@ -0,0 +1,196 @@
+// what happens after the last frame
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LoopMode {
+    // stops on the last frame
+    Once,
+    // starts over from the first frame
+    Loop,
+    // plays backwards to the first frame and then forwards again
+    PingPong
+}
+
+impl LoopMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "once" => Some(LoopMode::Once),
+            "loop" => Some(LoopMode::Loop),
+            "ping_pong" => Some(LoopMode::PingPong),
+            _ => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LoopMode::Once => "once",
+            LoopMode::Loop => "loop",
+            LoopMode::PingPong => "ping_pong"
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AnimationEvent {
+    // back on the first frame of a loop or ping pong
+    Looped,
+    // the last frame of a once animation ran out
+    Finished
+}
+
+impl AnimationEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AnimationEvent::Looped => "loop",
+            AnimationEvent::Finished => "finish"
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct AnimationFrame {
+    // into the RleImage list the sprite was loaded as
+    pub index: usize,
+    // seconds the frame stays up
+    pub duration: f64
+}
+
+// a sequence of sprite frames advanced by the update delta, instead of a frame timer in every script
+#[derive(Clone, Debug)]
+pub struct Animation {
+    frames: Vec<AnimationFrame>,
+    mode: LoopMode,
+    position: usize,
+    elapsed: f64,
+    // a ping pong on its way back
+    backwards: bool,
+    playing: bool,
+    finished: bool,
+    events: Vec<AnimationEvent>
+}
+
+impl Animation {
+    pub fn new(frames: Vec<AnimationFrame>, mode: LoopMode) -> Self {
+        let frames = frames.into_iter().map(|frame| AnimationFrame { duration: frame.duration.max(0.0), ..frame }).collect();
+        Animation { frames, mode, position: 0, elapsed: 0.0, backwards: false, playing: true, finished: false, events: Vec::new() }
+    }
+
+    // every frame shown for the same time
+    pub fn uniform(indices: &[usize], duration: f64, mode: LoopMode) -> Self {
+        Self::new(indices.iter().map(|&index| AnimationFrame { index, duration }).collect(), mode)
+    }
+
+    // the RleImage index to draw, None for an animation without frames
+    pub fn frame(&self) -> Option<usize> {
+        self.frames.get(self.position).map(|frame| frame.index)
+    }
+
+    // where in the sequence it is, not the image index
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn mode(&self) -> LoopMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: LoopMode) {
+        self.mode = mode;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing && !self.finished
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    pub fn restart(&mut self) {
+        self.position = 0;
+        self.elapsed = 0.0;
+        self.backwards = false;
+        self.finished = false;
+        self.playing = true;
+    }
+
+    // jumps to a frame of the sequence and shows it from its start
+    pub fn seek(&mut self, position: usize) {
+        self.position = position.min(self.frames.len().saturating_sub(1));
+        self.elapsed = 0.0;
+        self.finished = false;
+    }
+
+    // a whole delta can pass several short frames, each one still counts
+    pub fn update(&mut self, delta: f64) {
+        if !self.is_playing() || self.frames.is_empty() {
+            return;
+        }
+
+        // a loop of frames without time would never get through the delta
+        if self.mode != LoopMode::Once && self.frames.iter().all(|frame| frame.duration <= 0.0) {
+            return;
+        }
+
+        self.elapsed += delta.max(0.0);
+        while self.elapsed >= self.frames[self.position].duration {
+            self.elapsed -= self.frames[self.position].duration;
+            if !self.step() {
+                self.elapsed = 0.0;
+                break;
+            }
+        }
+    }
+
+    // false once a once animation has run out
+    fn step(&mut self) -> bool {
+        let last = self.frames.len() - 1;
+
+        match self.mode {
+            LoopMode::Once if self.position == last => {
+                self.finished = true;
+                self.events.push(AnimationEvent::Finished);
+                return false;
+            },
+            LoopMode::Once => self.position += 1,
+            LoopMode::Loop if self.position == last => {
+                self.position = 0;
+                self.events.push(AnimationEvent::Looped);
+            },
+            LoopMode::Loop => self.position += 1,
+            LoopMode::PingPong if last == 0 => self.events.push(AnimationEvent::Looped),
+            LoopMode::PingPong if self.backwards => {
+                self.position -= 1;
+                if self.position == 0 {
+                    self.backwards = false;
+                    self.events.push(AnimationEvent::Looped);
+                }
+            },
+            LoopMode::PingPong => {
+                self.position += 1;
+                self.backwards = self.position == last;
+            }
+        }
+
+        true
+    }
+
+    // what happened since the last call
+    pub fn drain_events(&mut self) -> Vec<AnimationEvent> {
+        std::mem::take(&mut self.events)
+    }
+}
@@ -0,0 +1,93 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::engine::decode::{read_u16, read_u32, DecodeError};
+use crate::engine::error::EngineResult;
+use crate::engine::graphics::{RleImage, Vector2};
+use crate::engine::vfs::Vfs;
+
+pub const SPRITE_ARCHIVE_EXTENSIONS: [&str; 1] = ["spr"];
+
+static ARCHIVE_ID: AtomicUsize = AtomicUsize::new(1);
+
+// the frames of one character or effect, in the order the game indexes them
+pub struct SpriteArchive {
+    // sets the frames apart from those of other archives in the sprite cache
+    id: usize,
+    frames: Vec<RleImage>
+}
+
+impl SpriteArchive {
+    // a u16 frame count, a u32 offset from the start of the file for every frame,
+    // then the frames in the RleImage::from_bytes layout. a frame ends where the next one starts,
+    // an entry without any bytes is a frame the game skips
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let count = read_u16(bytes, 0)? as usize;
+        let table_end = 2 + count * 4;
+
+        let mut offsets = Vec::with_capacity(count + 1);
+        for index in 0..count {
+            let offset = read_u32(bytes, 2 + index * 4)? as usize;
+            let previous = offsets.last().copied().unwrap_or(table_end);
+            if offset < previous || offset > bytes.len() {
+                return Err(DecodeError::InvalidOffset { index, offset });
+            }
+            offsets.push(offset);
+        }
+        offsets.push(bytes.len());
+
+        let frames = offsets.windows(2)
+            .map(|range| match &bytes[range[0]..range[1]] {
+                [] => RleImage::new(Vector2::new(0, 0), Vector2::new(0, 0), Vec::new()),
+                frame => RleImage::from_bytes(frame)
+            })
+            .collect::<Result<Vec<RleImage>, DecodeError>>()?;
+
+        Ok(SpriteArchive { id: ARCHIVE_ID.fetch_add(1, Ordering::Relaxed), frames })
+    }
+
+    pub fn load(vfs: &Vfs, name: &str) -> EngineResult<Self> {
+        Ok(Self::from_bytes(&vfs.read(name)?)?)
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    // the count is a u16, so the frame index fits below the archive id
+    pub fn frame_key(&self, index: usize) -> usize {
+        self.id << 16 | index
+    }
+
+    pub fn frame(&self, index: usize) -> Option<&RleImage> {
+        self.frames.get(index)
+    }
+
+    pub fn frames(&self) -> &[RleImage] {
+        &self.frames
+    }
+
+    pub fn into_frames(self) -> Vec<RleImage> {
+        self.frames
+    }
+}
+
+pub fn is_sprite_archive(name: &str) -> bool {
+    Path::new(name).extension()
+        .map(|extension| SPRITE_ARCHIVE_EXTENSIONS.iter().any(|known| extension.to_string_lossy().eq_ignore_ascii_case(known)))
+        .unwrap_or(false)
+}
+
+// every archive under data_path, by name. one broken archive fails the whole load
+pub fn load_sprite_archives(vfs: &Vfs) -> EngineResult<Vec<(String, SpriteArchive)>> {
+    vfs.files()?.into_iter()
+        .filter(|name| is_sprite_archive(name))
+        .map(|name| {
+            let archive = SpriteArchive::load(vfs, &name)?;
+            Ok((name, archive))
+        })
+        .collect()
+}
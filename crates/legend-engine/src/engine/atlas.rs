@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use crate::engine::graphics::{Image, Palette, RleImage, Vector2};
+
+#[derive(Copy, Clone, Debug)]
+pub struct AtlasEntry {
+    pub atlas_index: usize,
+    pub position: Vector2<u32>,
+    pub size: Vector2<u16>,
+    pub offset: Vector2<i16>
+}
+
+// one indexed page, frames are packed in shelves from top to bottom
+pub struct SpriteAtlas {
+    size: Vector2<u32>,
+    data: Vec<Option<u8>>,
+    shelf_x: u32,
+    shelf_y: u32,
+    shelf_height: u32
+}
+
+impl SpriteAtlas {
+    pub fn new(width: u32, height: u32) -> Self {
+        SpriteAtlas {
+            size: Vector2::new(width, height),
+            data: vec![None; (width * height) as usize],
+            shelf_x: 0,
+            shelf_y: 0,
+            shelf_height: 0
+        }
+    }
+
+    fn allocate(&mut self, width: u32, height: u32) -> Option<Vector2<u32>> {
+        if width > self.size.x || height > self.size.y {
+            return None;
+        }
+
+        if self.shelf_x + width > self.size.x {
+            self.shelf_y += self.shelf_height;
+            self.shelf_x = 0;
+            self.shelf_height = 0;
+        }
+
+        if self.shelf_y + height > self.size.y {
+            return None;
+        }
+
+        let position = Vector2::new(self.shelf_x, self.shelf_y);
+        self.shelf_x += width;
+        self.shelf_height = self.shelf_height.max(height);
+
+        Some(position)
+    }
+
+    fn insert(&mut self, source: &RleImage) -> Option<Vector2<u32>> {
+        let width = source.size.x as u32;
+        let height = source.size.y as u32;
+        let position = self.allocate(width, height)?;
+
+        let pixels = source.decode_indexed();
+        for y in 0..height {
+            let start = ((position.y + y) * self.size.x + position.x) as usize;
+            let line = (y * width) as usize;
+            self.data[start..start + width as usize].copy_from_slice(&pixels[line..line + width as usize]);
+        }
+
+        Some(position)
+    }
+
+    pub fn to_image(&self, palette: &Palette) -> Image {
+        let mut image = Image::new(self.size.x, self.size.y);
+
+        for (pixel, index) in image.data.iter_mut().zip(self.data.iter()) {
+            if let Some(index) = index {
+                *pixel = palette.get_color(*index);
+            }
+        }
+
+        image
+    }
+}
+
+// frames decoded once and shared between pages, looked up by their sprite index
+pub struct AtlasSet {
+    page_size: Vector2<u32>,
+    atlases: Vec<SpriteAtlas>,
+    entries: HashMap<usize, AtlasEntry>
+}
+
+impl AtlasSet {
+    pub fn new(page_width: u32, page_height: u32) -> Self {
+        AtlasSet {
+            page_size: Vector2::new(page_width, page_height),
+            atlases: Vec::new(),
+            entries: HashMap::new()
+        }
+    }
+
+    // packing taller frames first keeps the shelves tight
+    pub fn pack(page_width: u32, page_height: u32, frames: &[(usize, &RleImage)]) -> Self {
+        let mut sorted: Vec<&(usize, &RleImage)> = frames.iter().collect();
+        sorted.sort_by_key(|frame| std::cmp::Reverse(frame.1.size.y));
+
+        let mut atlas_set = AtlasSet::new(page_width, page_height);
+        for (key, frame) in sorted {
+            atlas_set.add(*key, frame);
+        }
+
+        atlas_set
+    }
+
+    // returns false when the frame is empty or larger than a page
+    pub fn add(&mut self, key: usize, source: &RleImage) -> bool {
+        if self.entries.contains_key(&key) {
+            return true;
+        }
+
+        if source.is_empty() || source.size.x as u32 > self.page_size.x || source.size.y as u32 > self.page_size.y {
+            return false;
+        }
+
+        let placed = match self.atlases.last_mut() {
+            Some(atlas) => atlas.insert(source),
+            None => None
+        };
+
+        let position = match placed {
+            Some(position) => position,
+            None => {
+                let mut atlas = SpriteAtlas::new(self.page_size.x, self.page_size.y);
+                let position = atlas.insert(source);
+                self.atlases.push(atlas);
+
+                match position {
+                    Some(position) => position,
+                    None => return false
+                }
+            }
+        };
+
+        self.entries.insert(key, AtlasEntry {
+            atlas_index: self.atlases.len() - 1,
+            position,
+            size: source.size,
+            offset: source.offset
+        });
+
+        true
+    }
+
+    pub fn get(&self, key: usize) -> Option<&AtlasEntry> {
+        self.entries.get(&key)
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.atlases.len()
+    }
+
+    pub fn page(&self, index: usize) -> Option<&SpriteAtlas> {
+        self.atlases.get(index)
+    }
+
+    // same placement rules as Image::blit, but without decoding the rle stream again
+    pub fn blit(&self, target: &mut Image, key: usize, x: i32, y: i32, palette: &Palette) -> bool {
+        let entry = match self.entries.get(&key) {
+            Some(entry) => entry,
+            None => return false
+        };
+
+        let atlas = &self.atlases[entry.atlas_index];
+        let start_x = x + entry.offset.x as i32;
+        let start_y = y + entry.offset.y as i32;
+
+        for j in 0..entry.size.y as u32 {
+            let line = ((entry.position.y + j) * atlas.size.x + entry.position.x) as usize;
+
+            for i in 0..entry.size.x as u32 {
+                if let Some(index) = atlas.data[line + i as usize] {
+                    target.set_pixel(start_x + i as i32, start_y + j as i32, &palette.get_color(index));
+                }
+            }
+        }
+
+        true
+    }
+}
@@ -0,0 +1,67 @@
+pub const CHANNELS: u16 = 2;
+
+#[cfg(target_os = "windows")]
+const DEFAULT_BUFFER_SIZE: u32 = 1024;
+
+#[cfg(target_os = "macos")]
+const DEFAULT_BUFFER_SIZE: u32 = 512;
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+const DEFAULT_BUFFER_SIZE: u32 = 768;
+
+const DEFAULT_SAMPLE_RATE: u32 = 44100;
+
+// largest buffer we fall back to, lag is better than no sound
+const SAFE_BUFFER_SIZE: u32 = 2048;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AudioConfig {
+    // None is the system default device
+    pub device: Option<String>,
+    pub sample_rate: u32,
+    // frames per output callback, smaller is less latency
+    pub buffer_size: u32
+}
+
+impl AudioConfig {
+    pub fn new(device: Option<String>, sample_rate: Option<u32>, buffer_size: Option<u32>) -> Self {
+        AudioConfig {
+            device,
+            sample_rate: sample_rate.unwrap_or(DEFAULT_SAMPLE_RATE),
+            buffer_size: buffer_size.unwrap_or(DEFAULT_BUFFER_SIZE)
+        }
+    }
+
+    // configurations to try in order when opening the output fails
+    pub fn fallback_chain(&self) -> Vec<AudioConfig> {
+        let mut chain = vec![self.clone()];
+
+        let mut push = |config: AudioConfig| {
+            if !chain.contains(&config) {
+                chain.push(config);
+            }
+        };
+
+        push(AudioConfig { device: self.device.clone(), sample_rate: self.sample_rate, buffer_size: DEFAULT_BUFFER_SIZE });
+        push(AudioConfig { device: None, sample_rate: self.sample_rate, buffer_size: DEFAULT_BUFFER_SIZE });
+        push(AudioConfig { device: None, sample_rate: DEFAULT_SAMPLE_RATE, buffer_size: DEFAULT_BUFFER_SIZE });
+        push(AudioConfig { device: None, sample_rate: DEFAULT_SAMPLE_RATE, buffer_size: SAFE_BUFFER_SIZE });
+
+        chain
+    }
+
+    pub fn latency_ms(&self) -> f64 {
+        self.buffer_size as f64 * 1000.0 / self.sample_rate as f64
+    }
+
+    // the stream thread keeps a few callbacks worth of samples queued
+    pub fn ring_buffer_capacity(&self) -> usize {
+        self.buffer_size as usize * CHANNELS as usize * 4
+    }
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        AudioConfig::new(None, None, None)
+    }
+}
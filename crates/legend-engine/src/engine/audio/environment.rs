@@ -0,0 +1,112 @@
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Environment {
+    None,
+    Outdoors,
+    Hall,
+    Cave
+}
+
+impl Environment {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(Environment::None),
+            "outdoors" => Some(Environment::Outdoors),
+            "hall" => Some(Environment::Hall),
+            "cave" => Some(Environment::Cave),
+            _ => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Environment::None => "none",
+            Environment::Outdoors => "outdoors",
+            Environment::Hall => "hall",
+            Environment::Cave => "cave"
+        }
+    }
+
+    // delay lines in milliseconds, feedback and wet level
+    fn parameters(&self) -> (&'static [f32], f32, f32) {
+        match self {
+            Environment::None => (&[], 0.0, 0.0),
+            Environment::Outdoors => (&[120.0], 0.15, 0.12),
+            Environment::Hall => (&[29.7, 37.1, 41.1, 43.7], 0.72, 0.25),
+            Environment::Cave => (&[53.0, 67.0, 79.0, 97.0, 180.0], 0.8, 0.4)
+        }
+    }
+}
+
+struct CombFilter {
+    buffer: Vec<f32>,
+    index: usize
+}
+
+impl CombFilter {
+    fn new(length: usize) -> Self {
+        CombFilter { buffer: vec![0.0; length.max(1)], index: 0 }
+    }
+
+    fn process(&mut self, input: f32, feedback: f32) -> f32 {
+        let output = self.buffer[self.index];
+        self.buffer[self.index] = input + output * feedback;
+        self.index = (self.index + 1) % self.buffer.len();
+        output
+    }
+}
+
+// parallel comb filters per channel, applied to the sound effect bus only
+pub struct EnvironmentEffect {
+    environment: Environment,
+    feedback: f32,
+    wet: f32,
+    left: Vec<CombFilter>,
+    right: Vec<CombFilter>
+}
+
+impl EnvironmentEffect {
+    pub fn new(environment: Environment, sample_rate: u32) -> Self {
+        let (delays, feedback, wet) = environment.parameters();
+
+        let make_filters = |spread: f32| -> Vec<CombFilter> {
+            delays.iter().map(|delay| CombFilter::new(((delay + spread) * sample_rate as f32 / 1000.0) as usize)).collect()
+        };
+
+        EnvironmentEffect {
+            environment,
+            feedback,
+            wet,
+            // a slightly longer right side keeps the tail from sounding mono
+            left: make_filters(0.0),
+            right: make_filters(1.3)
+        }
+    }
+
+    pub fn environment(&self) -> Environment {
+        self.environment
+    }
+
+    // interleaved stereo in place
+    pub fn process(&mut self, buffer: &mut [f32]) {
+        if self.left.is_empty() {
+            return;
+        }
+
+        let scale = self.wet / self.left.len() as f32;
+
+        for frame in buffer.chunks_exact_mut(2) {
+            let mut left = 0.0;
+            for filter in self.left.iter_mut() {
+                left += filter.process(frame[0], self.feedback);
+            }
+
+            let mut right = 0.0;
+            for filter in self.right.iter_mut() {
+                right += filter.process(frame[1], self.feedback);
+            }
+
+            frame[0] += left * scale;
+            frame[1] += right * scale;
+        }
+    }
+}
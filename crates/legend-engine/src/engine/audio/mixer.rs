@@ -0,0 +1,130 @@
+use std::sync::mpsc::{Receiver, TryRecvError};
+use crate::engine::audio::environment::EnvironmentEffect;
+use crate::engine::audio::ring_buffer::Consumer;
+use crate::engine::audio::sound::SoundInstance;
+
+// sounds playing at the same time, the oldest one is cut when another starts
+pub const MAX_VOICES: usize = 16;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AudioChannel {
+    Master,
+    Music,
+    Sound
+}
+
+impl AudioChannel {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "master" => Some(AudioChannel::Master),
+            "music" => Some(AudioChannel::Music),
+            "sound" => Some(AudioChannel::Sound),
+            _ => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AudioChannel::Master => "master",
+            AudioChannel::Music => "music",
+            AudioChannel::Sound => "sound"
+        }
+    }
+}
+
+pub enum MixerCommand {
+    PlaySound(SoundInstance),
+    StopSounds,
+    SetVolume(AudioChannel, f32),
+    SetEnvironment(EnvironmentEffect)
+}
+
+// runs inside the output callback, commands are polled so it never waits on the game thread
+pub struct Mixer {
+    commands: Receiver<MixerCommand>,
+    music: Consumer,
+    voices: Vec<SoundInstance>,
+    environment: Option<EnvironmentEffect>,
+    music_buffer: Vec<f32>,
+    sound_buffer: Vec<f32>,
+    master_volume: f32,
+    music_volume: f32,
+    sound_volume: f32
+}
+
+impl Mixer {
+    pub fn new(commands: Receiver<MixerCommand>, music: Consumer, max_buffer_len: usize) -> Self {
+        Mixer {
+            commands,
+            music,
+            voices: Vec::with_capacity(MAX_VOICES),
+            environment: None,
+            music_buffer: vec![0.0; max_buffer_len],
+            sound_buffer: vec![0.0; max_buffer_len],
+            master_volume: 1.0,
+            music_volume: 1.0,
+            sound_volume: 1.0
+        }
+    }
+
+    fn handle_commands(&mut self) {
+        loop {
+            match self.commands.try_recv() {
+                Ok(MixerCommand::PlaySound(instance)) => {
+                    if self.voices.len() >= MAX_VOICES {
+                        self.voices.remove(0);
+                    }
+                    self.voices.push(instance);
+                },
+                Ok(MixerCommand::StopSounds) => self.voices.clear(),
+                Ok(MixerCommand::SetVolume(channel, volume)) => {
+                    let volume = volume.clamp(0.0, 1.0);
+                    match channel {
+                        AudioChannel::Master => self.master_volume = volume,
+                        AudioChannel::Music => self.music_volume = volume,
+                        AudioChannel::Sound => self.sound_volume = volume
+                    }
+                },
+                Ok(MixerCommand::SetEnvironment(effect)) => self.environment = Some(effect),
+                Err(TryRecvError::Empty) | Err(TryRecvError::Disconnected) => break
+            }
+        }
+    }
+
+    // fills interleaved stereo output, output callbacks larger than max_buffer_len are mixed in pieces
+    pub fn mix(&mut self, output: &mut [f32]) {
+        self.handle_commands();
+
+        let chunk_len = self.music_buffer.len().max(2) & !1;
+        for chunk in output.chunks_mut(chunk_len) {
+            self.mix_chunk(chunk);
+        }
+    }
+
+    fn mix_chunk(&mut self, output: &mut [f32]) {
+        let len = output.len();
+        let music = &mut self.music_buffer[..len];
+        let sound = &mut self.sound_buffer[..len];
+
+        // an underrun only leaves the music silent for the missing part
+        let read = self.music.pop_slice(music);
+        for sample in music[read..].iter_mut() {
+            *sample = 0.0;
+        }
+
+        for sample in sound.iter_mut() {
+            *sample = 0.0;
+        }
+
+        self.voices.retain_mut(|voice| voice.mix_into(sound, 1.0));
+
+        if let Some(environment) = self.environment.as_mut() {
+            environment.process(sound);
+        }
+
+        for (i, sample) in output.iter_mut().enumerate() {
+            let mixed = (music[i] * self.music_volume + sound[i] * self.sound_volume) * self.master_volume;
+            *sample = mixed.clamp(-1.0, 1.0);
+        }
+    }
+}
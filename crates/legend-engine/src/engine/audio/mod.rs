@@ -0,0 +1,8 @@
+pub mod ring_buffer;
+pub mod stream;
+pub mod config;
+pub mod sound;
+pub mod environment;
+pub mod music;
+pub mod mixer;
+pub mod player;
@@ -0,0 +1,162 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use crate::engine::audio::sound::Sound;
+use crate::engine::audio::stream::AudioSource;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum MusicEvent {
+    Beat(u64),
+    Bar(u64),
+    LoopPoint,
+    TrackEnded
+}
+
+impl MusicEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MusicEvent::Beat(_) => "beat",
+            MusicEvent::Bar(_) => "bar",
+            MusicEvent::LoopPoint => "loop",
+            MusicEvent::TrackEnded => "end"
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct MusicTrack {
+    pub name: String,
+    pub sound: Arc<Sound>,
+    // frame to jump back to when the end is reached, None plays once
+    pub loop_start: Option<usize>,
+    pub tempo: Option<f64>,
+    pub beats_per_bar: u32
+}
+
+impl MusicTrack {
+    pub fn new(name: &str, sound: Arc<Sound>) -> Self {
+        MusicTrack { name: name.to_string(), sound, loop_start: None, tempo: None, beats_per_bar: 4 }
+    }
+
+    fn frames_per_beat(&self) -> Option<f64> {
+        self.tempo.filter(|tempo| *tempo > 0.0).map(|tempo| self.sound.sample_rate as f64 * 60.0 / tempo)
+    }
+}
+
+// streams a track and reports sync points back to the game thread
+pub struct MusicSource {
+    track: MusicTrack,
+    position: usize,
+    beat: u64,
+    ended: bool,
+    events: Sender<MusicEvent>,
+    shared_position: Arc<AtomicUsize>
+}
+
+impl MusicSource {
+    // shared_position is updated after every chunk so the game can read where playback is
+    pub fn new(track: MusicTrack, start: usize, events: Sender<MusicEvent>, shared_position: Arc<AtomicUsize>) -> Self {
+        let beat = match track.frames_per_beat() {
+            Some(frames_per_beat) => (start as f64 / frames_per_beat) as u64,
+            None => 0
+        };
+
+        shared_position.store(start, Ordering::Relaxed);
+
+        MusicSource { track, position: start, beat, ended: false, events, shared_position }
+    }
+
+    fn emit_beats(&mut self) {
+        if let Some(frames_per_beat) = self.track.frames_per_beat() {
+            let current = (self.position as f64 / frames_per_beat) as u64;
+
+            while self.beat < current {
+                self.beat += 1;
+                let _ = self.events.send(MusicEvent::Beat(self.beat));
+
+                if self.track.beats_per_bar > 0 && self.beat % self.track.beats_per_bar as u64 == 0 {
+                    let _ = self.events.send(MusicEvent::Bar(self.beat / self.track.beats_per_bar as u64));
+                }
+            }
+        }
+    }
+}
+
+impl AudioSource for MusicSource {
+    fn fill(&mut self, buffer: &mut [f32]) -> usize {
+        if self.ended {
+            return 0;
+        }
+
+        let frame_count = self.track.sound.frame_count();
+        let mut written = 0;
+
+        for frame in buffer.chunks_exact_mut(2) {
+            if self.position >= frame_count {
+                match self.track.loop_start {
+                    Some(loop_start) if loop_start < frame_count => {
+                        self.position = loop_start;
+                        self.beat = match self.track.frames_per_beat() {
+                            Some(frames_per_beat) => (loop_start as f64 / frames_per_beat) as u64,
+                            None => 0
+                        };
+                        let _ = self.events.send(MusicEvent::LoopPoint);
+                    },
+                    _ => {
+                        let _ = self.events.send(MusicEvent::TrackEnded);
+                        self.ended = true;
+                        break;
+                    }
+                }
+            }
+
+            let (left, right) = self.track.sound.frame(self.position);
+            frame[0] = left;
+            frame[1] = right;
+            self.position += 1;
+            written += 2;
+        }
+
+        self.emit_beats();
+        self.shared_position.store(self.position, Ordering::Relaxed);
+
+        written
+    }
+}
+
+// the game side end of the event channel
+pub struct MusicEvents {
+    sender: Sender<MusicEvent>,
+    receiver: Receiver<MusicEvent>
+}
+
+impl MusicEvents {
+    pub fn new() -> Self {
+        let (sender, receiver) = channel();
+        MusicEvents { sender, receiver }
+    }
+
+    pub fn sender(&self) -> Sender<MusicEvent> {
+        self.sender.clone()
+    }
+
+    pub fn drain(&self) -> Vec<MusicEvent> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+impl Default for MusicEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// what is needed to resume the soundtrack after loading a game
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AudioSnapshot {
+    pub music: Option<String>,
+    pub music_loop: bool,
+    // in frames of the track
+    pub music_position: usize,
+    pub environment: String
+}
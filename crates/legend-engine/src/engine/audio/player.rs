@@ -0,0 +1,245 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Sender};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use crate::engine::audio::config::{AudioConfig, CHANNELS};
+use crate::engine::audio::environment::{Environment, EnvironmentEffect};
+use crate::engine::audio::mixer::{AudioChannel, Mixer, MixerCommand};
+use crate::engine::audio::music::{AudioSnapshot, MusicEvent, MusicEvents, MusicSource, MusicTrack};
+use crate::engine::audio::ring_buffer::{ring_buffer, Producer};
+use crate::engine::audio::sound::{PlayOptions, Sound, SoundInstance};
+use crate::engine::audio::stream::StreamThread;
+use crate::engine::error::{EngineError, EngineResult};
+use crate::engine::random::Random;
+use crate::engine::vfs::Vfs;
+
+const SOUND_EXTENSIONS: [&str; 2] = ["", ".wav"];
+
+fn find_device(host: &cpal::Host, name: &Option<String>) -> EngineResult<cpal::Device> {
+    match name {
+        Some(name) => host.output_devices().map_err(|error| EngineError::Audio(error.to_string()))?
+            .find(|device| device.name().map(|device_name| device_name == *name).unwrap_or(false))
+            .ok_or_else(|| EngineError::Audio(format!("audio device {} not found", name))),
+        None => host.default_output_device().ok_or_else(|| EngineError::Audio("no audio output device".to_string()))
+    }
+}
+
+fn open_output(config: &AudioConfig) -> EngineResult<(cpal::Stream, Sender<MixerCommand>, Producer)> {
+    let host = cpal::default_host();
+    let device = find_device(&host, &config.device)?;
+
+    let stream_config = cpal::StreamConfig {
+        channels: CHANNELS,
+        sample_rate: cpal::SampleRate(config.sample_rate),
+        buffer_size: cpal::BufferSize::Fixed(config.buffer_size)
+    };
+
+    let (producer, consumer) = ring_buffer(config.ring_buffer_capacity());
+    let (sender, receiver) = channel();
+    let mut mixer = Mixer::new(receiver, consumer, config.buffer_size as usize * CHANNELS as usize);
+
+    let stream = device.build_output_stream(
+        &stream_config,
+        move |output: &mut [f32], _: &cpal::OutputCallbackInfo| mixer.mix(output),
+        |error| eprintln!("audio output error: {}", error)
+    ).map_err(|error| EngineError::Audio(error.to_string()))?;
+    stream.play().map_err(|error| EngineError::Audio(error.to_string()))?;
+
+    Ok((stream, sender, producer))
+}
+
+struct Output {
+    // dropping the stream stops the output callback
+    _stream: cpal::Stream,
+    commands: Sender<MixerCommand>,
+    music_stream: StreamThread
+}
+
+// the game side of the audio system, everything here is cheap enough to call from scripts every frame
+pub struct Audio {
+    config: AudioConfig,
+    output: Option<Output>,
+    vfs: Arc<Vfs>,
+    sounds: HashMap<String, Arc<Sound>>,
+    random: Random,
+    music_events: MusicEvents,
+    music_position: Arc<AtomicUsize>,
+    music: Option<String>,
+    music_loop: bool,
+    environment: Environment,
+    volumes: [f32; 3]
+}
+
+impl Audio {
+    // keeps track of what would play without opening a device, for tests and headless runs
+    pub fn silent(config: &AudioConfig, vfs: Arc<Vfs>) -> Self {
+        Audio {
+            config: config.clone(),
+            output: None,
+            vfs,
+            sounds: HashMap::new(),
+            random: Random::new(0x5eed),
+            music_events: MusicEvents::new(),
+            music_position: Arc::new(AtomicUsize::new(0)),
+            music: None,
+            music_loop: false,
+            environment: Environment::None,
+            volumes: [1.0; 3]
+        }
+    }
+
+    // walks the fallback chain, without any working output the game still runs silently
+    pub fn open(config: &AudioConfig, vfs: Arc<Vfs>) -> Self {
+        let mut audio = Self::silent(config, vfs);
+
+        for candidate in config.fallback_chain() {
+            match open_output(&candidate) {
+                Ok((stream, commands, producer)) => {
+                    audio.config = candidate;
+                    audio.output = Some(Output { _stream: stream, commands, music_stream: StreamThread::spawn(producer) });
+                    break;
+                },
+                Err(error) => eprintln!("can not open audio output {:?}: {}", candidate, error)
+            }
+        }
+
+        audio
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.output.is_some()
+    }
+
+    // the configuration that actually opened, may differ from the requested one
+    pub fn config(&self) -> &AudioConfig {
+        &self.config
+    }
+
+    fn send(&self, command: MixerCommand) {
+        if let Some(output) = &self.output {
+            let _ = output.commands.send(command);
+        }
+    }
+
+    // sounds are converted to the output rate once and kept for the rest of the game
+    pub fn load(&mut self, name: &str) -> EngineResult<Arc<Sound>> {
+        if let Some(sound) = self.sounds.get(name) {
+            return Ok(sound.clone());
+        }
+
+        let filename = SOUND_EXTENSIONS.iter()
+            .map(|extension| format!("{}{}", name, extension))
+            .find(|filename| self.vfs.exists(filename))
+            .ok_or_else(|| EngineError::NotFound(format!("sound {}", name)))?;
+
+        let sound = Arc::new(Sound::from_wav_bytes(&self.vfs.read(&filename)?)?.resample(self.config.sample_rate));
+        self.sounds.insert(name.to_string(), sound.clone());
+
+        Ok(sound)
+    }
+
+    pub fn play_sound(&mut self, name: &str, options: PlayOptions) -> EngineResult<()> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+
+        let sound = self.load(name)?;
+        let pitch = options.resolve_pitch(&mut self.random);
+        self.send(MixerCommand::PlaySound(SoundInstance::new(sound, self.config.sample_rate, options.volume, pitch)));
+
+        Ok(())
+    }
+
+    pub fn stop_sounds(&self) {
+        self.send(MixerCommand::StopSounds);
+    }
+
+    pub fn play_music(&mut self, name: &str, looping: bool) -> EngineResult<()> {
+        self.play_music_from(name, looping, 0)
+    }
+
+    fn play_music_from(&mut self, name: &str, looping: bool, start: usize) -> EngineResult<()> {
+        self.music = Some(name.to_string());
+        self.music_loop = looping;
+
+        if !self.is_enabled() {
+            return Ok(());
+        }
+
+        let mut track = MusicTrack::new(name, self.load(name)?);
+        if looping {
+            track.loop_start = Some(0);
+        }
+
+        let source = MusicSource::new(track, start, self.music_events.sender(), self.music_position.clone());
+        if let Some(output) = &self.output {
+            output.music_stream.play(Box::new(source));
+        }
+
+        Ok(())
+    }
+
+    pub fn stop_music(&mut self) {
+        self.music = None;
+
+        if let Some(output) = &self.output {
+            output.music_stream.stop();
+        }
+    }
+
+    pub fn music(&self) -> Option<&str> {
+        self.music.as_deref()
+    }
+
+    // beats, bars and loop points since the last call
+    pub fn drain_music_events(&mut self) -> Vec<MusicEvent> {
+        let events = self.music_events.drain();
+
+        if events.contains(&MusicEvent::TrackEnded) {
+            self.music = None;
+        }
+
+        events
+    }
+
+    pub fn set_volume(&mut self, channel: AudioChannel, volume: f32) {
+        let volume = volume.clamp(0.0, 1.0);
+        self.volumes[channel as usize] = volume;
+        self.send(MixerCommand::SetVolume(channel, volume));
+    }
+
+    pub fn get_volume(&self, channel: AudioChannel) -> f32 {
+        self.volumes[channel as usize]
+    }
+
+    pub fn set_environment(&mut self, environment: Environment) {
+        self.environment = environment;
+        self.send(MixerCommand::SetEnvironment(EnvironmentEffect::new(environment, self.config.sample_rate)));
+    }
+
+    pub fn environment(&self) -> Environment {
+        self.environment
+    }
+
+    pub fn snapshot(&self) -> AudioSnapshot {
+        AudioSnapshot {
+            music: self.music.clone(),
+            music_loop: self.music_loop,
+            music_position: if self.music.is_some() { self.music_position.load(Ordering::Relaxed) } else { 0 },
+            environment: self.environment.name().to_string()
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &AudioSnapshot) -> EngineResult<()> {
+        self.set_environment(Environment::from_name(&snapshot.environment).unwrap_or(Environment::None));
+
+        match &snapshot.music {
+            Some(music) => self.play_music_from(music, snapshot.music_loop, snapshot.music_position),
+            None => {
+                self.stop_music();
+                Ok(())
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+// single producer single consumer sample queue, samples are stored as f32 bits so no locking is needed
+struct Inner {
+    samples: Vec<AtomicU32>,
+    read: AtomicUsize,
+    write: AtomicUsize
+}
+
+impl Inner {
+    fn len(&self) -> usize {
+        self.write.load(Ordering::Acquire).wrapping_sub(self.read.load(Ordering::Acquire))
+    }
+}
+
+pub struct Producer {
+    inner: Arc<Inner>
+}
+
+pub struct Consumer {
+    inner: Arc<Inner>
+}
+
+pub fn ring_buffer(capacity: usize) -> (Producer, Consumer) {
+    let inner = Arc::new(Inner {
+        samples: (0..capacity.max(1)).map(|_| AtomicU32::new(0)).collect(),
+        read: AtomicUsize::new(0),
+        write: AtomicUsize::new(0)
+    });
+
+    (Producer { inner: inner.clone() }, Consumer { inner })
+}
+
+impl Producer {
+    pub fn free_len(&self) -> usize {
+        self.inner.samples.len() - self.inner.len()
+    }
+
+    // returns how many samples fit, the rest is left to the caller
+    pub fn push_slice(&mut self, samples: &[f32]) -> usize {
+        let capacity = self.inner.samples.len();
+        let write = self.inner.write.load(Ordering::Relaxed);
+        let count = samples.len().min(self.free_len());
+
+        for (i, sample) in samples[..count].iter().enumerate() {
+            self.inner.samples[write.wrapping_add(i) % capacity].store(sample.to_bits(), Ordering::Relaxed);
+        }
+
+        self.inner.write.store(write.wrapping_add(count), Ordering::Release);
+
+        count
+    }
+}
+
+impl Consumer {
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn pop_slice(&mut self, samples: &mut [f32]) -> usize {
+        let capacity = self.inner.samples.len();
+        let read = self.inner.read.load(Ordering::Relaxed);
+        let count = samples.len().min(self.len());
+
+        for (i, sample) in samples[..count].iter_mut().enumerate() {
+            *sample = f32::from_bits(self.inner.samples[read.wrapping_add(i) % capacity].load(Ordering::Relaxed));
+        }
+
+        self.inner.read.store(read.wrapping_add(count), Ordering::Release);
+
+        count
+    }
+}
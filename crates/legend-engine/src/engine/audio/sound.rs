@@ -0,0 +1,159 @@
+use std::io::Cursor;
+use std::sync::Arc;
+use crate::engine::error::EngineResult;
+use crate::engine::random::Random;
+
+// decoded interleaved stereo samples
+pub struct Sound {
+    pub sample_rate: u32,
+    samples: Vec<f32>
+}
+
+impl Sound {
+    pub fn new(sample_rate: u32, samples: Vec<f32>) -> Self {
+        Sound { sample_rate, samples }
+    }
+
+    // mono data is duplicated into both channels
+    pub fn from_mono(sample_rate: u32, samples: &[f32]) -> Self {
+        let mut stereo = Vec::with_capacity(samples.len() * 2);
+        for &sample in samples {
+            stereo.push(sample);
+            stereo.push(sample);
+        }
+
+        Sound { sample_rate, samples: stereo }
+    }
+
+    // pcm wav in any bit depth, more than two channels keeps the first two
+    pub fn from_wav_bytes(bytes: &[u8]) -> EngineResult<Self> {
+        let mut reader = hound::WavReader::new(Cursor::new(bytes))?;
+        let spec = reader.spec();
+
+        let samples: Vec<f32> = match spec.sample_format {
+            hound::SampleFormat::Float => reader.samples::<f32>().collect::<Result<_, _>>()?,
+            hound::SampleFormat::Int => {
+                let scale = (1i64 << (spec.bits_per_sample - 1)) as f32;
+                reader.samples::<i32>().map(|sample| sample.map(|sample| sample as f32 / scale)).collect::<Result<_, _>>()?
+            }
+        };
+
+        let channels = spec.channels.max(1) as usize;
+        if channels == 1 {
+            return Ok(Self::from_mono(spec.sample_rate, &samples));
+        }
+
+        let mut stereo = Vec::with_capacity(samples.len() / channels * 2);
+        for frame in samples.chunks_exact(channels) {
+            stereo.push(frame[0]);
+            stereo.push(frame[1]);
+        }
+
+        Ok(Sound::new(spec.sample_rate, stereo))
+    }
+
+    // linear resampling, done once on load so music can be streamed without converting
+    pub fn resample(&self, sample_rate: u32) -> Self {
+        if sample_rate == self.sample_rate || self.sample_rate == 0 {
+            return Sound::new(self.sample_rate, self.samples.clone());
+        }
+
+        let step = self.sample_rate as f64 / sample_rate as f64;
+        let frame_count = (self.frame_count() as f64 / step) as usize;
+        let mut samples = Vec::with_capacity(frame_count * 2);
+
+        for frame in 0..frame_count {
+            let position = frame as f64 * step;
+            let index = position as usize;
+            let fraction = (position - index as f64) as f32;
+            let (left_a, right_a) = self.frame(index);
+            let (left_b, right_b) = if index + 1 < self.frame_count() { self.frame(index + 1) } else { (left_a, right_a) };
+
+            samples.push(left_a + (left_b - left_a) * fraction);
+            samples.push(right_a + (right_b - right_a) * fraction);
+        }
+
+        Sound::new(sample_rate, samples)
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.samples.len() / 2
+    }
+
+    pub fn frame(&self, index: usize) -> (f32, f32) {
+        match self.samples.get(index * 2..index * 2 + 2) {
+            Some(frame) => (frame[0], frame[1]),
+            None => (0.0, 0.0)
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct PlayOptions {
+    pub volume: f32,
+    // 1.0 is the original pitch, 2.0 an octave higher and twice as fast
+    pub pitch: f32,
+    // random amount added to or removed from pitch on every play
+    pub pitch_variance: f32
+}
+
+impl PlayOptions {
+    pub fn resolve_pitch(&self, random: &mut Random) -> f32 {
+        let variance = self.pitch_variance.abs();
+        let pitch = if variance > 0.0 {
+            self.pitch + (random.next_f32() * 2.0 - 1.0) * variance
+        } else {
+            self.pitch
+        };
+
+        pitch.max(0.05)
+    }
+}
+
+impl Default for PlayOptions {
+    fn default() -> Self {
+        PlayOptions { volume: 1.0, pitch: 1.0, pitch_variance: 0.0 }
+    }
+}
+
+// one playing copy of a sound, pitch is applied by resampling
+pub struct SoundInstance {
+    sound: Arc<Sound>,
+    position: f64,
+    step: f64,
+    pub volume: f32
+}
+
+impl SoundInstance {
+    pub fn new(sound: Arc<Sound>, output_sample_rate: u32, volume: f32, pitch: f32) -> Self {
+        let step = sound.sample_rate as f64 / output_sample_rate.max(1) as f64 * pitch as f64;
+        SoundInstance { sound, position: 0.0, step, volume }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.sound.frame_count() as f64
+    }
+
+    // adds to interleaved stereo output, returns false once the sound has ended
+    pub fn mix_into(&mut self, output: &mut [f32], gain: f32) -> bool {
+        let volume = self.volume * gain;
+
+        for frame in output.chunks_exact_mut(2) {
+            if self.is_finished() {
+                return false;
+            }
+
+            let index = self.position as usize;
+            let fraction = (self.position - index as f64) as f32;
+            let (left_a, right_a) = self.sound.frame(index);
+            let (left_b, right_b) = if index + 1 < self.sound.frame_count() { self.sound.frame(index + 1) } else { (left_a, right_a) };
+
+            frame[0] += (left_a + (left_b - left_a) * fraction) * volume;
+            frame[1] += (right_a + (right_b - right_a) * fraction) * volume;
+
+            self.position += self.step;
+        }
+
+        !self.is_finished()
+    }
+}
@@ -0,0 +1,98 @@
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use crate::engine::audio::ring_buffer::Producer;
+
+const CHUNK_SAMPLES: usize = 1024;
+
+// anything that can produce interleaved stereo samples, decoded or synthesized
+pub trait AudioSource: Send {
+    // returns the number of samples written, 0 means the source is finished
+    fn fill(&mut self, buffer: &mut [f32]) -> usize;
+}
+
+enum StreamCommand {
+    Play(Box<dyn AudioSource>),
+    Stop,
+    Shutdown
+}
+
+// decodes the current source on its own thread so a slow frame never starves the output
+pub struct StreamThread {
+    sender: Sender<StreamCommand>,
+    handle: Option<JoinHandle<()>>
+}
+
+impl StreamThread {
+    pub fn spawn(producer: Producer) -> Self {
+        let (sender, receiver) = channel();
+
+        let handle = thread::Builder::new()
+            .name("audio stream".to_string())
+            .spawn(move || stream_loop(producer, receiver))
+            .ok();
+
+        StreamThread { sender, handle }
+    }
+
+    pub fn play(&self, source: Box<dyn AudioSource>) {
+        let _ = self.sender.send(StreamCommand::Play(source));
+    }
+
+    pub fn stop(&self) {
+        let _ = self.sender.send(StreamCommand::Stop);
+    }
+}
+
+impl Drop for StreamThread {
+    fn drop(&mut self) {
+        let _ = self.sender.send(StreamCommand::Shutdown);
+
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn stream_loop(mut producer: Producer, receiver: Receiver<StreamCommand>) {
+    let mut source: Option<Box<dyn AudioSource>> = None;
+    let mut chunk = vec![0.0f32; CHUNK_SAMPLES];
+    let mut pending = 0;
+
+    loop {
+        loop {
+            match receiver.try_recv() {
+                Ok(StreamCommand::Play(new_source)) => {
+                    source = Some(new_source);
+                    pending = 0;
+                },
+                Ok(StreamCommand::Stop) => {
+                    source = None;
+                    pending = 0;
+                },
+                Ok(StreamCommand::Shutdown) | Err(TryRecvError::Disconnected) => return,
+                Err(TryRecvError::Empty) => break
+            }
+        }
+
+        if pending == 0 {
+            if let Some(current) = source.as_mut() {
+                pending = current.fill(&mut chunk);
+                if pending == 0 {
+                    source = None;
+                }
+            }
+        }
+
+        // keep whatever did not fit for the next round
+        if pending > 0 {
+            let pushed = producer.push_slice(&chunk[..pending]);
+            chunk.copy_within(pushed..pending, 0);
+            pending -= pushed;
+        }
+
+        if pending > 0 || source.is_none() {
+            thread::sleep(Duration::from_millis(2));
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use crate::engine::vfs::Vfs;
+
+// shared between the loader thread and whoever shows the loading bar
+#[derive(Default)]
+struct LoadState {
+    // files read so far, failed ones included
+    done: AtomicUsize,
+    failed: Mutex<Vec<String>>,
+    cancelled: AtomicBool
+}
+
+// reads files into the prefetch cache of the vfs on a thread of its own, so a big scene never stops the event loop.
+// the files are there for a later read to pick up, nothing is decoded here
+pub struct BackgroundLoad {
+    total: usize,
+    state: Arc<LoadState>,
+    handle: Option<JoinHandle<()>>
+}
+
+fn load_files(vfs: &Vfs, names: &[String], state: &LoadState) {
+    for name in names {
+        if state.cancelled.load(Ordering::Relaxed) {
+            return;
+        }
+
+        // a file that can not be read is listed, loading it later reports the error where it matters
+        if vfs.prefetch(name).is_err() {
+            state.failed.lock().unwrap().push(name.clone());
+        }
+        state.done.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl BackgroundLoad {
+    // without a thread to run on the files are read right away instead
+    pub fn spawn(vfs: Arc<Vfs>, names: Vec<String>) -> Self {
+        let total = names.len();
+        let state = Arc::new(LoadState::default());
+
+        let (thread_vfs, thread_names, thread_state) = (vfs.clone(), names.clone(), state.clone());
+        let handle = thread::Builder::new()
+            .name("resource loader".to_string())
+            .spawn(move || load_files(&thread_vfs, &thread_names, &thread_state))
+            .map_err(|error| eprintln!("can not start the resource loader, loading right away: {}", error))
+            .ok();
+
+        if handle.is_none() {
+            load_files(&vfs, &names, &state);
+        }
+
+        BackgroundLoad { total, state, handle }
+    }
+
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    pub fn loaded(&self) -> usize {
+        self.state.done.load(Ordering::Relaxed).min(self.total)
+    }
+
+    // 0 to 1, for a loading bar
+    pub fn progress(&self) -> f64 {
+        if self.total == 0 {
+            return 1.0;
+        }
+
+        self.loaded() as f64 / self.total as f64
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.loaded() == self.total
+    }
+
+    pub fn failed(&self) -> Vec<String> {
+        self.state.failed.lock().unwrap().clone()
+    }
+
+    // the file being read is finished, the rest are left
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for BackgroundLoad {
+    fn drop(&mut self) {
+        self.cancel();
+
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::engine::atomic_file::write_atomic;
+use crate::engine::error::EngineResult;
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Default)]
+pub struct EnemyRecord {
+    pub enemy: u32,
+    pub encountered: u32,
+    pub defeated: u32
+}
+
+// everything the encyclopedia screens show, persisted apart from the save slots
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Bestiary {
+    // a list instead of a map, toml only allows string keys
+    #[serde(default)]
+    enemies: Vec<EnemyRecord>,
+    #[serde(default)]
+    items: BTreeSet<u32>,
+    #[serde(default)]
+    locations: BTreeSet<String>
+}
+
+impl Bestiary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // a missing file is an empty bestiary
+    pub fn load(filename: &str) -> EngineResult<Self> {
+        if !Path::new(filename).exists() {
+            return Ok(Self::new());
+        }
+
+        let mut bestiary: Bestiary = toml::from_str(&fs::read_to_string(filename)?)?;
+        bestiary.enemies.sort_by_key(|record| record.enemy);
+
+        Ok(bestiary)
+    }
+
+    pub fn save(&self, filename: &str) -> EngineResult<()> {
+        write_atomic(filename, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    fn enemy_mut(&mut self, enemy: u32) -> &mut EnemyRecord {
+        let index = match self.enemies.binary_search_by_key(&enemy, |record| record.enemy) {
+            Ok(index) => index,
+            Err(index) => {
+                self.enemies.insert(index, EnemyRecord { enemy, encountered: 0, defeated: 0 });
+                index
+            }
+        };
+
+        &mut self.enemies[index]
+    }
+
+    pub fn encounter_enemy(&mut self, enemy: u32) {
+        self.enemy_mut(enemy).encountered += 1;
+    }
+
+    pub fn defeat_enemy(&mut self, enemy: u32) {
+        self.enemy_mut(enemy).defeated += 1;
+    }
+
+    pub fn see_item(&mut self, item: u32) -> bool {
+        self.items.insert(item)
+    }
+
+    pub fn visit_location(&mut self, location: &str) -> bool {
+        self.locations.insert(location.to_string())
+    }
+
+    pub fn enemy(&self, enemy: u32) -> Option<&EnemyRecord> {
+        self.enemies.binary_search_by_key(&enemy, |record| record.enemy).ok().map(|index| &self.enemies[index])
+    }
+
+    pub fn has_seen_item(&self, item: u32) -> bool {
+        self.items.contains(&item)
+    }
+
+    pub fn has_visited(&self, location: &str) -> bool {
+        self.locations.contains(location)
+    }
+
+    pub fn enemies(&self) -> &[EnemyRecord] {
+        &self.enemies
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = u32> + '_ {
+        self.items.iter().copied()
+    }
+
+    pub fn locations(&self) -> impl Iterator<Item = &str> + '_ {
+        self.locations.iter().map(|location| location.as_str())
+    }
+}
@@ -0,0 +1,66 @@
+// how quickly follow closes the distance by default, in halvings per second about
+pub const DEFAULT_FOLLOW_SPEED: f64 = 6.0;
+
+// the top left of the screen in world pixels, so scripts draw maps and sprites where they are in the world
+#[derive(Copy, Clone, Debug)]
+pub struct Camera {
+    x: f64,
+    y: f64,
+    view_width: i32,
+    view_height: i32,
+    // the world size, the camera never shows anything outside of it
+    bounds: Option<(i32, i32)>
+}
+
+impl Camera {
+    pub fn new(view_width: u32, view_height: u32) -> Self {
+        Camera { x: 0.0, y: 0.0, view_width: view_width as i32, view_height: view_height as i32, bounds: None }
+    }
+
+    // whole pixels, a camera between pixels would make tiles shimmer
+    pub fn position(&self) -> (i32, i32) {
+        (self.x.floor() as i32, self.y.floor() as i32)
+    }
+
+    pub fn set_position(&mut self, x: f64, y: f64) {
+        self.x = x;
+        self.y = y;
+        self.clamp();
+    }
+
+    pub fn center_on(&mut self, x: f64, y: f64) {
+        self.set_position(x - self.view_width as f64 / 2.0, y - self.view_height as f64 / 2.0);
+    }
+
+    // eases toward centering on x and y, the same at any update rate
+    pub fn follow(&mut self, x: f64, y: f64, speed: f64, delta: f64) {
+        let (target_x, target_y) = (x - self.view_width as f64 / 2.0, y - self.view_height as f64 / 2.0);
+        let amount = 1.0 - (-speed.max(0.0) * delta).exp();
+        self.set_position(self.x + (target_x - self.x) * amount, self.y + (target_y - self.y) * amount);
+    }
+
+    pub fn bounds(&self) -> Option<(i32, i32)> {
+        self.bounds
+    }
+
+    pub fn set_bounds(&mut self, bounds: Option<(i32, i32)>) {
+        self.bounds = bounds;
+        self.clamp();
+    }
+
+    // a world smaller than the screen is centered
+    fn clamp(&mut self) {
+        if let Some((width, height)) = self.bounds {
+            self.x = Self::clamp_axis(self.x, width, self.view_width);
+            self.y = Self::clamp_axis(self.y, height, self.view_height);
+        }
+    }
+
+    fn clamp_axis(value: f64, world: i32, view: i32) -> f64 {
+        if world <= view {
+            -((view - world) as f64 / 2.0)
+        } else {
+            value.clamp(0.0, (world - view) as f64)
+        }
+    }
+}
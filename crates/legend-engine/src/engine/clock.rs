@@ -0,0 +1,196 @@
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_UPDATE_RATE: u32 = 60;
+// 30 like the original game, 60 and 120 for smoother motion
+pub const UPDATE_RATES: [u32; 3] = [30, DEFAULT_UPDATE_RATE, 120];
+
+// how many times per second the game scripts are updated
+#[derive(Copy, Clone, Debug)]
+pub struct UpdateRate {
+    hz: u32
+}
+
+impl UpdateRate {
+    pub fn new(hz: u32) -> Self {
+        UpdateRate { hz: hz.max(1) }
+    }
+
+    pub fn hz(&self) -> u32 {
+        self.hz
+    }
+
+    // dt in seconds handed to update, so scripts stay rate independent
+    pub fn delta(&self) -> f64 {
+        1.0 / self.hz as f64
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs_f64(self.delta())
+    }
+}
+
+impl Default for UpdateRate {
+    fn default() -> Self {
+        UpdateRate::new(DEFAULT_UPDATE_RATE)
+    }
+}
+
+// longest dt handed to scripts, a stall like a window drag should not teleport everything
+pub const MAX_FRAME_DELTA: f64 = 0.25;
+
+// measures the real time between two frames
+pub struct FrameTimer {
+    last: Instant
+}
+
+impl FrameTimer {
+    pub fn new(now: Instant) -> Self {
+        FrameTimer { last: now }
+    }
+
+    pub fn tick(&mut self, now: Instant) -> f64 {
+        let delta = now.saturating_duration_since(self.last).as_secs_f64();
+        self.last = now;
+
+        delta.min(MAX_FRAME_DELTA)
+    }
+}
+
+// updates due this frame and how far the frame is between the last update and the next one
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Steps {
+    pub updates: u32,
+    // 0 right on the last update, close to 1 just before the next, render blends positions by it
+    pub alpha: f64
+}
+
+// update runs a fixed number of times per second whatever the display rate,
+// the time left over is carried to the next frame
+#[derive(Copy, Clone, Debug)]
+pub struct FixedStep {
+    rate: UpdateRate,
+    accumulator: f64
+}
+
+impl FixedStep {
+    pub fn new(rate: UpdateRate) -> Self {
+        FixedStep { rate, accumulator: 0.0 }
+    }
+
+    pub fn delta(&self) -> f64 {
+        self.rate.delta()
+    }
+
+    // delta is the real frame time, already capped by FrameTimer so a stall can not queue up endless updates
+    pub fn advance(&mut self, delta: f64) -> Steps {
+        let step = self.rate.delta();
+        self.accumulator += delta;
+
+        let mut updates = 0;
+        while self.accumulator >= step {
+            self.accumulator -= step;
+            updates += 1;
+        }
+
+        Steps { updates, alpha: self.accumulator / step }
+    }
+}
+
+// fastest game time may run, past this a fixed step covers too much to stay stable
+pub const MAX_TIME_SCALE: f64 = 8.0;
+
+// game time that can run slower or faster than real time for slow motion and haste,
+// 0 stops it. input timing, menus and render keep real time
+#[derive(Copy, Clone, Debug)]
+pub struct GameTime {
+    time_scale: f64,
+    // seconds of game time and real time since the start
+    elapsed: f64,
+    real_elapsed: f64,
+    delta: f64,
+    real_delta: f64
+}
+
+impl GameTime {
+    pub fn new() -> Self {
+        GameTime { time_scale: 1.0, elapsed: 0.0, real_elapsed: 0.0, delta: 0.0, real_delta: 0.0 }
+    }
+
+    pub fn time_scale(&self) -> f64 {
+        self.time_scale
+    }
+
+    pub fn set_time_scale(&mut self, time_scale: f64) {
+        self.time_scale = time_scale.clamp(0.0, MAX_TIME_SCALE);
+    }
+
+    // call once per update with the real dt, what comes back is the dt game logic runs on
+    pub fn advance(&mut self, real_delta: f64) -> f64 {
+        self.real_delta = real_delta;
+        self.delta = real_delta * self.time_scale;
+        self.real_elapsed += real_delta;
+        self.elapsed += self.delta;
+
+        self.delta
+    }
+
+    pub fn delta(&self) -> f64 {
+        self.delta
+    }
+
+    pub fn real_delta(&self) -> f64 {
+        self.real_delta
+    }
+
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    pub fn real_elapsed(&self) -> f64 {
+        self.real_elapsed
+    }
+}
+
+impl Default for GameTime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub const MINUTES_PER_DAY: u32 = 24 * 60;
+
+// in game time of day, drives npc schedules and time based quests
+#[derive(Copy, Clone, Debug)]
+pub struct GameClock {
+    minutes: f64,
+    day: u32,
+    // game minutes that pass every real second
+    pub speed: f64
+}
+
+impl GameClock {
+    pub fn new(day: u32, minute: u32, speed: f64) -> Self {
+        GameClock { minutes: (minute % MINUTES_PER_DAY) as f64, day, speed }
+    }
+
+    pub fn update(&mut self, delta: f64) {
+        self.minutes += delta * self.speed;
+
+        while self.minutes >= MINUTES_PER_DAY as f64 {
+            self.minutes -= MINUTES_PER_DAY as f64;
+            self.day += 1;
+        }
+    }
+
+    pub fn day(&self) -> u32 {
+        self.day
+    }
+
+    pub fn minute_of_day(&self) -> u32 {
+        self.minutes as u32
+    }
+
+    pub fn hour(&self) -> u32 {
+        self.minute_of_day() / 60
+    }
+}
@@ -0,0 +1,52 @@
+use std::io::{Read, Write};
+use flate2::Compression as Level;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use crate::engine::error::{EngineError, EngineResult};
+
+// how save and snapshot bodies are stored, the name goes in the file header so loading never has to guess
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Compression {
+    None,
+    Deflate
+}
+
+impl Compression {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Compression::None => "none",
+            Compression::Deflate => "deflate"
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(Compression::None),
+            "deflate" => Some(Compression::Deflate),
+            _ => None
+        }
+    }
+
+    pub fn compress(&self, bytes: &[u8]) -> EngineResult<Vec<u8>> {
+        match self {
+            Compression::None => Ok(bytes.to_vec()),
+            Compression::Deflate => {
+                let mut encoder = DeflateEncoder::new(Vec::new(), Level::default());
+                encoder.write_all(bytes)?;
+                Ok(encoder.finish()?)
+            }
+        }
+    }
+
+    pub fn decompress(&self, bytes: &[u8]) -> EngineResult<Vec<u8>> {
+        match self {
+            Compression::None => Ok(bytes.to_vec()),
+            Compression::Deflate => {
+                let mut decompressed = Vec::new();
+                DeflateDecoder::new(bytes).read_to_end(&mut decompressed)
+                    .map_err(|error| EngineError::Save(format!("can not decompress: {}", error)))?;
+                Ok(decompressed)
+            }
+        }
+    }
+}
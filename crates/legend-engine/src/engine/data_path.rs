@@ -0,0 +1,86 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// the original game was made for dos, file names on disk may be in any case
+pub fn find_file(directory: &Path, name: &str) -> Option<PathBuf> {
+    let exact = directory.join(name);
+    if exact.is_file() {
+        return Some(exact);
+    }
+
+    let entries = fs::read_dir(directory).ok()?;
+
+    for entry in entries.flatten() {
+        if entry.file_name().to_string_lossy().eq_ignore_ascii_case(name) && entry.path().is_file() {
+            return Some(entry.path());
+        }
+    }
+
+    None
+}
+
+// name can contain sub folders, like "resource/mmap.col"
+pub fn find_path(root: &Path, name: &str) -> Option<PathBuf> {
+    let mut current = root.to_path_buf();
+    let parts: Vec<&str> = name.split(['/', '\\']).filter(|part| !part.is_empty()).collect();
+
+    for (index, part) in parts.iter().enumerate() {
+        if index + 1 == parts.len() {
+            return find_file(&current, part);
+        }
+
+        current = find_directory(&current, part)?;
+    }
+
+    None
+}
+
+fn find_directory(directory: &Path, name: &str) -> Option<PathBuf> {
+    let exact = directory.join(name);
+    if exact.is_dir() {
+        return Some(exact);
+    }
+
+    let entries = fs::read_dir(directory).ok()?;
+
+    for entry in entries.flatten() {
+        if entry.file_name().to_string_lossy().eq_ignore_ascii_case(name) && entry.path().is_dir() {
+            return Some(entry.path());
+        }
+    }
+
+    None
+}
+
+// like find_path, for a folder
+fn find_folder(root: &Path, name: &str) -> Option<PathBuf> {
+    name.split(['/', '\\']).filter(|part| !part.is_empty())
+        .try_fold(root.to_path_buf(), |current, part| find_directory(&current, part))
+}
+
+// where installers of the time put the game, and a data folder in the working folder or next to the executable.
+// only the ones that exist are returned, the caller decides which of them hold the game
+pub fn install_locations() -> Vec<PathBuf> {
+    let games = ["LEGEND", "GAMES/LEGEND", "GOG Games/Legend", "Program Files (x86)/GOG Galaxy/Games/Legend", "Program Files/GOG Galaxy/Games/Legend"];
+    let mut roots = vec![ (PathBuf::from("."), vec!["data"]) ];
+
+    if let Some(directory) = std::env::current_exe().ok().as_deref().and_then(Path::parent) {
+        roots.push((directory.to_path_buf(), vec!["data"]));
+    }
+    if cfg!(windows) {
+        roots.push((PathBuf::from("C:/"), games.to_vec()));
+    }
+    // dosbox and wine keep their c drive in the home folder
+    if let Some(home) = dirs::home_dir() {
+        roots.push((home.clone(), vec!["GOG Games/Legend"]));
+        for drive in ["dosbox", ".dosbox", ".wine/drive_c"] {
+            roots.push((home.join(drive), games.to_vec()));
+        }
+    }
+
+    let mut locations: Vec<PathBuf> = roots.iter()
+        .flat_map(|(root, names)| names.iter().filter_map(move |name| find_folder(root, name)))
+        .collect();
+    locations.dedup();
+    locations
+}
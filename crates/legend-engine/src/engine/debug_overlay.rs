@@ -0,0 +1,129 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+use crate::engine::graphics::{BlendMode, Color, GameFont, Image};
+use crate::engine::text::encode_big5;
+
+// frames in the frame time graph
+const GRAPH_FRAMES: usize = 64;
+// the numbers are averaged and refreshed this often so they can be read
+const REFRESH_SECONDS: f64 = 0.5;
+// a frame as tall as the graph took this long
+const GRAPH_MILLISECONDS: f64 = 50.0;
+const GRAPH_HEIGHT: i32 = 24;
+// wide enough for the longest line
+const WIDTH: i32 = 120;
+const MARGIN: i32 = 2;
+
+// what one presented frame cost
+#[derive(Copy, Clone, Debug, Default)]
+pub struct FrameSample {
+    // real time since the previous frame
+    pub interval: Duration,
+    // every fixed update of the frame, scripts included
+    pub update_time: Duration,
+    // the render script and presenting
+    pub render_time: Duration,
+    // update and render scripts alone
+    pub script_time: Duration,
+    pub updates: u32,
+    pub blits: u32,
+    // display frames missed before this one, against the median present interval
+    pub dropped_frames: u32,
+    // the scripts took more than half of a display frame
+    pub script_spike: bool
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+struct Averages {
+    fps: f64,
+    update_ms: f64,
+    render_ms: f64,
+    script_ms: f64,
+    updates: f64,
+    blits: f64,
+    // counted over the refresh instead of averaged, a single one is worth seeing
+    dropped_frames: u32,
+    script_spikes: usize
+}
+
+// fps and frame times in the corner of the game, toggled with f3
+#[derive(Default)]
+pub struct DebugOverlay {
+    graph: VecDeque<f64>,
+    pending: Vec<FrameSample>,
+    pending_seconds: f64,
+    shown: Option<Averages>
+}
+
+impl DebugOverlay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, sample: FrameSample) {
+        let interval = sample.interval.as_secs_f64();
+
+        self.graph.push_back(interval * 1000.0);
+        if self.graph.len() > GRAPH_FRAMES {
+            self.graph.pop_front();
+        }
+
+        self.pending.push(sample);
+        self.pending_seconds += interval;
+        if self.pending_seconds < REFRESH_SECONDS {
+            return;
+        }
+
+        let count = self.pending.len() as f64;
+        let milliseconds = |time: fn(&FrameSample) -> Duration| self.pending.iter().map(|sample| time(sample).as_secs_f64() * 1000.0).sum::<f64>() / count;
+        self.shown = Some(Averages {
+            fps: count / self.pending_seconds,
+            update_ms: milliseconds(|sample| sample.update_time),
+            render_ms: milliseconds(|sample| sample.render_time),
+            script_ms: milliseconds(|sample| sample.script_time),
+            updates: self.pending.iter().map(|sample| sample.updates as f64).sum::<f64>() / count,
+            blits: self.pending.iter().map(|sample| sample.blits as f64).sum::<f64>() / count,
+            dropped_frames: self.pending.iter().map(|sample| sample.dropped_frames).sum(),
+            script_spikes: self.pending.iter().filter(|sample| sample.script_spike).count()
+        });
+
+        self.pending.clear();
+        self.pending_seconds = 0.0;
+    }
+
+    // the numbers need the game font, without one only the graph is drawn
+    pub fn draw(&self, target: &mut Image, game_font: Option<&GameFont>) {
+        let lines = match (&self.shown, game_font) {
+            (Some(shown), Some(_)) => vec![
+                format!("fps {:.1}", shown.fps),
+                format!("update {:.2}ms x{:.1}", shown.update_ms, shown.updates),
+                format!("render {:.2}ms", shown.render_ms),
+                format!("script {:.2}ms", shown.script_ms),
+                format!("blits {:.0}", shown.blits),
+                format!("dropped {} spikes {}", shown.dropped_frames, shown.script_spikes)
+            ],
+            _ => Vec::new()
+        };
+
+        let line_height = game_font.map(|game_font| game_font.get_height()).unwrap_or(0);
+        let text_height = lines.len() as i32 * line_height;
+        let height = text_height + GRAPH_HEIGHT + MARGIN * 3;
+
+        target.fill_rect_with_mode(0, 0, WIDTH, height, &Color::new(0, 0, 0, 160), BlendMode::Alpha);
+
+        if let Some(game_font) = game_font {
+            let text_color = Color::new(255, 255, 255, 255);
+            for (index, line) in lines.iter().enumerate() {
+                target.draw_game_text(&encode_big5(line), MARGIN, MARGIN + index as i32 * line_height, game_font, &text_color);
+            }
+        }
+
+        // one column per frame, red when it was slower than 30 fps
+        let graph_bottom = text_height + MARGIN * 2 + GRAPH_HEIGHT;
+        for (index, &milliseconds) in self.graph.iter().enumerate() {
+            let bar = ((milliseconds / GRAPH_MILLISECONDS) * GRAPH_HEIGHT as f64).clamp(1.0, GRAPH_HEIGHT as f64) as i32;
+            let color = if milliseconds > 1000.0 / 30.0 { Color::new(255, 64, 64, 255) } else { Color::new(64, 255, 64, 255) };
+            target.fill_rect_with_mode(MARGIN + index as i32, graph_bottom - bar, 1, bar, &color, BlendMode::Alpha);
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use std::collections::{BTreeMap, VecDeque};
+use crate::engine::graphics::{Color, GameFont, Image};
+use crate::engine::text::encode_big5;
+
+pub const DEBUG_VIEW_WIDTH: u32 = 480;
+pub const DEBUG_VIEW_HEIGHT: u32 = 360;
+
+const LOG_LINES: usize = 100;
+const SWATCH_SIZE: i32 = 8;
+const MARGIN: i32 = 4;
+
+// what the inspector window shows, filled by the scripts through Debug()
+#[derive(Default)]
+pub struct DebugViews {
+    // inspected objects by name, each with its fields in order
+    sections: BTreeMap<String, BTreeMap<String, String>>,
+    log: VecDeque<String>,
+    palette: Option<Vec<Color>>
+}
+
+impl DebugViews {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn inspect(&mut self, section: &str, field: &str, value: String) {
+        self.sections.entry(section.to_string()).or_default().insert(field.to_string(), value);
+    }
+
+    pub fn forget(&mut self, section: &str) {
+        self.sections.remove(section);
+    }
+
+    // the oldest lines go once the log is full
+    pub fn log(&mut self, line: String) {
+        if self.log.len() == LOG_LINES {
+            self.log.pop_front();
+        }
+        self.log.push_back(line);
+    }
+
+    pub fn set_palette(&mut self, colors: Vec<Color>) {
+        self.palette = Some(colors);
+    }
+
+    // palette top left, inspected objects right of it, the latest log lines at the bottom.
+    // text needs the game font, without one only the palette is drawn
+    pub fn render(&self, game_font: Option<&GameFont>) -> Image {
+        let mut image = Image::new(DEBUG_VIEW_WIDTH, DEBUG_VIEW_HEIGHT);
+        image.clear_by_color(Color::new(24, 24, 32, 255));
+
+        let palette_size = SWATCH_SIZE * 16;
+        if let Some(colors) = &self.palette {
+            for (index, color) in colors.iter().enumerate().take(256) {
+                let (column, row) = (index as i32 % 16, index as i32 / 16);
+                image.fill_rect(MARGIN + column * SWATCH_SIZE, MARGIN + row * SWATCH_SIZE, SWATCH_SIZE, SWATCH_SIZE, color);
+            }
+        }
+        image.draw_rect(MARGIN - 1, MARGIN - 1, palette_size + 2, palette_size + 2, &Color::new(96, 96, 112, 255));
+
+        let game_font = match game_font {
+            Some(game_font) => game_font,
+            None => return image
+        };
+
+        let line_height = game_font.get_height();
+        let (title_color, text_color) = (Color::new(255, 224, 128, 255), Color::new(224, 224, 224, 255));
+
+        let log_top = MARGIN * 2 + palette_size;
+        let mut y = MARGIN;
+        for (section, fields) in self.sections.iter() {
+            if y + line_height > log_top {
+                break;
+            }
+
+            image.draw_game_text(&encode_big5(section), palette_size + MARGIN * 3, y, game_font, &title_color);
+            y += line_height;
+
+            for (field, value) in fields.iter() {
+                if y + line_height > log_top {
+                    break;
+                }
+
+                image.draw_game_text(&encode_big5(&format!("  {}: {}", field, value)), palette_size + MARGIN * 3, y, game_font, &text_color);
+                y += line_height;
+            }
+        }
+
+        let visible = ((DEBUG_VIEW_HEIGHT as i32 - log_top - MARGIN) / line_height).max(0) as usize;
+        let skip = self.log.len().saturating_sub(visible);
+        for (index, line) in self.log.iter().skip(skip).enumerate() {
+            image.draw_game_text(&encode_big5(line), MARGIN, log_top + index as i32 * line_height, game_font, &text_color);
+        }
+
+        image
+    }
+}
@@ -0,0 +1,67 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+// why a game file could not be read, corrupt data ends up here instead of panicking
+#[derive(Debug)]
+pub enum DecodeError {
+    Io(io::Error),
+    // the data ends before what a header or length byte promised
+    Truncated { needed: usize, available: usize },
+    InvalidSize { width: usize, height: usize },
+    // a run of pixels reaches past the end of its line
+    RunOverflow { line: usize },
+    // an archive table entry points outside of the file or before the previous entry
+    InvalidOffset { index: usize, offset: usize },
+    // a header that does not say the file is what it was opened as
+    InvalidHeader(String)
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::Io(error) => write!(f, "{}", error),
+            DecodeError::Truncated { needed, available } => write!(f, "data is truncated, needs {} bytes but has {}", needed, available),
+            DecodeError::InvalidSize { width, height } => write!(f, "invalid size {}x{}", width, height),
+            DecodeError::RunOverflow { line } => write!(f, "run on line {} goes past the end of the line", line),
+            DecodeError::InvalidOffset { index, offset } => write!(f, "entry {} has an invalid offset {}", index, offset),
+            DecodeError::InvalidHeader(message) => write!(f, "invalid header: {}", message)
+        }
+    }
+}
+
+impl Error for DecodeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DecodeError::Io(error) => Some(error),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for DecodeError {
+    fn from(error: io::Error) -> Self {
+        DecodeError::Io(error)
+    }
+}
+
+// little endian fields of the original file formats
+pub fn read_u16(data: &[u8], offset: usize) -> Result<u16, DecodeError> {
+    let end = offset.saturating_add(2);
+    match data.get(offset..end) {
+        Some(bytes) => Ok(u16::from_le_bytes([bytes[0], bytes[1]])),
+        None => Err(DecodeError::Truncated { needed: end, available: data.len() })
+    }
+}
+
+pub fn read_i16(data: &[u8], offset: usize) -> Result<i16, DecodeError> {
+    read_u16(data, offset).map(|value| value as i16)
+}
+
+pub fn read_u32(data: &[u8], offset: usize) -> Result<u32, DecodeError> {
+    let end = offset.saturating_add(4);
+    match data.get(offset..end) {
+        Some(bytes) => Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+        None => Err(DecodeError::Truncated { needed: end, available: data.len() })
+    }
+}
@@ -0,0 +1,516 @@
+use std::cell::RefCell;
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+use crate::engine::assets::SpriteArchive;
+use crate::engine::atomic_file::write_atomic;
+use crate::engine::error::EngineResult;
+use crate::engine::graphics::{Color, Graphics, Palette, TextAlign, TextDirection};
+use crate::engine::panel::PanelStyle;
+use crate::engine::text::wrap_text;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub enum TextSpeed {
+    Slow,
+    #[default]
+    Normal,
+    Fast,
+    Instant
+}
+
+impl TextSpeed {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "slow" => Some(TextSpeed::Slow),
+            "normal" => Some(TextSpeed::Normal),
+            "fast" => Some(TextSpeed::Fast),
+            "instant" => Some(TextSpeed::Instant),
+            _ => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TextSpeed::Slow => "slow",
+            TextSpeed::Normal => "normal",
+            TextSpeed::Fast => "fast",
+            TextSpeed::Instant => "instant"
+        }
+    }
+
+    // None reveals everything at once
+    pub fn characters_per_second(&self) -> Option<f64> {
+        match self {
+            TextSpeed::Slow => Some(15.0),
+            TextSpeed::Normal => Some(30.0),
+            TextSpeed::Fast => Some(60.0),
+            TextSpeed::Instant => None
+        }
+    }
+}
+
+// reveals a message one character at a time
+pub struct Typewriter {
+    text: Vec<usize>,
+    speed: TextSpeed,
+    elapsed: f64,
+    completed_at: Option<f64>
+}
+
+impl Typewriter {
+    // a message can override the global speed, like a shout that appears at once
+    pub fn new(text: Vec<usize>, global_speed: TextSpeed, message_speed: Option<TextSpeed>) -> Self {
+        Typewriter { text, speed: message_speed.unwrap_or(global_speed), elapsed: 0.0, completed_at: None }
+    }
+
+    pub fn update(&mut self, delta: f64) {
+        if self.completed_at.is_none() && self.is_complete() {
+            self.completed_at = Some(self.elapsed);
+        }
+
+        self.elapsed += delta;
+    }
+
+    pub fn visible_count(&self) -> usize {
+        match self.speed.characters_per_second() {
+            Some(characters_per_second) => ((self.elapsed * characters_per_second) as usize).min(self.text.len()),
+            None => self.text.len()
+        }
+    }
+
+    pub fn visible_text(&self) -> &[usize] {
+        &self.text[..self.visible_count()]
+    }
+
+    pub fn text(&self) -> &[usize] {
+        &self.text
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.visible_count() >= self.text.len()
+    }
+
+    // show the whole message, used when the player presses confirm while it types
+    pub fn complete(&mut self) {
+        self.speed = TextSpeed::Instant;
+        if self.completed_at.is_none() {
+            self.completed_at = Some(self.elapsed);
+        }
+    }
+
+    // true when the message should close without waiting for the player
+    pub fn should_advance(&self, settings: &AdvanceSettings, seen: bool) -> bool {
+        if settings.skip_seen && seen {
+            return true;
+        }
+
+        match self.completed_at {
+            Some(completed_at) if settings.auto_advance => self.elapsed - completed_at >= settings.auto_delay,
+            _ => false
+        }
+    }
+}
+
+const DIALOG_MARGIN: i32 = 6;
+const DIALOG_HEIGHT: i32 = 56;
+
+// the face sprites the portrait frames index and the palette they are drawn with, shared with the scripts
+struct Faces {
+    sprites: Rc<RefCell<SpriteArchive>>,
+    palette: Rc<RefCell<Palette>>
+}
+
+// the message window, scripts say what goes in it and the engine types it out and draws it over the game
+pub struct DialogBox {
+    current: Option<Typewriter>,
+    // messages said while another one was still open
+    queue: VecDeque<Typewriter>,
+    pub advance: AdvanceSettings,
+    // every message closed so far, in this and earlier sessions
+    pub seen: SeenTextStore,
+    pub portraits: DialogPortraits,
+    faces: Option<Faces>,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    pub style: PanelStyle,
+    pub color: Color
+}
+
+impl DialogBox {
+    // across the bottom of the screen
+    pub fn new(screen_width: i32, screen_height: i32) -> Self {
+        DialogBox {
+            current: None,
+            queue: VecDeque::new(),
+            advance: AdvanceSettings::default(),
+            seen: SeenTextStore::new(),
+            portraits: DialogPortraits::new(),
+            faces: None,
+            x: DIALOG_MARGIN,
+            y: screen_height - DIALOG_HEIGHT - DIALOG_MARGIN,
+            width: screen_width - DIALOG_MARGIN * 2,
+            height: DIALOG_HEIGHT,
+            style: PanelStyle { border: Some(Color::new(255, 255, 255, 255)), ..PanelStyle::default() },
+            color: Color::new(255, 255, 255, 255)
+        }
+    }
+
+    // global_speed is the setting of the player, message_speed what the script asked for this one message
+    pub fn say(&mut self, text: Vec<usize>, global_speed: TextSpeed, message_speed: Option<TextSpeed>) {
+        let typewriter = Typewriter::new(text, global_speed, message_speed);
+
+        match self.current {
+            Some(_) => self.queue.push_back(typewriter),
+            None => self.current = Some(typewriter)
+        }
+    }
+
+    pub fn set_faces(&mut self, faces: Rc<RefCell<SpriteArchive>>, palette: Rc<RefCell<Palette>>) {
+        self.faces = Some(Faces { sprites: faces, palette });
+    }
+
+    // auto advance and skipping read text close messages here, without the player
+    pub fn update(&mut self, delta: f64) {
+        self.portraits.update(delta);
+
+        if let Some(current) = self.current.as_mut() {
+            current.update(delta);
+
+            if current.should_advance(&self.advance, self.seen.is_seen(current.text())) {
+                self.next();
+            }
+        }
+    }
+
+    // the message on screen counts as read once it closes
+    fn next(&mut self) {
+        if let Some(current) = self.current.take() {
+            self.seen.mark_seen(current.text());
+        }
+        self.current = self.queue.pop_front();
+    }
+
+    // the first press shows the rest of a message that is still typing, the next one closes it.
+    // true when a message closed
+    pub fn confirm(&mut self) -> bool {
+        match self.current.as_mut() {
+            Some(current) if !current.is_complete() => {
+                current.complete();
+                false
+            },
+            Some(_) => {
+                self.next();
+                true
+            },
+            None => false
+        }
+    }
+
+    // drops the message on screen and every one waiting, the faces slide out
+    pub fn close(&mut self) {
+        self.current = None;
+        self.queue.clear();
+        self.portraits.hide(PortraitSlot::Left);
+        self.portraits.hide(PortraitSlot::Right);
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.current.is_some()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.current.as_ref().map(|current| current.is_complete()).unwrap_or(true)
+    }
+
+    pub fn pending(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn text(&self) -> Option<&[usize]> {
+        self.current.as_ref().map(|current| current.text())
+    }
+
+    // the faces stand on top of the window and stay while they slide out after it closed.
+    // the whole message is wrapped first, so a word never jumps to the next line while it types
+    pub fn draw(&self, graphics: &mut Graphics) {
+        if let Some(faces) = self.faces.as_ref() {
+            self.portraits.draw(graphics, &faces.sprites.borrow(), &faces.palette.borrow(), self.x, self.y, self.width);
+        }
+
+        let current = match self.current.as_ref() {
+            Some(current) => current,
+            None => return
+        };
+
+        let text_width = self.width - DIALOG_MARGIN * 2;
+        let (lines, line_height) = match graphics.get_game_font() {
+            Some(game_font) => (wrap_text(current.text(), text_width, game_font), game_font.get_height()),
+            None => return
+        };
+
+        graphics.draw_panel(self.x, self.y, self.width, self.height, &self.style);
+
+        let mut remaining = current.visible_count();
+        for (row, line) in lines.iter().enumerate() {
+            if remaining == 0 {
+                break;
+            }
+
+            let shown = &line[..remaining.min(line.len())];
+            remaining -= shown.len();
+            graphics.draw_text(shown, self.x + DIALOG_MARGIN, self.y + DIALOG_MARGIN + row as i32 * line_height, text_width, TextAlign::Left, TextDirection::Horizontal, &self.color);
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct AdvanceSettings {
+    pub auto_advance: bool,
+    // seconds a finished message stays on screen in auto mode
+    pub auto_delay: f64,
+    pub skip_seen: bool
+}
+
+impl Default for AdvanceSettings {
+    fn default() -> Self {
+        AdvanceSettings { auto_advance: false, auto_delay: 1.5, skip_seen: false }
+    }
+}
+
+// hashes of every message the player has already read, kept across play sessions
+pub struct SeenTextStore {
+    hashes: HashSet<u64>
+}
+
+impl SeenTextStore {
+    pub fn new() -> Self {
+        SeenTextStore { hashes: HashSet::new() }
+    }
+
+    // fnv-1a over the character codes
+    pub fn hash_text(text: &[usize]) -> u64 {
+        let mut hash: u64 = 0xcbf29ce484222325;
+
+        for &character in text {
+            for byte in (character as u32).to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x100000001b3);
+            }
+        }
+
+        hash
+    }
+
+    pub fn mark_seen(&mut self, text: &[usize]) {
+        self.hashes.insert(Self::hash_text(text));
+    }
+
+    pub fn is_seen(&self, text: &[usize]) -> bool {
+        self.hashes.contains(&Self::hash_text(text))
+    }
+
+    pub fn len(&self) -> usize {
+        self.hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hashes.is_empty()
+    }
+
+    // a missing file is an empty store
+    pub fn load(filename: &str) -> EngineResult<Self> {
+        if !Path::new(filename).exists() {
+            return Ok(Self::new());
+        }
+
+        let data = fs::read(filename)?;
+        let hashes = data.chunks_exact(8).map(|bytes| {
+            let mut hash = [0u8; 8];
+            hash.copy_from_slice(bytes);
+            u64::from_le_bytes(hash)
+        }).collect();
+
+        Ok(SeenTextStore { hashes })
+    }
+
+    pub fn save(&self, filename: &str) -> EngineResult<()> {
+        if let Some(directory) = Path::new(filename).parent().filter(|directory| !directory.as_os_str().is_empty()) {
+            fs::create_dir_all(directory)?;
+        }
+
+        let mut data = Vec::with_capacity(self.hashes.len() * 8);
+        for hash in &self.hashes {
+            data.extend_from_slice(&hash.to_le_bytes());
+        }
+
+        write_atomic(filename, data)?;
+        Ok(())
+    }
+}
+
+impl Default for SeenTextStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PortraitSlot {
+    Left,
+    Right
+}
+
+impl PortraitSlot {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "left" => Some(PortraitSlot::Left),
+            "right" => Some(PortraitSlot::Right),
+            _ => None
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum PortraitState {
+    Entering,
+    Shown,
+    Exiting,
+    Hidden
+}
+
+const SLIDE_DURATION: f64 = 0.25;
+const BLINK_INTERVAL: f64 = 4.0;
+const BLINK_FRAME_DURATION: f64 = 0.06;
+
+// a face next to the dialog box, frames are sprite indices: eyes open, half closed, closed
+pub struct Portrait {
+    pub slot: PortraitSlot,
+    pub frames: Vec<usize>,
+    state: PortraitState,
+    progress: f64,
+    blink_timer: f64
+}
+
+impl Portrait {
+    pub fn new(slot: PortraitSlot, frames: Vec<usize>) -> Self {
+        // the right slot blinks out of step with the left one
+        let blink_timer = match slot {
+            PortraitSlot::Left => 0.0,
+            PortraitSlot::Right => BLINK_INTERVAL / 2.0
+        };
+
+        Portrait { slot, frames, state: PortraitState::Entering, progress: 0.0, blink_timer }
+    }
+
+    pub fn exit(&mut self) {
+        if self.state != PortraitState::Hidden {
+            self.state = PortraitState::Exiting;
+        }
+    }
+
+    pub fn is_hidden(&self) -> bool {
+        self.state == PortraitState::Hidden
+    }
+
+    pub fn update(&mut self, delta: f64) {
+        match self.state {
+            PortraitState::Entering => {
+                self.progress = (self.progress + delta / SLIDE_DURATION).min(1.0);
+                if self.progress >= 1.0 {
+                    self.state = PortraitState::Shown;
+                }
+            },
+            PortraitState::Exiting => {
+                self.progress = (self.progress - delta / SLIDE_DURATION).max(0.0);
+                if self.progress <= 0.0 {
+                    self.state = PortraitState::Hidden;
+                }
+            },
+            _ => ()
+        }
+
+        self.blink_timer = (self.blink_timer + delta) % BLINK_INTERVAL;
+    }
+
+    // open, half, closed, half, then open again until the next blink
+    pub fn current_frame(&self) -> Option<usize> {
+        let blink_frames = [1, 2, 1];
+        let blink_index = (self.blink_timer / BLINK_FRAME_DURATION) as usize;
+
+        match blink_frames.get(blink_index) {
+            Some(&frame) if frame < self.frames.len() => Some(self.frames[frame]),
+            _ => self.frames.first().copied()
+        }
+    }
+
+    // the face sits on top of the dialog box, sliding in from its side of the screen
+    pub fn position(&self, dialog_x: i32, dialog_y: i32, dialog_width: i32, portrait_width: i32, portrait_height: i32) -> (i32, i32) {
+        let eased = 1.0 - (1.0 - self.progress) * (1.0 - self.progress);
+        let slide = ((1.0 - eased) * (portrait_width + dialog_x) as f64) as i32;
+        let y = dialog_y - portrait_height;
+
+        match self.slot {
+            PortraitSlot::Left => (dialog_x - slide, y),
+            PortraitSlot::Right => (dialog_x + dialog_width - portrait_width + slide, y)
+        }
+    }
+}
+
+pub struct DialogPortraits {
+    portraits: Vec<Portrait>
+}
+
+impl DialogPortraits {
+    pub fn new() -> Self {
+        DialogPortraits { portraits: Vec::new() }
+    }
+
+    // a new face in an occupied slot pushes the old one out
+    pub fn show(&mut self, slot: PortraitSlot, frames: Vec<usize>) {
+        for portrait in self.portraits.iter_mut().filter(|portrait| portrait.slot == slot) {
+            portrait.exit();
+        }
+
+        self.portraits.push(Portrait::new(slot, frames));
+    }
+
+    pub fn hide(&mut self, slot: PortraitSlot) {
+        for portrait in self.portraits.iter_mut().filter(|portrait| portrait.slot == slot) {
+            portrait.exit();
+        }
+    }
+
+    pub fn update(&mut self, delta: f64) {
+        for portrait in self.portraits.iter_mut() {
+            portrait.update(delta);
+        }
+
+        self.portraits.retain(|portrait| !portrait.is_hidden());
+    }
+
+    pub fn portraits(&self) -> &[Portrait] {
+        &self.portraits
+    }
+
+    // faces are the face sprites of the game data, indexed by the portrait frames
+    pub fn draw(&self, graphics: &mut Graphics, faces: &SpriteArchive, palette: &Palette, dialog_x: i32, dialog_y: i32, dialog_width: i32) {
+        for portrait in &self.portraits {
+            let (index, face) = match portrait.current_frame().and_then(|frame| faces.frame(frame).map(|face| (frame, face))) {
+                Some(found) => found,
+                None => continue
+            };
+
+            let (x, y) = portrait.position(dialog_x, dialog_y, dialog_width, face.size.x as i32, face.size.y as i32);
+            graphics.blit(faces.frame_key(index), face, x - face.offset.x as i32, y - face.offset.y as i32, palette);
+        }
+    }
+}
+
+impl Default for DialogPortraits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use crate::engine::decode::{read_u32, DecodeError};
+use crate::engine::error::EngineResult;
+use crate::engine::vfs::Mount;
+
+const SECTOR_BYTES: u64 = 2048;
+// raw bin images keep the sync, header and error correction of every sector
+const RAW_SECTOR_BYTES: u64 = 2352;
+// volume descriptors start after the system area
+const FIRST_DESCRIPTOR_SECTOR: u64 = 16;
+const PRIMARY_DESCRIPTOR: u8 = 1;
+const DESCRIPTOR_TERMINATOR: u8 = 255;
+const ROOT_RECORD_OFFSET: usize = 156;
+const DIRECTORY_FLAG: u8 = 2;
+// a broken image could nest directories forever, or claim one is gigabytes long
+const MAX_DEPTH: usize = 16;
+const MAX_DIRECTORY_BYTES: u64 = 1 << 20;
+// the fixed part of a directory record before its name
+const RECORD_HEADER_BYTES: usize = 33;
+
+// where the 2048 bytes of user data are in each sector of the image
+#[derive(Copy, Clone, Debug)]
+struct SectorLayout {
+    sector_bytes: u64,
+    data_offset: u64,
+    // the data track starts this far into the file
+    start: u64
+}
+
+struct FileEntry {
+    sector: u64,
+    size: u64
+}
+
+// the original cd read from an iso9660 image, or the data track of a cue and bin, without extracting it first.
+// only the plain iso9660 names are read, which is all a dos game of the time has
+pub struct DiscImageMount {
+    path: PathBuf,
+    file: Mutex<File>,
+    // of the image file, no extent is read past it
+    length: u64,
+    layout: SectorLayout,
+    // lowercase path with / between folders
+    files: HashMap<String, FileEntry>,
+    names: Vec<String>
+}
+
+// MODE1/2352 has 16 bytes of sync and header before the data, MODE2/2352 another 8 of subheader
+fn track_layout(mode: &str) -> Option<SectorLayout> {
+    match mode.to_ascii_uppercase().as_str() {
+        "MODE1/2048" => Some(SectorLayout { sector_bytes: SECTOR_BYTES, data_offset: 0, start: 0 }),
+        "MODE1/2352" => Some(SectorLayout { sector_bytes: RAW_SECTOR_BYTES, data_offset: 16, start: 0 }),
+        "MODE2/2352" => Some(SectorLayout { sector_bytes: RAW_SECTOR_BYTES, data_offset: 24, start: 0 }),
+        _ => None
+    }
+}
+
+// mm:ss:ff, 75 frames a second
+fn parse_msf(value: &str) -> Option<u64> {
+    let parts: Vec<u64> = value.split(':').map(|part| part.parse().ok()).collect::<Option<Vec<u64>>>()?;
+    match parts.as_slice() {
+        [minutes, seconds, frames] => Some((minutes * 60 + seconds) * 75 + frames),
+        _ => None
+    }
+}
+
+// the bin and sector layout of the first data track, audio tracks are left to the cd player
+fn parse_cue(cue_path: &Path) -> EngineResult<(PathBuf, SectorLayout)> {
+    let source = fs::read_to_string(cue_path)?;
+    let directory = cue_path.parent().unwrap_or(Path::new("."));
+    let mut bin: Option<PathBuf> = None;
+    let mut track: Option<(PathBuf, SectorLayout)> = None;
+    // the INDEX lines that follow belong to the data track
+    let mut in_data_track = false;
+
+    for line in source.lines() {
+        let line = line.trim();
+        let keyword = line.split_whitespace().next().unwrap_or("").to_ascii_uppercase();
+
+        match keyword.as_str() {
+            // FILE "GAME.BIN" BINARY, the name may be quoted and hold spaces
+            "FILE" => {
+                let rest = line[4..].trim();
+                let name = match rest.strip_prefix('"') {
+                    Some(quoted) => quoted.split('"').next().unwrap_or(""),
+                    None => rest.split_whitespace().next().unwrap_or("")
+                };
+                bin = Some(directory.join(name));
+            },
+            // TRACK 01 MODE1/2352
+            "TRACK" => {
+                in_data_track = false;
+                let mode = line.split_whitespace().nth(2).unwrap_or("");
+                if let (None, Some(bin), Some(layout)) = (&track, bin.clone(), track_layout(mode)) {
+                    track = Some((bin, layout));
+                    in_data_track = true;
+                }
+            },
+            // INDEX 01 00:02:00 is where the data starts in the bin
+            "INDEX" if in_data_track => {
+                let mut parts = line.split_whitespace().skip(1);
+                if let (Some((_, layout)), Some("01"), Some(frames)) = (track.as_mut(), parts.next(), parts.next().and_then(parse_msf)) {
+                    layout.start = frames * layout.sector_bytes;
+                }
+            },
+            _ => ()
+        }
+    }
+
+    track.ok_or_else(|| DecodeError::InvalidHeader(format!("{} has no data track", cue_path.display())).into())
+}
+
+impl DiscImageMount {
+    // an .iso is read as plain 2048 byte sectors, a .cue through the bin it names
+    pub fn open(path: &Path) -> EngineResult<Self> {
+        let is_cue = path.extension().map(|extension| extension.eq_ignore_ascii_case("cue")).unwrap_or(false);
+        let (image_path, layout) = match is_cue {
+            true => parse_cue(path)?,
+            false => (path.to_path_buf(), SectorLayout { sector_bytes: SECTOR_BYTES, data_offset: 0, start: 0 })
+        };
+
+        let file = File::open(&image_path)?;
+        let mut mount = DiscImageMount {
+            path: path.to_path_buf(),
+            length: file.metadata()?.len(),
+            file: Mutex::new(file),
+            layout,
+            files: HashMap::new(),
+            names: Vec::new()
+        };
+
+        let root = mount.find_root()?;
+        mount.read_directory(&root, "", 0)?;
+        Ok(mount)
+    }
+
+    // where the bytes of an extent end in the image file
+    fn extent_end(&self, sector: u64, size: u64) -> u64 {
+        let layout = self.layout;
+        match layout.sector_bytes == SECTOR_BYTES {
+            true => layout.start + sector * SECTOR_BYTES + size,
+            false => layout.start + (sector + size.div_ceil(SECTOR_BYTES)) * layout.sector_bytes
+        }
+    }
+
+    fn read_sectors(&self, sector: u64, size: u64) -> io::Result<Vec<u8>> {
+        // a broken record can claim gigabytes, checked before any of it is allocated
+        if self.extent_end(sector, size) > self.length {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} bytes from sector {} are past the end of {}", size, sector, self.path.display())));
+        }
+
+        let mut file = self.file.lock().unwrap();
+        let layout = self.layout;
+
+        if layout.sector_bytes == SECTOR_BYTES {
+            let mut bytes = vec![0; size as usize];
+            file.seek(SeekFrom::Start(layout.start + sector * SECTOR_BYTES))?;
+            file.read_exact(&mut bytes)?;
+            return Ok(bytes);
+        }
+
+        // raw sectors carry their data in the middle, one sector at a time
+        let mut bytes = Vec::with_capacity(size as usize);
+        let mut current = sector;
+        while (bytes.len() as u64) < size {
+            let chunk = (size - bytes.len() as u64).min(SECTOR_BYTES) as usize;
+            let mut data = vec![0; chunk];
+            file.seek(SeekFrom::Start(layout.start + current * layout.sector_bytes + layout.data_offset))?;
+            file.read_exact(&mut data)?;
+            bytes.extend_from_slice(&data);
+            current += 1;
+        }
+
+        Ok(bytes)
+    }
+
+    // the root directory record of the primary volume descriptor
+    fn find_root(&self) -> EngineResult<Vec<u8>> {
+        let mut sector = FIRST_DESCRIPTOR_SECTOR;
+
+        loop {
+            let descriptor = self.read_sectors(sector, SECTOR_BYTES)?;
+            if &descriptor[1..6] != b"CD001" {
+                return Err(DecodeError::InvalidHeader(format!("{} is not an iso9660 image", self.path.display())).into());
+            }
+
+            match descriptor[0] {
+                PRIMARY_DESCRIPTOR => return Ok(descriptor[ROOT_RECORD_OFFSET..ROOT_RECORD_OFFSET + 34].to_vec()),
+                DESCRIPTOR_TERMINATOR => return Err(DecodeError::InvalidHeader(format!("{} has no primary volume descriptor", self.path.display())).into()),
+                _ => sector += 1
+            }
+        }
+    }
+
+    fn read_directory(&mut self, record: &[u8], prefix: &str, depth: usize) -> EngineResult<()> {
+        if depth > MAX_DEPTH {
+            return Err(DecodeError::InvalidHeader(format!("directories in {} nest too deep", self.path.display())).into());
+        }
+
+        let (sector, size) = (read_u32(record, 2)? as u64, read_u32(record, 10)? as u64);
+        if size > MAX_DIRECTORY_BYTES {
+            return Err(DecodeError::InvalidHeader(format!("a directory in {} is {} bytes long", self.path.display(), size)).into());
+        }
+        let data = self.read_sectors(sector, size)?;
+        let mut offset = 0;
+
+        while offset < data.len() {
+            let length = data[offset] as usize;
+            // records never cross a sector, the rest of one is padded with zeros
+            if length == 0 {
+                offset = (offset / SECTOR_BYTES as usize + 1) * SECTOR_BYTES as usize;
+                continue;
+            }
+
+            let entry = data.get(offset..offset + length.max(RECORD_HEADER_BYTES)).ok_or(DecodeError::Truncated { needed: offset + length, available: data.len() })?;
+            offset += length;
+
+            let name_length = entry[32] as usize;
+            let name = match entry.get(RECORD_HEADER_BYTES..RECORD_HEADER_BYTES + name_length) {
+                // 0 and 1 are the directory itself and its parent
+                Some([0]) | Some([1]) | None => continue,
+                Some(name) => String::from_utf8_lossy(name).to_string()
+            };
+
+            // FILE.TXT;1 is FILE.TXT, a file without extension keeps a trailing dot
+            let name = name.split(';').next().unwrap_or("").trim_end_matches('.').to_string();
+            let path = if prefix.is_empty() { name } else { format!("{}/{}", prefix, name) };
+
+            if entry[25] & DIRECTORY_FLAG != 0 {
+                self.read_directory(entry, &path, depth + 1)?;
+            } else {
+                self.files.insert(path.to_lowercase(), FileEntry { sector: read_u32(entry, 2)? as u64, size: read_u32(entry, 10)? as u64 });
+                self.names.push(path);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn entry(&self, name: &str) -> Option<&FileEntry> {
+        self.files.get(&name.replace('\\', "/").trim_start_matches('/').to_lowercase())
+    }
+}
+
+impl Mount for DiscImageMount {
+    fn describe(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn read(&self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        match self.entry(name) {
+            Some(entry) => Ok(Some(self.read_sectors(entry.sector, entry.size)?)),
+            None => Ok(None)
+        }
+    }
+
+    fn exists(&self, name: &str) -> bool {
+        self.entry(name).is_some()
+    }
+
+    fn size(&self, name: &str) -> io::Result<Option<u64>> {
+        Ok(self.entry(name).map(|entry| entry.size))
+    }
+
+    fn files(&self) -> io::Result<Vec<String>> {
+        Ok(self.names.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    fn record(name: &[u8], sector: u32, size: u32, directory: bool) -> Vec<u8> {
+        let length = RECORD_HEADER_BYTES + name.len() + (RECORD_HEADER_BYTES + name.len()) % 2;
+        let mut record = vec![0; length];
+        record[0] = length as u8;
+        record[2..6].copy_from_slice(&sector.to_le_bytes());
+        record[6..10].copy_from_slice(&sector.to_be_bytes());
+        record[10..14].copy_from_slice(&size.to_le_bytes());
+        record[14..18].copy_from_slice(&size.to_be_bytes());
+        record[25] = if directory { DIRECTORY_FLAG } else { 0 };
+        record[32] = name.len() as u8;
+        record[RECORD_HEADER_BYTES..RECORD_HEADER_BYTES + name.len()].copy_from_slice(name);
+        record
+    }
+
+    fn directory(sector: u32, records: &[Vec<u8>]) -> Vec<u8> {
+        let mut data = [record(&[0], sector, SECTOR_BYTES as u32, true), record(&[1], sector, SECTOR_BYTES as u32, true)].concat();
+        for record in records {
+            data.extend_from_slice(record);
+        }
+        data.resize(SECTOR_BYTES as usize, 0);
+        data
+    }
+
+    // the descriptors, the root with INTRO.TXT and a DATA folder holding MAP.BIN, 22 sectors of 2048 bytes
+    fn iso(intro_size: u32) -> Vec<u8> {
+        let mut sectors = vec![vec![0; SECTOR_BYTES as usize]; 22];
+        sectors[16][0] = PRIMARY_DESCRIPTOR;
+        sectors[16][1..6].copy_from_slice(b"CD001");
+        sectors[16][ROOT_RECORD_OFFSET..ROOT_RECORD_OFFSET + 34].copy_from_slice(&record(&[0], 18, SECTOR_BYTES as u32, true));
+        sectors[17][0] = DESCRIPTOR_TERMINATOR;
+        sectors[17][1..6].copy_from_slice(b"CD001");
+        sectors[18] = directory(18, &[record(b"INTRO.TXT;1", 19, intro_size, false), record(b"DATA", 20, SECTOR_BYTES as u32, true)]);
+        sectors[19][..5].copy_from_slice(b"hello");
+        sectors[20] = directory(20, &[record(b"MAP.BIN;1", 21, 3, false)]);
+        sectors[21][..3].copy_from_slice(&[1, 2, 3]);
+        sectors.concat()
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("legend-clover-disc-{}-{}", process::id(), name))
+    }
+
+    #[test]
+    fn reads_an_iso() {
+        let path = temp_path("game.iso");
+        fs::write(&path, iso(5)).unwrap();
+        let mount = DiscImageMount::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(mount.files().unwrap(), vec!["INTRO.TXT".to_string(), "DATA/MAP.BIN".to_string()]);
+        assert_eq!(mount.read("intro.txt").unwrap(), Some(b"hello".to_vec()));
+        assert_eq!(mount.read("Data\\Map.bin").unwrap(), Some(vec![1, 2, 3]));
+        assert_eq!(mount.size("data/map.bin").unwrap(), Some(3));
+        assert_eq!(mount.read("missing.txt").unwrap(), None);
+    }
+
+    #[test]
+    fn reads_the_data_track_of_a_cue() {
+        // raw sectors of 16 bytes of sync and header, the data and 288 bytes of error correction
+        let bin: Vec<u8> = iso(5).chunks(SECTOR_BYTES as usize)
+            .flat_map(|sector| [vec![0; 16], sector.to_vec(), vec![0; 288]].concat())
+            .collect();
+        let (cue_path, bin_path) = (temp_path("game.cue"), temp_path("game.bin"));
+        fs::write(&bin_path, bin).unwrap();
+        let bin_name = bin_path.file_name().unwrap().to_string_lossy().to_string();
+        fs::write(&cue_path, format!("FILE \"{}\" BINARY\n  TRACK 01 MODE1/2352\n    INDEX 01 00:00:00\n  TRACK 02 AUDIO\n", bin_name)).unwrap();
+        let mount = DiscImageMount::open(&cue_path);
+        fs::remove_file(&cue_path).unwrap();
+        fs::remove_file(&bin_path).unwrap();
+
+        let mount = mount.unwrap();
+        assert_eq!(mount.read("INTRO.TXT").unwrap(), Some(b"hello".to_vec()));
+        assert_eq!(mount.read("DATA/MAP.BIN").unwrap(), Some(vec![1, 2, 3]));
+    }
+
+    #[test]
+    fn extents_past_the_image_are_refused() {
+        let path = temp_path("broken.iso");
+        fs::write(&path, iso(u32::MAX)).unwrap();
+        let mount = DiscImageMount::open(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(mount.read("INTRO.TXT").unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert_eq!(mount.read("DATA/MAP.BIN").unwrap(), Some(vec![1, 2, 3]));
+    }
+}
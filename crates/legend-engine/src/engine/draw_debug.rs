@@ -0,0 +1,101 @@
+use std::collections::HashSet;
+use crate::engine::graphics::{Color, Image};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DrawRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32
+}
+
+impl DrawRect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        DrawRect { x, y, width, height }
+    }
+
+    pub fn intersects(&self, width: i32, height: i32) -> bool {
+        self.width > 0 && self.height > 0 && self.x < width && self.y < height && self.x + self.width > 0 && self.y + self.height > 0
+    }
+}
+
+// records every draw of a frame, then outlines them and tints overdraw hotspots
+pub struct DrawDebugger {
+    width: i32,
+    height: i32,
+    rects: Vec<DrawRect>,
+    overdraw: Vec<u16>,
+    // draws outside of the frame buffer already logged, a script repeats the same ones every frame
+    logged_misses: HashSet<(String, DrawRect)>
+}
+
+impl DrawDebugger {
+    pub fn new(width: u32, height: u32) -> Self {
+        DrawDebugger {
+            width: width as i32,
+            height: height as i32,
+            rects: Vec::new(),
+            overdraw: vec![0; (width * height) as usize],
+            logged_misses: HashSet::new()
+        }
+    }
+
+    pub fn record(&mut self, name: &str, rect: DrawRect) {
+        if !rect.intersects(self.width, self.height) {
+            if !self.logged_misses.insert((name.to_string(), rect)) {
+                return;
+            }
+            eprintln!("{} drawn outside of the frame buffer at ({}, {}) size {}x{}", name, rect.x, rect.y, rect.width, rect.height);
+            return;
+        }
+
+        let start_x = rect.x.max(0);
+        let end_x = (rect.x + rect.width).min(self.width);
+        let start_y = rect.y.max(0);
+        let end_y = (rect.y + rect.height).min(self.height);
+
+        for y in start_y..end_y {
+            for x in start_x..end_x {
+                let count = &mut self.overdraw[(y * self.width + x) as usize];
+                *count = count.saturating_add(1);
+            }
+        }
+
+        self.rects.push(rect);
+    }
+
+    pub fn draw_count(&self) -> usize {
+        self.rects.len()
+    }
+
+    pub fn render_overlay(&self, target: &mut Image) {
+        let heat_color = Color::new(255, 0, 0, 255);
+
+        for (index, &count) in self.overdraw.iter().enumerate() {
+            if count < 2 {
+                continue;
+            }
+
+            let strength = ((count - 1).min(4) as f64) / 4.0 * 0.6;
+            let x = index as i32 % self.width;
+            let y = index as i32 / self.width;
+
+            if let Some(color) = target.get_pixel(x, y) {
+                target.set_pixel(x, y, &color.alpha_blend(&heat_color, strength));
+            }
+        }
+
+        let outline_color = Color::new(0, 255, 0, 255);
+
+        for rect in &self.rects {
+            target.draw_rect(rect.x, rect.y, rect.width, rect.height, &outline_color);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.rects.clear();
+        for count in self.overdraw.iter_mut() {
+            *count = 0;
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::fs;
+use serde::Deserialize;
+use crate::engine::error::EngineResult;
+use crate::engine::random::Random;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct EncounterGroup {
+    // battle id handed to the battle scene
+    pub battle: u32,
+    #[serde(default = "default_weight")]
+    pub weight: u32
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+fn default_steps() -> u32 {
+    32
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct EncounterTable {
+    pub map: String,
+    // average steps between two encounters
+    #[serde(default = "default_steps")]
+    pub steps: u32,
+    pub groups: Vec<EncounterGroup>
+}
+
+#[derive(Deserialize)]
+struct EncounterFile {
+    #[serde(default)]
+    table: Vec<EncounterTable>
+}
+
+// [[table]]
+// map = "world"
+// steps = 40
+// groups = [ { battle = 12, weight = 3 }, { battle = 13 } ]
+pub struct EncounterTables {
+    tables: HashMap<String, EncounterTable>
+}
+
+impl EncounterTables {
+    pub fn new() -> Self {
+        EncounterTables { tables: HashMap::new() }
+    }
+
+    pub fn from_toml(source: &str) -> EngineResult<Self> {
+        let file: EncounterFile = toml::from_str(source)?;
+
+        let mut tables = Self::new();
+        for table in file.table {
+            tables.insert(table);
+        }
+
+        Ok(tables)
+    }
+
+    pub fn load(filename: &str) -> EngineResult<Self> {
+        Self::from_toml(&fs::read_to_string(filename)?)
+    }
+
+    pub fn insert(&mut self, table: EncounterTable) {
+        self.tables.insert(table.map.clone(), table);
+    }
+
+    pub fn get(&self, map: &str) -> Option<&EncounterTable> {
+        self.tables.get(map)
+    }
+}
+
+impl Default for EncounterTables {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// every step adds danger, an encounter happens when it reaches a random threshold around the table average
+pub struct EncounterSystem {
+    pub enabled: bool,
+    // the scene a triggered encounter is pushed as, with the battle id as its value
+    pub battle_scene: Option<String>,
+    tables: EncounterTables,
+    danger: u32,
+    threshold: Option<u32>,
+    random: Random
+}
+
+impl EncounterSystem {
+    pub fn new(tables: EncounterTables, seed: u32) -> Self {
+        EncounterSystem { enabled: true, battle_scene: None, tables, danger: 0, threshold: None, random: Random::new(seed) }
+    }
+
+    // story sequences turn encounters off
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    // tables of a newly loaded file replace the old ones, the danger built up so far stays
+    pub fn set_tables(&mut self, tables: EncounterTables) {
+        self.tables = tables;
+    }
+
+    pub fn reset(&mut self) {
+        self.danger = 0;
+        self.threshold = None;
+    }
+
+    // returns the group to fight when this step triggers an encounter
+    pub fn step(&mut self, map: &str) -> Option<EncounterGroup> {
+        if !self.enabled {
+            return None;
+        }
+
+        let table = self.tables.get(map)?;
+        if table.groups.is_empty() {
+            return None;
+        }
+
+        let steps = table.steps.max(1);
+        let threshold = match self.threshold {
+            Some(threshold) => threshold,
+            None => {
+                let threshold = self.random.range(steps / 2 + 1, steps + steps / 2);
+                self.threshold = Some(threshold);
+                threshold
+            }
+        };
+
+        self.danger += 1;
+        if self.danger < threshold {
+            return None;
+        }
+
+        self.danger = 0;
+        self.threshold = None;
+
+        let total: u32 = table.groups.iter().map(|group| group.weight).sum();
+        let mut pick = self.random.range(0, total.max(1) - 1);
+
+        for group in &table.groups {
+            if pick < group.weight {
+                return Some(group.clone());
+            }
+            pick -= group.weight;
+        }
+
+        table.groups.last().cloned()
+    }
+}
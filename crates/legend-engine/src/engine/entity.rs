@@ -0,0 +1,372 @@
+use std::collections::VecDeque;
+use crate::engine::clock::GameClock;
+use crate::engine::graphics::Vector2;
+use crate::engine::movement::{Direction, MovementMode, MovementRules, TILE_SIZE};
+
+// where an npc should be from start_minute on, until the next entry
+#[derive(Clone, Debug)]
+pub struct ScheduleEntry {
+    pub start_minute: u32,
+    pub position: Vector2<i32>,
+    pub facing: Direction,
+    // free form name the scripts react to, like "sleep" or "shop"
+    pub behavior: String
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Schedule {
+    entries: Vec<ScheduleEntry>
+}
+
+impl Schedule {
+    pub fn new(entries: Vec<ScheduleEntry>) -> Self {
+        let mut entries = entries;
+        entries.sort_by_key(|entry| entry.start_minute);
+        Schedule { entries }
+    }
+
+    // the last entry that started, wrapping to the previous day before the first one
+    pub fn active_entry(&self, minute_of_day: u32) -> Option<&ScheduleEntry> {
+        self.entries.iter().rev().find(|entry| entry.start_minute <= minute_of_day).or_else(|| self.entries.last())
+    }
+}
+
+pub struct Entity {
+    pub id: u32,
+    pub position: Vector2<i32>,
+    pub facing: Direction,
+    pub sprite: usize,
+    pub behavior: String,
+    pub mode: MovementMode,
+    pub schedule: Option<Schedule>,
+    // seconds per tile when walking to a schedule position
+    pub step_interval: f64,
+    // party followers walk through the leader and everyone else
+    pub solid: bool,
+    target: Option<Vector2<i32>>,
+    step: Option<Step>,
+    // pixel_position before the last update, render blends from it by the fixed step alpha
+    previous: Vector2<f64>
+}
+
+// the tile being left while position already holds the next one, so collision always sees whole tiles
+#[derive(Copy, Clone, Debug)]
+struct Step {
+    from: Vector2<i32>,
+    // 0 at from, 1 at position
+    progress: f64,
+    // diagonal steps take longer to keep the pixel speed the same
+    length: f64
+}
+
+impl Entity {
+    pub fn new(id: u32, position: Vector2<i32>, facing: Direction, sprite: usize) -> Self {
+        Entity {
+            id,
+            position,
+            facing,
+            sprite,
+            behavior: String::new(),
+            mode: MovementMode::Walking,
+            schedule: None,
+            step_interval: 0.25,
+            solid: true,
+            target: None,
+            step: None,
+            previous: Vector2::new((position.x * TILE_SIZE) as f64, (position.y * TILE_SIZE) as f64)
+        }
+    }
+
+    pub fn is_walking(&self) -> bool {
+        self.target.is_some() || self.step.is_some()
+    }
+
+    pub fn speed(&self) -> f64 {
+        TILE_SIZE as f64 / self.step_interval.max(f64::EPSILON)
+    }
+
+    // pixels per second, kept as the time for one tile
+    pub fn set_speed(&mut self, pixels_per_second: f64) {
+        if pixels_per_second > 0.0 {
+            self.step_interval = TILE_SIZE as f64 / pixels_per_second;
+        }
+    }
+
+    // where to draw, between the tile left and the tile entered while a step is going on
+    pub fn pixel_position(&self) -> Vector2<f64> {
+        let to = Vector2::new(self.position.x as f64, self.position.y as f64);
+
+        let (x, y) = match self.step {
+            Some(step) => {
+                let progress = step.progress.clamp(0.0, 1.0);
+                (step.from.x as f64 + (to.x - step.from.x as f64) * progress, step.from.y as f64 + (to.y - step.from.y as f64) * progress)
+            },
+            None => (to.x, to.y)
+        };
+
+        Vector2::new(x * TILE_SIZE as f64, y * TILE_SIZE as f64)
+    }
+
+    // alpha is how far the frame is between the last update and the next one, so walking stays smooth at any display rate
+    pub fn interpolated_position(&self, alpha: f64) -> Vector2<f64> {
+        let current = self.pixel_position();
+        let alpha = alpha.clamp(0.0, 1.0);
+        Vector2::new(self.previous.x + (current.x - self.previous.x) * alpha, self.previous.y + (current.y - self.previous.y) * alpha)
+    }
+
+    // walks there over the next updates, around whatever blocks the way
+    pub fn walk_to(&mut self, target: Vector2<i32>) {
+        self.target = Some(target);
+    }
+
+    // a follower takes the tile the trail gives it, stepping there when it is next to it so it walks with the leader
+    pub fn follow(&mut self, position: Vector2<i32>, facing: Direction, step_interval: f64) {
+        self.facing = facing;
+        if position.x == self.position.x && position.y == self.position.y {
+            return;
+        }
+
+        if Direction::between(self.position, position).is_none() {
+            self.place(position);
+            return;
+        }
+
+        self.step_interval = step_interval;
+        self.target = None;
+        self.step = Some(Step { from: self.position, progress: 0.0, length: 1.0 });
+        self.position = position;
+    }
+
+    // the tile it is on, and while stepping also the one it is leaving
+    pub fn stands_on(&self, x: i32, y: i32) -> bool {
+        [Some(self.position), self.step.map(|step| step.from)].into_iter().flatten().any(|position| position.x == x && position.y == y)
+    }
+
+    // jumps straight to a tile, like after a warp, without walking there
+    pub fn place(&mut self, position: Vector2<i32>) {
+        self.position = position;
+        self.target = None;
+        self.step = None;
+        self.previous = self.pixel_position();
+    }
+
+    // mounting a horse or boarding a boat, refused when the current tile does not allow the new mode
+    pub fn set_mode(&mut self, rules: &MovementRules, mode: MovementMode, tile: u16) -> bool {
+        if !rules.can_switch(mode, tile) {
+            return false;
+        }
+
+        if let Some(rule) = rules.get(mode) {
+            self.step_interval = rule.step_interval;
+        }
+
+        self.mode = mode;
+        true
+    }
+
+    // 0 standing, 1 and 2 for the two halves of a step
+    pub fn walk_frame(&self) -> usize {
+        self.step.map(|step| 1 + (step.progress * 2.0) as usize % 2).unwrap_or(0)
+    }
+
+    pub fn current_sprite(&self, rules: &MovementRules, frame: usize) -> usize {
+        rules.get(self.mode).map(|rule| rule.sprite(self.facing, frame)).unwrap_or(self.sprite)
+    }
+
+    fn apply_schedule(&mut self, clock: &GameClock) {
+        let entry = match self.schedule.as_ref().and_then(|schedule| schedule.active_entry(clock.minute_of_day())) {
+            Some(entry) => entry.clone(),
+            None => return
+        };
+
+        if self.behavior != entry.behavior {
+            self.behavior = entry.behavior.clone();
+        }
+
+        if self.position.x != entry.position.x || self.position.y != entry.position.y {
+            self.target = Some(entry.position);
+        } else if self.target.is_none() {
+            self.facing = entry.facing;
+        }
+    }
+
+    // picks the next tile towards the target: diagonal when it does not cut a corner, else horizontal first
+    fn next_tile<F>(&self, target: Vector2<i32>, passable: &F) -> Option<Vector2<i32>> where F: Fn(i32, i32) -> bool {
+        let dx = (target.x - self.position.x).signum();
+        let dy = (target.y - self.position.y).signum();
+        let (x, y) = (self.position.x, self.position.y);
+
+        if dx != 0 && dy != 0 && passable(x + dx, y + dy) && passable(x + dx, y) && passable(x, y + dy) {
+            return Some(Vector2::new(x + dx, y + dy));
+        }
+
+        [Vector2::new(x + dx, y), Vector2::new(x, y + dy)].into_iter()
+            .find(|next| (next.x != x || next.y != y) && passable(next.x, next.y))
+    }
+
+    // moves smoothly between tiles, waiting on the current tile when the way is blocked
+    fn walk<F>(&mut self, delta: f64, passable: &F) where F: Fn(i32, i32) -> bool {
+        let mut remaining = delta;
+
+        while remaining > 0.0 {
+            if let Some(step) = self.step.as_mut() {
+                let duration = self.step_interval * step.length;
+                let needed = (1.0 - step.progress) * duration;
+
+                if remaining < needed {
+                    step.progress += remaining / duration.max(f64::EPSILON);
+                    return;
+                }
+
+                // the rest of the frame goes to the next step, so walking never stutters on tile borders
+                remaining -= needed;
+                self.step = None;
+            }
+
+            let target = match self.target {
+                Some(target) if target.x != self.position.x || target.y != self.position.y => target,
+                _ => {
+                    self.target = None;
+                    return;
+                }
+            };
+
+            let next = match self.next_tile(target, passable) {
+                Some(next) => next,
+                None => return
+            };
+
+            let horizontal = Vector2::new(next.x, self.position.y);
+            if let Some(direction) = Direction::between(self.position, next).or_else(|| Direction::between(self.position, horizontal)) {
+                self.facing = direction;
+            }
+
+            let diagonal = next.x != self.position.x && next.y != self.position.y;
+            self.step = Some(Step { from: self.position, progress: 0.0, length: if diagonal { std::f64::consts::SQRT_2 } else { 1.0 } });
+            self.position = next;
+
+            if self.step_interval <= 0.0 {
+                self.step = None;
+            }
+        }
+    }
+
+    pub fn update<F>(&mut self, delta: f64, clock: &GameClock, passable: &F) where F: Fn(i32, i32) -> bool {
+        self.previous = self.pixel_position();
+        self.apply_schedule(clock);
+        self.walk(delta, passable);
+    }
+}
+
+#[derive(Default)]
+pub struct Entities {
+    entities: Vec<Entity>
+}
+
+impl Entities {
+    pub fn new() -> Self {
+        Entities { entities: Vec::new() }
+    }
+
+    pub fn add(&mut self, entity: Entity) {
+        self.entities.push(entity);
+    }
+
+    pub fn remove(&mut self, id: u32) -> Option<Entity> {
+        let index = self.entities.iter().position(|entity| entity.id == id)?;
+        Some(self.entities.remove(index))
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Entity> {
+        self.entities.iter().find(|entity| entity.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: u32) -> Option<&mut Entity> {
+        self.entities.iter_mut().find(|entity| entity.id == id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Entity> {
+        self.entities.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Entity> {
+        self.entities.iter_mut()
+    }
+
+    pub fn entity_at(&self, x: i32, y: i32) -> Option<&Entity> {
+        self.entities.iter().find(|entity| entity.position.x == x && entity.position.y == y)
+    }
+
+    // no entity but id stands on the tile
+    pub fn is_free(&self, id: u32, x: i32, y: i32) -> bool {
+        !self.entities.iter().any(|entity| entity.id != id && entity.solid && entity.stands_on(x, y))
+    }
+
+    // passable only covers the map for the movement mode of each entity, entities also block each other
+    pub fn update<F>(&mut self, delta: f64, clock: &GameClock, passable: F) where F: Fn(MovementMode, i32, i32) -> bool {
+        for index in 0..self.entities.len() {
+            // an entity half way through a step still stands on the tile it is leaving
+            let occupied: Vec<Vector2<i32>> = self.entities.iter().enumerate()
+                .filter(|(other, entity)| *other != index && entity.solid)
+                .flat_map(|(_, entity)| [Some(entity.position), entity.step.map(|step| step.from)])
+                .flatten()
+                .collect();
+            let mode = self.entities[index].mode;
+            let free = |x: i32, y: i32| passable(mode, x, y) && !occupied.iter().any(|position| position.x == x && position.y == y);
+
+            self.entities[index].update(delta, clock, &free);
+        }
+    }
+}
+
+// party members walking behind the leader along the exact tiles the leader walked
+pub struct FollowerTrail {
+    // newest first, each entry is a tile the leader left and the direction it was left in
+    history: VecDeque<(Vector2<i32>, Direction)>,
+    // tiles between two members
+    pub spacing: usize,
+    capacity: usize
+}
+
+impl FollowerTrail {
+    pub fn new(follower_count: usize, spacing: usize) -> Self {
+        let spacing = spacing.max(1);
+        FollowerTrail { history: VecDeque::new(), spacing, capacity: (follower_count + 1) * spacing }
+    }
+
+    // call with the tile the leader is leaving, before it moves
+    pub fn record(&mut self, from: Vector2<i32>, to: Vector2<i32>) {
+        let direction = match Direction::between(from, to) {
+            Some(direction) => direction,
+            None => return
+        };
+
+        self.history.push_front((from, direction));
+        self.history.truncate(self.capacity);
+    }
+
+    // warping the party clears the trail so nobody walks across the map
+    pub fn reset(&mut self) {
+        self.history.clear();
+    }
+
+    // None while the trail is still too short, followers then stay on the leader tile
+    pub fn follower(&self, index: usize) -> Option<(Vector2<i32>, Direction)> {
+        self.history.get((index + 1) * self.spacing - 1).copied()
+    }
+
+    pub fn apply(&self, entities: &mut Entities, leader: u32, followers: &[u32]) {
+        let (leader_position, leader_facing, step_interval) = match entities.get(leader) {
+            Some(entity) => (entity.position, entity.facing, entity.step_interval),
+            None => return
+        };
+
+        for (index, &id) in followers.iter().enumerate() {
+            let (position, facing) = self.follower(index).unwrap_or((leader_position, leader_facing));
+
+            if let Some(entity) = entities.get_mut(id) {
+                entity.follow(position, facing, step_interval);
+            }
+        }
+    }
+}
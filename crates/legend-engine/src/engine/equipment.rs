@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use serde::Deserialize;
+use crate::engine::error::EngineResult;
+use crate::engine::inventory::Inventory;
+use crate::engine::stats::Stats;
+
+#[derive(Deserialize, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum EquipSlot {
+    Weapon,
+    Armor,
+    Accessory
+}
+
+impl EquipSlot {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "weapon" => Some(EquipSlot::Weapon),
+            "armor" => Some(EquipSlot::Armor),
+            "accessory" => Some(EquipSlot::Accessory),
+            _ => None
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct EquipmentItem {
+    pub item: u32,
+    pub name: String,
+    pub slot: EquipSlot,
+    #[serde(default)]
+    pub bonus: Stats,
+    // empty means anyone can use it
+    #[serde(default)]
+    pub classes: Vec<String>,
+    #[serde(default)]
+    pub characters: Vec<u32>
+}
+
+impl EquipmentItem {
+    pub fn can_equip(&self, character: u32, class: &str) -> bool {
+        (self.classes.is_empty() || self.classes.iter().any(|allowed| allowed == class))
+            && (self.characters.is_empty() || self.characters.contains(&character))
+    }
+}
+
+#[derive(Deserialize)]
+struct EquipmentFile {
+    #[serde(default)]
+    equipment: Vec<EquipmentItem>
+}
+
+pub struct EquipmentDatabase {
+    items: HashMap<u32, EquipmentItem>
+}
+
+impl EquipmentDatabase {
+    pub fn new() -> Self {
+        EquipmentDatabase { items: HashMap::new() }
+    }
+
+    // [[equipment]] tables, see EquipmentItem for the fields
+    pub fn from_toml(source: &str) -> EngineResult<Self> {
+        let file: EquipmentFile = toml::from_str(source)?;
+
+        let mut database = Self::new();
+        for item in file.equipment {
+            database.items.insert(item.item, item);
+        }
+
+        Ok(database)
+    }
+
+    pub fn load(filename: &str) -> EngineResult<Self> {
+        Self::from_toml(&fs::read_to_string(filename)?)
+    }
+
+    pub fn get(&self, item: u32) -> Option<&EquipmentItem> {
+        self.items.get(&item)
+    }
+}
+
+impl Default for EquipmentDatabase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+pub enum EquipError {
+    NotEquipment(u32),
+    NotAllowed(u32),
+    NotInInventory(u32)
+}
+
+impl fmt::Display for EquipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EquipError::NotEquipment(item) => write!(f, "item {} can not be equipped", item),
+            EquipError::NotAllowed(item) => write!(f, "item {} can not be used by this character", item),
+            EquipError::NotInInventory(item) => write!(f, "item {} is not in the inventory", item)
+        }
+    }
+}
+
+impl Error for EquipError {}
+
+// what one character is wearing
+#[derive(Clone, Debug, Default)]
+pub struct Equipment {
+    slots: HashMap<EquipSlot, u32>
+}
+
+impl Equipment {
+    pub fn new() -> Self {
+        Equipment { slots: HashMap::new() }
+    }
+
+    pub fn get(&self, slot: EquipSlot) -> Option<u32> {
+        self.slots.get(&slot).copied()
+    }
+
+    // the item leaves the inventory, whatever was in the slot goes back
+    pub fn equip(&mut self, database: &EquipmentDatabase, inventory: &mut Inventory, item: u32, character: u32, class: &str) -> Result<Option<u32>, EquipError> {
+        let equipment_item = database.get(item).ok_or(EquipError::NotEquipment(item))?;
+
+        if !equipment_item.can_equip(character, class) {
+            return Err(EquipError::NotAllowed(item));
+        }
+
+        if !inventory.remove(item, 1) {
+            return Err(EquipError::NotInInventory(item));
+        }
+
+        let previous = self.slots.insert(equipment_item.slot, item);
+        if let Some(previous) = previous {
+            inventory.add(previous, 1);
+        }
+
+        Ok(previous)
+    }
+
+    pub fn unequip(&mut self, inventory: &mut Inventory, slot: EquipSlot) -> Option<u32> {
+        let item = self.slots.remove(&slot)?;
+        inventory.add(item, 1);
+        Some(item)
+    }
+
+    pub fn bonus(&self, database: &EquipmentDatabase) -> Stats {
+        self.slots.values().filter_map(|item| database.get(*item)).fold(Stats::default(), |total, item| total.add(&item.bonus))
+    }
+
+    // base stats plus everything worn
+    pub fn derived_stats(&self, database: &EquipmentDatabase, base: &Stats) -> Stats {
+        base.add(&self.bonus(database))
+    }
+
+    // stat changes if item replaced what is in its slot, for the equip menu arrows
+    pub fn compare(&self, database: &EquipmentDatabase, item: u32) -> Option<Stats> {
+        let candidate = database.get(item)?;
+        let current = self.get(candidate.slot).and_then(|current| database.get(current)).map(|current| current.bonus).unwrap_or_default();
+
+        Some(Stats {
+            hp: candidate.bonus.hp - current.hp,
+            max_hp: candidate.bonus.max_hp - current.max_hp,
+            mp: candidate.bonus.mp - current.mp,
+            max_mp: candidate.bonus.max_mp - current.max_mp,
+            attack: candidate.bonus.attack - current.attack,
+            defence: candidate.bonus.defence - current.defence,
+            speed: candidate.bonus.speed - current.speed
+        })
+    }
+}
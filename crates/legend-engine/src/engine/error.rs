@@ -0,0 +1,60 @@
+use std::io;
+use clover::debug::{Position, RuntimeError};
+use thiserror::Error;
+use crate::engine::decode::DecodeError;
+
+// every failure the engine reports, grouped so callers can tell a missing file from a broken script
+#[derive(Debug, Error)]
+pub enum EngineError {
+    #[error("{0}")]
+    Io(#[from] io::Error),
+    // original game files that are corrupt or not what they claim to be
+    #[error("{0}")]
+    Decode(#[from] DecodeError),
+    // toml data and save files
+    #[error("invalid data: {0}")]
+    Data(#[from] toml::de::Error),
+    #[error("can not serialize data: {0}")]
+    Serialize(#[from] toml::ser::Error),
+    #[error("script error: {0}")]
+    Script(String),
+    #[error("audio error: {0}")]
+    Audio(String),
+    #[error("graphics error: {0}")]
+    Graphics(String),
+    // key and gamepad mappings
+    #[error("input error: {0}")]
+    Input(String),
+    // save slots that are corrupt or from a newer version
+    #[error("save error: {0}")]
+    Save(String),
+    #[error("{0} not found")]
+    NotFound(String)
+}
+
+pub type EngineResult<T> = Result<T, EngineError>;
+
+impl From<RuntimeError> for EngineError {
+    fn from(error: RuntimeError) -> Self {
+        EngineError::Script(error.to_string())
+    }
+}
+
+impl From<hound::Error> for EngineError {
+    fn from(error: hound::Error) -> Self {
+        EngineError::Audio(error.to_string())
+    }
+}
+
+impl From<image::ImageError> for EngineError {
+    fn from(error: image::ImageError) -> Self {
+        EngineError::Graphics(error.to_string())
+    }
+}
+
+// bindings hand engine failures back to the script that caused them
+impl From<EngineError> for RuntimeError {
+    fn from(error: EngineError) -> Self {
+        RuntimeError::new(&error.to_string(), Position::none())
+    }
+}
@@ -0,0 +1,58 @@
+use crate::engine::error::EngineError;
+use crate::engine::graphics::{BlendMode, Color, Graphics, TextAlign, TextDirection};
+use crate::engine::text::{encode_big5, wrap_text};
+
+const MARGIN: i32 = 8;
+
+// drawn instead of the game once a frame fails, so a script author can read what went wrong without a console
+pub struct ErrorScreen {
+    title: &'static str,
+    message: String
+}
+
+impl ErrorScreen {
+    pub fn new(error: &EngineError) -> Self {
+        let title = match error {
+            EngineError::Script(_) => "the script stopped",
+            _ => "the engine stopped"
+        };
+
+        ErrorScreen { title, message: error.to_string() }
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    // whatever the failed frame left behind is cleared, text needs the game font
+    pub fn draw(&self, graphics: &mut Graphics) {
+        graphics.clear_wave();
+        graphics.set_camera_enabled(false);
+        graphics.set_blend_mode(BlendMode::Alpha);
+        graphics.clear(&Color::new(32, 0, 0, 255));
+
+        let game_font = match graphics.shared_game_font() {
+            Some(game_font) => game_font,
+            None => return
+        };
+
+        let line_height = game_font.get_height();
+        let (left, width) = (MARGIN, graphics.get_width() as i32 - MARGIN * 2);
+        let bottom = graphics.get_height() as i32 - MARGIN - line_height;
+        let (title_color, text_color) = (Color::new(255, 96, 96, 255), Color::new(240, 240, 240, 255));
+
+        graphics.draw_text(&encode_big5(self.title), left, MARGIN, width, TextAlign::Left, TextDirection::Horizontal, &title_color);
+
+        // the message and its position, cut off above the hint if it runs long
+        let mut y = MARGIN + line_height * 2;
+        for line in wrap_text(&encode_big5(&self.message), width, &game_font) {
+            if y + line_height > bottom {
+                break;
+            }
+            graphics.draw_text(&line, left, y, width, TextAlign::Left, TextDirection::Horizontal, &text_color);
+            y += line_height;
+        }
+
+        graphics.draw_text(&encode_big5("press a key to quit"), left, bottom, width, TextAlign::Left, TextDirection::Horizontal, &title_color);
+    }
+}
@@ -0,0 +1,245 @@
+use clover::Reference;
+use clover::helper::make_reference;
+use crate::engine::assets::SpriteArchive;
+use crate::engine::clock::GameClock;
+use crate::engine::entity::{Entities, Entity, FollowerTrail};
+use crate::engine::error::EngineResult;
+use crate::engine::graphics::{Graphics, Palette, Vector2};
+use crate::engine::inventory::Inventory;
+use crate::engine::movement::{MovementMode, MovementRules, TILE_SIZE};
+use crate::engine::party::Party;
+use crate::engine::pickup::{PickupEvent, Pickups};
+use crate::engine::transition::{TransitionDefinition, Transitions};
+use crate::engine::vfs::Vfs;
+use crate::engine::world_map::WorldMap;
+
+// a new game starts at eight in the morning of the first day
+const START_DAY: u32 = 1;
+const START_MINUTE: u32 = 8 * 60;
+// game minutes per real second, a day lasts 24 real minutes
+const CLOCK_SPEED: f64 = 1.0;
+
+// party members walking behind the leader
+struct Followers {
+    members: Vec<u32>,
+    trail: FollowerTrail
+}
+
+// the local map the party walks on, who is on it and the time of day their schedules follow
+pub struct Field {
+    name: String,
+    // local maps are stored like the world map, one u16 tile per cell
+    map: Option<WorldMap>,
+    pub clock: GameClock,
+    pub entities: Entities,
+    // the entity the player walks, its steps count towards random encounters
+    pub leader: Option<u32>,
+    // the party the leader walks for, its mode decides where the leader can go and how it is drawn
+    pub party: Option<Reference<Party>>,
+    // chests and items of every map, the opened ones go into the save
+    pub pickups: Pickups,
+    // doors and stairs, the leader stepping on one starts a map change
+    pub transitions: Transitions,
+    entered: Option<TransitionDefinition>,
+    // shared with the world map, set by Movement()
+    movement: Reference<MovementRules>,
+    followers: Option<Followers>,
+    leader_steps: u32,
+    // fixed step alpha of the frame being rendered, entities are drawn between their last two updates
+    pub alpha: f64
+}
+
+impl Field {
+    pub fn new(movement: Reference<MovementRules>) -> Self {
+        Field { name: String::new(), map: None, clock: GameClock::new(START_DAY, START_MINUTE, CLOCK_SPEED), entities: Entities::new(), leader: None, party: None, pickups: Pickups::new(), transitions: Transitions::new(), entered: None, movement, followers: None, leader_steps: 0, alpha: 0.0 }
+    }
+
+    // name is what encounter tables, pickups and transitions call the map, the people of the old map are gone
+    pub fn load(&mut self, vfs: &Vfs, name: &str, filename: &str) -> EngineResult<()> {
+        self.map = Some(WorldMap::load(vfs, filename)?);
+        self.name = name.to_string();
+        self.entities = Entities::new();
+        self.leader = None;
+        self.followers = None;
+        self.leader_steps = 0;
+        self.entered = None;
+        Ok(())
+    }
+
+    // members walk behind the leader in order, spacing tiles apart, no members stops following
+    pub fn set_followers(&mut self, leader: u32, members: Vec<u32>, spacing: usize) {
+        for entity in self.entities.iter_mut() {
+            entity.solid = true;
+        }
+
+        self.leader = Some(leader);
+        self.followers = match members.is_empty() {
+            true => None,
+            false => Some(Followers { trail: FollowerTrail::new(members.len(), spacing), members })
+        };
+    }
+
+    // the door the leader stepped on since the last call
+    pub fn take_transition(&mut self) -> Option<TransitionDefinition> {
+        self.entered.take()
+    }
+
+    // tiles the leader walked since the last call
+    pub fn take_leader_steps(&mut self) -> u32 {
+        std::mem::take(&mut self.leader_steps)
+    }
+
+    // None before the first map is loaded
+    pub fn name(&self) -> Option<&str> {
+        self.map.as_ref().map(|_| self.name.as_str())
+    }
+
+    pub fn map(&self) -> Option<&WorldMap> {
+        self.map.as_ref()
+    }
+
+    // chests stand in the way, taken ground pickups are gone
+    pub fn is_passable(&self, mode: MovementMode, x: i32, y: i32) -> bool {
+        let movement = self.movement.borrow();
+        let pickup = self.pickups.visible_on(&self.name).iter().any(|&(pickup_x, pickup_y, _)| pickup_x == x && pickup_y == y);
+        !pickup && self.map.as_ref().and_then(|map| map.get_tile(x, y)).map(|tile| movement.allows(mode, tile)).unwrap_or(false)
+    }
+
+    // whether the entity could step onto the tile now, nothing walks before a map is loaded
+    pub fn can_enter(&self, id: u32, x: i32, y: i32) -> bool {
+        let mode = self.entities.get(id).map(|entity| entity.mode).unwrap_or_default();
+        self.is_passable(mode, x, y) && self.entities.is_free(id, x, y)
+    }
+
+    // the leader boards or leaves what the party mode says, a mode the tile under it refuses sets the party back
+    fn sync_leader_mode(&mut self) {
+        let (party, leader) = match (self.party.as_ref(), self.leader.and_then(|leader| self.entities.get_mut(leader))) {
+            (Some(party), Some(leader)) => (party, leader),
+            _ => return
+        };
+
+        let mut party = party.borrow_mut();
+        if party.mode == leader.mode {
+            return;
+        }
+
+        let tile = self.map.as_ref().and_then(|map| map.get_tile(leader.position.x, leader.position.y));
+        if !tile.map(|tile| leader.set_mode(&self.movement.borrow(), party.mode, tile)).unwrap_or(false) {
+            party.mode = leader.mode;
+        }
+    }
+
+    // the clock runs on every map, entities only walk on a loaded one
+    pub fn update(&mut self, delta: f64) {
+        self.clock.update(delta);
+        self.sync_leader_mode();
+
+        let map = match self.map.as_ref() {
+            Some(map) => map,
+            None => return
+        };
+
+        // members added after set_followers walk through others as well
+        if let Some(followers) = self.followers.as_ref() {
+            for entity in self.entities.iter_mut().filter(|entity| followers.members.contains(&entity.id)) {
+                entity.solid = false;
+            }
+        }
+
+        let from = self.leader_position();
+        let movement = self.movement.borrow();
+        let pickups = self.pickups.visible_on(&self.name);
+        self.entities.update(delta, &self.clock, |mode, x, y| {
+            !pickups.iter().any(|&(pickup_x, pickup_y, _)| pickup_x == x && pickup_y == y)
+                && map.get_tile(x, y).map(|tile| movement.allows(mode, tile)).unwrap_or(false)
+        });
+        drop(movement);
+        let to = self.leader_position();
+
+        // a warp is no step, the members gather on the leader instead of walking across the map
+        if let (Some(from), Some(to)) = (from, to) {
+            match (to.x - from.x).abs() + (to.y - from.y).abs() {
+                0 => (),
+                1 => {
+                    self.leader_steps += 1;
+                    if let Some(door) = self.transitions.at(&self.name, to.x, to.y) {
+                        self.entered = Some(door.clone());
+                    }
+                    if let Some(followers) = self.followers.as_mut() {
+                        followers.trail.record(from, to);
+                    }
+                },
+                _ => if let Some(followers) = self.followers.as_mut() {
+                    followers.trail.reset();
+                }
+            }
+        }
+
+        if let (Some(leader), Some(followers)) = (self.leader, self.followers.as_ref()) {
+            followers.trail.apply(&mut self.entities, leader, &followers.members);
+        }
+    }
+
+    // the tile the leader looks at, what talking and opening act on
+    pub fn facing_tile(&self) -> Option<Vector2<i32>> {
+        let leader = self.leader.and_then(|leader| self.entities.get(leader))?;
+        let offset = leader.facing.offset();
+        Some(Vector2::new(leader.position.x + offset.x, leader.position.y + offset.y))
+    }
+
+    // opens what the leader faces into the inventory, None when nothing unopened is there
+    pub fn open_facing(&mut self, inventory: &mut Inventory) -> Option<PickupEvent> {
+        let tile = self.facing_tile()?;
+        let map = self.name()?.to_string();
+        self.pickups.open(&map, tile.x, tile.y, inventory)
+    }
+
+    fn leader_position(&self) -> Option<Vector2<i32>> {
+        self.leader.and_then(|leader| self.entities.get(leader)).map(|entity| entity.position)
+    }
+
+    // tiles in world pixels, so the camera scrolls them, then the entities from the back to the front
+    pub fn draw(&self, graphics: &mut Graphics, tiles: &SpriteArchive, sprites: &SpriteArchive, palette: &Palette) {
+        let map = match self.map.as_ref() {
+            Some(map) => map,
+            None => return
+        };
+
+        // only the tiles on screen, a big map has thousands
+        let (left, top) = if graphics.is_camera_enabled() { graphics.camera().position() } else { (0, 0) };
+        let (first_x, first_y) = (left.div_euclid(TILE_SIZE).max(0), top.div_euclid(TILE_SIZE).max(0));
+        let (last_x, last_y) = ((left + graphics.get_width() as i32) / TILE_SIZE, (top + graphics.get_height() as i32) / TILE_SIZE);
+
+        for y in first_y..=last_y.min(map.size.y - 1) {
+            for x in first_x..=last_x.min(map.size.x - 1) {
+                if let Some(tile) = map.get_tile(x, y) {
+                    graphics.draw_sprite(tiles, tile as usize, x * TILE_SIZE, y * TILE_SIZE, palette);
+                }
+            }
+        }
+
+        for (x, y, sprite) in self.pickups.visible_on(&self.name) {
+            graphics.draw_sprite(sprites, sprite, x * TILE_SIZE, y * TILE_SIZE, palette);
+        }
+
+        // the leader wears the sprite set of its mode, walking, riding or sailing
+        let movement = self.movement.borrow();
+        let sprite = |entity: &Entity| match Some(entity.id) == self.leader {
+            true => entity.current_sprite(&movement, entity.walk_frame()),
+            false => entity.sprite
+        };
+
+        let mut entities: Vec<_> = self.entities.iter().map(|entity| (entity.interpolated_position(self.alpha), sprite(entity))).collect();
+        entities.sort_by(|(a, _), (b, _)| a.y.total_cmp(&b.y));
+
+        for (position, sprite) in entities {
+            graphics.draw_sprite(sprites, sprite, position.x.round() as i32, position.y.round() as i32, palette);
+        }
+    }
+}
+
+impl Default for Field {
+    fn default() -> Self {
+        Self::new(make_reference(MovementRules::new()))
+    }
+}
@@ -0,0 +1,68 @@
+use crate::engine::graphics::{Color, Font, Image};
+
+// glyphs laid out left to right, top to bottom, with a one pixel grid line between cells
+pub struct FontSheet {
+    pub columns: usize
+}
+
+impl FontSheet {
+    pub fn new(columns: usize) -> Self {
+        FontSheet { columns: columns.max(1) }
+    }
+
+    fn cell_position(&self, font: &Font, index: usize) -> (i32, i32) {
+        let cell_width = font.get_width() + 1;
+        let cell_height = font.get_height() + 1;
+        (((index % self.columns) * cell_width + 1) as i32, ((index / self.columns) * cell_height + 1) as i32)
+    }
+
+    pub fn export(&self, font: &Font, characters: &[usize]) -> Image {
+        let rows = (characters.len() + self.columns - 1) / self.columns;
+        let width = self.columns * (font.get_width() + 1) + 1;
+        let height = rows.max(1) * (font.get_height() + 1) + 1;
+
+        let mut image = Image::new(width as u32, height as u32);
+        image.clear_by_color(Color::new(128, 128, 128, 255));
+
+        let background = Color::new(0, 0, 0, 255);
+        let foreground = Color::new(255, 255, 255, 255);
+
+        for (index, &character) in characters.iter().enumerate() {
+            let (x, y) = self.cell_position(font, index);
+            image.fill_rect(x, y, font.get_width() as i32, font.get_height() as i32, &background);
+            image.draw_char(character, x, y, font, &foreground);
+        }
+
+        image
+    }
+
+    // returns how many glyphs were replaced
+    pub fn import(&self, font: &mut Font, characters: &[usize], image: &Image) -> usize {
+        let row_bytes = font.get_width() / 8;
+        let mut count = 0;
+
+        for (index, &character) in characters.iter().enumerate() {
+            let (x, y) = self.cell_position(font, index);
+            let mut glyph = vec![0u8; font.glyph_bytes()];
+
+            for j in 0..font.get_height() {
+                for i in 0..font.get_width() {
+                    let lit = match image.get_pixel(x + i as i32, y + j as i32) {
+                        Some(color) => (color.r as u32 + color.g as u32 + color.b as u32) / 3 > 127,
+                        None => false
+                    };
+
+                    if lit {
+                        glyph[j * row_bytes + i / 8] |= 0x80 >> (i % 8);
+                    }
+                }
+            }
+
+            if font.set_glyph(character, &glyph) {
+                count += 1;
+            }
+        }
+
+        count
+    }
+}
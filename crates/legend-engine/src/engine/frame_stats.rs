@@ -0,0 +1,166 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::{Duration, Instant};
+use crate::engine::error::EngineResult;
+
+// the display interval is the median of this many presents, about two seconds at 60hz
+const MEDIAN_FRAMES: usize = 120;
+
+#[derive(Copy, Clone, Debug)]
+pub struct FrameRecord {
+    pub interval: Duration,
+    pub script_time: Duration,
+    pub dropped_frames: u32,
+    pub script_spike: bool
+}
+
+#[derive(Copy, Clone, Default, Debug)]
+pub struct FrameSummary {
+    pub frame_count: usize,
+    pub average_interval: Duration,
+    pub worst_interval: Duration,
+    pub dropped_frames: u32,
+    pub script_spikes: usize
+}
+
+// present-to-present timing, used to track down stutter.
+// presenting waits for vsync, so the median interval is the refresh of the monitor whatever the update rate
+pub struct FrameStats {
+    recent: VecDeque<Duration>,
+    last_present: Option<Instant>,
+    last: Option<FrameRecord>,
+    summary: FrameSummary,
+    total_interval: Duration,
+    // every frame for the csv, only kept with --frame-stats
+    records: Option<Vec<FrameRecord>>
+}
+
+impl FrameStats {
+    pub fn new(keep_records: bool) -> Self {
+        FrameStats {
+            recent: VecDeque::with_capacity(MEDIAN_FRAMES),
+            last_present: None,
+            last: None,
+            summary: FrameSummary::default(),
+            total_interval: Duration::ZERO,
+            records: keep_records.then(Vec::new)
+        }
+    }
+
+    // what one frame of the display takes, none before the second present
+    pub fn target_interval(&self) -> Option<Duration> {
+        let mut recent: Vec<Duration> = self.recent.iter().copied().collect();
+        recent.sort();
+        recent.get(recent.len() / 2).copied()
+    }
+
+    pub fn record_present(&mut self, now: Instant, script_time: Duration) {
+        let last_present = self.last_present.replace(now);
+
+        let interval = match last_present {
+            Some(last_present) => now.duration_since(last_present),
+            None => return
+        };
+
+        if self.recent.len() == MEDIAN_FRAMES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(interval);
+
+        let target = self.target_interval().unwrap_or(interval).as_secs_f64();
+        let frames = interval.as_secs_f64() / target;
+        let dropped_frames = if frames > 1.5 { frames.round() as u32 - 1 } else { 0 };
+
+        // a script taking more than half of the frame budget is what users notice as a hitch
+        let script_spike = script_time.as_secs_f64() > target * 0.5;
+
+        let record = FrameRecord { interval, script_time, dropped_frames, script_spike };
+        self.summary.frame_count += 1;
+        self.summary.worst_interval = self.summary.worst_interval.max(interval);
+        self.summary.dropped_frames += dropped_frames;
+        self.summary.script_spikes += script_spike as usize;
+        self.total_interval += interval;
+
+        self.last = Some(record);
+        if let Some(records) = self.records.as_mut() {
+            records.push(record);
+        }
+    }
+
+    pub fn last(&self) -> Option<&FrameRecord> {
+        self.last.as_ref()
+    }
+
+    // empty unless the records are kept
+    pub fn records(&self) -> &[FrameRecord] {
+        self.records.as_deref().unwrap_or(&[])
+    }
+
+    pub fn summary(&self) -> FrameSummary {
+        if self.summary.frame_count == 0 {
+            return FrameSummary::default();
+        }
+
+        FrameSummary { average_interval: self.total_interval / self.summary.frame_count as u32, ..self.summary }
+    }
+
+    pub fn write_csv(&self, filename: &str) -> EngineResult<()> {
+        let mut writer = BufWriter::new(File::create(filename)?);
+
+        writeln!(writer, "frame,interval_ms,script_ms,dropped_frames,script_spike")?;
+
+        for (index, record) in self.records().iter().enumerate() {
+            writeln!(
+                writer,
+                "{},{:.3},{:.3},{},{}",
+                index,
+                record.interval.as_secs_f64() * 1000.0,
+                record.script_time.as_secs_f64() * 1000.0,
+                record.dropped_frames,
+                record.script_spike
+            )?;
+        }
+
+        writer.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn present(stats: &mut FrameStats, start: Instant, milliseconds: &[u64]) {
+        let mut now = start;
+        stats.record_present(now, Duration::ZERO);
+        for &interval in milliseconds {
+            now += Duration::from_millis(interval);
+            stats.record_present(now, Duration::from_millis(2));
+        }
+    }
+
+    #[test]
+    fn drops_against_the_display_interval() {
+        let mut stats = FrameStats::new(false);
+        // a 60hz display, whatever rate the game updates at
+        present(&mut stats, Instant::now(), &[17, 17, 17, 17, 17, 50]);
+
+        assert_eq!(stats.target_interval(), Some(Duration::from_millis(17)));
+        assert_eq!(stats.last().unwrap().dropped_frames, 2);
+        assert_eq!(stats.summary().dropped_frames, 2);
+        assert_eq!(stats.summary().frame_count, 6);
+        assert!(stats.records().is_empty());
+    }
+
+    #[test]
+    fn keeps_the_median_window() {
+        let mut stats = FrameStats::new(true);
+        present(&mut stats, Instant::now(), &[7; MEDIAN_FRAMES * 2]);
+
+        assert_eq!(stats.recent.len(), MEDIAN_FRAMES);
+        assert_eq!(stats.records().len(), MEDIAN_FRAMES * 2);
+        assert!(!stats.last().unwrap().script_spike);
+    }
+}
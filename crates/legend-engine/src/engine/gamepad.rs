@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::fs;
+use serde::Deserialize;
+use crate::engine::error::{EngineError, EngineResult};
+
+// how far a stick has to lean before it counts as a direction key
+pub const STICK_THRESHOLD: f32 = 0.5;
+
+// buttons are named after their position, so "south" is A on xbox pads and cross on playstation pads
+pub const BUTTONS: [&str; 21] = [
+    "south", "east", "north", "west",
+    "left_trigger", "left_trigger2", "right_trigger", "right_trigger2",
+    "select", "start", "mode", "left_thumb", "right_thumb",
+    "dpad_up", "dpad_down", "dpad_left", "dpad_right",
+    // the left stick pushed past STICK_THRESHOLD
+    "stick_up", "stick_down", "stick_left", "stick_right"
+];
+
+#[derive(Deserialize)]
+struct MappingFile {
+    #[serde(default)]
+    buttons: HashMap<String, String>
+}
+
+// which key of the original game a gamepad button presses
+#[derive(Clone, Debug)]
+pub struct GamepadMapping {
+    buttons: HashMap<String, String>
+}
+
+impl GamepadMapping {
+    pub fn new() -> Self {
+        GamepadMapping { buttons: HashMap::new() }
+    }
+
+    // [buttons]
+    // south = "enter"
+    // east = "escape"
+    pub fn from_toml(source: &str) -> EngineResult<Self> {
+        let file: MappingFile = toml::from_str(source)?;
+
+        let mut mapping = Self::default();
+        for (button, key) in file.buttons.iter() {
+            mapping.set(button, key)?;
+        }
+
+        Ok(mapping)
+    }
+
+    pub fn load(filename: &str) -> EngineResult<Self> {
+        Self::from_toml(&fs::read_to_string(filename)?)
+    }
+
+    // an empty key leaves the button unmapped
+    pub fn set(&mut self, button: &str, key: &str) -> EngineResult<()> {
+        if !BUTTONS.contains(&button) {
+            return Err(EngineError::Input(format!("unknown gamepad button {}", button)));
+        }
+
+        if key.is_empty() {
+            self.buttons.remove(button);
+        } else {
+            self.buttons.insert(button.to_string(), key.to_string());
+        }
+        Ok(())
+    }
+
+    // "button=key" as given on the command line
+    pub fn set_from_str(&mut self, value: &str) -> EngineResult<()> {
+        match value.split_once('=') {
+            Some((button, key)) => self.set(button.trim(), key.trim()),
+            None => Err(EngineError::Input(format!("{} is not button=key", value)))
+        }
+    }
+
+    pub fn key_for(&self, button: &str) -> Option<&str> {
+        self.buttons.get(button).map(|key| key.as_str())
+    }
+}
+
+// the original game only knows the arrows, enter, escape and space, and the mouse
+impl Default for GamepadMapping {
+    fn default() -> Self {
+        let mut mapping = Self::new();
+
+        for (button, key) in [
+            ("dpad_up", "up"), ("dpad_down", "down"), ("dpad_left", "left"), ("dpad_right", "right"),
+            ("stick_up", "up"), ("stick_down", "down"), ("stick_left", "left"), ("stick_right", "right"),
+            ("south", "enter"), ("east", "escape"), ("west", "space"), ("start", "escape"),
+            // clicks where the right stick moved the pointer
+            ("right_trigger", "mouse_left")
+        ] {
+            mapping.buttons.insert(button.to_string(), key.to_string());
+        }
+
+        mapping
+    }
+}
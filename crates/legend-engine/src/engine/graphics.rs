@@ -1,9 +1,26 @@
 use std::cmp::max;
 use std::collections::HashMap;
 use std::error::Error;
-use std::fs::File;
+use std::fmt;
 use std::io::Read;
+use std::ops::Range;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
 use byteorder::ReadBytesExt;
+use crate::engine::assets::SpriteArchive;
+use crate::engine::atomic_file::write_atomic;
+use crate::engine::camera::Camera;
+use crate::engine::decode::{read_i16, read_u16, DecodeError};
+use crate::engine::debug_overlay::{DebugOverlay, FrameSample};
+use crate::engine::draw_debug::{DrawDebugger, DrawRect};
+use crate::engine::error::{EngineError, EngineResult};
+use crate::engine::palette_blend::PaletteBlend;
+use crate::engine::palette_remap::PaletteRemap;
+use crate::engine::panel::{draw_panel, PanelStyle};
+use crate::engine::sprite_cache::SpriteCache;
+use crate::engine::vfs::Vfs;
 
 #[derive(Copy, Clone)]
 pub struct Color {
@@ -31,6 +48,79 @@ impl Color {
         let alpha = (target.a as f64) / 255.0;
         self.alpha_blend(target, alpha)
     }
+
+    pub fn lerp(&self, target: &Color, t: f64) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        Color::new(
+            ((self.r as f64) + ((target.r as f64) - (self.r as f64)) * t).round() as u8,
+            ((self.g as f64) + ((target.g as f64) - (self.g as f64)) * t).round() as u8,
+            ((self.b as f64) + ((target.b as f64) - (self.b as f64)) * t).round() as u8,
+            ((self.a as f64) + ((target.a as f64) - (self.a as f64)) * t).round() as u8
+        )
+    }
+}
+
+// ordered dither thresholds, used to fake transparency like the original hardware
+const BAYER_MATRIX: [[u8; 4]; 4] = [
+    [0, 8, 2, 10],
+    [12, 4, 14, 6],
+    [3, 11, 1, 9],
+    [15, 7, 13, 5]
+];
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BlendMode {
+    Alpha,
+    Dither
+}
+
+impl BlendMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "alpha" => Some(BlendMode::Alpha),
+            "dither" => Some(BlendMode::Dither),
+            _ => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BlendMode::Alpha => "alpha",
+            BlendMode::Dither => "dither"
+        }
+    }
+
+    // None means the destination pixel should be kept
+    pub fn mix(&self, dest: &Color, source: &Color, alpha: f64, x: i32, y: i32) -> Option<Color> {
+        match self {
+            BlendMode::Alpha => Some(dest.alpha_blend(source, alpha)),
+            BlendMode::Dither => {
+                let threshold = (BAYER_MATRIX[(y & 3) as usize][(x & 3) as usize] as f64 + 0.5) / 16.0;
+
+                if alpha > threshold {
+                    Some(Color::new(source.r, source.g, source.b, 255))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum GradientDirection {
+    Vertical,
+    Horizontal
+}
+
+impl GradientDirection {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "vertical" => Some(GradientDirection::Vertical),
+            "horizontal" => Some(GradientDirection::Horizontal),
+            _ => None
+        }
+    }
 }
 
 #[derive(Copy, Clone, Default, Debug)]
@@ -45,26 +135,46 @@ impl<T> Vector2<T> {
     }
 }
 
+static PALETTE_GENERATION: AtomicU64 = AtomicU64::new(1);
+static PALETTE_ID: AtomicU64 = AtomicU64::new(1);
+
+fn next_palette_generation() -> u64 {
+    PALETTE_GENERATION.fetch_add(1, Ordering::Relaxed)
+}
+
+fn next_palette_id() -> u64 {
+    PALETTE_ID.fetch_add(1, Ordering::Relaxed)
+}
+
 pub struct Palette {
-    colors: [Color; 256]
+    colors: [Color; 256],
+    // the same for the life of the palette, the generation moves on with every change
+    id: u64,
+    generation: u64
 }
 
 impl Palette {
-    fn new(filename: &str) -> Option<Self> {
-        if let Ok(mut file) = File::open(filename) {
-            let mut buffer = [0; 256 * 3];
-            if let Ok(_) = file.read(&mut buffer) {
-                let mut colors = [Color::new(0, 0, 0, 255); 256];
-
-                for (i, pixel) in buffer.chunks_exact_mut(4).enumerate() {
-                    colors[i] = Color::new(pixel[0] * 4, pixel[1] * 4, pixel[2] * 4, 255);
-                }
+    // 256 rgb triples of 6 bit vga values
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.len() < 256 * 3 {
+            return Err(DecodeError::Truncated { needed: 256 * 3, available: bytes.len() });
+        }
 
-                return Some(Self { colors });
-            }
+        let mut colors = [Color::new(0, 0, 0, 255); 256];
+        for (color, pixel) in colors.iter_mut().zip(bytes.chunks_exact(3)) {
+            *color = Color::new(pixel[0].min(63) * 4, pixel[1].min(63) * 4, pixel[2].min(63) * 4, 255);
         }
 
-        None
+        Ok(Self { colors, id: next_palette_id(), generation: next_palette_generation() })
+    }
+
+    pub fn load(vfs: &Vfs, name: &str) -> EngineResult<Self> {
+        Ok(Self::from_bytes(&vfs.read(name)?)?)
+    }
+
+    // the from_bytes layout, 8 bit colors lose their low two bits
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.colors.iter().flat_map(|color| [color.r / 4, color.g / 4, color.b / 4]).collect()
     }
 
     pub fn create_by_buffer<R: Read>(buffer: &mut R) -> Self {
@@ -76,7 +186,16 @@ impl Palette {
             color.b = buffer.read_u8().unwrap_or(0);
         }
 
-        Self { colors }
+        Self { colors, id: next_palette_id(), generation: next_palette_generation() }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    // changes whenever a color changes, unique between palettes
+    pub fn generation(&self) -> u64 {
+        self.generation
     }
 
     pub fn get_color(&self, index: u8) -> Color {
@@ -85,6 +204,7 @@ impl Palette {
 
     pub fn set_color(&mut self, index: u8, color: Color) {
         self.colors[index as usize] = color;
+        self.generation = next_palette_generation();
     }
 
     pub fn swap(&mut self, index_a: u8, index_b: u8) {
@@ -94,6 +214,8 @@ impl Palette {
     }
 
     pub fn animate(&mut self, index: u8, count: u8) {
+        // a cycle can not reach below the first color
+        let count = count.min(index);
         let color = self.get_color(index);
         for i in 0..count {
             self.set_color(index - i, self.get_color(index - i - 1));
@@ -102,7 +224,45 @@ impl Palette {
     }
 
     pub fn empty() -> Self {
-        Self { colors: [Color::new(0, 0, 0, 255); 256] }
+        Self::filled(Color::new(0, 0, 0, 255))
+    }
+
+    // every color the same, a blend towards white flashes the screen
+    pub fn filled(color: Color) -> Self {
+        Self { colors: [color; 256], id: next_palette_id(), generation: next_palette_generation() }
+    }
+
+    // each color t of the way from from to to, t is clamped to 0..1
+    pub fn lerp(from: &Palette, to: &Palette, t: f64) -> Self {
+        let mut colors = from.colors;
+        for (color, target) in colors.iter_mut().zip(to.colors.iter()) {
+            *color = color.lerp(target, t);
+        }
+
+        Self { colors, id: next_palette_id(), generation: next_palette_generation() }
+    }
+
+    // takes all the colors of the other palette as one change
+    pub fn copy_from(&mut self, other: &Palette) {
+        self.colors = other.colors;
+        self.generation = next_palette_generation();
+    }
+
+    // the index of the color nearest to the given one, the first of equal ones
+    pub fn closest_index(&self, color: &Color) -> u8 {
+        let distance = |other: &Color| {
+            let (r, g, b) = (color.r as i32 - other.r as i32, color.g as i32 - other.g as i32, color.b as i32 - other.b as i32);
+            r * r + g * g + b * b
+        };
+
+        (0..=255u8).min_by_key(|&index| distance(&self.colors[index as usize])).unwrap_or(0)
+    }
+}
+
+// a copy is a different palette, so it gets its own generation
+impl Clone for Palette {
+    fn clone(&self) -> Self {
+        Self { colors: self.colors, id: next_palette_id(), generation: next_palette_generation() }
     }
 }
 
@@ -114,26 +274,127 @@ pub struct Font {
 }
 
 impl Font {
-    fn new(filename: &str, width: usize, height: usize) -> Option<Self> {
-        let mut data: Vec<u8> = Vec::new();
-        let mut file = File::open(filename).unwrap();
-        if let Ok(_) = file.read_to_end(&mut data) {
-            Some(Self { width, height, data })
+    pub fn load(vfs: &Vfs, name: &str, width: usize, height: usize) -> EngineResult<Self> {
+        Ok(Self::from_bytes(vfs.read(name)?, width, height)?)
+    }
+
+    // glyphs are one bit per pixel, so the width has to fill whole bytes
+    pub fn from_bytes(data: Vec<u8>, width: usize, height: usize) -> Result<Self, DecodeError> {
+        if width == 0 || width % 8 != 0 || height == 0 {
+            return Err(DecodeError::InvalidSize { width, height });
+        }
+
+        let glyph_bytes = (width / 8) * height;
+        if data.len() < glyph_bytes {
+            return Err(DecodeError::Truncated { needed: glyph_bytes, available: data.len() });
+        }
+
+        Ok(Self { width, height, data })
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    pub fn glyph_bytes(&self) -> usize {
+        (self.width / 8) * self.height
+    }
+
+    pub fn glyph_count(&self) -> usize {
+        self.data.len() / self.glyph_bytes().max(1)
+    }
+
+    // position of a character in the font file, big5 characters are stored page by page,
+    // None for codes that are not big5 at all
+    pub fn glyph_index(character: usize) -> Option<usize> {
+        if character >= 0xa140 {
+            let page = ((character & 0xff00) / 0x100).checked_sub(0xa1)?;
+            let trail = character & 0xff;
+
+            let position = if trail >= 0xa1 {
+                trail - 0xa1 + 0x7e - 0x40 + 1
+            } else {
+                trail.checked_sub(0x40)?
+            };
+
+            Some(page * (0xfe - 0xa1 + 0x7e - 0x40 + 2) + position)
         } else {
-            None
+            Some(character)
+        }
+    }
+
+    fn glyph_range(&self, character: usize) -> Option<Range<usize>> {
+        let character_bytes = self.glyph_bytes();
+        let index = Font::glyph_index(character)?.checked_mul(character_bytes)?;
+        Some(index..index.checked_add(character_bytes)?)
+    }
+
+    pub fn get_glyph(&self, character: usize) -> Option<&[u8]> {
+        self.data.get(self.glyph_range(character)?)
+    }
+
+    pub fn set_glyph(&mut self, character: usize, glyph: &[u8]) -> bool {
+        match self.glyph_range(character).and_then(|range| self.data.get_mut(range)) {
+            Some(target) if target.len() == glyph.len() => {
+                target.copy_from_slice(glyph);
+                true
+            },
+            _ => false
+        }
+    }
+
+    pub fn save(&self, filename: &str) -> EngineResult<()> {
+        write_atomic(filename, &self.data)?;
+        Ok(())
+    }
+}
+
+// every valid big5 code between first and last, trail bytes are 0x40-0x7e and 0xa1-0xfe
+pub fn big5_codes(first: usize, last: usize) -> Vec<usize> {
+    (first..=last).filter(|code| {
+        let trail = code & 0xff;
+        *code >= 0xa140 && ((0x40..=0x7e).contains(&trail) || (0xa1..=0xfe).contains(&trail))
+    }).collect()
+}
+
+// english and chinese font files shipped with each release of the game
+pub const FONT_CANDIDATES: [(&str, &str, &str); 3] = [
+    ("cd", "ENGLISH.FNT", "CHINESE.FNT"),
+    ("floppy", "ASC16", "STDFONT.15"),
+    ("eten", "ASC16.FNT", "STDFONT.16")
+];
+
+#[derive(Debug)]
+pub struct FontNotFoundError {
+    pub data_path: String,
+    pub candidates: Vec<String>
+}
+
+impl fmt::Display for FontNotFoundError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "can not find the game font in {}, looked for:", self.data_path)?;
+        for candidate in &self.candidates {
+            writeln!(f, "  {}", candidate)?;
         }
+        Ok(())
     }
 }
 
+impl Error for FontNotFoundError {}
+
 pub struct GameFont {
     english_font: Font,
     chinese_font: Font
 }
 
 impl GameFont {
-    fn new(english_filename: &str, chinese_filename: &str) -> Option<Self> {
-        if let Some(english_font) = Font::new(english_filename, 8, 16) {
-            if let Some(chinese_font) = Font::new(chinese_filename, 16, 16) {
+    fn load(vfs: &Vfs, english_filename: &str, chinese_filename: &str) -> Option<Self> {
+        if let Ok(english_font) = Font::load(vfs, english_filename, 8, 16) {
+            if let Ok(chinese_font) = Font::load(vfs, chinese_filename, 16, 16) {
                 return Some(Self { english_font, chinese_font });
             };
         };
@@ -141,25 +402,143 @@ impl GameFont {
         None
     }
 
+    // try the font files of every known release in the game data
+    pub fn discover(vfs: &Vfs) -> Result<Self, FontNotFoundError> {
+        let mut candidates = Vec::new();
+
+        for (version, english_filename, chinese_filename) in FONT_CANDIDATES {
+            candidates.push(format!("{} and {} ({} version)", english_filename, chinese_filename, version));
+
+            if let Some(game_font) = GameFont::load(vfs, english_filename, chinese_filename) {
+                return Ok(game_font);
+            }
+        }
+
+        Err(FontNotFoundError { data_path: vfs.describe(), candidates })
+    }
+
     pub fn get_height(&self) -> i32 {
         max(self.english_font.height as i32, self.chinese_font.height as i32)
     }
 
-    pub fn get_width(&self, text: &[usize]) -> i32 {
+    pub fn font_for(&self, character: usize) -> &Font {
+        if character < 128 {
+            &self.english_font
+        } else {
+            &self.chinese_font
+        }
+    }
+
+    pub fn line_width(&self, line: &[usize]) -> i32 {
+        let tab_width = self.english_font.width as i32 * TAB_COLUMNS;
         let mut width = 0;
 
-        for &character in text {
-            let font = if character < 128 {
-                &self.english_font
+        for &character in line {
+            if character == TAB {
+                width = next_tab_stop(width, tab_width);
             } else {
-                &self.chinese_font
-            };
-
-            width += font.width as i32;
+                width += self.font_for(character).width as i32;
+            }
         }
 
         width
     }
+
+    // width of the longest line
+    pub fn get_width(&self, text: &[usize]) -> i32 {
+        split_lines(text).iter().map(|line| self.line_width(line)).max().unwrap_or(0)
+    }
+
+    pub fn get_text_height(&self, text: &[usize]) -> i32 {
+        split_lines(text).len() as i32 * self.get_height()
+    }
+}
+
+const CARRIAGE_RETURN: usize = 13;
+const LINE_FEED: usize = 10;
+const TAB: usize = 9;
+
+// tab stops every 4 english characters
+const TAB_COLUMNS: i32 = 4;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right
+}
+
+impl TextAlign {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "left" => Some(TextAlign::Left),
+            "center" => Some(TextAlign::Center),
+            "right" => Some(TextAlign::Right),
+            _ => None
+        }
+    }
+
+    fn offset(&self, line_width: i32, width: i32) -> i32 {
+        match self {
+            TextAlign::Left => 0,
+            TextAlign::Center => (width - line_width) / 2,
+            TextAlign::Right => width - line_width
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TextDirection {
+    Horizontal,
+    // top to bottom columns, the first column on the right like classical chinese
+    Vertical
+}
+
+impl TextDirection {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "horizontal" => Some(TextDirection::Horizontal),
+            "vertical" => Some(TextDirection::Vertical),
+            _ => None
+        }
+    }
+}
+
+// CR, LF and CRLF all end a line, the break characters are not part of the lines
+pub fn split_lines(text: &[usize]) -> Vec<&[usize]> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut index = 0;
+
+    while index < text.len() {
+        match text[index] {
+            CARRIAGE_RETURN => {
+                lines.push(&text[start..index]);
+                if text.get(index + 1) == Some(&LINE_FEED) {
+                    index += 1;
+                }
+                start = index + 1;
+            },
+            LINE_FEED => {
+                lines.push(&text[start..index]);
+                start = index + 1;
+            },
+            _ => ()
+        }
+
+        index += 1;
+    }
+
+    lines.push(&text[start..]);
+    lines
+}
+
+fn next_tab_stop(offset: i32, tab_width: i32) -> i32 {
+    if tab_width <= 0 {
+        return offset;
+    }
+
+    (offset / tab_width + 1) * tab_width
 }
 
 #[derive(Clone)]
@@ -170,6 +549,70 @@ pub struct RleImage {
 }
 
 impl RleImage {
+    // every line is walked once here, so a corrupt image is rejected before anything draws it
+    pub fn new(size: Vector2<u16>, offset: Vector2<i16>, data: Vec<u8>) -> Result<Self, DecodeError> {
+        for_each_run(&data, size.y as usize, |_, _, _| {})?;
+        Ok(RleImage { size, offset, data })
+    }
+
+    // width and height as u16, offset as two i16, then the lines
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let size = Vector2::new(read_u16(bytes, 0)?, read_u16(bytes, 2)?);
+        let offset = Vector2::new(read_i16(bytes, 4)?, read_i16(bytes, 6)?);
+        Self::new(size, offset, bytes[8..].to_vec())
+    }
+
+    pub fn load(vfs: &Vfs, name: &str) -> EngineResult<Self> {
+        Ok(Self::from_bytes(&vfs.read(name)?)?)
+    }
+
+    // the from_bytes layout, to write a sprite back into the game data
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(8 + self.data.len());
+        for value in [self.size.x, self.size.y, self.offset.x as u16, self.offset.y as u16] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+
+    // the other way of decode_indexed, size.x * size.y palette indices with None transparent.
+    // skips and runs longer than a byte are split, a line has to fit in the 255 bytes its length byte can count
+    pub fn encode(size: Vector2<u16>, offset: Vector2<i16>, pixels: &[Option<u8>]) -> EngineResult<Self> {
+        let width = size.x as usize;
+        if pixels.len() != width * size.y as usize {
+            return Err(DecodeError::Truncated { needed: width * size.y as usize, available: pixels.len() }.into());
+        }
+
+        let mut data = Vec::new();
+        for line in 0..size.y as usize {
+            let row = &pixels[line * width..(line + 1) * width];
+            let mut bytes = vec![0u8];
+            let mut x = 0;
+
+            while let Some(start) = (x..width).find(|&i| row[i].is_some()) {
+                let mut skip = start - x;
+                while skip > 255 {
+                    bytes.extend_from_slice(&[255, 0]);
+                    skip -= 255;
+                }
+
+                let run: Vec<u8> = row[start..].iter().take(255).map_while(|pixel| *pixel).collect();
+                bytes.extend_from_slice(&[skip as u8, run.len() as u8]);
+                bytes.extend_from_slice(&run);
+                x = start + run.len();
+            }
+
+            if bytes.len() > 255 {
+                return Err(EngineError::Graphics(format!("line {} of the sprite needs {} bytes, a rle line holds 255", line, bytes.len())));
+            }
+            bytes[0] = bytes.len() as u8;
+            data.extend_from_slice(&bytes);
+        }
+
+        Ok(Self::new(size, offset, data)?)
+    }
+
     pub fn is_empty(&self) -> bool {
         self.data.len() == 0
     }
@@ -177,6 +620,24 @@ impl RleImage {
     pub fn reference_index(&self) -> usize {
         self.size.x as usize
     }
+
+    // decode into a size.x * size.y buffer of palette indices, None is transparent
+    pub fn decode_indexed(&self) -> Vec<Option<u8>> {
+        let mut buffer = vec![None; self.size.x as usize * self.size.y as usize];
+        blit(&mut buffer, self.size.x as i32, self.size.y as i32, self, -(self.offset.x as i32), -(self.offset.y as i32), Some);
+        buffer
+    }
+
+    // decode to a full color image, the offset is not applied
+    pub fn decode(&self, palette: &Palette) -> Image {
+        self.decode_remapped(palette, &PaletteRemap::identity())
+    }
+
+    pub fn decode_remapped(&self, palette: &Palette, remap: &PaletteRemap) -> Image {
+        let mut image = Image::new(self.size.x as u32, self.size.y as u32);
+        blit(&mut image.data, self.size.x as i32, self.size.y as i32, self, -(self.offset.x as i32), -(self.offset.y as i32), |value| palette.get_color(remap.get(value)));
+        image
+    }
 }
 
 #[derive(Clone)]
@@ -186,8 +647,55 @@ pub struct Image {
 }
 
 
-fn blit<T, F>(target: &mut Vec<T>, width: i32, height: i32, source: &RleImage, x: i32, y: i32, value_function: F) where F: Fn(&RleImage, usize) -> T {
-    let mut start_x = x + source.offset.x as i32;
+// every line starts with its length in bytes, counting the length byte itself,
+// followed by pairs of transparent pixels to skip and a run of that many palette indices
+fn for_each_run<F>(data: &[u8], height: usize, mut draw: F) -> Result<(), DecodeError> where F: FnMut(usize, i32, &[u8]) {
+    if data.is_empty() {
+        return Ok(());
+    }
+
+    let mut index: usize = 0;
+
+    for line in 0..height {
+        let line_length = match data.get(index) {
+            Some(&line_length) => line_length.max(1) as usize,
+            None => return Err(DecodeError::Truncated { needed: index + 1, available: data.len() })
+        };
+
+        let line_end = index + line_length;
+        if line_end > data.len() {
+            return Err(DecodeError::Truncated { needed: line_end, available: data.len() });
+        }
+
+        index += 1;
+        let mut current_x = 0;
+
+        while index < line_end {
+            current_x += data[index] as i32;
+            index += 1;
+
+            if index >= line_end {
+                break;
+            }
+
+            let run_end = index + 1 + data[index] as usize;
+            index += 1;
+
+            if run_end > line_end {
+                return Err(DecodeError::RunOverflow { line });
+            }
+
+            draw(line, current_x, &data[index..run_end]);
+            current_x += (run_end - index) as i32;
+            index = run_end;
+        }
+    }
+
+    Ok(())
+}
+
+fn blit<T, F>(target: &mut [T], width: i32, height: i32, source: &RleImage, x: i32, y: i32, value_function: F) where F: Fn(u8) -> T {
+    let start_x = x + source.offset.x as i32;
 
     if start_x >= width {
         return;
@@ -195,7 +703,7 @@ fn blit<T, F>(target: &mut Vec<T>, width: i32, height: i32, source: &RleImage, x
         return;
     }
 
-    let mut start_y = y + source.offset.y as i32;
+    let start_y = y + source.offset.y as i32;
 
     if start_y >= height {
         return;
@@ -203,47 +711,66 @@ fn blit<T, F>(target: &mut Vec<T>, width: i32, height: i32, source: &RleImage, x
         return;
     }
 
-    let mut index: usize = 0;
-
-    for j in 0..source.size.y as i32 {
-        let line_start_index = index;
-        let line_length = source.data[index];
-        index += 1;
-
-        let mut current_x = start_x;
-
-        while index - line_start_index < line_length as usize {
-            current_x += source.data[index] as i32;
-            index += 1;
-
-            if index - line_start_index >= line_length as usize {
-                break;
-            }
-
-            let data_length = source.data[index] as i32;
-            index += 1;
+    // the data was checked when the image was made, this only guards the target
+    let _ = for_each_run(&source.data, source.size.y as usize, |line, skip, run| {
+        let current_y = start_y + line as i32;
+        if current_y < 0 || current_y >= height {
+            return;
+        }
 
-            for _ in 0..data_length {
-                let current_y = start_y + j;
+        for (i, &value) in run.iter().enumerate() {
+            let current_x = start_x + skip + i as i32;
 
-                if current_x >= 0 && current_x < width && current_y >= 0 && current_y < height {
-                    target[(current_y * width + current_x) as usize] = value_function(source, index);
+            if current_x >= 0 && current_x < width {
+                if let Some(pixel) = target.get_mut((current_y * width + current_x) as usize) {
+                    *pixel = value_function(value);
                 }
+            }
+        }
+    });
+}
 
-                index += 1;
-                current_x += 1;
+// averages along one axis, step walks along it and line_step moves to the next line
+fn box_blur(source: &[Color], length: usize, lines: usize, radius: usize, step: usize, line_step: usize) -> Vec<Color> {
+    let mut result = source.to_vec();
+
+    for line in 0..lines {
+        for i in 0..length {
+            let first = i.saturating_sub(radius);
+            let last = (i + radius).min(length - 1);
+
+            let mut sum = [0usize; 4];
+            for k in first..=last {
+                let color = &source[line * line_step + k * step];
+                sum[0] += color.r as usize;
+                sum[1] += color.g as usize;
+                sum[2] += color.b as usize;
+                sum[3] += color.a as usize;
             }
+
+            let count = last - first + 1;
+            result[line * line_step + i * step] = Color::new((sum[0] / count) as u8, (sum[1] / count) as u8, (sum[2] / count) as u8, (sum[3] / count) as u8);
         }
     }
+
+    result
 }
 
 impl Image {
 
     pub fn blit(&mut self, source: &RleImage, x: i32, y: i32, palette: &Palette) {
-        blit(&mut self.data, self.size.x as i32, self.size.y as i32, source, x, y, |rle_image, index| { palette.get_color(rle_image.data[index]) });
+        blit(&mut self.data, self.size.x as i32, self.size.y as i32, source, x, y, |value| palette.get_color(value));
+    }
+
+    pub fn blit_remapped(&mut self, source: &RleImage, x: i32, y: i32, palette: &Palette, remap: &PaletteRemap) {
+        blit(&mut self.data, self.size.x as i32, self.size.y as i32, source, x, y, |value| palette.get_color(remap.get(value)));
     }
 
     pub fn alpha_blit(&mut self, source: &Image, x: i32, y: i32, alpha: f64) {
+        self.alpha_blit_with_mode(source, x, y, alpha, BlendMode::Alpha);
+    }
+
+    pub fn alpha_blit_with_mode(&mut self, source: &Image, x: i32, y: i32, alpha: f64, mode: BlendMode) {
         for j in 0..source.size.y as i32 {
             if y + j < 0 || y + j >= self.size.y as i32 {
                 continue;
@@ -261,15 +788,23 @@ impl Image {
 
                 let pixel = {
                     let dest_color = &self.data[(j + y) as usize * self.size.x as usize + (x + i) as usize];
-                    dest_color.alpha_blend(&source_color, alpha)
+                    mode.mix(dest_color, &source_color, alpha, x + i, y + j)
                 };
 
-                self.set_pixel(i + x, j + y, &pixel);
+                if let Some(pixel) = pixel {
+                    self.set_pixel(i + x, j + y, &pixel);
+                }
             }
         }
     }
 
     pub fn fill_rect(&mut self, x: i32, y: i32, width: i32, height: i32, color: &Color) {
+        self.fill_rect_with_mode(x, y, width, height, color, BlendMode::Alpha);
+    }
+
+    pub fn fill_rect_with_mode(&mut self, x: i32, y: i32, width: i32, height: i32, color: &Color, mode: BlendMode) {
+        let alpha = (color.a as f64) / 255.0;
+
         for j in 0..height {
             if y + j < 0 || y + j >= self.size.y as i32 {
                 continue;
@@ -282,57 +817,107 @@ impl Image {
 
                 let pixel = {
                     let dest_color = &self.data[(j + y) as usize * self.size.x as usize + (x + i) as usize];
-                    dest_color.blend(color)
+                    mode.mix(dest_color, color, alpha, x + i, y + j)
                 };
 
-                self.set_pixel(i + x, j + y, &pixel);
+                if let Some(pixel) = pixel {
+                    self.set_pixel(i + x, j + y, &pixel);
+                }
             }
         }
     }
 
-    pub fn set_pixel(&mut self, x: i32, y: i32, color: &Color) {
-        if x < 0 || x >= self.size.x as i32 {
-            return;
-        }
+    // the color function receives the position along the gradient and the gradient length
+    fn fill_gradient_by<F>(&mut self, x: i32, y: i32, width: i32, height: i32, direction: GradientDirection, color_function: F) where F: Fn(i32, i32) -> Color {
+        for j in 0..height {
+            if y + j < 0 || y + j >= self.size.y as i32 {
+                continue;
+            }
 
-        if y < 0 || y >= self.size.y as i32 {
-            return;
-        }
+            for i in 0..width {
+                if x + i < 0 || x + i >= self.size.x as i32 {
+                    continue;
+                }
 
-        let index = y as usize * self.size.x as usize + x as usize;
+                let color = match direction {
+                    GradientDirection::Vertical => color_function(j, height),
+                    GradientDirection::Horizontal => color_function(i, width)
+                };
 
-        self.data[index] = color.clone();
+                let pixel = {
+                    let dest_color = &self.data[(j + y) as usize * self.size.x as usize + (x + i) as usize];
+                    dest_color.blend(&color)
+                };
+
+                self.set_pixel(i + x, j + y, &pixel);
+            }
+        }
     }
 
-    pub fn draw_char(&mut self, character: usize, x: i32, y: i32, font: &Font, color: &Color) {
-        let character_bytes = (font.width / 8) * font.height;
+    pub fn fill_gradient_rect(&mut self, x: i32, y: i32, width: i32, height: i32, from: &Color, to: &Color, direction: GradientDirection) {
+        self.fill_gradient_by(x, y, width, height, direction, |position, length| {
+            if length <= 1 {
+                *from
+            } else {
+                from.lerp(to, position as f64 / (length - 1) as f64)
+            }
+        });
+    }
 
-        // big5 page
-        let code = if character >= 0xa140 {
-            let page = (character & 0xff00) / 0x100 - 0xa1;
+    // step through the palette entries from start_index to end_index, like the banded skies of the original.
+    // an end below the start steps down through the palette
+    pub fn fill_palette_ramp_rect(&mut self, x: i32, y: i32, width: i32, height: i32, palette: &Palette, start_index: u8, end_index: u8, direction: GradientDirection) {
+        let count = start_index.abs_diff(end_index) as usize + 1;
 
-            let position = if (character & 0xff) >= 0xa1 {
-                (character & 0xff) - 0xa1 + 0x7e - 0x40 + 1
-            } else {
-                (character & 0xff) - 0x40
-            };
+        self.fill_gradient_by(x, y, width, height, direction, |position, length| {
+            let step = ((position as usize * count) / length.max(1) as usize).min(count - 1) as u8;
+            palette.get_color(if end_index < start_index { start_index - step } else { start_index + step })
+        });
+    }
 
-            page * (0xfe - 0xa1 + 0x7e - 0x40 + 2) + position
-        } else {
-            character
-        };
+    pub fn draw_rect(&mut self, x: i32, y: i32, width: i32, height: i32, color: &Color) {
+        if width <= 0 || height <= 0 {
+            return;
+        }
+
+        for i in 0..width {
+            self.set_pixel(x + i, y, color);
+            self.set_pixel(x + i, y + height - 1, color);
+        }
 
-        let index = character_bytes * code;
+        for j in 0..height {
+            self.set_pixel(x, y + j, color);
+            self.set_pixel(x + width - 1, y + j, color);
+        }
+    }
 
-        // out of bound
-        if index + character_bytes > font.data.len() {
+    pub fn set_pixel(&mut self, x: i32, y: i32, color: &Color) {
+        if x < 0 || x >= self.size.x as i32 {
+            return;
+        }
+
+        if y < 0 || y >= self.size.y as i32 {
             return;
         }
 
+        let index = y as usize * self.size.x as usize + x as usize;
+
+        self.data[index] = color.clone();
+    }
+
+    pub fn draw_char(&mut self, character: usize, x: i32, y: i32, font: &Font, color: &Color) {
+        let glyph = match font.get_glyph(character) {
+            Some(glyph) => glyph,
+            // out of bound
+            None => return
+        };
+
+        let row_bytes = font.width / 8;
+
         for j in 0..font.height {
             let mut current_x = 0;
-            for i in 0..(font.width / 8) {
-                let byte = font.data[index + j * (font.width / 8) + i];
+            for i in 0..row_bytes {
+                let byte = glyph[j * row_bytes + i];
 
                 for bit in (0..8).rev() {
                     if (1 << bit) & byte > 0 {
@@ -346,37 +931,92 @@ impl Image {
     }
 
     pub fn draw_text(&mut self, text: &[usize], x: i32, y: i32, font: &Font, color: &Color) {
-        let mut count = 0;
-        let mut line = 0;
-        for &character in text {
-            if character == 13 {
-                line += 1;
-                count = 0;
-            }
+        self.draw_text_aligned(text, x, y, 0, TextAlign::Left, font, color);
+    }
+
+    // every line is aligned inside width, starting at x
+    pub fn draw_text_aligned(&mut self, text: &[usize], x: i32, y: i32, width: i32, align: TextAlign, font: &Font, color: &Color) {
+        let tab_width = font.width as i32 * TAB_COLUMNS;
+
+        for (line_index, line) in split_lines(text).iter().enumerate() {
+            let line_width = line.iter().fold(0, |offset, &character| {
+                if character == TAB { next_tab_stop(offset, tab_width) } else { offset + font.width as i32 }
+            });
+
+            let line_x = x + align.offset(line_width, width);
+            let line_y = y + line_index as i32 * font.height as i32;
 
-            self.draw_char(character, x + count * font.width as i32, y + line * font.height as i32, font, color);
-            count += 1;
+            let mut offset = 0;
+            for &character in line.iter() {
+                if character == TAB {
+                    offset = next_tab_stop(offset, tab_width);
+                    continue;
+                }
+
+                self.draw_char(character, line_x + offset, line_y, font, color);
+                offset += font.width as i32;
+            }
         }
     }
 
     pub fn draw_game_text(&mut self, text: &[usize], x: i32, y: i32, game_font: &GameFont, color: &Color) {
+        self.draw_game_text_aligned(text, x, y, 0, TextAlign::Left, game_font, color);
+    }
 
-        let mut offset = 0;
-        for &character in text {
-            let font = if character < 128 {
-                &game_font.english_font
-            } else {
-                &game_font.chinese_font
-            };
+    pub fn draw_game_text_aligned(&mut self, text: &[usize], x: i32, y: i32, width: i32, align: TextAlign, game_font: &GameFont, color: &Color) {
+        let tab_width = game_font.english_font.width as i32 * TAB_COLUMNS;
+
+        for (line_index, line) in split_lines(text).iter().enumerate() {
+            let line_x = x + align.offset(game_font.line_width(line), width);
+            let line_y = y + line_index as i32 * game_font.get_height();
+
+            let mut offset = 0;
+            for &character in line.iter() {
+                if character == TAB {
+                    offset = next_tab_stop(offset, tab_width);
+                    continue;
+                }
+
+                let font = game_font.font_for(character);
+                self.draw_char(character, line_x + offset, line_y, font, color);
+                offset += font.width as i32;
+            }
+        }
+    }
+
+    // x is the right edge of the first column, each line becomes a column further left
+    pub fn draw_game_text_vertical(&mut self, text: &[usize], x: i32, y: i32, game_font: &GameFont, color: &Color) {
+        let column_width = game_font.chinese_font.width as i32;
+        let row_height = game_font.get_height();
+
+        for (column, line) in split_lines(text).iter().enumerate() {
+            let column_x = x - (column as i32 + 1) * column_width;
 
-            self.draw_char(character, x + offset, y, font, color);
-            offset += font.width as i32;
+            let mut offset = 0;
+            for &character in line.iter() {
+                if character == TAB {
+                    offset = next_tab_stop(offset, row_height * TAB_COLUMNS);
+                    continue;
+                }
+
+                // half width characters are centered in the column
+                let font = game_font.font_for(character);
+                self.draw_char(character, column_x + (column_width - font.width as i32) / 2, y + offset, font, color);
+                offset += row_height;
+            }
+        }
+    }
+
+    pub fn draw_game_text_with_direction(&mut self, text: &[usize], x: i32, y: i32, direction: TextDirection, game_font: &GameFont, color: &Color) {
+        match direction {
+            TextDirection::Horizontal => self.draw_game_text(text, x, y, game_font, color),
+            TextDirection::Vertical => self.draw_game_text_vertical(text, x, y, game_font, color)
         }
     }
 
     pub fn draw_game_text_center(&mut self, text: &[usize], x: i32, y: i32, width: i32, height: i32, game_font: &GameFont, color: &Color) {
-        let text_width = game_font.get_width(text);
-        self.draw_game_text(text, x + (width - text_width) / 2, y + (height - game_font.get_height()) / 2, game_font, color);
+        let text_height = game_font.get_text_height(text);
+        self.draw_game_text_aligned(text, x, y + (height - text_height) / 2, width, TextAlign::Center, game_font, color);
     }
 
     pub fn draw_shadow_text(&mut self, text: &[usize], x: i32, y: i32, game_font: &GameFont, color: &Color, shadow_color: &Color) {
@@ -385,8 +1025,73 @@ impl Image {
     }
 
     pub fn draw_shadow_text_center(&mut self, text: &[usize], x: i32, y: i32, width: i32, height: i32, game_font: &GameFont, color: &Color, shadow_color: &Color) {
-        let text_width = game_font.get_width(text);
-        self.draw_shadow_text(text, x + (width - text_width) / 2, y + (height - game_font.get_height()) / 2, game_font, color, shadow_color);
+        self.draw_game_text_center(text, x + 1, y, width, height, game_font, shadow_color);
+        self.draw_game_text_center(text, x, y, width, height, game_font, color);
+    }
+
+    // average each level x level cell into a single color
+    pub fn mosaic(&mut self, level: u32) {
+        if level <= 1 {
+            return;
+        }
+
+        let cell = level as usize;
+        let width = self.size.x as usize;
+        let height = self.size.y as usize;
+
+        for cell_y in (0..height).step_by(cell) {
+            for cell_x in (0..width).step_by(cell) {
+                let end_x = (cell_x + cell).min(width);
+                let end_y = (cell_y + cell).min(height);
+
+                let mut sum = [0usize; 4];
+                for y in cell_y..end_y {
+                    for color in &self.data[y * width + cell_x..y * width + end_x] {
+                        sum[0] += color.r as usize;
+                        sum[1] += color.g as usize;
+                        sum[2] += color.b as usize;
+                        sum[3] += color.a as usize;
+                    }
+                }
+
+                let count = (end_x - cell_x) * (end_y - cell_y);
+                let average = Color::new((sum[0] / count) as u8, (sum[1] / count) as u8, (sum[2] / count) as u8, (sum[3] / count) as u8);
+
+                for y in cell_y..end_y {
+                    for pixel in self.data[y * width + cell_x..y * width + end_x].iter_mut() {
+                        *pixel = average;
+                    }
+                }
+            }
+        }
+    }
+
+    // box blur inside the rect, pixels outside are neither read nor changed
+    pub fn blur_rect(&mut self, x: i32, y: i32, width: i32, height: i32, radius: u32) {
+        let start_x = x.max(0);
+        let start_y = y.max(0);
+        let end_x = (x + width).min(self.size.x as i32);
+        let end_y = (y + height).min(self.size.y as i32);
+
+        if radius == 0 || start_x >= end_x || start_y >= end_y {
+            return;
+        }
+
+        let (width, height) = ((end_x - start_x) as usize, (end_y - start_y) as usize);
+        let mut region: Vec<Color> = Vec::with_capacity(width * height);
+        for j in start_y..end_y {
+            let line = j as usize * self.size.x as usize;
+            region.extend_from_slice(&self.data[line + start_x as usize..line + end_x as usize]);
+        }
+
+        // horizontal then vertical pass
+        let region = box_blur(&region, width, height, radius as usize, 1, width);
+        let region = box_blur(&region, height, width, radius as usize, width, 1);
+
+        for j in 0..height {
+            let line = (start_y as usize + j) * self.size.x as usize + start_x as usize;
+            self.data[line..line + width].copy_from_slice(&region[j * width..(j + 1) * width]);
+        }
     }
 
     pub fn copy_to(&self, buffer: &mut [u8]) {
@@ -424,41 +1129,567 @@ impl Image {
         self.clear_by_color(Color::new(0, 0, 0, 0));
     }
 
-    pub fn save(&self, filename: &str) {
+    pub fn get_pixel(&self, x: i32, y: i32) -> Option<Color> {
+        if x < 0 || x >= self.size.x as i32 || y < 0 || y >= self.size.y as i32 {
+            return None;
+        }
+
+        Some(self.data[y as usize * self.size.x as usize + x as usize])
+    }
+
+    // copy a region into a new image, pixels outside this image stay transparent
+    pub fn sub_image(&self, x: i32, y: i32, width: u32, height: u32) -> Image {
+        let mut image = Image::new(width, height);
+
+        for j in 0..height as i32 {
+            for i in 0..width as i32 {
+                if let Some(color) = self.get_pixel(x + i, y + j) {
+                    image.set_pixel(i, j, &color);
+                }
+            }
+        }
+
+        image
+    }
+
+    pub fn load(filename: &str) -> EngineResult<Image> {
+        Ok(Self::from_dynamic(image::open(filename)?))
+    }
+
+    // a png of the game data or of a mod replacing it
+    pub fn load_vfs(vfs: &Vfs, name: &str) -> EngineResult<Image> {
+        Ok(Self::from_dynamic(image::load_from_memory(&vfs.read(name)?)?))
+    }
+
+    fn from_dynamic(source: image::DynamicImage) -> Image {
+        let source = source.to_rgba8();
+        let mut image = Image::new(source.width(), source.height());
+
+        for (x, y, pixel) in source.enumerate_pixels() {
+            image.set_pixel(x as i32, y as i32, &Color::new(pixel[0], pixel[1], pixel[2], pixel[3]));
+        }
+
+        image
+    }
+
+    pub fn save(&self, filename: &str) -> EngineResult<()> {
         let mut image_to_save: image::RgbaImage = image::ImageBuffer::new(self.size.x, self.size.y);
 
-        for y in 0..self.size.y {
-            for x in 0..self.size.x {
-                let color = self.data.get((y * self.size.x + x) as usize).unwrap();
-                image_to_save.put_pixel(x, y, image::Rgba([color.r, color.g, color.b, color.a]));
+        for (x, y, pixel) in image_to_save.enumerate_pixels_mut() {
+            let color = &self.data[(y * self.size.x + x) as usize];
+            *pixel = image::Rgba([color.r, color.g, color.b, color.a]);
+        }
+
+        image_to_save.save(filename)?;
+        Ok(())
+    }
+
+    // nearest neighbour, every pixel becomes a scale by scale square
+    pub fn upscale(&self, scale: u32) -> Image {
+        let scale = scale.max(1);
+        let mut result = Image::new(self.size.x * scale, self.size.y * scale);
+
+        for y in 0..result.size.y {
+            let source_row = ((y / scale) * self.size.x) as usize;
+            for x in 0..result.size.x {
+                result.data[(y * result.size.x + x) as usize] = self.data[source_row + (x / scale) as usize];
             }
         }
 
-        image_to_save.save(filename).unwrap();
+        result
     }
 }
 
+#[derive(Copy, Clone, Default, Debug)]
+pub struct WaveEffect {
+    pub amplitude: f64,
+    pub frequency: f64,
+    pub phase: f64
+}
+
+impl WaveEffect {
+    pub fn new(amplitude: f64, frequency: f64, phase: f64) -> Self {
+        WaveEffect { amplitude, frequency, phase }
+    }
+
+    pub fn offset(&self, line: u32) -> i32 {
+        (self.amplitude * (self.frequency * line as f64 + self.phase).sin()).round() as i32
+    }
+}
+
+// the screenshot flash fades out over this long, starting at this alpha
+const FLASH_SECONDS: f64 = 0.25;
+const FLASH_ALPHA: f64 = 192.0;
+
+// offscreen layer kept by name, shared with the scripts that draw into it
+struct EffectBuffer {
+    image: Rc<RefCell<Image>>,
+    // persistent buffers survive end_scene and stay until freed
+    persistent: bool
+}
+
 pub struct Graphics {
     frame_buffer: Image,
-    effect_buffers: HashMap<String, Image>,
+    effect_buffers: HashMap<String, EffectBuffer>,
+    wave: Option<WaveEffect>,
+    blend_mode: BlendMode,
+    draw_debugger: Option<DrawDebugger>,
+    debug_overlay: Option<DebugOverlay>,
+    // draws of the current frame, for the debug overlay
+    draw_count: u32,
+    // a screenshot was just taken
+    flash_started: Option<Instant>,
+    camera: Camera,
+    // draws are in world coordinates, until the end of the frame
+    camera_enabled: bool,
+    // palettes moving towards a target, advanced with the game time
+    palette_blends: Vec<PaletteBlend>,
+    // shared with script images so they can draw text too
+    game_font: Option<Rc<GameFont>>,
+    // rle frames decoded with the palettes they were drawn with
+    sprite_cache: SpriteCache,
     width: u32,
     height: u32
 }
 
 impl Graphics {
-    pub fn new(width: u32, height: u32) -> Result<Self, Box<dyn Error>> {
+    pub fn new(width: u32, height: u32) -> EngineResult<Self> {
         Ok(Self {
             frame_buffer: Image::new(width, height),
             effect_buffers: HashMap::new(),
+            wave: None,
+            blend_mode: BlendMode::Alpha,
+            draw_debugger: None,
+            debug_overlay: None,
+            draw_count: 0,
+            flash_started: None,
+            camera: Camera::new(width, height),
+            camera_enabled: false,
+            palette_blends: Vec::new(),
+            game_font: None,
+            sprite_cache: SpriteCache::new(),
             width,
             height
         })
     }
 
-    pub fn render_to(&self, frame_buffer: &mut [u8]) -> Result<(), Box<dyn Error>> {
+    pub fn set_game_font(&mut self, game_font: GameFont) {
+        self.game_font = Some(Rc::new(game_font));
+    }
+
+    pub fn frame_buffer(&self) -> &Image {
+        &self.frame_buffer
+    }
+
+    // a white flash which fades out, only on screen
+    pub fn flash(&mut self) {
+        self.flash_started = Some(Instant::now());
+    }
+
+    fn flash_alpha(&self) -> Option<u8> {
+        let elapsed = self.flash_started?.elapsed().as_secs_f64();
+        (elapsed < FLASH_SECONDS).then(|| ((1.0 - elapsed / FLASH_SECONDS) * FLASH_ALPHA) as u8)
+    }
+
+    // replaces a blend already running on the same palette, it goes on from the colors it reached
+    pub fn blend_palette(&mut self, palette: Rc<RefCell<Palette>>, target: Palette, duration: f64) {
+        self.palette_blends.retain(|blend| !blend.blends(&palette));
+        self.palette_blends.push(PaletteBlend::new(palette, target, duration));
+    }
+
+    pub fn stop_palette_blend(&mut self, palette: &Rc<RefCell<Palette>>) -> bool {
+        let count = self.palette_blends.len();
+        self.palette_blends.retain(|blend| !blend.blends(palette));
+        self.palette_blends.len() != count
+    }
+
+    pub fn is_palette_blending(&self, palette: &Rc<RefCell<Palette>>) -> bool {
+        self.palette_blends.iter().any(|blend| blend.blends(palette))
+    }
+
+    // once per update with the game delta, so blends stop while the game is paused
+    pub fn update_palette_blends(&mut self, delta: f64) {
+        self.palette_blends.retain_mut(|blend| !blend.advance(delta));
+    }
+
+    pub fn get_game_font(&self) -> Option<&GameFont> {
+        self.game_font.as_deref()
+    }
+
+    pub fn shared_game_font(&self) -> Option<Rc<GameFont>> {
+        self.game_font.clone()
+    }
+
+    // in frame buffer coordinates, the camera does not move it
+    pub fn capture(&self, x: i32, y: i32, width: u32, height: u32) -> Image {
+        self.frame_buffer.sub_image(x, y, width, height)
+    }
+
+    // the named buffer, made transparent when it does not exist yet or has a different size
+    pub fn effect_buffer(&mut self, name: &str, width: u32, height: u32) -> Rc<RefCell<Image>> {
+        let buffer = self.effect_buffers.entry(name.to_string()).or_insert_with(|| EffectBuffer {
+            image: Rc::new(RefCell::new(Image::new(width, height))),
+            persistent: false
+        });
+
+        let resized = {
+            let image = buffer.image.borrow();
+            image.size.x != width || image.size.y != height
+        };
+        if resized {
+            buffer.image = Rc::new(RefCell::new(Image::new(width, height)));
+        }
+
+        buffer.image.clone()
+    }
+
+    pub fn get_effect_buffer(&self, name: &str) -> Option<Rc<RefCell<Image>>> {
+        self.effect_buffers.get(name).map(|buffer| buffer.image.clone())
+    }
+
+    // keeps a buffer across scene changes, like a fog of war layer or a painted minimap
+    pub fn persist_effect_buffer(&mut self, name: &str) -> bool {
+        match self.effect_buffers.get_mut(name) {
+            Some(buffer) => {
+                buffer.persistent = true;
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn free_effect_buffer(&mut self, name: &str) -> bool {
+        self.effect_buffers.remove(name).is_some()
+    }
+
+    // drops every buffer of the scene that is left, persistent ones stay, and the sprites it decoded
+    pub fn end_scene(&mut self) {
+        self.effect_buffers.retain(|_, buffer| buffer.persistent);
+        self.sprite_cache.clear();
+    }
+
+    pub fn effect_buffer_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.effect_buffers.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    // memory held by all buffers, for keeping an eye on persistent ones
+    pub fn effect_buffer_bytes(&self) -> usize {
+        self.effect_buffers.values().map(|buffer| buffer.image.borrow().data.len() * 4).sum()
+    }
+
+    pub fn mosaic(&mut self, level: u32) {
+        self.frame_buffer.mosaic(level);
+    }
+
+    // default blend mode for translucent draws, draw_image_with_mode and fill_rect_with_mode pick one per call
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+    }
+
+    pub fn get_blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    pub fn set_wave(&mut self, amplitude: f64, frequency: f64, phase: f64) {
+        self.wave = Some(WaveEffect::new(amplitude, frequency, phase));
+    }
+
+    pub fn clear_wave(&mut self) {
+        self.wave = None;
+    }
+
+    pub fn get_wave(&self) -> Option<WaveEffect> {
+        self.wave
+    }
+
+    pub fn set_draw_debug(&mut self, enabled: bool) {
+        self.draw_debugger = if enabled {
+            Some(DrawDebugger::new(self.width, self.height))
+        } else {
+            None
+        };
+    }
+
+    pub fn is_draw_debug(&self) -> bool {
+        self.draw_debugger.is_some()
+    }
+
+    pub fn set_debug_overlay(&mut self, enabled: bool) {
+        self.debug_overlay = if enabled { Some(DebugOverlay::new()) } else { None };
+    }
+
+    pub fn is_debug_overlay(&self) -> bool {
+        self.debug_overlay.is_some()
+    }
+
+    // the cost of the frame just presented, shown from the next one on
+    pub fn record_frame(&mut self, sample: FrameSample) {
+        if let Some(debug_overlay) = self.debug_overlay.as_mut() {
+            debug_overlay.record(sample);
+        }
+    }
+
+    pub fn draw_count(&self) -> u32 {
+        self.draw_count
+    }
+
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    pub fn camera_mut(&mut self) -> &mut Camera {
+        &mut self.camera
+    }
+
+    // off again at the end of every frame, so the engine draws its own overlays in screen coordinates
+    pub fn set_camera_enabled(&mut self, enabled: bool) {
+        self.camera_enabled = enabled;
+    }
+
+    pub fn is_camera_enabled(&self) -> bool {
+        self.camera_enabled
+    }
+
+    // world to frame buffer coordinates while the camera is on
+    fn to_screen(&self, x: i32, y: i32) -> (i32, i32) {
+        if !self.camera_enabled {
+            return (x, y);
+        }
+
+        let (camera_x, camera_y) = self.camera.position();
+        (x - camera_x, y - camera_y)
+    }
+
+    fn record_draw(&mut self, name: &str, x: i32, y: i32, width: i32, height: i32) {
+        self.draw_count += 1;
+        if let Some(draw_debugger) = self.draw_debugger.as_mut() {
+            draw_debugger.record(name, DrawRect::new(x, y, width, height));
+        }
+    }
+
+    // key tells the frames apart in the sprite cache, like SpriteArchive::frame_key
+    pub fn blit(&mut self, key: usize, source: &RleImage, x: i32, y: i32, palette: &Palette) {
+        let (x, y) = self.to_screen(x, y);
+        self.record_draw("rle image", x + source.offset.x as i32, y + source.offset.y as i32, source.size.x as i32, source.size.y as i32);
+        self.sprite_cache.blit(&mut self.frame_buffer, key, source, x, y, palette);
+    }
+
+    // false for a frame the archive does not have
+    pub fn draw_sprite(&mut self, archive: &SpriteArchive, index: usize, x: i32, y: i32, palette: &Palette) -> bool {
+        match archive.frame(index) {
+            Some(frame) => {
+                self.blit(archive.frame_key(index), frame, x, y, palette);
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn cached_sprites(&self) -> usize {
+        self.sprite_cache.len()
+    }
+
+    pub fn blit_remapped(&mut self, source: &RleImage, x: i32, y: i32, palette: &Palette, remap: &PaletteRemap) {
+        let (x, y) = self.to_screen(x, y);
+        self.record_draw("rle image", x + source.offset.x as i32, y + source.offset.y as i32, source.size.x as i32, source.size.y as i32);
+        self.frame_buffer.blit_remapped(source, x, y, palette, remap);
+    }
+
+    pub fn draw_image(&mut self, source: &Image, x: i32, y: i32, alpha: f64) {
+        self.draw_image_with_mode(source, x, y, alpha, self.blend_mode);
+    }
+
+    pub fn draw_image_with_mode(&mut self, source: &Image, x: i32, y: i32, alpha: f64, mode: BlendMode) {
+        let (x, y) = self.to_screen(x, y);
+        self.record_draw("image", x, y, source.size.x as i32, source.size.y as i32);
+        self.frame_buffer.alpha_blit_with_mode(source, x, y, alpha, mode);
+    }
+
+    pub fn fill_rect(&mut self, x: i32, y: i32, width: i32, height: i32, color: &Color) {
+        self.fill_rect_with_mode(x, y, width, height, color, self.blend_mode);
+    }
+
+    pub fn fill_rect_with_mode(&mut self, x: i32, y: i32, width: i32, height: i32, color: &Color, mode: BlendMode) {
+        let (x, y) = self.to_screen(x, y);
+        self.record_draw("rect", x, y, width, height);
+        self.frame_buffer.fill_rect_with_mode(x, y, width, height, color, mode);
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn clear(&mut self, color: &Color) {
+        self.frame_buffer.clear_by_color(*color);
+    }
+
+    pub fn set_pixel(&mut self, x: i32, y: i32, color: &Color) {
+        let (x, y) = self.to_screen(x, y);
+        self.frame_buffer.set_pixel(x, y, color);
+    }
+
+    pub fn get_pixel(&self, x: i32, y: i32) -> Option<Color> {
+        let (x, y) = self.to_screen(x, y);
+        self.frame_buffer.get_pixel(x, y)
+    }
+
+    pub fn draw_rect(&mut self, x: i32, y: i32, width: i32, height: i32, color: &Color) {
+        let (x, y) = self.to_screen(x, y);
+        self.record_draw("rect outline", x, y, width, height);
+        self.frame_buffer.draw_rect(x, y, width, height, color);
+    }
+
+    pub fn draw_panel(&mut self, x: i32, y: i32, width: i32, height: i32, style: &PanelStyle) {
+        let (x, y) = self.to_screen(x, y);
+        self.record_draw("panel", x, y, width, height);
+        draw_panel(&mut self.frame_buffer, x, y, width, height, style);
+    }
+
+    // icons in a row from x, like the status effects next to a hp bar, the width they took is returned
+    pub fn draw_icons(&mut self, archive: &SpriteArchive, icons: &[usize], x: i32, y: i32, spacing: i32, palette: &Palette) -> i32 {
+        let mut offset = 0;
+
+        for &icon in icons {
+            let width = match archive.frame(icon) {
+                Some(frame) => frame.size.x as i32,
+                None => continue
+            };
+
+            self.draw_sprite(archive, icon, x + offset, y, palette);
+            offset += width + spacing;
+        }
 
-        self.frame_buffer.copy_to(frame_buffer);
+        (offset - spacing).max(0)
+    }
+
+    pub fn fill_gradient_rect(&mut self, x: i32, y: i32, width: i32, height: i32, from: &Color, to: &Color, direction: GradientDirection) {
+        let (x, y) = self.to_screen(x, y);
+        self.record_draw("gradient", x, y, width, height);
+        self.frame_buffer.fill_gradient_rect(x, y, width, height, from, to, direction);
+    }
+
+    pub fn fill_palette_ramp_rect(&mut self, x: i32, y: i32, width: i32, height: i32, palette: &Palette, start_index: u8, end_index: u8, direction: GradientDirection) {
+        let (x, y) = self.to_screen(x, y);
+        self.record_draw("gradient", x, y, width, height);
+        self.frame_buffer.fill_palette_ramp_rect(x, y, width, height, palette, start_index, end_index, direction);
+    }
+
+    // width and height of text in the game font, None before a font was found
+    pub fn text_size(&self, text: &[usize]) -> Option<(i32, i32)> {
+        self.game_font.as_ref().map(|game_font| (game_font.get_width(text), game_font.get_text_height(text)))
+    }
+
+    // returns false when there is no game font to draw with
+    pub fn draw_text(&mut self, text: &[usize], x: i32, y: i32, width: i32, align: TextAlign, direction: TextDirection, color: &Color) -> bool {
+        let (x, y) = self.to_screen(x, y);
+        let game_font = match self.game_font.as_ref() {
+            Some(game_font) => game_font,
+            None => return false
+        };
+
+        let (text_width, text_height) = (game_font.get_width(text), game_font.get_text_height(text));
+        self.draw_count += 1;
+        if let Some(draw_debugger) = self.draw_debugger.as_mut() {
+            draw_debugger.record("text", DrawRect::new(x, y, text_width.max(width), text_height));
+        }
+
+        match direction {
+            TextDirection::Horizontal => self.frame_buffer.draw_game_text_aligned(text, x, y, width, align, game_font, color),
+            TextDirection::Vertical => self.frame_buffer.draw_game_text_vertical(text, x, y, game_font, color)
+        }
+
+        true
+    }
+
+    pub fn draw_text_center(&mut self, text: &[usize], x: i32, y: i32, width: i32, height: i32, color: &Color) -> bool {
+        let (x, y) = self.to_screen(x, y);
+        let game_font = match self.game_font.as_ref() {
+            Some(game_font) => game_font,
+            None => return false
+        };
+
+        self.draw_count += 1;
+        if let Some(draw_debugger) = self.draw_debugger.as_mut() {
+            draw_debugger.record("text", DrawRect::new(x, y, width, height));
+        }
+
+        self.frame_buffer.draw_game_text_center(text, x, y, width, height, game_font, color);
+        true
+    }
+
+    // called once the frame has been presented
+    pub fn end_frame(&mut self) {
+        self.draw_count = 0;
+        self.camera_enabled = false;
+        if let Some(draw_debugger) = self.draw_debugger.as_mut() {
+            draw_debugger.reset();
+        }
+    }
+
+    // what render_to would present, as an image
+    pub fn render_image(&self) -> EngineResult<Image> {
+        let mut frame_buffer = vec![0; self.width as usize * self.height as usize * 4];
+        self.render_to(&mut frame_buffer)?;
+
+        let mut image = Image::new(self.width, self.height);
+        for (color, pixel) in image.data.iter_mut().zip(frame_buffer.chunks_exact(4)) {
+            *color = Color::new(pixel[0], pixel[1], pixel[2], pixel[3]);
+        }
+
+        Ok(image)
+    }
+
+    pub fn render_to(&self, frame_buffer: &mut [u8]) -> EngineResult<()> {
+
+        let flash_alpha = self.flash_alpha();
+        let overlay_image = (self.draw_debugger.is_some() || self.debug_overlay.is_some() || flash_alpha.is_some()).then(|| {
+            let mut image = self.frame_buffer.clone();
+            if let Some(draw_debugger) = self.draw_debugger.as_ref() {
+                draw_debugger.render_overlay(&mut image);
+            }
+            if let Some(debug_overlay) = self.debug_overlay.as_ref() {
+                debug_overlay.draw(&mut image, self.game_font.as_deref());
+            }
+            if let Some(alpha) = flash_alpha {
+                image.fill_rect_with_mode(0, 0, self.width as i32, self.height as i32, &Color::new(255, 255, 255, alpha), BlendMode::Alpha);
+            }
+            image
+        });
+
+        let image = overlay_image.as_ref().unwrap_or(&self.frame_buffer);
+
+        match self.wave {
+            Some(wave) => Self::render_wave_to(image, frame_buffer, &wave),
+            None => image.copy_to(frame_buffer)
+        }
 
         Ok(())
     }
+
+    // shift every scanline horizontally, the edge pixel is repeated to fill the gap
+    fn render_wave_to(image: &Image, frame_buffer: &mut [u8], wave: &WaveEffect) {
+        let width = image.size.x as i32;
+
+        for y in 0..image.size.y {
+            let offset = wave.offset(y);
+            let line_start = (y * image.size.x) as usize;
+
+            for x in 0..width {
+                let source_x = (x - offset).clamp(0, width - 1);
+                let color = &image.data[line_start + source_x as usize];
+                let index = (line_start + x as usize) * 4;
+
+                frame_buffer[index] = color.r;
+                frame_buffer[index + 1] = color.g;
+                frame_buffer[index + 2] = color.b;
+                frame_buffer[index + 3] = color.a;
+            }
+        }
+    }
 }
\ No newline at end of file
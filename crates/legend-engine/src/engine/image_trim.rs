@@ -0,0 +1,90 @@
+use crate::engine::error::EngineResult;
+use crate::engine::graphics::{Color, Image, Vector2};
+
+// the part of an image that draws anything
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ContentBounds {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32
+}
+
+// an image cut down to its content, offset is where it sat in the original like RleImage.offset,
+// so drawing it at the offset puts every pixel where the untrimmed one had it
+pub struct TrimmedImage {
+    pub image: Image,
+    pub offset: Vector2<i16>
+}
+
+// sprites drawn in modern tools often keep a background instead of alpha, magenta or the top left pixel.
+// every pixel within tolerance of the key on each channel turns transparent, returns how many did
+pub fn apply_color_key(image: &mut Image, key: &Color, tolerance: u8) -> usize {
+    let close = |a: u8, b: u8| a.abs_diff(b) <= tolerance;
+    let mut count = 0;
+
+    for pixel in image.data.iter_mut() {
+        if pixel.a > 0 && close(pixel.r, key.r) && close(pixel.g, key.g) && close(pixel.b, key.b) {
+            *pixel = Color::new(0, 0, 0, 0);
+            count += 1;
+        }
+    }
+
+    count
+}
+
+// the color of the top left pixel, the usual background of a sprite sheet
+pub fn corner_color(image: &Image) -> Option<Color> {
+    image.get_pixel(0, 0)
+}
+
+// None when every pixel is transparent
+pub fn content_bounds(image: &Image) -> Option<ContentBounds> {
+    let width = image.size.x as usize;
+    if width == 0 {
+        return None;
+    }
+
+    let (mut left, mut top, mut right, mut bottom) = (usize::MAX, usize::MAX, 0, 0);
+    for (y, line) in image.data.chunks_exact(width).enumerate() {
+        let first = match line.iter().position(|pixel| pixel.a > 0) {
+            Some(first) => first,
+            None => continue
+        };
+        let last = line.iter().rposition(|pixel| pixel.a > 0).unwrap_or(first);
+
+        left = left.min(first);
+        right = right.max(last);
+        top = top.min(y);
+        bottom = y;
+    }
+
+    (top != usize::MAX).then(|| ContentBounds {
+        x: left as u32,
+        y: top as u32,
+        width: (right - left + 1) as u32,
+        height: (bottom - top + 1) as u32
+    })
+}
+
+// an image without content trims to nothing at offset 0, 0
+pub fn trim(image: &Image) -> TrimmedImage {
+    match content_bounds(image) {
+        Some(bounds) => TrimmedImage {
+            image: image.sub_image(bounds.x as i32, bounds.y as i32, bounds.width, bounds.height),
+            offset: Vector2::new(bounds.x as i16, bounds.y as i16)
+        },
+        None => TrimmedImage { image: Image::new(0, 0), offset: Vector2::new(0, 0) }
+    }
+}
+
+// a png keyed and trimmed in one go, the key is taken from the top left pixel when none is given
+pub fn load_keyed(filename: &str, key: Option<Color>, tolerance: u8) -> EngineResult<TrimmedImage> {
+    let mut image = Image::load(filename)?;
+
+    if let Some(key) = key.or_else(|| corner_color(&image)) {
+        apply_color_key(&mut image, &key, tolerance);
+    }
+
+    Ok(trim(&image))
+}
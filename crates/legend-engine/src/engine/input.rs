@@ -0,0 +1,391 @@
+use std::collections::{HashMap, HashSet};
+use crate::engine::action_map::ActionMap;
+use crate::engine::input_replay::InputReplay;
+use crate::engine::pointer::Pointer;
+
+// the context every key goes to when nothing above it captures the key
+pub const GAMEPLAY_CONTEXT: &str = "gameplay";
+
+const MENU_KEYS: [&str; 7] = ["up", "down", "left", "right", "enter", "escape", "space"];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KeyAction {
+    // sent while held, after the repeat delay and then every repeat interval
+    Repeat,
+    // pressed again shortly after the last press, the key counts as double tapped until released
+    DoubleTap,
+    // held longer than the hold time, once per press
+    Hold
+}
+
+impl KeyAction {
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyAction::Repeat => "repeat",
+            KeyAction::DoubleTap => "double_tap",
+            KeyAction::Hold => "hold"
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum KeyEvent {
+    Down { key: String, context: String },
+    Up { key: String, context: String },
+    Action { action: KeyAction, key: String, context: String },
+    // the key pressed while waiting for a rebind, it is bound to the action and goes nowhere else
+    Rebound { action: String, key: String }
+}
+
+// in seconds of game time, a repeat interval of 0 turns repeating off
+#[derive(Clone, Copy, Debug)]
+pub struct InputTiming {
+    pub repeat_delay: f64,
+    pub repeat_interval: f64,
+    pub double_tap_time: f64,
+    pub hold_time: f64
+}
+
+impl Default for InputTiming {
+    fn default() -> Self {
+        InputTiming { repeat_delay: 0.4, repeat_interval: 0.08, double_tap_time: 0.25, hold_time: 0.5 }
+    }
+}
+
+#[derive(Default)]
+struct KeyTimer {
+    held_for: f64,
+    next_repeat: f64,
+    double_tapped: bool,
+    hold_sent: bool,
+    // the press went to a rebind, so its release and repeats are not sent either
+    rebound: bool
+}
+
+#[derive(Clone, Debug)]
+enum Capture {
+    Keys(HashSet<String>),
+    All
+}
+
+// a layer of the input stack, keys it captures never reach the contexts below it
+#[derive(Clone, Debug)]
+pub struct InputContext {
+    pub name: String,
+    capture: Capture
+}
+
+impl InputContext {
+    pub fn new(name: &str, keys: &[&str]) -> Self {
+        InputContext { name: name.to_string(), capture: Capture::Keys(keys.iter().map(|key| key.to_string()).collect()) }
+    }
+
+    pub fn capture_all(name: &str) -> Self {
+        InputContext { name: name.to_string(), capture: Capture::All }
+    }
+
+    // takes the movement and confirm keys, so the party stands still behind an open menu
+    pub fn menu() -> Self {
+        Self::new("menu", &MENU_KEYS)
+    }
+
+    // typing a name must not trigger anything else
+    pub fn text() -> Self {
+        Self::capture_all("text")
+    }
+
+    // "menu" and "text" are the known ones, anything else captures nothing until given keys
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "menu" => Self::menu(),
+            "text" => Self::text(),
+            _ => Self::new(name, &[])
+        }
+    }
+
+    pub fn captures(&self, key: &str) -> bool {
+        match &self.capture {
+            Capture::Keys(keys) => keys.contains(key),
+            Capture::All => true
+        }
+    }
+}
+
+// keys are named like "left", "enter" or "a", whatever device they come from
+#[derive(Default)]
+pub struct Input {
+    held: HashMap<String, KeyTimer>,
+    pub timing: InputTiming,
+    pub actions: ActionMap,
+    // the action the next pressed key gets bound to
+    rebinding: Option<String>,
+    // game time of the last press of every key, for telling double taps apart
+    last_press: HashMap<String, f64>,
+    time: f64,
+    // updates done so far, replays are timed by it
+    frame: u64,
+    recording: Option<InputReplay>,
+    // collected between frames, handed to the script callbacks at the start of the next one
+    events: Vec<KeyEvent>,
+    // pushed on top of gameplay, the last one is checked first
+    contexts: Vec<InputContext>,
+    // gamepad buttons by their gamepad::BUTTONS name, what keys they press is up to the mapping
+    buttons: HashSet<String>,
+    // "left_x", "left_y", "right_x" and "right_y" from -1 to 1, down is positive
+    axes: HashMap<String, f32>,
+    pub gamepad_connected: bool,
+    // the mouse, or the right stick standing in for it. its buttons are the keys mouse_left, mouse_right and mouse_middle
+    pub pointer: Pointer
+}
+
+impl Input {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // the topmost context capturing the key
+    pub fn owner(&self, key: &str) -> &str {
+        self.contexts.iter().rev()
+            .find(|context| context.captures(key))
+            .map(|context| context.name.as_str())
+            .unwrap_or(GAMEPLAY_CONTEXT)
+    }
+
+    fn key_event(&self, key: &str, down: bool) -> KeyEvent {
+        let (key, context) = (key.to_string(), self.owner(key).to_string());
+        if down { KeyEvent::Down { key, context } } else { KeyEvent::Up { key, context } }
+    }
+
+    fn action_event(&self, action: KeyAction, key: &str) -> KeyEvent {
+        KeyEvent::Action { action, key: key.to_string(), context: self.owner(key).to_string() }
+    }
+
+    // held keys move to their new owner, the old one sees them go up so nothing keeps walking
+    fn change_contexts(&mut self, change: impl FnOnce(&mut Vec<InputContext>)) {
+        let owners: Vec<(String, String)> = self.held_keys().into_iter().map(|key| {
+            let owner = self.owner(&key).to_string();
+            (key, owner)
+        }).collect();
+
+        change(&mut self.contexts);
+
+        for (key, owner) in owners {
+            if self.owner(&key) != owner {
+                self.events.push(KeyEvent::Up { key, context: owner });
+            }
+        }
+    }
+
+    pub fn push_context(&mut self, context: InputContext) {
+        self.change_contexts(|contexts| contexts.push(context));
+    }
+
+    // removes the topmost context with that name, dialogs may close out of order
+    pub fn pop_context(&mut self, name: &str) -> bool {
+        let index = match self.contexts.iter().rposition(|context| context.name == name) {
+            Some(index) => index,
+            None => return false
+        };
+
+        self.change_contexts(|contexts| { contexts.remove(index); });
+        true
+    }
+
+    pub fn clear_contexts(&mut self) {
+        self.change_contexts(|contexts| contexts.clear());
+    }
+
+    pub fn context_names(&self) -> Vec<String> {
+        std::iter::once(GAMEPLAY_CONTEXT.to_string())
+            .chain(self.contexts.iter().map(|context| context.name.clone()))
+            .collect()
+    }
+
+    // the system repeats held keys as well, those are left out for the repeat timing below
+    pub fn press(&mut self, key: &str) {
+        if self.held.contains_key(key) {
+            return;
+        }
+
+        if let Some(recording) = self.recording.as_mut() {
+            recording.record(self.frame, key, true);
+        }
+
+        if let Some(action) = self.rebinding.take() {
+            self.actions.bind(&action, vec![ key.to_string() ]);
+            self.held.insert(key.to_string(), KeyTimer { rebound: true, ..KeyTimer::default() });
+            self.events.push(KeyEvent::Rebound { action, key: key.to_string() });
+            return;
+        }
+
+        let double_tapped = self.last_press.get(key).map(|last| self.time - last <= self.timing.double_tap_time).unwrap_or(false);
+        self.last_press.insert(key.to_string(), self.time);
+        self.held.insert(key.to_string(), KeyTimer { next_repeat: self.timing.repeat_delay, double_tapped, ..KeyTimer::default() });
+
+        let event = self.key_event(key, true);
+        self.events.push(event);
+
+        if double_tapped {
+            let event = self.action_event(KeyAction::DoubleTap, key);
+            self.events.push(event);
+        }
+    }
+
+    pub fn release(&mut self, key: &str) {
+        let timer = match self.held.remove(key) {
+            Some(timer) => timer,
+            None => return
+        };
+
+        if let Some(recording) = self.recording.as_mut() {
+            recording.record(self.frame, key, false);
+        }
+
+        if !timer.rebound {
+            let event = self.key_event(key, false);
+            self.events.push(event);
+        }
+    }
+
+    // advances the hold timers once per update, so repeats follow game time and not the system setting
+    pub fn update(&mut self, delta: f64) {
+        self.time += delta;
+        self.frame += 1;
+        self.pointer.update_stick(self.axis("right_x"), self.axis("right_y"), delta);
+
+        let timing = self.timing;
+        let mut actions = Vec::new();
+
+        for key in self.held_keys() {
+            let timer = match self.held.get_mut(&key) {
+                Some(timer) => timer,
+                None => continue
+            };
+            timer.held_for += delta;
+            if timer.rebound {
+                continue;
+            }
+
+            if !timer.hold_sent && timer.held_for >= timing.hold_time {
+                timer.hold_sent = true;
+                actions.push((KeyAction::Hold, key.clone()));
+            }
+
+            if timing.repeat_interval > 0.0 {
+                while timer.held_for >= timer.next_repeat {
+                    timer.next_repeat += timing.repeat_interval;
+                    actions.push((KeyAction::Repeat, key.clone()));
+                }
+            }
+        }
+
+        for (action, key) in actions {
+            let event = self.action_event(action, &key);
+            self.events.push(event);
+        }
+    }
+
+    // any key bound to the action is down
+    pub fn is_action_down(&self, action: &str) -> bool {
+        self.actions.keys(action).iter().any(|key| self.is_down(key))
+    }
+
+    pub fn start_rebind(&mut self, action: &str) {
+        self.rebinding = Some(action.to_string());
+    }
+
+    pub fn cancel_rebind(&mut self) {
+        self.rebinding = None;
+    }
+
+    pub fn rebinding(&self) -> Option<&str> {
+        self.rebinding.as_deref()
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    // every key change from now on is kept, including gamepad buttons through their keys
+    pub fn start_recording(&mut self) {
+        self.recording = Some(InputReplay::new());
+    }
+
+    pub fn take_recording(&mut self) -> Option<InputReplay> {
+        self.recording.take()
+    }
+
+    // seconds the key is down, 0 when it is not
+    pub fn held_time(&self, key: &str) -> f64 {
+        self.held.get(key).map(|timer| timer.held_for).unwrap_or(0.0)
+    }
+
+    // down longer than the hold time, like running while the direction is kept down
+    pub fn is_held(&self, key: &str) -> bool {
+        self.held_time(key) >= self.timing.hold_time
+    }
+
+    // down since a double tap, like dashing until the direction is let go
+    pub fn is_double_tapped(&self, key: &str) -> bool {
+        self.held.get(key).map(|timer| timer.double_tapped).unwrap_or(false)
+    }
+
+    // losing focus never delivers the key ups, so everything held is let go
+    pub fn release_all(&mut self) {
+        for key in self.held_keys() {
+            self.release(&key);
+        }
+    }
+
+    // held no matter which context has it
+    pub fn is_down(&self, key: &str) -> bool {
+        self.held.get(key).map(|timer| !timer.rebound).unwrap_or(false)
+    }
+
+    // held and not captured by a context above this one
+    pub fn is_down_in(&self, context: &str, key: &str) -> bool {
+        self.is_down(key) && self.owner(key) == context
+    }
+
+    pub fn held_keys(&self) -> Vec<String> {
+        let mut held: Vec<String> = self.held.keys().cloned().collect();
+        held.sort();
+        held
+    }
+
+    pub fn take_events(&mut self) -> Vec<KeyEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn set_button(&mut self, button: &str, down: bool) {
+        if down {
+            self.buttons.insert(button.to_string());
+        } else {
+            self.buttons.remove(button);
+        }
+    }
+
+    pub fn is_button_down(&self, button: &str) -> bool {
+        self.buttons.contains(button)
+    }
+
+    pub fn held_buttons(&self) -> Vec<String> {
+        let mut held: Vec<String> = self.buttons.iter().cloned().collect();
+        held.sort();
+        held
+    }
+
+    pub fn set_axis(&mut self, axis: &str, value: f32) {
+        self.axes.insert(axis.to_string(), value.clamp(-1.0, 1.0));
+    }
+
+    pub fn axis(&self, axis: &str) -> f32 {
+        self.axes.get(axis).copied().unwrap_or(0.0)
+    }
+
+    // an unplugged pad leaves nothing pressed
+    pub fn clear_gamepad(&mut self) {
+        self.buttons.clear();
+        self.axes.clear();
+    }
+}
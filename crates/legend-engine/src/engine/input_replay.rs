@@ -0,0 +1,93 @@
+use std::fs;
+use crate::engine::atomic_file::write_atomic;
+use crate::engine::error::{EngineError, EngineResult};
+use crate::engine::input::Input;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ReplayEvent {
+    // updates done before the key changed
+    pub frame: u64,
+    pub key: String,
+    pub down: bool
+}
+
+// key changes by update count, played back they give the same game on every run
+// 12 down left
+// 40 up left
+#[derive(Clone, Debug, Default)]
+pub struct InputReplay {
+    events: Vec<ReplayEvent>,
+    next: usize
+}
+
+impl InputReplay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_text(source: &str) -> EngineResult<Self> {
+        let mut replay = Self::new();
+
+        for (index, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let invalid = || EngineError::Input(format!("replay line {} is not \"frame down|up key\": {}", index + 1, line));
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let (frame, change, key) = match parts.as_slice() {
+                [frame, change, key] => (frame.parse::<u64>().map_err(|_| invalid())?, *change, *key),
+                _ => return Err(invalid())
+            };
+
+            let down = match change {
+                "down" => true,
+                "up" => false,
+                _ => return Err(invalid())
+            };
+
+            replay.record(frame, key, down);
+        }
+
+        replay.events.sort_by_key(|event| event.frame);
+        Ok(replay)
+    }
+
+    pub fn load(filename: &str) -> EngineResult<Self> {
+        Self::from_text(&fs::read_to_string(filename)?)
+    }
+
+    pub fn save(&self, filename: &str) -> EngineResult<()> {
+        let lines: String = self.events.iter()
+            .map(|event| format!("{} {} {}\n", event.frame, if event.down { "down" } else { "up" }, event.key))
+            .collect();
+
+        write_atomic(filename, lines)?;
+        Ok(())
+    }
+
+    pub fn record(&mut self, frame: u64, key: &str, down: bool) {
+        self.events.push(ReplayEvent { frame, key: key.to_string(), down });
+    }
+
+    // presses and releases everything due up to the updates the input has done so far
+    pub fn apply(&mut self, input: &mut Input) {
+        while let Some(event) = self.events.get(self.next) {
+            if event.frame > input.frame() {
+                break;
+            }
+
+            if event.down {
+                input.press(&event.key);
+            } else {
+                input.release(&event.key);
+            }
+            self.next += 1;
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.events.len()
+    }
+}
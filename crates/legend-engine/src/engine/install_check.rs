@@ -0,0 +1,123 @@
+use std::fs;
+use std::path::Path;
+use serde::Deserialize;
+use crate::engine::error::EngineResult;
+use crate::engine::graphics::FONT_CANDIDATES;
+use crate::engine::vfs::Vfs;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct RequiredFile {
+    pub name: String,
+    // the same file in other releases of the game, any one of them will do
+    #[serde(default)]
+    pub alternatives: Vec<String>,
+    // bytes the file has when it is name itself, alternatives are not checked
+    #[serde(default)]
+    pub size: Option<u64>
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum FileStatus {
+    // the name it was found as
+    Found(String),
+    Missing,
+    WrongSize { expected: u64, actual: u64 }
+}
+
+#[derive(Deserialize)]
+struct RequiredFilesFile {
+    #[serde(default)]
+    file: Vec<RequiredFile>
+}
+
+// what the game data has to contain before anything is loaded
+// [[file]]
+// name = "ENGLISH.FNT"
+// alternatives = ["ASC16", "ASC16.FNT"]
+// size = 4096
+pub struct RequiredFiles {
+    files: Vec<RequiredFile>
+}
+
+impl Default for RequiredFiles {
+    // the fonts, without them not even an error can be shown in the window
+    fn default() -> Self {
+        let english = FONT_CANDIDATES.iter().map(|(_, english, _)| english.to_string()).collect::<Vec<String>>();
+        let chinese = FONT_CANDIDATES.iter().map(|(_, _, chinese)| chinese.to_string()).collect::<Vec<String>>();
+
+        RequiredFiles { files: vec![
+            RequiredFile { name: english[0].clone(), alternatives: english[1..].to_vec(), size: None },
+            RequiredFile { name: chinese[0].clone(), alternatives: chinese[1..].to_vec(), size: None }
+        ] }
+    }
+}
+
+impl RequiredFiles {
+    pub fn from_toml(source: &str) -> EngineResult<Self> {
+        let file: RequiredFilesFile = toml::from_str(source)?;
+        Ok(RequiredFiles { files: file.file })
+    }
+
+    // without the file only the fonts are required
+    pub fn load(filename: &Path) -> EngineResult<Self> {
+        if !filename.is_file() {
+            return Ok(Self::default());
+        }
+
+        Self::from_toml(&fs::read_to_string(filename)?)
+    }
+
+    pub fn check(&self, vfs: &Vfs) -> EngineResult<InstallReport> {
+        let mut entries = Vec::new();
+
+        for file in self.files.iter() {
+            let mut status = FileStatus::Missing;
+
+            for name in std::iter::once(&file.name).chain(file.alternatives.iter()) {
+                if let Some(actual) = vfs.size(name)? {
+                    status = match file.size {
+                        Some(expected) if name == &file.name && expected != actual => FileStatus::WrongSize { expected, actual },
+                        _ => FileStatus::Found(name.clone())
+                    };
+                    break;
+                }
+            }
+
+            entries.push((file.clone(), status));
+        }
+
+        Ok(InstallReport { data_path: vfs.describe(), entries })
+    }
+}
+
+// every required file and how it was found, printed at startup when something is off
+pub struct InstallReport {
+    pub data_path: String,
+    pub entries: Vec<(RequiredFile, FileStatus)>
+}
+
+impl InstallReport {
+    pub fn is_complete(&self) -> bool {
+        self.entries.iter().all(|(_, status)| matches!(status, FileStatus::Found(_)))
+    }
+
+    pub fn problem_count(&self) -> usize {
+        self.entries.iter().filter(|(_, status)| !matches!(status, FileStatus::Found(_))).count()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![ format!("game data in {}:", self.data_path) ];
+
+        for (file, status) in self.entries.iter() {
+            lines.push(match status {
+                FileStatus::Found(name) => format!("  found    {}", name),
+                FileStatus::Missing if file.alternatives.is_empty() => format!("  missing  {}", file.name),
+                FileStatus::Missing => format!("  missing  {} (or {})", file.name, file.alternatives.join(", ")),
+                FileStatus::WrongSize { expected, actual } => format!("  size     {} is {} bytes, {} expected", file.name, actual, expected)
+            });
+        }
+
+        lines.push(format!("{} of {} files found, {} problems", self.entries.len() - self.problem_count(), self.entries.len(), self.problem_count()));
+        lines
+    }
+}
@@ -0,0 +1,45 @@
+use std::collections::BTreeMap;
+
+// item id to count, kept sorted so menus list items in a stable order
+#[derive(Clone, Debug, Default)]
+pub struct Inventory {
+    items: BTreeMap<u32, u32>
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Inventory { items: BTreeMap::new() }
+    }
+
+    pub fn add(&mut self, item: u32, count: u32) {
+        if count == 0 {
+            return;
+        }
+
+        *self.items.entry(item).or_insert(0) += count;
+    }
+
+    // returns false without changing anything when there are not enough
+    pub fn remove(&mut self, item: u32, count: u32) -> bool {
+        let current = self.count(item);
+        if current < count {
+            return false;
+        }
+
+        if current == count {
+            self.items.remove(&item);
+        } else {
+            self.items.insert(item, current - count);
+        }
+
+        true
+    }
+
+    pub fn count(&self, item: u32) -> u32 {
+        self.items.get(&item).copied().unwrap_or(0)
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.items.iter().map(|(&item, &count)| (item, count))
+    }
+}
@@ -0,0 +1,155 @@
+use std::fs;
+use serde::Deserialize;
+use crate::engine::error::EngineResult;
+use crate::engine::stats::Stats;
+
+// total experience needed to reach level index + 2, the first entry is for level 2
+#[derive(Deserialize, Clone, Debug)]
+pub struct ExperienceCurve {
+    pub thresholds: Vec<u32>
+}
+
+impl ExperienceCurve {
+    // a raw table of little endian u16 values, as dumped from the game data
+    pub fn from_le_u16(data: &[u8]) -> Self {
+        let thresholds = data.chunks_exact(2).map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]) as u32).collect();
+        ExperienceCurve { thresholds }
+    }
+
+    pub fn max_level(&self) -> u32 {
+        self.thresholds.len() as u32 + 1
+    }
+
+    // None at the max level
+    pub fn required_for(&self, level: u32) -> Option<u32> {
+        if level < 2 {
+            return Some(0);
+        }
+
+        self.thresholds.get(level as usize - 2).copied()
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct GrowthTable {
+    pub curve: ExperienceCurve,
+    // stats added on every level up
+    pub growth: Stats
+}
+
+impl GrowthTable {
+    // [curve]
+    // thresholds = [50, 150, 300]
+    // [growth]
+    // max_hp = 8
+    // attack = 2
+    pub fn from_toml(source: &str) -> EngineResult<Self> {
+        Ok(toml::from_str(source)?)
+    }
+
+    pub fn load(filename: &str) -> EngineResult<Self> {
+        Self::from_toml(&fs::read_to_string(filename)?)
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct LevelUp {
+    pub level: u32,
+    pub gained: Stats
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Progression {
+    pub level: u32,
+    pub experience: u32
+}
+
+impl Progression {
+    pub fn new(level: u32, experience: u32) -> Self {
+        Progression { level: level.max(1), experience }
+    }
+
+    pub fn experience_to_next(&self, curve: &ExperienceCurve) -> Option<u32> {
+        curve.required_for(self.level + 1).map(|required| required.saturating_sub(self.experience))
+    }
+
+    // applies every level gained to stats, the returned level ups drive the notification
+    pub fn gain_experience(&mut self, amount: u32, table: &GrowthTable, stats: &mut Stats) -> Vec<LevelUp> {
+        self.experience = self.experience.saturating_add(amount);
+
+        let mut level_ups = Vec::new();
+
+        while let Some(required) = table.curve.required_for(self.level + 1) {
+            if self.experience < required {
+                break;
+            }
+
+            self.level += 1;
+
+            *stats = stats.add(&table.growth);
+            // growth may add current hp and mp, but never past the new maximum
+            stats.hp = stats.hp.min(stats.max_hp);
+            stats.mp = stats.mp.min(stats.max_mp);
+
+            level_ups.push(LevelUp { level: self.level, gained: table.growth });
+        }
+
+        level_ups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> GrowthTable {
+        GrowthTable::from_toml("[curve]\nthresholds = [50, 150, 300]\n[growth]\nhp = 20\nmax_hp = 8\nattack = 2\n").unwrap()
+    }
+
+    fn stats() -> Stats {
+        Stats { hp: 30, max_hp: 30, attack: 5, ..Stats::default() }
+    }
+
+    #[test]
+    fn curve_lookup() {
+        let curve = ExperienceCurve::from_le_u16(&[50, 0, 150, 0, 44, 1]);
+
+        assert_eq!(curve.thresholds, vec![50, 150, 300]);
+        assert_eq!(curve.max_level(), 4);
+        assert_eq!(curve.required_for(1), Some(0));
+        assert_eq!(curve.required_for(2), Some(50));
+        assert_eq!(curve.required_for(4), Some(300));
+        assert_eq!(curve.required_for(5), None);
+    }
+
+    #[test]
+    fn gains_several_levels_at_once() {
+        let (table, mut stats) = (table(), stats());
+        let mut progression = Progression::new(1, 0);
+
+        let level_ups = progression.gain_experience(160, &table, &mut stats);
+
+        assert_eq!(level_ups.iter().map(|level_up| level_up.level).collect::<Vec<_>>(), vec![2, 3]);
+        assert_eq!(progression.level, 3);
+        assert_eq!(progression.experience, 160);
+        assert_eq!(stats.max_hp, 46);
+        assert_eq!(stats.attack, 9);
+        // growth adds 20 hp twice, capped by the new maximum
+        assert_eq!(stats.hp, 46);
+        assert_eq!(progression.experience_to_next(&table.curve), Some(140));
+    }
+
+    #[test]
+    fn stops_at_the_max_level() {
+        let (table, mut stats) = (table(), stats());
+        let mut progression = Progression::new(1, 0);
+
+        assert_eq!(progression.gain_experience(10_000, &table, &mut stats).len(), 3);
+        assert_eq!(progression.level, table.curve.max_level());
+        assert_eq!(progression.experience_to_next(&table.curve), None);
+
+        assert!(progression.gain_experience(10_000, &table, &mut stats).is_empty());
+        assert_eq!(progression.level, 4);
+        assert_eq!(stats.max_hp, 54);
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use serde::Deserialize;
+use crate::engine::background_load::BackgroundLoad;
+use crate::engine::error::{EngineError, EngineResult};
+use crate::engine::vfs::Vfs;
+
+// files read ahead each update while a prefetch is running, small enough to never drop a frame
+pub const PREFETCH_FILES_PER_UPDATE: usize = 4;
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ResourceKind {
+    Sprite,
+    Sound,
+    Music,
+    Map
+}
+
+impl ResourceKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ResourceKind::Sprite => "sprite",
+            ResourceKind::Sound => "sound",
+            ResourceKind::Music => "music",
+            ResourceKind::Map => "map"
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct Resource {
+    pub kind: ResourceKind,
+    // a game data file name, read through the vfs
+    pub name: String
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.kind.name(), self.name)
+    }
+}
+
+// what one scene needs, scenes it depends on are needed as well
+// depends = ["common"]
+// sprites = ["SPRITES/HERO.RLE"]
+// sounds = ["SOUND/DOOR.WAV"]
+// music = ["MUSIC/TOWN.WAV"]
+// maps = ["MAPS/TOWN.MAP"]
+#[derive(Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct SceneManifest {
+    pub depends: Vec<String>,
+    pub sprites: Vec<String>,
+    pub sounds: Vec<String>,
+    pub music: Vec<String>,
+    pub maps: Vec<String>
+}
+
+impl SceneManifest {
+    pub fn resources(&self) -> impl Iterator<Item = Resource> + '_ {
+        let kinds = [
+            (ResourceKind::Sprite, &self.sprites),
+            (ResourceKind::Sound, &self.sounds),
+            (ResourceKind::Music, &self.music),
+            (ResourceKind::Map, &self.maps)
+        ];
+
+        kinds.into_iter().flat_map(|(kind, names)| names.iter().map(move |name| Resource { kind, name: name.clone() }))
+    }
+}
+
+// a resource a manifest lists that is not in the game data, or a scene it depends on that has no manifest
+#[derive(Clone, Debug)]
+pub struct ManifestProblem {
+    pub scene: String,
+    pub message: String
+}
+
+// one toml file per scene in the manifest folder, named after the scene
+#[derive(Clone, Debug, Default)]
+pub struct Manifests {
+    scenes: HashMap<String, SceneManifest>
+}
+
+impl Manifests {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // a missing folder is a game without manifests, nothing is prefetched then
+    pub fn load(directory: &Path) -> EngineResult<Self> {
+        let mut manifests = Self::new();
+        if !directory.is_dir() {
+            return Ok(manifests);
+        }
+
+        for entry in fs::read_dir(directory)?.flatten() {
+            let path = entry.path();
+            if path.extension().map(|extension| extension != "toml").unwrap_or(true) {
+                continue;
+            }
+
+            let scene = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+            let manifest: SceneManifest = toml::from_str(&fs::read_to_string(&path)?)?;
+            manifests.insert(&scene, manifest);
+        }
+
+        Ok(manifests)
+    }
+
+    pub fn insert(&mut self, scene: &str, manifest: SceneManifest) {
+        self.scenes.insert(scene.to_string(), manifest);
+    }
+
+    pub fn scenes(&self) -> Vec<String> {
+        let mut scenes: Vec<String> = self.scenes.keys().cloned().collect();
+        scenes.sort();
+        scenes
+    }
+
+    // everything the scene and the scenes it depends on list, each resource once.
+    // a scene reached twice through depends is only walked once, so cycles end
+    pub fn resources(&self, scene: &str) -> EngineResult<Vec<Resource>> {
+        let mut resources = Vec::new();
+        let mut seen_resources = HashSet::new();
+        let mut visited = HashSet::new();
+        let mut pending = vec![scene.to_string()];
+
+        while let Some(scene) = pending.pop() {
+            if !visited.insert(scene.clone()) {
+                continue;
+            }
+
+            let manifest = self.scenes.get(&scene).ok_or_else(|| EngineError::NotFound(format!("manifest of scene {}", scene)))?;
+            for resource in manifest.resources() {
+                if seen_resources.insert(resource.clone()) {
+                    resources.push(resource);
+                }
+            }
+
+            pending.extend(manifest.depends.iter().rev().cloned());
+        }
+
+        Ok(resources)
+    }
+
+    // for --check, every listed file has to be in the game data or a mod on top of it
+    pub fn check(&self, vfs: &Vfs) -> Vec<ManifestProblem> {
+        let mut problems = Vec::new();
+
+        for scene in self.scenes() {
+            let manifest = &self.scenes[&scene];
+
+            for depends in manifest.depends.iter().filter(|depends| !self.scenes.contains_key(*depends)) {
+                problems.push(ManifestProblem { scene: scene.clone(), message: format!("depends on {} which has no manifest", depends) });
+            }
+
+            for resource in manifest.resources().filter(|resource| !vfs.exists(&resource.name)) {
+                problems.push(ManifestProblem { scene: scene.clone(), message: format!("{} is not in {}", resource, vfs.describe()) });
+            }
+        }
+
+        problems
+    }
+}
+
+// reads the files of the next scene a few per update while the transition plays
+pub struct Preloader {
+    manifests: Manifests,
+    vfs: Arc<Vfs>,
+    queue: VecDeque<String>,
+    total: usize
+}
+
+impl Preloader {
+    pub fn new(manifests: Manifests, vfs: Arc<Vfs>) -> Self {
+        Preloader { manifests, vfs, queue: VecDeque::new(), total: 0 }
+    }
+
+    pub fn manifests(&self) -> &Manifests {
+        &self.manifests
+    }
+
+    // files of the scene left before are let go unless the next one needs them too
+    pub fn prefetch(&mut self, scene: &str) -> EngineResult<()> {
+        let resources = self.manifests.resources(scene)?;
+        let names: HashSet<String> = resources.iter().map(|resource| resource.name.to_lowercase()).collect();
+        self.vfs.retain_prefetched(&names);
+
+        self.queue = resources.into_iter()
+            .map(|resource| resource.name)
+            .filter(|name| !self.vfs.is_prefetched(name))
+            .collect();
+        self.total = self.queue.len();
+        Ok(())
+    }
+
+    // reads the files on a thread instead of a few each update, for a loading screen in front of a big scene.
+    // a name with a manifest stands for every file of that scene, any other name is a game data file
+    pub fn preload(&self, names: &[String]) -> EngineResult<BackgroundLoad> {
+        let mut files = Vec::new();
+        let mut seen = HashSet::new();
+
+        for name in names {
+            let scene_files = match self.manifests.scenes.contains_key(name) {
+                true => self.manifests.resources(name)?.into_iter().map(|resource| resource.name).collect(),
+                false => vec![ name.clone() ]
+            };
+
+            for file in scene_files {
+                if !self.vfs.is_prefetched(&file) && seen.insert(file.to_lowercase()) {
+                    files.push(file);
+                }
+            }
+        }
+
+        Ok(BackgroundLoad::spawn(self.vfs.clone(), files))
+    }
+
+    // a file that can not be read is skipped, loading it later reports the error where it matters
+    pub fn update(&mut self) {
+        for _ in 0..PREFETCH_FILES_PER_UPDATE {
+            match self.queue.pop_front() {
+                Some(name) => { let _ = self.vfs.prefetch(&name); },
+                None => break
+            }
+        }
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    // 0 to 1, for a loading bar
+    pub fn progress(&self) -> f64 {
+        if self.total == 0 {
+            return 1.0;
+        }
+
+        (self.total - self.queue.len()) as f64 / self.total as f64
+    }
+}
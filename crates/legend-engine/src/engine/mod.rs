@@ -1 +1,62 @@
-pub mod graphics;
\ No newline at end of file
+pub mod graphics;
+pub mod draw_debug;
+pub mod atlas;
+pub mod sprite_cache;
+pub mod frame_stats;
+pub mod clock;
+pub mod audio;
+pub mod data_path;
+pub mod font_sheet;
+pub mod text;
+pub mod dialog;
+pub mod movement;
+pub mod world_map;
+pub mod random;
+pub mod encounter;
+pub mod status;
+pub mod stats;
+pub mod skill;
+pub mod level;
+pub mod inventory;
+pub mod equipment;
+pub mod party;
+pub mod bestiary;
+pub mod entity;
+pub mod field;
+pub mod pickup;
+pub mod transition;
+pub mod photo_mode;
+pub mod presence;
+pub mod atomic_file;
+pub mod decode;
+pub mod error;
+pub mod palette_remap;
+pub mod panel;
+pub mod input;
+pub mod gamepad;
+pub mod action_map;
+pub mod debug_view;
+pub mod vfs;
+pub mod input_replay;
+pub mod save;
+pub mod settings;
+pub mod manifest;
+pub mod compression;
+pub mod debug_overlay;
+pub mod options_menu;
+pub mod screenshot;
+pub mod error_screen;
+pub mod accessibility;
+pub mod pointer;
+pub mod assets;
+pub mod resource_trace;
+pub mod camera;
+pub mod palette_blend;
+pub mod animation;
+pub mod image_trim;
+pub mod background_load;
+pub mod install_check;
+pub mod disc_image;
+pub mod zip_mount;
+pub mod release_check;
+pub mod palette_file;
\ No newline at end of file
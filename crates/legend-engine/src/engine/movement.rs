@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+use crate::engine::graphics::Vector2;
+
+// pixels per map tile
+pub const TILE_SIZE: i32 = 16;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Direction {
+    Up,
+    Right,
+    Left,
+    Down
+}
+
+impl Direction {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "up" => Some(Direction::Up),
+            "right" => Some(Direction::Right),
+            "left" => Some(Direction::Left),
+            "down" => Some(Direction::Down),
+            _ => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Direction::Up => "up",
+            Direction::Right => "right",
+            Direction::Left => "left",
+            Direction::Down => "down"
+        }
+    }
+
+    pub fn offset(&self) -> Vector2<i32> {
+        match self {
+            Direction::Up => Vector2::new(0, -1),
+            Direction::Right => Vector2::new(1, 0),
+            Direction::Left => Vector2::new(-1, 0),
+            Direction::Down => Vector2::new(0, 1)
+        }
+    }
+
+    // the direction to face when walking from one tile to a neighbour
+    pub fn between(from: Vector2<i32>, to: Vector2<i32>) -> Option<Self> {
+        match (to.x - from.x, to.y - from.y) {
+            (0, y) if y < 0 => Some(Direction::Up),
+            (0, y) if y > 0 => Some(Direction::Down),
+            (x, 0) if x < 0 => Some(Direction::Left),
+            (x, 0) if x > 0 => Some(Direction::Right),
+            _ => None
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MovementMode {
+    #[default]
+    Walking,
+    Boat,
+    Horse
+}
+
+impl MovementMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "walking" => Some(MovementMode::Walking),
+            "boat" => Some(MovementMode::Boat),
+            "horse" => Some(MovementMode::Horse),
+            _ => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MovementMode::Walking => "walking",
+            MovementMode::Boat => "boat",
+            MovementMode::Horse => "horse"
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ModeRule {
+    // inclusive tile id ranges this mode can enter
+    pub passable: Vec<(u16, u16)>,
+    // first sprite of the set, laid out as frames_per_direction frames for up, right, left, down
+    pub sprite_base: usize,
+    pub frames_per_direction: usize,
+    // seconds per tile
+    pub step_interval: f64
+}
+
+impl ModeRule {
+    pub fn can_enter(&self, tile: u16) -> bool {
+        self.passable.iter().any(|&(first, last)| tile >= first && tile <= last)
+    }
+
+    pub fn sprite(&self, direction: Direction, frame: usize) -> usize {
+        let direction_index = match direction {
+            Direction::Up => 0,
+            Direction::Right => 1,
+            Direction::Left => 2,
+            Direction::Down => 3
+        };
+
+        self.sprite_base + direction_index * self.frames_per_direction + frame % self.frames_per_direction.max(1)
+    }
+}
+
+pub struct MovementRules {
+    rules: Vec<(MovementMode, ModeRule)>
+}
+
+impl MovementRules {
+    pub fn new() -> Self {
+        MovementRules { rules: Vec::new() }
+    }
+
+    pub fn set(&mut self, mode: MovementMode, rule: ModeRule) {
+        self.rules.retain(|(current, _)| *current != mode);
+        self.rules.push((mode, rule));
+    }
+
+    pub fn get(&self, mode: MovementMode) -> Option<&ModeRule> {
+        self.rules.iter().find(|(current, _)| *current == mode).map(|(_, rule)| rule)
+    }
+
+    pub fn can_enter(&self, mode: MovementMode, tile: u16) -> bool {
+        self.get(mode).map(|rule| rule.can_enter(tile)).unwrap_or(false)
+    }
+
+    // where a mode may move, one without a rule goes anywhere so a game without boats needs no rules
+    pub fn allows(&self, mode: MovementMode, tile: u16) -> bool {
+        self.get(mode).map(|rule| rule.can_enter(tile)).unwrap_or(true)
+    }
+
+    // leaving a boat or a horse needs a tile the new mode can stand on
+    pub fn can_switch(&self, to: MovementMode, tile: u16) -> bool {
+        self.can_enter(to, tile)
+    }
+}
+
+impl Default for MovementRules {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,267 @@
+use crate::engine::action_map::ActionMap;
+use crate::engine::dialog::TextSpeed;
+use crate::engine::graphics::{Color, Graphics, TextAlign, TextDirection};
+use crate::engine::input::{Input, InputContext, KeyAction, KeyEvent};
+use crate::engine::panel::PanelStyle;
+use crate::engine::settings::{Settings, MAX_SCALE, MIN_SCALE};
+use crate::engine::text::encode_big5;
+
+// captures every key while the menu is open, the game behind it sees nothing
+pub const OPTIONS_CONTEXT: &str = "options";
+pub const LANGUAGES: [&str; 2] = ["en", "zh-tw"];
+const TEXT_SPEEDS: [TextSpeed; 4] = [TextSpeed::Slow, TextSpeed::Normal, TextSpeed::Fast, TextSpeed::Instant];
+const VOLUME_STEP: f32 = 0.1;
+
+const PANEL_X: i32 = 32;
+const PANEL_Y: i32 = 16;
+const PANEL_WIDTH: i32 = 256;
+const PANEL_HEIGHT: i32 = 168;
+const MARGIN: i32 = 8;
+
+#[derive(Clone, PartialEq, Debug)]
+enum OptionItem {
+    MasterVolume,
+    MusicVolume,
+    SoundVolume,
+    Fullscreen,
+    Scale,
+    TextSpeed,
+    Language,
+    ScreenReader,
+    // confirm waits for the next key and binds it to the action
+    Binding(String),
+    ResetBindings,
+    Back
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum MenuCommand {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Cancel
+}
+
+// what the caller has to pass on after an update, the menu only changes the settings
+#[derive(Copy, Clone, Default, Debug)]
+pub struct OptionsChanges {
+    // fullscreen or scale, the window has to follow
+    pub display: bool,
+    pub volumes: bool,
+    // the screen reader was turned on or off
+    pub speech: bool,
+    // the settings should be written now
+    pub closed: bool
+}
+
+// volumes, display, text speed, language and key bindings, drawn over the paused game
+#[derive(Default)]
+pub struct OptionsMenu {
+    open: bool,
+    context_pushed: bool,
+    selected: usize,
+    // kept until the window owner gets to it, which is outside of the update
+    display_changed: bool
+}
+
+fn cycle<T: PartialEq + Copy>(values: &[T], current: T, step: i32) -> T {
+    let index = values.iter().position(|value| *value == current).unwrap_or(0) as i32;
+    values[(index + step).rem_euclid(values.len() as i32) as usize]
+}
+
+impl OptionsMenu {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn open(&mut self) {
+        self.open = true;
+        self.selected = 0;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn take_display_change(&mut self) -> bool {
+        std::mem::take(&mut self.display_changed)
+    }
+
+    fn items(&self, input: &Input) -> Vec<OptionItem> {
+        let mut items = vec![
+            OptionItem::MasterVolume, OptionItem::MusicVolume, OptionItem::SoundVolume,
+            OptionItem::Fullscreen, OptionItem::Scale, OptionItem::TextSpeed, OptionItem::Language, OptionItem::ScreenReader
+        ];
+        items.extend(input.actions.actions().into_iter().map(OptionItem::Binding));
+        items.push(OptionItem::ResetBindings);
+        items.push(OptionItem::Back);
+        items
+    }
+
+    // navigation follows the bindings, so a rebound confirm works here as well
+    fn command(input: &Input, key: &str) -> Option<MenuCommand> {
+        [
+            ("up", MenuCommand::Up), ("down", MenuCommand::Down), ("left", MenuCommand::Left),
+            ("right", MenuCommand::Right), ("confirm", MenuCommand::Confirm), ("cancel", MenuCommand::Cancel)
+        ].into_iter()
+            .find(|(action, _)| input.actions.keys(action).iter().any(|bound| bound == key))
+            .map(|(_, command)| command)
+    }
+
+    // takes the key events of the update, the game gets none of them while the menu is open
+    pub fn update(&mut self, input: &mut Input, settings: &mut Settings) -> OptionsChanges {
+        let mut changes = OptionsChanges::default();
+        if !self.open {
+            return changes;
+        }
+
+        if !self.context_pushed {
+            input.push_context(InputContext::capture_all(OPTIONS_CONTEXT));
+            self.context_pushed = true;
+        }
+
+        for event in input.take_events() {
+            self.handle(event, input, settings, &mut changes);
+            if !self.open {
+                break;
+            }
+        }
+
+        self.display_changed |= changes.display;
+
+        if !self.open {
+            input.cancel_rebind();
+            input.pop_context(OPTIONS_CONTEXT);
+            self.context_pushed = false;
+            changes.closed = true;
+        }
+
+        changes
+    }
+
+    fn handle(&mut self, event: KeyEvent, input: &mut Input, settings: &mut Settings, changes: &mut OptionsChanges) {
+        let (key, repeat) = match event {
+            KeyEvent::Down { key, context } if context == OPTIONS_CONTEXT => (key, false),
+            KeyEvent::Action { action: KeyAction::Repeat, key, context } if context == OPTIONS_CONTEXT => (key, true),
+            // the input already bound it, it is kept in the settings so it lasts
+            KeyEvent::Rebound { action, key } => {
+                settings.bindings.insert(action, vec![ key ]);
+                return;
+            },
+            _ => return
+        };
+
+        let items = self.items(input);
+        self.selected = self.selected.min(items.len() - 1);
+        let item = items[self.selected].clone();
+
+        match Self::command(input, &key) {
+            Some(MenuCommand::Up) => self.selected = (self.selected + items.len() - 1) % items.len(),
+            Some(MenuCommand::Down) => self.selected = (self.selected + 1) % items.len(),
+            Some(MenuCommand::Left) => Self::adjust(&item, -1, settings, changes),
+            Some(MenuCommand::Right) => Self::adjust(&item, 1, settings, changes),
+            Some(MenuCommand::Confirm) if !repeat => match item {
+                OptionItem::Binding(action) => input.start_rebind(&action),
+                OptionItem::ResetBindings => {
+                    settings.bindings.clear();
+                    input.actions = ActionMap::default();
+                },
+                OptionItem::Back => self.open = false,
+                item => Self::adjust(&item, 1, settings, changes)
+            },
+            Some(MenuCommand::Cancel) if !repeat => self.open = false,
+            _ => ()
+        }
+    }
+
+    fn adjust(item: &OptionItem, step: i32, settings: &mut Settings, changes: &mut OptionsChanges) {
+        let volume = |volume: &mut f32| *volume = (*volume + VOLUME_STEP * step as f32).clamp(0.0, 1.0);
+
+        match item {
+            OptionItem::MasterVolume => volume(&mut settings.master_volume),
+            OptionItem::MusicVolume => volume(&mut settings.music_volume),
+            OptionItem::SoundVolume => volume(&mut settings.sound_volume),
+            OptionItem::Fullscreen => settings.fullscreen = !settings.fullscreen,
+            OptionItem::Scale => settings.scale = (settings.scale as i32 + step).clamp(MIN_SCALE as i32, MAX_SCALE as i32) as u32,
+            OptionItem::TextSpeed => settings.text_speed = cycle(&TEXT_SPEEDS, settings.text_speed(), step).name().to_string(),
+            OptionItem::Language => settings.language = cycle(&LANGUAGES, settings.language.as_str(), step).to_string(),
+            OptionItem::ScreenReader => settings.screen_reader = !settings.screen_reader,
+            OptionItem::Binding(_) | OptionItem::ResetBindings | OptionItem::Back => return
+        }
+
+        match item {
+            OptionItem::MasterVolume | OptionItem::MusicVolume | OptionItem::SoundVolume => changes.volumes = true,
+            OptionItem::Fullscreen | OptionItem::Scale => changes.display = true,
+            OptionItem::ScreenReader => changes.speech = true,
+            _ => ()
+        }
+    }
+
+    fn describe(item: &OptionItem, settings: &Settings, input: &Input) -> (String, String) {
+        let percent = |volume: f32| format!("{}%", (volume * 100.0).round());
+
+        match item {
+            OptionItem::MasterVolume => ("volume".to_string(), percent(settings.master_volume)),
+            OptionItem::MusicVolume => ("music".to_string(), percent(settings.music_volume)),
+            OptionItem::SoundVolume => ("sound".to_string(), percent(settings.sound_volume)),
+            OptionItem::Fullscreen => ("display".to_string(), if settings.fullscreen { "fullscreen" } else { "window" }.to_string()),
+            OptionItem::Scale => ("window scale".to_string(), format!("{}x", settings.scale)),
+            OptionItem::TextSpeed => ("text speed".to_string(), settings.text_speed().name().to_string()),
+            OptionItem::Language => ("language".to_string(), settings.language.clone()),
+            OptionItem::ScreenReader => ("screen reader".to_string(), if settings.screen_reader { "on" } else { "off" }.to_string()),
+            OptionItem::Binding(action) => {
+                let keys = if input.rebinding() == Some(action.as_str()) { "press a key".to_string() } else { input.actions.keys(action).join(", ") };
+                (format!("key {}", action), keys)
+            },
+            OptionItem::ResetBindings => ("reset keys".to_string(), String::new()),
+            OptionItem::Back => ("back".to_string(), String::new())
+        }
+    }
+
+    // the selected row as it would be read aloud
+    pub fn selected_text(&self, settings: &Settings, input: &Input) -> Option<String> {
+        if !self.open {
+            return None;
+        }
+
+        let items = self.items(input);
+        let (label, value) = Self::describe(&items[self.selected.min(items.len() - 1)], settings, input);
+        Some(format!("{} {}", label, value))
+    }
+
+    // over whatever the game rendered, text needs the game font
+    pub fn draw(&self, graphics: &mut Graphics, settings: &Settings, input: &Input) {
+        if !self.open {
+            return;
+        }
+
+        let line_height = match graphics.get_game_font() {
+            Some(game_font) => game_font.get_height(),
+            None => return
+        };
+
+        let style = PanelStyle { dim: 0.4, border: Some(Color::new(255, 255, 255, 255)), ..PanelStyle::default() };
+        graphics.draw_panel(PANEL_X, PANEL_Y, PANEL_WIDTH, PANEL_HEIGHT, &style);
+
+        let (text_color, selected_color) = (Color::new(224, 224, 224, 255), Color::new(255, 224, 96, 255));
+        let (left, width) = (PANEL_X + MARGIN, PANEL_WIDTH - MARGIN * 2);
+        graphics.draw_text(&encode_big5("options"), left, PANEL_Y + MARGIN, width, TextAlign::Center, TextDirection::Horizontal, &selected_color);
+
+        // the list scrolls so the selected row stays in view
+        let top = PANEL_Y + MARGIN * 2 + line_height;
+        let rows = ((PANEL_HEIGHT - MARGIN * 3 - line_height) / line_height).max(1) as usize;
+        let items = self.items(input);
+        let first = (self.selected + 1).saturating_sub(rows);
+
+        for (row, (index, item)) in items.iter().enumerate().skip(first).take(rows).enumerate() {
+            let (label, value) = Self::describe(item, settings, input);
+            let color = if index == self.selected { &selected_color } else { &text_color };
+            let y = top + row as i32 * line_height;
+
+            graphics.draw_text(&encode_big5(&label), left, y, width, TextAlign::Left, TextDirection::Horizontal, color);
+            graphics.draw_text(&encode_big5(&value), left, y, width, TextAlign::Right, TextDirection::Horizontal, color);
+        }
+    }
+}
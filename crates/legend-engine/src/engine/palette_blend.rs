@@ -0,0 +1,49 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use crate::engine::graphics::Palette;
+
+// moves a shared palette towards a target over some seconds of game time, for dawn and dusk or a flash to white.
+// it starts from whatever the palette holds when the blend starts, so a second blend picks up where the first one was
+pub struct PaletteBlend {
+    palette: Rc<RefCell<Palette>>,
+    from: Palette,
+    to: Palette,
+    duration: f64,
+    elapsed: f64
+}
+
+impl PaletteBlend {
+    pub fn new(palette: Rc<RefCell<Palette>>, to: Palette, duration: f64) -> Self {
+        let from = palette.borrow().clone();
+        PaletteBlend { palette, from, to, duration: duration.max(0.0), elapsed: 0.0 }
+    }
+
+    pub fn blends(&self, palette: &Rc<RefCell<Palette>>) -> bool {
+        Rc::ptr_eq(&self.palette, palette)
+    }
+
+    // 0 at the start and 1 once the target is reached
+    pub fn progress(&self) -> f64 {
+        if self.duration <= 0.0 { 1.0 } else { (self.elapsed / self.duration).min(1.0) }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.progress() >= 1.0
+    }
+
+    // writes the blended colors into the palette, true once it holds the target
+    pub fn advance(&mut self, delta: f64) -> bool {
+        self.elapsed += delta.max(0.0);
+
+        // a palette a script is holding right now gets the colors next frame
+        if let Ok(mut palette) = self.palette.try_borrow_mut() {
+            match self.is_finished() {
+                true => palette.copy_from(&self.to),
+                false => palette.copy_from(&Palette::lerp(&self.from, &self.to, self.progress()))
+            }
+            return self.is_finished();
+        }
+
+        false
+    }
+}
@@ -0,0 +1,103 @@
+use std::path::Path;
+use crate::engine::decode::DecodeError;
+use crate::engine::graphics::Palette;
+
+// photoshop color tables are 256 rgb triples, newer ones add a u16 color count and transparent index
+const ACT_BYTES: usize = 256 * 3;
+const GPL_HEADER: &str = "GIMP Palette";
+
+// the formats paint programs save palettes in, next to the 6 bit vga one of the game
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PaletteFormat {
+    // .pal and .col of the original game
+    Vga,
+    Act,
+    Gpl
+}
+
+impl PaletteFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        match extension.as_str() {
+            "pal" | "col" => Some(PaletteFormat::Vga),
+            "act" => Some(PaletteFormat::Act),
+            "gpl" => Some(PaletteFormat::Gpl),
+            _ => None
+        }
+    }
+}
+
+fn rgb_bytes(palette: &Palette) -> Vec<u8> {
+    (0..=255u8).flat_map(|index| {
+        let color = palette.get_color(index);
+        [color.r, color.g, color.b]
+    }).collect()
+}
+
+pub fn to_act(palette: &Palette) -> Vec<u8> {
+    let mut bytes = rgb_bytes(palette);
+    // all 256 colors count, none is transparent
+    bytes.extend_from_slice(&[1, 0, 0xff, 0xff]);
+    bytes
+}
+
+pub fn from_act(bytes: &[u8]) -> Result<Palette, DecodeError> {
+    if bytes.len() < ACT_BYTES {
+        return Err(DecodeError::Truncated { needed: ACT_BYTES, available: bytes.len() });
+    }
+
+    Ok(Palette::create_by_buffer(&mut &bytes[..ACT_BYTES]))
+}
+
+// name is what gimp lists the palette as, the colors are named by their index
+pub fn to_gpl(palette: &Palette, name: &str) -> String {
+    let mut lines = vec![ GPL_HEADER.to_string(), format!("Name: {}", name), "Columns: 16".to_string(), "#".to_string() ];
+
+    for index in 0..=255u8 {
+        let color = palette.get_color(index);
+        lines.push(format!("{:3} {:3} {:3}\tIndex {}", color.r, color.g, color.b, index));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+// the first 256 colors, a shorter palette leaves the rest black
+pub fn from_gpl(source: &str) -> Result<Palette, DecodeError> {
+    let mut lines = source.lines();
+    if lines.next().map(str::trim) != Some(GPL_HEADER) {
+        return Err(DecodeError::InvalidHeader("not a GIMP palette".to_string()));
+    }
+
+    let mut bytes = Vec::with_capacity(ACT_BYTES);
+    for line in lines.map(str::trim) {
+        // Name:, Columns: and comments come before the colors
+        if line.is_empty() || line.starts_with('#') || line.starts_with("Name:") || line.starts_with("Columns:") {
+            continue;
+        }
+
+        let values: Vec<u8> = line.split_whitespace().take(3).map_while(|value| value.parse().ok()).collect();
+        if values.len() != 3 {
+            return Err(DecodeError::InvalidHeader(format!("not a color: {}", line)));
+        }
+        bytes.extend_from_slice(&values);
+    }
+
+    bytes.truncate(ACT_BYTES);
+    Ok(Palette::create_by_buffer(&mut bytes.as_slice()))
+}
+
+pub fn palette_from_bytes(bytes: &[u8], format: PaletteFormat) -> Result<Palette, DecodeError> {
+    match format {
+        PaletteFormat::Vga => Palette::from_bytes(bytes),
+        PaletteFormat::Act => from_act(bytes),
+        PaletteFormat::Gpl => from_gpl(&String::from_utf8_lossy(bytes))
+    }
+}
+
+pub fn palette_to_bytes(palette: &Palette, format: PaletteFormat, name: &str) -> Vec<u8> {
+    match format {
+        PaletteFormat::Vga => palette.to_bytes(),
+        PaletteFormat::Act => to_act(palette),
+        PaletteFormat::Gpl => to_gpl(palette, name).into_bytes()
+    }
+}
@@ -0,0 +1,51 @@
+// swaps palette indices before the color lookup, so one sprite can be drawn in every faction color
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PaletteRemap {
+    table: [u8; 256]
+}
+
+impl PaletteRemap {
+    pub fn identity() -> Self {
+        let mut table = [0; 256];
+        for (index, value) in table.iter_mut().enumerate() {
+            *value = index as u8;
+        }
+
+        PaletteRemap { table }
+    }
+
+    pub fn get(&self, index: u8) -> u8 {
+        self.table[index as usize]
+    }
+
+    pub fn set(&mut self, index: u8, target: u8) {
+        self.table[index as usize] = target;
+    }
+
+    // draws count indices starting at from with the colors starting at to,
+    // like moving the red uniform range of a soldier onto the blue one
+    pub fn remap_range(&mut self, from: u8, to: u8, count: u8) {
+        for offset in 0..count {
+            let (index, target) = match (from.checked_add(offset), to.checked_add(offset)) {
+                (Some(index), Some(target)) => (index, target),
+                _ => break
+            };
+
+            self.set(index, target);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::identity();
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.table.iter().enumerate().all(|(index, value)| index == *value as usize)
+    }
+}
+
+impl Default for PaletteRemap {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
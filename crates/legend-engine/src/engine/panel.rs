@@ -0,0 +1,45 @@
+use crate::engine::graphics::{Color, Image};
+
+// how a menu or dialog window covers what is behind it
+#[derive(Clone)]
+pub struct PanelStyle {
+    pub backdrop: Color,
+    // 0 shows only the background, 1 is the solid panel of the original game
+    pub opacity: f64,
+    // blur radius in pixels applied to the background first, 0 keeps it sharp
+    pub blur: u32,
+    // darkens the background before the backdrop goes on top
+    pub dim: f64,
+    pub border: Option<Color>
+}
+
+impl PanelStyle {
+    pub fn solid(backdrop: Color) -> Self {
+        PanelStyle { backdrop, opacity: 1.0, blur: 0, dim: 0.0, border: None }
+    }
+}
+
+impl Default for PanelStyle {
+    fn default() -> Self {
+        PanelStyle { backdrop: Color::new(0, 0, 64, 255), opacity: 0.75, blur: 0, dim: 0.0, border: None }
+    }
+}
+
+pub fn draw_panel(target: &mut Image, x: i32, y: i32, width: i32, height: i32, style: &PanelStyle) {
+    if style.blur > 0 {
+        target.blur_rect(x, y, width, height, style.blur);
+    }
+
+    if style.dim > 0.0 {
+        let dim = (style.dim.clamp(0.0, 1.0) * 255.0) as u8;
+        target.fill_rect(x, y, width, height, &Color::new(0, 0, 0, dim));
+    }
+
+    let alpha = (style.opacity.clamp(0.0, 1.0) * style.backdrop.a as f64) as u8;
+    let backdrop = Color::new(style.backdrop.r, style.backdrop.g, style.backdrop.b, alpha);
+    target.fill_rect(x, y, width, height, &backdrop);
+
+    if let Some(border) = &style.border {
+        target.draw_rect(x, y, width, height, border);
+    }
+}
@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+use crate::engine::movement::MovementMode;
+
+pub const DEFAULT_ACTIVE_SIZE: usize = 6;
+
+// character ids, the active list is in formation order and its first member leads
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Party {
+    active: Vec<u32>,
+    reserve: Vec<u32>,
+    max_active: usize,
+    // how the party currently travels on maps
+    #[serde(default)]
+    pub mode: MovementMode
+}
+
+impl Party {
+    pub fn new(max_active: usize) -> Self {
+        Party { active: Vec::new(), reserve: Vec::new(), max_active: max_active.max(1), mode: MovementMode::Walking }
+    }
+
+    pub fn active(&self) -> &[u32] {
+        &self.active
+    }
+
+    pub fn reserve(&self) -> &[u32] {
+        &self.reserve
+    }
+
+    pub fn leader(&self) -> Option<u32> {
+        self.active.first().copied()
+    }
+
+    pub fn contains(&self, member: u32) -> bool {
+        self.active.contains(&member) || self.reserve.contains(&member)
+    }
+
+    // joins the active members, or the reserve when they are full
+    pub fn add(&mut self, member: u32) -> bool {
+        if self.contains(member) {
+            return false;
+        }
+
+        if self.active.len() < self.max_active {
+            self.active.push(member);
+        } else {
+            self.reserve.push(member);
+        }
+
+        true
+    }
+
+    pub fn remove(&mut self, member: u32) -> bool {
+        let count = self.active.len() + self.reserve.len();
+        self.active.retain(|&current| current != member);
+        self.reserve.retain(|&current| current != member);
+        count != self.active.len() + self.reserve.len()
+    }
+
+    // exchange two members wherever they are, so an active and a reserve member can trade places
+    pub fn swap(&mut self, member_a: u32, member_b: u32) -> bool {
+        let position_a = self.position(member_a);
+        let position_b = self.position(member_b);
+
+        match (position_a, position_b) {
+            (Some((in_active_a, index_a)), Some((in_active_b, index_b))) => {
+                *self.slot_mut(in_active_a, index_a) = member_b;
+                *self.slot_mut(in_active_b, index_b) = member_a;
+                true
+            },
+            _ => false
+        }
+    }
+
+    pub fn set_leader(&mut self, member: u32) -> bool {
+        match self.active.iter().position(|&current| current == member) {
+            Some(index) => {
+                let leader = self.active.remove(index);
+                self.active.insert(0, leader);
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn move_to_reserve(&mut self, member: u32) -> bool {
+        match self.active.iter().position(|&current| current == member) {
+            Some(index) => {
+                self.reserve.push(self.active.remove(index));
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn move_to_active(&mut self, member: u32) -> bool {
+        if self.active.len() >= self.max_active {
+            return false;
+        }
+
+        match self.reserve.iter().position(|&current| current == member) {
+            Some(index) => {
+                self.active.push(self.reserve.remove(index));
+                true
+            },
+            None => false
+        }
+    }
+
+    fn position(&self, member: u32) -> Option<(bool, usize)> {
+        if let Some(index) = self.active.iter().position(|&current| current == member) {
+            return Some((true, index));
+        }
+
+        self.reserve.iter().position(|&current| current == member).map(|index| (false, index))
+    }
+
+    fn slot_mut(&mut self, in_active: bool, index: usize) -> &mut u32 {
+        if in_active {
+            &mut self.active[index]
+        } else {
+            &mut self.reserve[index]
+        }
+    }
+}
+
+impl Default for Party {
+    fn default() -> Self {
+        Party::new(DEFAULT_ACTIVE_SIZE)
+    }
+}
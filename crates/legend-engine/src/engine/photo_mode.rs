@@ -0,0 +1,173 @@
+use crate::engine::error::EngineResult;
+use crate::engine::graphics::{Color, Image, Vector2};
+
+pub const MAX_PHOTO_SCALE: u32 = 8;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PhotoFilter {
+    None,
+    Scanlines,
+    Grayscale,
+    Sepia
+}
+
+impl PhotoFilter {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "none" => Some(PhotoFilter::None),
+            "scanlines" => Some(PhotoFilter::Scanlines),
+            "grayscale" => Some(PhotoFilter::Grayscale),
+            "sepia" => Some(PhotoFilter::Sepia),
+            _ => None
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PhotoFilter::None => "none",
+            PhotoFilter::Scanlines => "scanlines",
+            PhotoFilter::Grayscale => "grayscale",
+            PhotoFilter::Sepia => "sepia"
+        }
+    }
+
+    // for a single key cycling through the filters
+    pub fn next(&self) -> Self {
+        match self {
+            PhotoFilter::None => PhotoFilter::Scanlines,
+            PhotoFilter::Scanlines => PhotoFilter::Grayscale,
+            PhotoFilter::Grayscale => PhotoFilter::Sepia,
+            PhotoFilter::Sepia => PhotoFilter::None
+        }
+    }
+
+    fn apply_color(&self, color: &Color) -> Color {
+        let luma = (color.r as f64) * 0.299 + (color.g as f64) * 0.587 + (color.b as f64) * 0.114;
+
+        match self {
+            PhotoFilter::Grayscale => Color::new(luma as u8, luma as u8, luma as u8, color.a),
+            PhotoFilter::Sepia => Color::new(
+                (luma * 1.07).min(255.0) as u8,
+                (luma * 0.74) as u8,
+                (luma * 0.43) as u8,
+                color.a
+            ),
+            _ => *color
+        }
+    }
+}
+
+// while active the game is paused, the ui is hidden and the camera is moved freely over the map
+pub struct PhotoMode {
+    active: bool,
+    camera: Vector2<f64>,
+    // pixels per second
+    pub camera_speed: f64,
+    pub filter: PhotoFilter,
+    pub scale: u32
+}
+
+impl PhotoMode {
+    pub fn new() -> Self {
+        PhotoMode {
+            active: false,
+            camera: Vector2::new(0.0, 0.0),
+            camera_speed: 120.0,
+            filter: PhotoFilter::None,
+            scale: 4
+        }
+    }
+
+    // starts from where the game camera was so the shot is framed around the party
+    pub fn enter(&mut self, camera_x: i32, camera_y: i32) {
+        self.active = true;
+        self.camera = Vector2::new(camera_x as f64, camera_y as f64);
+    }
+
+    pub fn leave(&mut self) {
+        self.active = false;
+        self.filter = PhotoFilter::None;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn hides_ui(&self) -> bool {
+        self.active
+    }
+
+    pub fn pauses_game(&self) -> bool {
+        self.active
+    }
+
+    // direction is -1, 0 or 1 on each axis, the camera stays inside the map
+    pub fn move_camera(&mut self, direction_x: i32, direction_y: i32, delta: f64, max_x: i32, max_y: i32) {
+        let step = self.camera_speed * delta;
+        self.camera.x = (self.camera.x + direction_x as f64 * step).max(0.0).min(max_x.max(0) as f64);
+        self.camera.y = (self.camera.y + direction_y as f64 * step).max(0.0).min(max_y.max(0) as f64);
+    }
+
+    pub fn camera(&self) -> Vector2<i32> {
+        Vector2::new(self.camera.x as i32, self.camera.y as i32)
+    }
+
+    pub fn set_scale(&mut self, scale: u32) {
+        self.scale = scale.clamp(1, MAX_PHOTO_SCALE);
+    }
+
+    // filters are applied on a copy so the preview and the frame buffer stay untouched
+    pub fn apply_filter(&self, image: &Image) -> Image {
+        let mut result = image.clone();
+
+        if self.filter != PhotoFilter::None && self.filter != PhotoFilter::Scanlines {
+            for color in result.data.iter_mut() {
+                *color = self.filter.apply_color(color);
+            }
+        }
+
+        result
+    }
+
+    // nearest neighbour upscale, scanlines darken every last row of a scaled pixel
+    pub fn upscale(&self, image: &Image) -> Image {
+        let scale = self.scale.max(1);
+        let mut result = Image::new(image.size.x * scale, image.size.y * scale);
+
+        for y in 0..result.size.y {
+            let scanline = self.filter == PhotoFilter::Scanlines && scale > 1 && y % scale == scale - 1;
+
+            for x in 0..result.size.x {
+                let color = &image.data[((y / scale) * image.size.x + x / scale) as usize];
+                let color = if scanline {
+                    Color::new(color.r / 2, color.g / 2, color.b / 2, color.a)
+                } else {
+                    *color
+                };
+
+                result.data[(y * result.size.x + x) as usize] = color;
+            }
+        }
+
+        result
+    }
+
+    pub fn save_photo(&self, source: &Image, filename: &str) -> EngineResult<()> {
+        let photo = self.upscale(&self.apply_filter(source));
+
+        let mut image_to_save: image::RgbaImage = image::ImageBuffer::new(photo.size.x, photo.size.y);
+        for (x, y, pixel) in image_to_save.enumerate_pixels_mut() {
+            let color = &photo.data[(y * photo.size.x + x) as usize];
+            *pixel = image::Rgba([color.r, color.g, color.b, color.a]);
+        }
+
+        image_to_save.save(filename)?;
+        Ok(())
+    }
+}
+
+impl Default for PhotoMode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::engine::atomic_file::write_atomic;
+use crate::engine::error::EngineResult;
+use crate::engine::inventory::Inventory;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PickupItem {
+    pub item: u32,
+    #[serde(default = "default_count")]
+    pub count: u32
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+fn default_sound() -> String {
+    "chest".to_string()
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct PickupDefinition {
+    // unique over the whole game, this is what gets persisted once opened
+    pub id: String,
+    pub map: String,
+    pub x: i32,
+    pub y: i32,
+    pub closed_sprite: usize,
+    // pickups lying on the ground have no open sprite and disappear instead
+    #[serde(default)]
+    pub open_sprite: Option<usize>,
+    #[serde(default)]
+    pub items: Vec<PickupItem>,
+    #[serde(default)]
+    pub gold: u32,
+    #[serde(default = "default_sound")]
+    pub sound: String,
+    // popup text, items are listed by the caller when empty
+    #[serde(default)]
+    pub message: String
+}
+
+#[derive(Deserialize)]
+struct PickupFile {
+    #[serde(default)]
+    pickup: Vec<PickupDefinition>
+}
+
+// what the map scene has to show after opening something
+#[derive(Clone, Debug)]
+pub struct PickupEvent {
+    pub id: String,
+    pub items: Vec<(u32, u32)>,
+    pub gold: u32,
+    pub sound: String,
+    pub message: String
+}
+
+// ids of every pickup already taken, stored with the save
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct OpenedPickups {
+    #[serde(default)]
+    opened: BTreeSet<String>
+}
+
+impl OpenedPickups {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_opened(&self, id: &str) -> bool {
+        self.opened.contains(id)
+    }
+
+    pub fn set_opened(&mut self, id: &str) {
+        self.opened.insert(id.to_string());
+    }
+
+    // a missing file means nothing was opened yet
+    pub fn load(filename: &str) -> EngineResult<Self> {
+        if !Path::new(filename).exists() {
+            return Ok(Self::new());
+        }
+
+        Ok(toml::from_str(&fs::read_to_string(filename)?)?)
+    }
+
+    pub fn save(&self, filename: &str) -> EngineResult<()> {
+        write_atomic(filename, toml::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+// [[pickup]]
+// id = "castle_chest_1"
+// map = "castle"
+// x = 12
+// y = 7
+// closed_sprite = 40
+// open_sprite = 41
+// items = [ { item = 3, count = 2 } ]
+// gold = 100
+pub struct Pickups {
+    definitions: HashMap<String, PickupDefinition>,
+    pub opened: OpenedPickups
+}
+
+impl Pickups {
+    pub fn new() -> Self {
+        Pickups { definitions: HashMap::new(), opened: OpenedPickups::new() }
+    }
+
+    pub fn from_toml(source: &str) -> EngineResult<Self> {
+        let file: PickupFile = toml::from_str(source)?;
+
+        let mut pickups = Self::new();
+        for definition in file.pickup {
+            pickups.definitions.insert(definition.id.clone(), definition);
+        }
+
+        Ok(pickups)
+    }
+
+    pub fn load(filename: &str) -> EngineResult<Self> {
+        Self::from_toml(&fs::read_to_string(filename)?)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&PickupDefinition> {
+        self.definitions.get(id)
+    }
+
+    pub fn at(&self, map: &str, x: i32, y: i32) -> Option<&PickupDefinition> {
+        self.definitions.values().find(|definition| definition.map == map && definition.x == x && definition.y == y)
+    }
+
+    // sprite to draw for every pickup of a map, taken ground pickups are left out
+    pub fn visible_on(&self, map: &str) -> Vec<(i32, i32, usize)> {
+        self.definitions.values()
+            .filter(|definition| definition.map == map)
+            .filter_map(|definition| {
+                if self.opened.is_opened(&definition.id) {
+                    definition.open_sprite.map(|sprite| (definition.x, definition.y, sprite))
+                } else {
+                    Some((definition.x, definition.y, definition.closed_sprite))
+                }
+            })
+            .collect()
+    }
+
+    // puts the contents into the inventory, None when there is nothing or it was already opened
+    pub fn open(&mut self, map: &str, x: i32, y: i32, inventory: &mut Inventory) -> Option<PickupEvent> {
+        let definition = self.at(map, x, y)?;
+        if self.opened.is_opened(&definition.id) {
+            return None;
+        }
+
+        let event = PickupEvent {
+            id: definition.id.clone(),
+            items: definition.items.iter().map(|item| (item.item, item.count)).collect(),
+            gold: definition.gold,
+            sound: definition.sound.clone(),
+            message: definition.message.clone()
+        };
+
+        for &(item, count) in event.items.iter() {
+            inventory.add(item, count);
+        }
+
+        self.opened.set_opened(&event.id);
+        Some(event)
+    }
+}
+
+impl Default for Pickups {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,120 @@
+use crate::engine::graphics::{Color, Graphics};
+
+// the right stick has to lean this far before the pointer moves
+pub const POINTER_DEADZONE: f32 = 0.2;
+// frame buffer pixels per second at full tilt, the same at every window scale
+const START_SPEED: f64 = 90.0;
+const MAX_SPEED: f64 = 360.0;
+// seconds the stick has to stay pushed to get from START_SPEED to MAX_SPEED
+const ACCELERATION_TIME: f64 = 0.75;
+
+// rows of the arrow drawn for the stick, the mouse has the system one
+const ARROW: [&str; 8] = [
+    "X",
+    "XX",
+    "X.X",
+    "X..X",
+    "X...X",
+    "X..XXX",
+    "X.X",
+    "XX"
+];
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PointerSource {
+    Mouse,
+    Stick
+}
+
+// a mouse position in frame buffer pixels the right stick can move as well,
+// so screens made for clicking work on a gamepad alone
+#[derive(Clone, Debug)]
+pub struct Pointer {
+    x: f64,
+    y: f64,
+    width: u32,
+    height: u32,
+    // seconds the stick has been pushed past the deadzone
+    pushed_time: f64,
+    source: Option<PointerSource>
+}
+
+impl Default for Pointer {
+    fn default() -> Self {
+        Pointer { x: 0.0, y: 0.0, width: 1, height: 1, pushed_time: 0.0, source: None }
+    }
+}
+
+impl Pointer {
+    pub fn new(width: u32, height: u32) -> Self {
+        let mut pointer = Self::default();
+        pointer.set_bounds(width, height);
+        pointer
+    }
+
+    // starts in the middle, the game resolution is not known when the input is made
+    pub fn set_bounds(&mut self, width: u32, height: u32) {
+        self.width = width.max(1);
+        self.height = height.max(1);
+        self.x = self.width as f64 / 2.0;
+        self.y = self.height as f64 / 2.0;
+    }
+
+    pub fn position(&self) -> (i32, i32) {
+        (self.x as i32, self.y as i32)
+    }
+
+    // None until the mouse or the stick moved it
+    pub fn source(&self) -> Option<PointerSource> {
+        self.source
+    }
+
+    pub fn move_to(&mut self, x: f64, y: f64) {
+        self.set_position(x, y);
+        self.source = Some(PointerSource::Mouse);
+    }
+
+    fn set_position(&mut self, x: f64, y: f64) {
+        self.x = x.clamp(0.0, (self.width - 1) as f64);
+        self.y = y.clamp(0.0, (self.height - 1) as f64);
+    }
+
+    // slow at first for aiming at a button, faster the longer the stick stays pushed
+    pub fn update_stick(&mut self, x: f32, y: f32, delta: f64) {
+        let length = x.hypot(y);
+        if length < POINTER_DEADZONE {
+            self.pushed_time = 0.0;
+            return;
+        }
+
+        self.pushed_time += delta;
+        let tilt = ((length - POINTER_DEADZONE) / (1.0 - POINTER_DEADZONE)).min(1.0) as f64;
+        let acceleration = (self.pushed_time / ACCELERATION_TIME).min(1.0);
+        // squared so a small lean moves a pixel at a time
+        let speed = (START_SPEED + (MAX_SPEED - START_SPEED) * acceleration) * tilt * tilt;
+        let (direction_x, direction_y) = (x as f64 / length as f64, y as f64 / length as f64);
+
+        self.set_position(self.x + direction_x * speed * delta, self.y + direction_y * speed * delta);
+        self.source = Some(PointerSource::Stick);
+    }
+
+    // only while the stick drives it
+    pub fn draw(&self, graphics: &mut Graphics) {
+        if self.source != Some(PointerSource::Stick) {
+            return;
+        }
+
+        let (x, y) = self.position();
+        let (outline, fill) = (Color::new(0, 0, 0, 255), Color::new(255, 255, 255, 255));
+        for (row, line) in ARROW.iter().enumerate() {
+            for (column, pixel) in line.chars().enumerate() {
+                let color = match pixel {
+                    'X' => &outline,
+                    '.' => &fill,
+                    _ => continue
+                };
+                graphics.set_pixel(x + column as i32, y + row as i32, color);
+            }
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use std::error::Error;
+use std::time::{Duration, Instant};
+
+// what launchers and chat clients show about the running game
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Presence {
+    pub chapter: String,
+    pub location: String,
+    // seconds
+    pub playtime: f64
+}
+
+impl Presence {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_playtime(&mut self, delta: f64) {
+        self.playtime += delta;
+    }
+
+    pub fn playtime_text(&self) -> String {
+        let minutes = (self.playtime / 60.0) as u64;
+        format!("{}:{:02}", minutes / 60, minutes % 60)
+    }
+}
+
+pub trait PresenceBackend {
+    fn update(&mut self, presence: &Presence) -> Result<(), Box<dyn Error>>;
+    fn clear(&mut self) -> Result<(), Box<dyn Error>>;
+}
+
+// used when no backend is compiled in or connecting failed
+pub struct NullPresence;
+
+impl PresenceBackend for NullPresence {
+    fn update(&mut self, _presence: &Presence) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+
+    fn clear(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+// discord drops updates sent more often than this
+pub const MIN_UPDATE_INTERVAL: Duration = Duration::from_secs(15);
+
+// forwards presence changes to the backend, at most once per interval and only when something shown changed
+pub struct PresenceManager {
+    backend: Box<dyn PresenceBackend>,
+    last_sent: Option<(Instant, String, String)>
+}
+
+impl PresenceManager {
+    pub fn new(backend: Box<dyn PresenceBackend>) -> Self {
+        PresenceManager { backend, last_sent: None }
+    }
+
+    pub fn disabled() -> Self {
+        Self::new(Box::new(NullPresence))
+    }
+
+    pub fn update(&mut self, presence: &Presence, now: Instant) -> Result<(), Box<dyn Error>> {
+        if let Some((sent_at, chapter, location)) = &self.last_sent {
+            if *chapter == presence.chapter && *location == presence.location {
+                return Ok(());
+            }
+
+            if now.duration_since(*sent_at) < MIN_UPDATE_INTERVAL {
+                return Ok(());
+            }
+        }
+
+        self.backend.update(presence)?;
+        self.last_sent = Some((now, presence.chapter.clone(), presence.location.clone()));
+
+        Ok(())
+    }
+
+    pub fn clear(&mut self) -> Result<(), Box<dyn Error>> {
+        self.last_sent = None;
+        self.backend.clear()
+    }
+}
+
+#[cfg(feature = "discord")]
+pub mod discord {
+    use std::error::Error;
+    use std::io::{Read, Write};
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+    use serde_json::json;
+    use super::{Presence, PresenceBackend};
+
+    const OPCODE_HANDSHAKE: u32 = 0;
+    const OPCODE_FRAME: u32 = 1;
+
+    #[cfg(unix)]
+    type Connection = std::os::unix::net::UnixStream;
+
+    #[cfg(windows)]
+    type Connection = std::fs::File;
+
+    #[cfg(unix)]
+    fn connect() -> Result<Connection, Box<dyn Error>> {
+        let directory = ["XDG_RUNTIME_DIR", "TMPDIR", "TMP", "TEMP"].iter()
+            .find_map(|name| std::env::var(name).ok())
+            .unwrap_or_else(|| "/tmp".to_string());
+
+        let mut last_error: Box<dyn Error> = "discord is not running".into();
+        for index in 0..10 {
+            match Connection::connect(format!("{}/discord-ipc-{}", directory, index)) {
+                Ok(connection) => return Ok(connection),
+                Err(error) => last_error = error.into()
+            }
+        }
+
+        Err(last_error)
+    }
+
+    #[cfg(windows)]
+    fn connect() -> Result<Connection, Box<dyn Error>> {
+        let mut last_error: Box<dyn Error> = "discord is not running".into();
+        for index in 0..10 {
+            match std::fs::OpenOptions::new().read(true).write(true).open(format!(r"\\.\pipe\discord-ipc-{}", index)) {
+                Ok(connection) => return Ok(connection),
+                Err(error) => last_error = error.into()
+            }
+        }
+
+        Err(last_error)
+    }
+
+    pub struct DiscordPresence {
+        connection: Connection,
+        nonce: u64
+    }
+
+    impl DiscordPresence {
+        pub fn connect(client_id: &str) -> Result<Self, Box<dyn Error>> {
+            let mut presence = DiscordPresence { connection: connect()?, nonce: 0 };
+
+            presence.send(OPCODE_HANDSHAKE, &json!({ "v": 1, "client_id": client_id }))?;
+            presence.receive()?;
+
+            Ok(presence)
+        }
+
+        fn send(&mut self, opcode: u32, payload: &serde_json::Value) -> Result<(), Box<dyn Error>> {
+            let data = serde_json::to_vec(payload)?;
+
+            let mut frame = Vec::with_capacity(data.len() + 8);
+            frame.write_u32::<LittleEndian>(opcode)?;
+            frame.write_u32::<LittleEndian>(data.len() as u32)?;
+            frame.extend_from_slice(&data);
+
+            self.connection.write_all(&frame)?;
+            Ok(())
+        }
+
+        // replies are only read to keep the pipe drained
+        fn receive(&mut self) -> Result<(), Box<dyn Error>> {
+            let _opcode = self.connection.read_u32::<LittleEndian>()?;
+            let length = self.connection.read_u32::<LittleEndian>()?;
+
+            let mut data = vec![0u8; length as usize];
+            self.connection.read_exact(&mut data)?;
+
+            Ok(())
+        }
+
+        fn set_activity(&mut self, activity: serde_json::Value) -> Result<(), Box<dyn Error>> {
+            self.nonce += 1;
+
+            let payload = json!({
+                "cmd": "SET_ACTIVITY",
+                "args": { "pid": std::process::id(), "activity": activity },
+                "nonce": self.nonce.to_string()
+            });
+
+            self.send(OPCODE_FRAME, &payload)?;
+            self.receive()
+        }
+    }
+
+    impl PresenceBackend for DiscordPresence {
+        fn update(&mut self, presence: &Presence) -> Result<(), Box<dyn Error>> {
+            // discord counts the elapsed time itself from the start timestamp
+            let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+            let start = now.saturating_sub(presence.playtime as u64);
+
+            self.set_activity(json!({
+                "details": presence.chapter,
+                "state": presence.location,
+                "timestamps": { "start": start }
+            }))
+        }
+
+        fn clear(&mut self) -> Result<(), Box<dyn Error>> {
+            self.set_activity(serde_json::Value::Null)
+        }
+    }
+}
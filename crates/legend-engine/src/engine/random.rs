@@ -0,0 +1,32 @@
+// xorshift, good enough for gameplay randomness without pulling in a dependency
+pub struct Random {
+    state: u32
+}
+
+impl Random {
+    pub fn new(seed: u32) -> Self {
+        Random { state: seed.max(1) }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    // inclusive range
+    pub fn range(&mut self, min: u32, max: u32) -> u32 {
+        if max <= min {
+            return min;
+        }
+
+        min + (self.next_u32() as u64 % (max as u64 - min as u64 + 1)) as u32
+    }
+}
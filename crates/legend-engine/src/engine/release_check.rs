@@ -0,0 +1,138 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use serde::Deserialize;
+use crate::engine::error::{EngineError, EngineResult};
+use crate::engine::vfs::Vfs;
+
+// a release of the game and the crc32 of its files, the checksum dumps of the discs list
+#[derive(Deserialize, Clone, Debug)]
+pub struct Release {
+    pub name: String,
+    pub files: BTreeMap<String, String>
+}
+
+#[derive(Deserialize)]
+struct ReleasesFile {
+    #[serde(default)]
+    release: Vec<Release>
+}
+
+// every known release of the game, to tell which one the game data is and what is broken in it
+// [[release]]
+// name = "cd, traditional chinese"
+// [release.files]
+// "ENGLISH.FNT" = "1a2b3c4d"
+pub struct Releases {
+    releases: Vec<Release>
+}
+
+// how the game data compares to one release
+#[derive(Clone, Debug)]
+pub struct ReleaseMatch {
+    pub release: String,
+    pub matched: usize,
+    pub missing: Vec<String>,
+    // name, expected and actual checksum
+    pub changed: Vec<(String, String, String)>
+}
+
+impl ReleaseMatch {
+    pub fn is_exact(&self) -> bool {
+        self.missing.is_empty() && self.changed.is_empty()
+    }
+
+    pub fn total(&self) -> usize {
+        self.matched + self.missing.len() + self.changed.len()
+    }
+}
+
+pub fn checksum(bytes: &[u8]) -> String {
+    format!("{:08x}", crc32fast::hash(bytes))
+}
+
+impl Releases {
+    pub fn from_toml(source: &str) -> EngineResult<Self> {
+        let file: ReleasesFile = toml::from_str(source)?;
+        Ok(Releases { releases: file.release })
+    }
+
+    pub fn load(filename: &Path) -> EngineResult<Self> {
+        if !filename.is_file() {
+            return Err(EngineError::NotFound(format!("release checksums {}", filename.display())));
+        }
+
+        Self::from_toml(&fs::read_to_string(filename)?)
+    }
+
+    // every file any release lists is read once
+    pub fn verify(&self, vfs: &Vfs) -> EngineResult<VerifyReport> {
+        let mut checksums: HashMap<String, Option<String>> = HashMap::new();
+        let mut matches = Vec::new();
+
+        for release in self.releases.iter() {
+            let mut result = ReleaseMatch { release: release.name.clone(), matched: 0, missing: Vec::new(), changed: Vec::new() };
+
+            for (name, expected) in release.files.iter() {
+                let key = name.to_lowercase();
+                if !checksums.contains_key(&key) {
+                    let actual = match vfs.exists(name) {
+                        true => Some(checksum(&vfs.read(name)?)),
+                        false => None
+                    };
+                    checksums.insert(key.clone(), actual);
+                }
+
+                match &checksums[&key] {
+                    Some(actual) if actual.eq_ignore_ascii_case(expected) => result.matched += 1,
+                    Some(actual) => result.changed.push((name.clone(), expected.to_lowercase(), actual.clone())),
+                    None => result.missing.push(name.clone())
+                }
+            }
+
+            matches.push(result);
+        }
+
+        // the exact release first, then the ones with the most files in common
+        matches.sort_by_key(|result| (!result.is_exact(), std::cmp::Reverse(result.matched)));
+        Ok(VerifyReport { data_path: vfs.describe(), matches })
+    }
+}
+
+pub struct VerifyReport {
+    pub data_path: String,
+    // best match first
+    pub matches: Vec<ReleaseMatch>
+}
+
+impl VerifyReport {
+    // the release every file matches
+    pub fn detected(&self) -> Option<&ReleaseMatch> {
+        self.matches.first().filter(|result| result.is_exact())
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![ format!("game data in {}:", self.data_path) ];
+
+        let best = match self.matches.first() {
+            Some(best) if best.matched > 0 => best,
+            _ => {
+                lines.push("  no file matches a known release".to_string());
+                return lines;
+            }
+        };
+
+        match best.is_exact() {
+            true => lines.push(format!("  detected {}, all {} files match", best.release, best.total())),
+            false => lines.push(format!("  closest to {}, {} of {} files match", best.release, best.matched, best.total()))
+        }
+        for name in best.missing.iter() {
+            lines.push(format!("  missing  {}", name));
+        }
+        for (name, expected, actual) in best.changed.iter() {
+            lines.push(format!("  changed  {} is {}, {} expected", name, actual, expected));
+        }
+
+        lines
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+// files read more often than this are worth caching
+const REPEATED_READS: usize = 2;
+// rows of the slowest and most repeated lists in the summary
+const SUMMARY_ROWS: usize = 10;
+
+// one read through the vfs
+#[derive(Clone, Debug)]
+pub struct TraceEntry {
+    pub name: String,
+    // the mount the file came from, None when no mount had it
+    pub layer: Option<String>,
+    pub bytes: usize,
+    pub time: Duration
+}
+
+#[derive(Copy, Clone, Default)]
+struct LayerTotals {
+    reads: usize,
+    bytes: usize,
+    time: Duration
+}
+
+// what --trace-resources prints for every read and sums up at exit
+#[derive(Default)]
+pub struct ResourceTrace {
+    entries: Vec<TraceEntry>
+}
+
+impl ResourceTrace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, entry: TraceEntry) {
+        match &entry.layer {
+            Some(layer) => eprintln!("[vfs] {} <- {} ({} bytes, {:.2}ms)", entry.name, layer, entry.bytes, entry.time.as_secs_f64() * 1000.0),
+            None => eprintln!("[vfs] {} not found ({:.2}ms)", entry.name, entry.time.as_secs_f64() * 1000.0)
+        }
+        self.entries.push(entry);
+    }
+
+    pub fn entries(&self) -> &[TraceEntry] {
+        &self.entries
+    }
+
+    // totals per layer, the files nobody had, the ones read over and over and the slowest ones
+    pub fn summary(&self) -> Vec<String> {
+        let milliseconds = |time: Duration| time.as_secs_f64() * 1000.0;
+        let mut layers: BTreeMap<&str, LayerTotals> = BTreeMap::new();
+        let mut reads: BTreeMap<String, usize> = BTreeMap::new();
+        let mut missing: Vec<&str> = Vec::new();
+
+        for entry in self.entries.iter() {
+            *reads.entry(entry.name.to_lowercase()).or_default() += 1;
+            match &entry.layer {
+                Some(layer) => {
+                    let totals = layers.entry(layer.as_str()).or_default();
+                    totals.reads += 1;
+                    totals.bytes += entry.bytes;
+                    totals.time += entry.time;
+                },
+                None => missing.push(entry.name.as_str())
+            }
+        }
+
+        let total_bytes: usize = self.entries.iter().map(|entry| entry.bytes).sum();
+        let total_time: Duration = self.entries.iter().map(|entry| entry.time).sum();
+        let mut lines = vec![format!("{} reads of {} files, {} bytes in {:.2}ms", self.entries.len(), reads.len(), total_bytes, milliseconds(total_time))];
+
+        for (layer, totals) in layers.iter() {
+            lines.push(format!("  {}: {} reads, {} bytes in {:.2}ms", layer, totals.reads, totals.bytes, milliseconds(totals.time)));
+        }
+
+        missing.sort_unstable();
+        missing.dedup();
+        if !missing.is_empty() {
+            lines.push(format!("not found: {}", missing.join(", ")));
+        }
+
+        let mut repeated: Vec<(&String, &usize)> = reads.iter().filter(|(_, &count)| count >= REPEATED_READS).collect();
+        repeated.sort_by_key(|(_, &count)| std::cmp::Reverse(count));
+        if !repeated.is_empty() {
+            lines.push("read more than once:".to_string());
+            lines.extend(repeated.iter().take(SUMMARY_ROWS).map(|(name, count)| format!("  {} x{}", name, count)));
+        }
+
+        let mut slowest: Vec<&TraceEntry> = self.entries.iter().collect();
+        slowest.sort_by_key(|entry| std::cmp::Reverse(entry.time));
+        if !slowest.is_empty() {
+            lines.push("slowest reads:".to_string());
+            lines.extend(slowest.iter().take(SUMMARY_ROWS).map(|entry| format!("  {} {:.2}ms", entry.name, milliseconds(entry.time))));
+        }
+
+        lines
+    }
+}
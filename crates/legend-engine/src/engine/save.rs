@@ -0,0 +1,191 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use crate::engine::atomic_file::write_atomic;
+use crate::engine::compression::Compression;
+use crate::engine::error::{EngineError, EngineResult};
+
+// bumped whenever the layout of the file changes, older files are still read
+pub const SAVE_VERSION: u32 = 2;
+const SAVE_MAGIC: &str = "legend-clover-save";
+const SAVE_EXTENSION: &str = "sav";
+
+// plain data a script can store, scripts build tables with SaveTable()
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+pub enum SaveValue {
+    Null,
+    Boolean(bool),
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Array(Vec<SaveValue>),
+    Table(BTreeMap<String, SaveValue>)
+}
+
+impl SaveValue {
+    // engine state like the party, as plain data a save table can hold
+    pub fn from_data<T: Serialize>(data: &T) -> EngineResult<Self> {
+        let value = serde_json::to_value(data).map_err(|error| EngineError::Save(error.to_string()))?;
+        serde_json::from_value(value).map_err(|error| EngineError::Save(error.to_string()))
+    }
+
+    // and back, an error when the value was not written by from_data for T
+    pub fn to_data<T: DeserializeOwned>(&self) -> EngineResult<T> {
+        let value = serde_json::to_value(self).map_err(|error| EngineError::Save(error.to_string()))?;
+        serde_json::from_value(value).map_err(|error| EngineError::Save(error.to_string()))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SaveGame {
+    pub slot: u32,
+    pub version: u32,
+    // seconds since the unix epoch
+    pub saved_at: u64,
+    pub data: SaveValue
+}
+
+// what the load menu lists, corrupt slots are shown so the player knows they are gone
+#[derive(Clone, Debug)]
+pub struct SlotInfo {
+    pub slot: u32,
+    pub version: Option<u32>,
+    pub saved_at: Option<u64>,
+    pub corrupt: bool
+}
+
+struct SaveHeader {
+    version: u32,
+    checksum: u32,
+    saved_at: u64,
+    compression: Compression
+}
+
+// "legend-clover-save <version> <crc32 of the body in hex> <saved at> <compression>" on the first line,
+// the json body after it. version 1 files have no compression and leave it out
+fn parse_header(line: &str) -> Option<SaveHeader> {
+    let parts: Vec<&str> = line.split_whitespace().collect();
+    let (version, checksum, saved_at, compression) = match parts.as_slice() {
+        [SAVE_MAGIC, version, checksum, saved_at] => (version, checksum, saved_at, Compression::None),
+        [SAVE_MAGIC, version, checksum, saved_at, compression] => (version, checksum, saved_at, Compression::from_name(compression)?),
+        _ => return None
+    };
+
+    Some(SaveHeader {
+        version: version.parse().ok()?,
+        checksum: u32::from_str_radix(checksum, 16).ok()?,
+        saved_at: saved_at.parse().ok()?,
+        compression
+    })
+}
+
+fn corrupt(slot: u32, reason: &str) -> EngineError {
+    EngineError::Save(format!("slot {} is corrupt, {}", slot, reason))
+}
+
+// one file per slot under the save directory
+pub struct SaveStore {
+    directory: PathBuf,
+    // for new saves, files are read back whatever they were written with
+    compression: Compression
+}
+
+impl SaveStore {
+    pub fn new(directory: &Path) -> Self {
+        SaveStore { directory: directory.to_path_buf(), compression: Compression::None }
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    // the user data folder of the platform, like ~/.local/share/legend-clover/saves,
+    // or saves next to the game when there is none
+    pub fn default_directory() -> PathBuf {
+        dirs::data_dir()
+            .map(|directory| directory.join("legend-clover").join("saves"))
+            .unwrap_or_else(|| PathBuf::from("saves"))
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    fn path(&self, slot: u32) -> PathBuf {
+        self.directory.join(format!("slot_{:02}.{}", slot, SAVE_EXTENSION))
+    }
+
+    pub fn write(&self, slot: u32, data: &SaveValue) -> EngineResult<()> {
+        let json = serde_json::to_vec(data).map_err(|error| EngineError::Save(error.to_string()))?;
+        // the checksum covers the stored bytes, so damage shows before anything is decompressed
+        let body = self.compression.compress(&json)?;
+        let saved_at = SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0);
+        let header = format!("{} {} {:08x} {} {}\n", SAVE_MAGIC, SAVE_VERSION, crc32fast::hash(&body), saved_at, self.compression.name());
+
+        fs::create_dir_all(&self.directory)?;
+        write_atomic(self.path(slot), [header.as_bytes(), &body].concat())?;
+        Ok(())
+    }
+
+    pub fn exists(&self, slot: u32) -> bool {
+        self.path(slot).is_file()
+    }
+
+    pub fn read(&self, slot: u32) -> EngineResult<SaveGame> {
+        let path = self.path(slot);
+        if !path.is_file() {
+            return Err(EngineError::NotFound(format!("save slot {}", slot)));
+        }
+
+        let contents = fs::read(&path)?;
+        let split = contents.iter().position(|&byte| byte == b'\n').ok_or_else(|| corrupt(slot, "the header is missing"))?;
+        let (header, body) = (&contents[..split], &contents[split + 1..]);
+
+        let header = std::str::from_utf8(header).ok().and_then(parse_header).ok_or_else(|| corrupt(slot, "the header is not readable"))?;
+        if header.version > SAVE_VERSION {
+            return Err(EngineError::Save(format!("slot {} was written by a newer version (save version {})", slot, header.version)));
+        }
+
+        if crc32fast::hash(body) != header.checksum {
+            return Err(corrupt(slot, "the checksum does not match"));
+        }
+
+        let body = header.compression.decompress(body).map_err(|_| corrupt(slot, "the body does not decompress"))?;
+        let data = serde_json::from_slice(&body).map_err(|error| corrupt(slot, &error.to_string()))?;
+        Ok(SaveGame { slot, version: header.version, saved_at: header.saved_at, data })
+    }
+
+    pub fn delete(&self, slot: u32) -> EngineResult<()> {
+        let path = self.path(slot);
+        if path.is_file() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    // every slot with a file, in order
+    pub fn slots(&self) -> Vec<SlotInfo> {
+        let entries = match fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new()
+        };
+
+        let mut slots: Vec<u32> = entries.flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                name.strip_prefix("slot_")?.strip_suffix(&format!(".{}", SAVE_EXTENSION))?.parse().ok()
+            })
+            .collect();
+        slots.sort_unstable();
+
+        slots.into_iter().map(|slot| match self.read(slot) {
+            Ok(save) => SlotInfo { slot, version: Some(save.version), saved_at: Some(save.saved_at), corrupt: false },
+            Err(_) => SlotInfo { slot, version: None, saved_at: None, corrupt: true }
+        }).collect()
+    }
+}
@@ -0,0 +1,75 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::engine::error::{EngineError, EngineResult};
+use crate::engine::graphics::Graphics;
+
+// days since 1970-01-01 to year, month and day
+fn civil_date(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    // months counted from march, so the leap day is the last one
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month as u32, day as u32)
+}
+
+// utc, so the names sort in the order they were taken
+fn timestamp(seconds: u64) -> String {
+    let (year, month, day) = civil_date((seconds / 86400) as i64);
+    let time = seconds % 86400;
+    format!("{:04}-{:02}-{:02}_{:02}-{:02}-{:02}", year, month, day, time / 3600, time / 60 % 60, time % 60)
+}
+
+// f12 writes the frame buffer to legend_2022-07-01_12-30-00.png in here
+pub struct Screenshots {
+    directory: PathBuf
+}
+
+impl Screenshots {
+    pub fn new(directory: &Path) -> Self {
+        Screenshots { directory: directory.to_path_buf() }
+    }
+
+    // next to the saves, like ~/.local/share/legend-clover/screenshots
+    pub fn default_directory() -> PathBuf {
+        dirs::data_dir()
+            .map(|directory| directory.join("legend-clover").join("screenshots"))
+            .unwrap_or_else(|| PathBuf::from("screenshots"))
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    // the game image alone, without the debug overlays drawn on top when presenting
+    pub fn take(&self, graphics: &Graphics) -> EngineResult<PathBuf> {
+        let path = self.next_path()?;
+        let filename = path.to_str().ok_or_else(|| EngineError::NotFound(format!("a usable file name for {}", path.display())))?;
+        graphics.frame_buffer().save(filename)?;
+        Ok(path)
+    }
+
+    // a new file in the folder, which is made when missing, photo mode writes its photos there too
+    pub fn next_path(&self) -> EngineResult<PathBuf> {
+        fs::create_dir_all(&self.directory)?;
+
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0);
+        let stamp = timestamp(seconds);
+        let mut path = self.directory.join(format!("legend_{}.png", stamp));
+        // more than one in the same second
+        let mut count = 2;
+        while path.exists() {
+            path = self.directory.join(format!("legend_{}_{}.png", stamp, count));
+            count += 1;
+        }
+
+        Ok(path)
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::engine::action_map::ActionMap;
+use crate::engine::atomic_file::write_atomic;
+use crate::engine::clock::{DEFAULT_UPDATE_RATE, UPDATE_RATES};
+use crate::engine::dialog::TextSpeed;
+use crate::engine::error::EngineResult;
+
+pub const MIN_SCALE: u32 = 1;
+pub const MAX_SCALE: u32 = 10;
+
+// what a player sets once instead of passing on every launch, missing keys keep their default
+// scale = 3
+// update_rate = 30
+// audio_buffer = 256
+// data_path = "C:/LEGEND"
+// script = "./mods/arena/main.luck"
+// [bindings]
+// confirm = ["z"]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub scale: u32,
+    pub fullscreen: bool,
+    // game updates per second, one of UPDATE_RATES
+    pub update_rate: u32,
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sound_volume: f32,
+    // output device name, sample rate and frames per callback, the platform defaults when left out
+    pub audio_device: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub audio_buffer: Option<u32>,
+    // folder which contain the original game install path or CD
+    pub data_path: Option<String>,
+    // entry script instead of scripts/main.luck, --script wins over it
+    pub script: Option<String>,
+    pub language: String,
+    // a TextSpeed name
+    pub text_speed: String,
+    // read dialogue and menus aloud, needs a build with the tts feature
+    pub screen_reader: bool,
+    // deflate save files, worth it once saves hold whole engine snapshots
+    pub compress_saves: bool,
+    // actions the player rebound, the others keep the keys of the original game.
+    // a table, so it has to stay the last field for toml
+    pub bindings: BTreeMap<String, Vec<String>>
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            scale: 2,
+            fullscreen: false,
+            update_rate: DEFAULT_UPDATE_RATE,
+            master_volume: 1.0,
+            music_volume: 1.0,
+            sound_volume: 1.0,
+            audio_device: None,
+            sample_rate: None,
+            audio_buffer: None,
+            data_path: None,
+            script: None,
+            language: "en".to_string(),
+            text_speed: TextSpeed::default().name().to_string(),
+            screen_reader: false,
+            compress_saves: false,
+            bindings: BTreeMap::new()
+        }
+    }
+}
+
+impl Settings {
+    pub fn from_toml(source: &str) -> EngineResult<Self> {
+        let mut settings: Settings = toml::from_str(source)?;
+        settings.clamp();
+        Ok(settings)
+    }
+
+    // hand edited files can hold anything, out of range values are pulled back in
+    fn clamp(&mut self) {
+        self.scale = self.scale.clamp(MIN_SCALE, MAX_SCALE);
+        if !UPDATE_RATES.contains(&self.update_rate) {
+            self.update_rate = DEFAULT_UPDATE_RATE;
+        }
+        // a rate or buffer of 0 can not open any device
+        self.sample_rate = self.sample_rate.filter(|&sample_rate| sample_rate > 0);
+        self.audio_buffer = self.audio_buffer.filter(|&audio_buffer| audio_buffer > 0);
+        self.master_volume = self.master_volume.clamp(0.0, 1.0);
+        self.music_volume = self.music_volume.clamp(0.0, 1.0);
+        self.sound_volume = self.sound_volume.clamp(0.0, 1.0);
+        self.text_speed = self.text_speed().name().to_string();
+    }
+
+    pub fn text_speed(&self) -> TextSpeed {
+        TextSpeed::from_name(&self.text_speed).unwrap_or_default()
+    }
+
+    pub fn bind_actions(&self, actions: &mut ActionMap) {
+        for (action, keys) in self.bindings.iter() {
+            actions.bind(action, keys.clone());
+        }
+    }
+}
+
+// what the command line chose for this launch, on top of the file and never written into it
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SettingsOverrides {
+    pub scale: Option<u32>,
+    pub fullscreen: Option<bool>,
+    pub update_rate: Option<u32>,
+    pub audio_device: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub audio_buffer: Option<u32>,
+    pub data_path: Option<String>
+}
+
+impl SettingsOverrides {
+    pub fn apply(&self, settings: &mut Settings) {
+        if let Some(scale) = self.scale {
+            settings.scale = scale;
+        }
+        if let Some(fullscreen) = self.fullscreen {
+            settings.fullscreen = fullscreen;
+        }
+        if let Some(update_rate) = self.update_rate {
+            settings.update_rate = update_rate;
+        }
+        if let Some(audio_device) = &self.audio_device {
+            settings.audio_device = Some(audio_device.clone());
+        }
+        if let Some(sample_rate) = self.sample_rate {
+            settings.sample_rate = Some(sample_rate);
+        }
+        if let Some(audio_buffer) = self.audio_buffer {
+            settings.audio_buffer = Some(audio_buffer);
+        }
+        if let Some(data_path) = &self.data_path {
+            settings.data_path = Some(data_path.clone());
+        }
+    }
+
+    // the file value back wherever the settings still hold what the command line set,
+    // a value the options menu or a script changed since is the player's and is kept
+    fn remove(&self, settings: &mut Settings, stored: &Settings) {
+        fn restore<T: PartialEq + Clone>(value: &mut T, overridden: Option<T>, stored: &T) {
+            if overridden.as_ref() == Some(value) {
+                *value = stored.clone();
+            }
+        }
+
+        restore(&mut settings.scale, self.scale, &stored.scale);
+        restore(&mut settings.fullscreen, self.fullscreen, &stored.fullscreen);
+        restore(&mut settings.update_rate, self.update_rate, &stored.update_rate);
+        restore(&mut settings.audio_device, self.audio_device.clone().map(Some), &stored.audio_device);
+        restore(&mut settings.sample_rate, self.sample_rate.map(Some), &stored.sample_rate);
+        restore(&mut settings.audio_buffer, self.audio_buffer.map(Some), &stored.audio_buffer);
+        restore(&mut settings.data_path, self.data_path.clone().map(Some), &stored.data_path);
+    }
+}
+
+// the settings and the file they came from, so the options menu can write them back.
+// settings is what the game runs with, the file plus the command line
+pub struct SettingsFile {
+    path: PathBuf,
+    // what the file holds, as of the last load or save
+    stored: Settings,
+    overrides: SettingsOverrides,
+    pub settings: Settings
+}
+
+impl SettingsFile {
+    pub fn new(path: &Path, settings: Settings) -> Self {
+        SettingsFile { path: path.to_path_buf(), stored: settings.clone(), overrides: SettingsOverrides::default(), settings }
+    }
+
+    pub fn with_overrides(mut self, overrides: SettingsOverrides) -> Self {
+        overrides.apply(&mut self.settings);
+        self.overrides = overrides;
+        self
+    }
+
+    // settings.toml in the config folder of the platform, like ~/.config/legend-clover,
+    // or next to the game when there is none
+    pub fn default_path() -> PathBuf {
+        dirs::config_dir()
+            .map(|directory| directory.join("legend-clover").join("settings.toml"))
+            .unwrap_or_else(|| PathBuf::from("settings.toml"))
+    }
+
+    // a missing file is the first launch, so the defaults are used
+    pub fn load(path: &Path) -> EngineResult<Self> {
+        let settings = if path.is_file() {
+            Settings::from_toml(&fs::read_to_string(path)?)?
+        } else {
+            Settings::default()
+        };
+
+        Ok(Self::new(path, settings))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // the settings without the command line, so a --scale or --data-path for one launch does not stick
+    pub fn save(&mut self) -> EngineResult<()> {
+        if let Some(directory) = self.path.parent().filter(|directory| !directory.as_os_str().is_empty()) {
+            fs::create_dir_all(directory)?;
+        }
+
+        let mut stored = self.settings.clone();
+        self.overrides.remove(&mut stored, &self.stored);
+        write_atomic(&self.path, toml::to_string(&stored)?)?;
+        self.stored = stored;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    fn settings_file(name: &str) -> SettingsFile {
+        let path = env::temp_dir().join(format!("legend-clover-settings-{}-{}.toml", name, process::id()));
+        let stored = Settings { scale: 3, ..Settings::default() };
+        SettingsFile::new(&path, stored).with_overrides(SettingsOverrides { scale: Some(5), data_path: Some("D:/".to_string()), ..SettingsOverrides::default() })
+    }
+
+    fn saved(file: &SettingsFile) -> Settings {
+        let settings = Settings::from_toml(&fs::read_to_string(file.path()).unwrap()).unwrap();
+        fs::remove_file(file.path()).unwrap();
+        settings
+    }
+
+    #[test]
+    fn overrides_are_not_saved() {
+        let mut file = settings_file("overrides");
+        assert_eq!(file.settings.scale, 5);
+        assert_eq!(file.settings.data_path.as_deref(), Some("D:/"));
+
+        // what the options menu does, closing it saves
+        file.settings.music_volume = 0.5;
+        file.save().unwrap();
+
+        let settings = saved(&file);
+        assert_eq!(settings.scale, 3);
+        assert_eq!(settings.data_path, None);
+        assert_eq!(settings.music_volume, 0.5);
+        assert_eq!(file.settings.scale, 5);
+    }
+
+    #[test]
+    fn changed_overrides_are_saved() {
+        let mut file = settings_file("changed");
+
+        file.settings.scale = 4;
+        file.save().unwrap();
+
+        assert_eq!(saved(&file).scale, 4);
+    }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use serde::Deserialize;
+use crate::engine::error::EngineResult;
+use crate::engine::stats::Stats;
+
+#[derive(Deserialize, Copy, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SkillTarget {
+    SingleEnemy,
+    AllEnemies,
+    SingleAlly,
+    AllAllies,
+    User
+}
+
+impl SkillTarget {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SkillTarget::SingleEnemy => "single_enemy",
+            SkillTarget::AllEnemies => "all_enemies",
+            SkillTarget::SingleAlly => "single_ally",
+            SkillTarget::AllAllies => "all_allies",
+            SkillTarget::User => "user"
+        }
+    }
+}
+
+#[derive(Deserialize, Copy, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SkillKind {
+    Damage,
+    Heal
+}
+
+impl SkillKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SkillKind::Damage => "damage",
+            SkillKind::Heal => "heal"
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Skill {
+    pub id: u32,
+    pub name: String,
+    #[serde(default)]
+    pub cost: i32,
+    pub target: SkillTarget,
+    pub kind: SkillKind,
+    pub power: i32,
+    // battle effect animation played on the targets
+    #[serde(default)]
+    pub animation: Option<u32>,
+    // status effect applied to every target
+    #[serde(default)]
+    pub status: Option<String>
+}
+
+#[derive(Deserialize)]
+struct SkillFile {
+    #[serde(default)]
+    skill: Vec<Skill>
+}
+
+pub struct SkillDatabase {
+    skills: HashMap<u32, Skill>
+}
+
+impl SkillDatabase {
+    pub fn new() -> Self {
+        SkillDatabase { skills: HashMap::new() }
+    }
+
+    // [[skill]] tables, see Skill for the fields
+    pub fn from_toml(source: &str) -> EngineResult<Self> {
+        let file: SkillFile = toml::from_str(source)?;
+
+        let mut database = Self::new();
+        for skill in file.skill {
+            database.insert(skill);
+        }
+
+        Ok(database)
+    }
+
+    pub fn load(filename: &str) -> EngineResult<Self> {
+        Self::from_toml(&fs::read_to_string(filename)?)
+    }
+
+    pub fn insert(&mut self, skill: Skill) {
+        self.skills.insert(skill.id, skill);
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Skill> {
+        self.skills.get(&id)
+    }
+}
+
+impl Default for SkillDatabase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// games replace the formula to match their own damage math
+pub trait SkillFormula {
+    fn amount(&self, skill: &Skill, user: &Stats, target: &Stats) -> i32;
+}
+
+pub struct DefaultFormula;
+
+impl SkillFormula for DefaultFormula {
+    fn amount(&self, skill: &Skill, user: &Stats, target: &Stats) -> i32 {
+        match skill.kind {
+            SkillKind::Damage => (skill.power + user.attack - target.defence / 2).max(1),
+            SkillKind::Heal => skill.power.max(0)
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SkillResult {
+    // index into the targets passed to execute
+    pub target: usize,
+    // positive amount of damage or healing that was applied
+    pub amount: i32,
+    pub kind: SkillKind,
+    pub status: Option<String>,
+    pub defeated: bool
+}
+
+// everything the battle ui needs to show the skill
+#[derive(Clone, Debug)]
+pub struct SkillOutcome {
+    pub skill: u32,
+    pub animation: Option<u32>,
+    pub results: Vec<SkillResult>
+}
+
+#[derive(Debug)]
+pub enum SkillError {
+    UnknownSkill(u32),
+    NotEnoughMp { cost: i32, mp: i32 },
+    NoTarget
+}
+
+impl fmt::Display for SkillError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkillError::UnknownSkill(id) => write!(f, "unknown skill {}", id),
+            SkillError::NotEnoughMp { cost, mp } => write!(f, "skill needs {} mp but only {} left", cost, mp),
+            SkillError::NoTarget => write!(f, "skill has no target")
+        }
+    }
+}
+
+impl Error for SkillError {}
+
+// targets were already chosen by the battle code following skill.target
+pub fn execute_skill(database: &SkillDatabase, id: u32, user: &mut Stats, targets: &mut [&mut Stats], formula: &dyn SkillFormula) -> Result<SkillOutcome, SkillError> {
+    let skill = database.get(id).ok_or(SkillError::UnknownSkill(id))?;
+
+    if user.mp < skill.cost {
+        return Err(SkillError::NotEnoughMp { cost: skill.cost, mp: user.mp });
+    }
+
+    if targets.is_empty() && skill.target != SkillTarget::User {
+        return Err(SkillError::NoTarget);
+    }
+
+    user.mp -= skill.cost;
+
+    let mut results = Vec::new();
+
+    if skill.target == SkillTarget::User {
+        let amount = formula.amount(skill, user, user);
+        apply_amount(skill, user, amount);
+        results.push(SkillResult { target: 0, amount, kind: skill.kind, status: skill.status.clone(), defeated: !user.is_alive() });
+    } else {
+        for (index, target) in targets.iter_mut().enumerate() {
+            let amount = formula.amount(skill, user, target);
+            apply_amount(skill, target, amount);
+            results.push(SkillResult { target: index, amount, kind: skill.kind, status: skill.status.clone(), defeated: !target.is_alive() });
+        }
+    }
+
+    Ok(SkillOutcome { skill: skill.id, animation: skill.animation, results })
+}
+
+fn apply_amount(skill: &Skill, target: &mut Stats, amount: i32) {
+    match skill.kind {
+        SkillKind::Damage => target.change_hp(-amount),
+        SkillKind::Heal => target.change_hp(amount)
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::HashMap;
+use crate::engine::graphics::{Image, Palette, RleImage};
+
+// decoded frames keyed by (sprite key, palette id), a static scene decodes each of its sprites once
+pub struct SpriteCache {
+    images: HashMap<(usize, u64), Image>,
+    // the generation each palette was at when its frames were decoded
+    generations: HashMap<u64, u64>
+}
+
+impl SpriteCache {
+    pub fn new() -> Self {
+        SpriteCache { images: HashMap::new(), generations: HashMap::new() }
+    }
+
+    pub fn get(&mut self, key: usize, source: &RleImage, palette: &Palette) -> &Image {
+        let (palette_id, generation) = (palette.id(), palette.generation());
+
+        // a palette that changed takes every frame decoded with it along
+        if self.generations.insert(palette_id, generation) != Some(generation) {
+            self.images.retain(|&(_, cached_palette), _| cached_palette != palette_id);
+        }
+
+        self.images.entry((key, palette_id)).or_insert_with(|| source.decode(palette))
+    }
+
+    pub fn blit(&mut self, target: &mut Image, key: usize, source: &RleImage, x: i32, y: i32, palette: &Palette) {
+        if source.is_empty() {
+            return;
+        }
+
+        let image = self.get(key, source, palette);
+        target.alpha_blit(image, x + source.offset.x as i32, y + source.offset.y as i32, 1.0);
+    }
+
+    pub fn invalidate(&mut self, key: usize) {
+        self.images.retain(|&(cached_key, _), _| cached_key != key);
+    }
+
+    pub fn clear(&mut self) {
+        self.images.clear();
+        self.generations.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.images.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty()
+    }
+}
+
+impl Default for SpriteCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
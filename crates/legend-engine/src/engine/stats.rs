@@ -0,0 +1,69 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    #[serde(default)]
+    pub hp: i32,
+    #[serde(default)]
+    pub max_hp: i32,
+    #[serde(default)]
+    pub mp: i32,
+    #[serde(default)]
+    pub max_mp: i32,
+    #[serde(default)]
+    pub attack: i32,
+    #[serde(default)]
+    pub defence: i32,
+    #[serde(default)]
+    pub speed: i32
+}
+
+impl Stats {
+    pub fn get(&self, name: &str) -> Option<i32> {
+        match name {
+            "hp" => Some(self.hp),
+            "max_hp" => Some(self.max_hp),
+            "mp" => Some(self.mp),
+            "max_mp" => Some(self.max_mp),
+            "attack" => Some(self.attack),
+            "defence" => Some(self.defence),
+            "speed" => Some(self.speed),
+            _ => None
+        }
+    }
+
+    pub fn set(&mut self, name: &str, value: i32) -> bool {
+        match name {
+            "hp" => self.hp = value,
+            "max_hp" => self.max_hp = value,
+            "mp" => self.mp = value,
+            "max_mp" => self.max_mp = value,
+            "attack" => self.attack = value,
+            "defence" => self.defence = value,
+            "speed" => self.speed = value,
+            _ => return false
+        };
+        true
+    }
+
+    // field by field sum, used for growth and equipment bonuses
+    pub fn add(&self, other: &Stats) -> Stats {
+        Stats {
+            hp: self.hp + other.hp,
+            max_hp: self.max_hp + other.max_hp,
+            mp: self.mp + other.mp,
+            max_mp: self.max_mp + other.max_mp,
+            attack: self.attack + other.attack,
+            defence: self.defence + other.defence,
+            speed: self.speed + other.speed
+        }
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.hp > 0
+    }
+
+    pub fn change_hp(&mut self, amount: i32) {
+        self.hp = (self.hp + amount).clamp(0, self.max_hp.max(0));
+    }
+}
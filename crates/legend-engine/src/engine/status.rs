@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use crate::engine::graphics::{Image, Palette, RleImage};
+
+// what happens when an effect is applied to someone who already has it
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum StackRule {
+    // restart the duration
+    Refresh,
+    // add the new duration to what is left
+    Extend,
+    // add a stack up to the limit, the duration is refreshed
+    Stack(u32),
+    // keep the current one
+    Ignore
+}
+
+#[derive(Clone, Debug)]
+pub struct StatusDefinition {
+    pub name: String,
+    // in turns, 0 lasts until removed
+    pub duration: u32,
+    pub stack_rule: StackRule,
+    // hp change per stack every turn, negative for poison
+    pub hp_per_turn: i32,
+    // stat name and amount per stack, like ("attack", 10)
+    pub modifiers: Vec<(String, i32)>,
+    pub prevents_action: bool,
+    pub icon: Option<usize>
+}
+
+impl StatusDefinition {
+    pub fn new(name: &str, duration: u32, stack_rule: StackRule) -> Self {
+        StatusDefinition {
+            name: name.to_string(),
+            duration,
+            stack_rule,
+            hp_per_turn: 0,
+            modifiers: Vec::new(),
+            prevents_action: false,
+            icon: None
+        }
+    }
+}
+
+// known effects, games and scripts register their own next to the built in ones
+pub struct StatusRegistry {
+    definitions: HashMap<String, StatusDefinition>
+}
+
+impl StatusRegistry {
+    pub fn new() -> Self {
+        StatusRegistry { definitions: HashMap::new() }
+    }
+
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+
+        let mut poison = StatusDefinition::new("poison", 5, StackRule::Stack(5));
+        poison.hp_per_turn = -5;
+        registry.register(poison);
+
+        let mut sleep = StatusDefinition::new("sleep", 3, StackRule::Refresh);
+        sleep.prevents_action = true;
+        registry.register(sleep);
+
+        let mut attack_up = StatusDefinition::new("attack_up", 3, StackRule::Extend);
+        attack_up.modifiers.push(("attack".to_string(), 10));
+        registry.register(attack_up);
+
+        let mut defence_up = StatusDefinition::new("defence_up", 3, StackRule::Extend);
+        defence_up.modifiers.push(("defence".to_string(), 10));
+        registry.register(defence_up);
+
+        registry
+    }
+
+    pub fn register(&mut self, definition: StatusDefinition) {
+        self.definitions.insert(definition.name.clone(), definition);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&StatusDefinition> {
+        self.definitions.get(name)
+    }
+}
+
+impl Default for StatusRegistry {
+    fn default() -> Self {
+        Self::with_defaults()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct StatusEffect {
+    pub name: String,
+    // None lasts until removed
+    pub remaining: Option<u32>,
+    pub stacks: u32
+}
+
+#[derive(Clone, Debug)]
+pub struct StatusTick {
+    pub name: String,
+    pub hp_delta: i32,
+    pub expired: bool
+}
+
+// effects on one character, the same set is used in battle and on the field
+#[derive(Clone, Debug, Default)]
+pub struct StatusSet {
+    effects: Vec<StatusEffect>
+}
+
+impl StatusSet {
+    pub fn new() -> Self {
+        StatusSet { effects: Vec::new() }
+    }
+
+    // returns false when the effect is unknown or ignored
+    pub fn apply(&mut self, registry: &StatusRegistry, name: &str) -> bool {
+        let definition = match registry.get(name) {
+            Some(definition) => definition,
+            None => return false
+        };
+
+        let duration = if definition.duration > 0 { Some(definition.duration) } else { None };
+
+        let effect = match self.effects.iter_mut().find(|effect| effect.name == name) {
+            Some(effect) => effect,
+            None => {
+                self.effects.push(StatusEffect { name: name.to_string(), remaining: duration, stacks: 1 });
+                return true;
+            }
+        };
+
+        match definition.stack_rule {
+            StackRule::Refresh => effect.remaining = duration,
+            StackRule::Extend => effect.remaining = match (effect.remaining, duration) {
+                (Some(remaining), Some(duration)) => Some(remaining + duration),
+                _ => None
+            },
+            StackRule::Stack(limit) => {
+                effect.stacks = (effect.stacks + 1).min(limit.max(1));
+                effect.remaining = duration;
+            },
+            StackRule::Ignore => return false
+        }
+
+        true
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let count = self.effects.len();
+        self.effects.retain(|effect| effect.name != name);
+        count != self.effects.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.effects.clear();
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.effects.iter().any(|effect| effect.name == name)
+    }
+
+    pub fn effects(&self) -> &[StatusEffect] {
+        &self.effects
+    }
+
+    // run once per turn, the caller applies the hp changes and reacts to expired effects
+    pub fn tick(&mut self, registry: &StatusRegistry) -> Vec<StatusTick> {
+        let mut ticks = Vec::new();
+
+        for effect in self.effects.iter_mut() {
+            let hp_per_turn = registry.get(&effect.name).map(|definition| definition.hp_per_turn).unwrap_or(0);
+
+            if let Some(remaining) = effect.remaining.as_mut() {
+                *remaining = remaining.saturating_sub(1);
+            }
+
+            ticks.push(StatusTick {
+                name: effect.name.clone(),
+                hp_delta: hp_per_turn * effect.stacks as i32,
+                expired: effect.remaining == Some(0)
+            });
+        }
+
+        self.effects.retain(|effect| effect.remaining != Some(0));
+
+        ticks
+    }
+
+    pub fn can_act(&self, registry: &StatusRegistry) -> bool {
+        !self.effects.iter().any(|effect| registry.get(&effect.name).map(|definition| definition.prevents_action).unwrap_or(false))
+    }
+
+    // total change of a stat from every effect
+    pub fn modifier(&self, registry: &StatusRegistry, stat: &str) -> i32 {
+        self.effects.iter().map(|effect| {
+            registry.get(&effect.name).map(|definition| {
+                definition.modifiers.iter().filter(|(name, _)| name == stat).map(|(_, amount)| amount * effect.stacks as i32).sum::<i32>()
+            }).unwrap_or(0)
+        }).sum()
+    }
+
+    // icon frames of the effects that have one, in the order they were applied
+    pub fn icons(&self, registry: &StatusRegistry) -> Vec<usize> {
+        self.effects.iter().filter_map(|effect| registry.get(&effect.name).and_then(|definition| definition.icon)).collect()
+    }
+
+    // icons in a row starting at x, meant to sit next to a hp bar
+    pub fn draw_icons(&self, target: &mut Image, registry: &StatusRegistry, icons: &[RleImage], palette: &Palette, x: i32, y: i32, spacing: i32) {
+        let mut offset = 0;
+
+        for effect in &self.effects {
+            let icon = registry.get(&effect.name).and_then(|definition| definition.icon).and_then(|icon| icons.get(icon));
+
+            if let Some(icon) = icon {
+                target.blit(icon, x + offset - icon.offset.x as i32, y - icon.offset.y as i32, palette);
+                offset += icon.size.x as i32 + spacing;
+            }
+        }
+    }
+}
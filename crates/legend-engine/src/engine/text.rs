@@ -0,0 +1,105 @@
+use encoding_rs::BIG5;
+use crate::engine::graphics::{split_lines, GameFont};
+
+// script strings are utf-8, the fonts are indexed by big5 code with ascii as single bytes
+pub fn encode_big5(text: &str) -> Vec<usize> {
+    let (bytes, _, _) = BIG5.encode(text);
+    let mut characters = Vec::with_capacity(bytes.len());
+
+    let mut index = 0;
+    while index < bytes.len() {
+        let byte = bytes[index] as usize;
+
+        if byte >= 0x81 && index + 1 < bytes.len() {
+            characters.push(byte << 8 | bytes[index + 1] as usize);
+            index += 2;
+        } else {
+            characters.push(byte);
+            index += 1;
+        }
+    }
+
+    characters
+}
+
+// big5 punctuation that may never start a line: ，、。．‧；：？！︰…‥ and closing brackets
+const NO_LINE_START: [usize; 20] = [
+    0xa141, 0xa142, 0xa143, 0xa144, 0xa145, 0xa146, 0xa147, 0xa148, 0xa149, 0xa14a, 0xa14b, 0xa14c,
+    0xa15e, 0xa162, 0xa166, 0xa16a, 0xa16e, 0xa172, 0xa176, 0xa17a
+];
+
+// opening brackets that may never end a line: （｛〔【《〈「『
+const NO_LINE_END: [usize; 8] = [0xa15d, 0xa161, 0xa165, 0xa169, 0xa16d, 0xa171, 0xa175, 0xa179];
+
+fn is_no_line_start(character: usize) -> bool {
+    NO_LINE_START.contains(&character) || (character < 128 && matches!(character as u8, b',' | b'.' | b'!' | b'?' | b';' | b':' | b')' | b']' | b'}'))
+}
+
+fn is_no_line_end(character: usize) -> bool {
+    NO_LINE_END.contains(&character) || (character < 128 && matches!(character as u8, b'(' | b'[' | b'{'))
+}
+
+fn is_word_character(character: usize) -> bool {
+    character < 128 && (character as u8).is_ascii_alphanumeric()
+}
+
+fn can_break_before(line: &[usize], index: usize) -> bool {
+    let previous = line[index - 1];
+    let next = line[index];
+
+    !is_no_line_start(next) && !is_no_line_end(previous) && !(is_word_character(previous) && is_word_character(next))
+}
+
+// greedy wrapping that follows the kinsoku rules, explicit line breaks are kept
+pub fn wrap_text(text: &[usize], max_width: i32, game_font: &GameFont) -> Vec<Vec<usize>> {
+    let mut lines = Vec::new();
+
+    for line in split_lines(text) {
+        let mut start = 0;
+
+        while start < line.len() {
+            let mut width = 0;
+            let mut end = start;
+
+            while end < line.len() {
+                let character_width = game_font.font_for(line[end]).get_width() as i32;
+                if width + character_width > max_width && end > start {
+                    break;
+                }
+                width += character_width;
+                end += 1;
+            }
+
+            if end < line.len() {
+                // move the break back until it is allowed, a line that can not be broken is cut hard
+                let mut position = end;
+                while position > start + 1 && !can_break_before(line, position) {
+                    position -= 1;
+                }
+
+                if position > start + 1 || can_break_before(line, position) {
+                    end = position;
+                }
+            }
+
+            let mut wrapped: Vec<usize> = line[start..end].to_vec();
+
+            // spaces at the break are dropped
+            while wrapped.last() == Some(&32) && end < line.len() {
+                wrapped.pop();
+            }
+            lines.push(wrapped);
+
+            start = end;
+            while start < line.len() && line[start] == 32 {
+                start += 1;
+            }
+        }
+
+        if line.is_empty() {
+            lines.push(Vec::new());
+        }
+    }
+
+    lines
+}
@@ -0,0 +1,212 @@
+use std::fs;
+use serde::Deserialize;
+use crate::engine::error::EngineResult;
+use crate::engine::graphics::{Color, Graphics};
+use crate::engine::movement::Direction;
+use crate::engine::vfs::Vfs;
+
+// cell size of a fully mosaicked screen
+const MOSAIC_LEVEL: f64 = 16.0;
+
+#[derive(Deserialize, Copy, Clone, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum TransitionEffect {
+    None,
+    Fade,
+    Mosaic
+}
+
+fn default_effect() -> TransitionEffect {
+    TransitionEffect::Fade
+}
+
+fn default_duration() -> f64 {
+    0.3
+}
+
+fn default_facing() -> String {
+    "down".to_string()
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TransitionDefinition {
+    pub map: String,
+    pub x: i32,
+    pub y: i32,
+    pub destination_map: String,
+    pub destination_x: i32,
+    pub destination_y: i32,
+    #[serde(default = "default_facing")]
+    pub facing: String,
+    #[serde(default = "default_effect")]
+    pub effect: TransitionEffect,
+    // seconds for each of the fade out and fade in
+    #[serde(default = "default_duration")]
+    pub duration: f64,
+    #[serde(default)]
+    pub sound: Option<String>
+}
+
+impl TransitionDefinition {
+    pub fn facing(&self) -> Direction {
+        Direction::from_name(&self.facing).unwrap_or(Direction::Down)
+    }
+}
+
+#[derive(Deserialize)]
+struct TransitionFile {
+    #[serde(default)]
+    transition: Vec<TransitionDefinition>
+}
+
+// doors, stairs and teleport tiles
+// [[transition]]
+// map = "town"
+// x = 4
+// y = 9
+// destination_map = "inn"
+// destination_x = 10
+// destination_y = 14
+// facing = "up"
+// sound = "door"
+pub struct Transitions {
+    definitions: Vec<TransitionDefinition>
+}
+
+impl Transitions {
+    pub fn new() -> Self {
+        Transitions { definitions: Vec::new() }
+    }
+
+    pub fn from_toml(source: &str) -> EngineResult<Self> {
+        let file: TransitionFile = toml::from_str(source)?;
+        Ok(Transitions { definitions: file.transition })
+    }
+
+    pub fn load(filename: &str) -> EngineResult<Self> {
+        Self::from_toml(&fs::read_to_string(filename)?)
+    }
+
+    // mods can move doors like any other game data
+    pub fn load_vfs(vfs: &Vfs, filename: &str) -> EngineResult<Self> {
+        Self::from_toml(&String::from_utf8_lossy(&vfs.read(filename)?))
+    }
+
+    pub fn at(&self, map: &str, x: i32, y: i32) -> Option<&TransitionDefinition> {
+        self.definitions.iter().find(|definition| definition.map == map && definition.x == x && definition.y == y)
+    }
+}
+
+impl Default for Transitions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum TransitionStep {
+    PlaySound(String),
+    // the screen is fully covered, the new map can be loaded now
+    LoadMap { map: String, x: i32, y: i32, facing: Direction },
+    Finished
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Phase {
+    Start,
+    Out,
+    In,
+    Done
+}
+
+// runs one map change: sound, cover the screen, load, uncover
+pub struct MapTransition {
+    definition: TransitionDefinition,
+    phase: Phase,
+    elapsed: f64
+}
+
+impl MapTransition {
+    pub fn new(definition: &TransitionDefinition) -> Self {
+        MapTransition { definition: definition.clone(), phase: Phase::Start, elapsed: 0.0 }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.phase == Phase::Done
+    }
+
+    pub fn effect(&self) -> TransitionEffect {
+        self.definition.effect
+    }
+
+    // 0 is the normal picture, 1 is fully faded or mosaicked
+    pub fn coverage(&self) -> f64 {
+        let duration = self.duration();
+        if duration <= 0.0 {
+            return 0.0;
+        }
+
+        match self.phase {
+            Phase::Start | Phase::Done => 0.0,
+            Phase::Out => (self.elapsed / duration).min(1.0),
+            Phase::In => 1.0 - (self.elapsed / duration).min(1.0)
+        }
+    }
+
+    // covers the whole screen by coverage, on top of the scene
+    pub fn draw(&self, graphics: &mut Graphics) {
+        let coverage = self.coverage();
+        if coverage <= 0.0 {
+            return;
+        }
+
+        match self.definition.effect {
+            TransitionEffect::Fade => {
+                let (width, height) = (graphics.get_width() as i32, graphics.get_height() as i32);
+                graphics.fill_rect(0, 0, width, height, &Color::new(0, 0, 0, (coverage * 255.0).round() as u8));
+            },
+            TransitionEffect::Mosaic => graphics.mosaic((1.0 + coverage * (MOSAIC_LEVEL - 1.0)).round() as u32),
+            TransitionEffect::None => ()
+        }
+    }
+
+    fn duration(&self) -> f64 {
+        if self.definition.effect == TransitionEffect::None { 0.0 } else { self.definition.duration }
+    }
+
+    // call once per update, steps come out in the order the scene has to handle them
+    pub fn step(&mut self, delta: f64) -> Vec<TransitionStep> {
+        let mut steps = Vec::new();
+        let duration = self.duration();
+
+        if self.phase == Phase::Start {
+            if let Some(sound) = &self.definition.sound {
+                steps.push(TransitionStep::PlaySound(sound.clone()));
+            }
+
+            self.phase = Phase::Out;
+            self.elapsed = 0.0;
+        } else {
+            self.elapsed += delta;
+        }
+
+        if self.phase == Phase::Out && self.elapsed >= duration {
+            steps.push(TransitionStep::LoadMap {
+                map: self.definition.destination_map.clone(),
+                x: self.definition.destination_x,
+                y: self.definition.destination_y,
+                facing: self.definition.facing()
+            });
+
+            self.phase = Phase::In;
+            self.elapsed = 0.0;
+        }
+
+        if self.phase == Phase::In && self.elapsed >= duration {
+            steps.push(TransitionStep::Finished);
+            self.phase = Phase::Done;
+        }
+
+        steps
+    }
+}
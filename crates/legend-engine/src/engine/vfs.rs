@@ -0,0 +1,220 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use crate::engine::data_path::find_path;
+use crate::engine::disc_image::DiscImageMount;
+use crate::engine::error::{EngineError, EngineResult};
+use crate::engine::resource_trace::{ResourceTrace, TraceEntry};
+use crate::engine::zip_mount::ZipMount;
+
+// a source of game data files, named relative to its root with / between folders.
+// a plain directory, an image of the cd or a zip archive
+pub trait Mount: Send + Sync {
+    // where the files come from, for messages
+    fn describe(&self) -> String;
+
+    // None when this mount does not have the file, so the next one is asked
+    fn read(&self, name: &str) -> io::Result<Option<Vec<u8>>>;
+
+    fn exists(&self, name: &str) -> bool;
+
+    // in bytes, None when this mount does not have the file. reads it unless the mount knows better
+    fn size(&self, name: &str) -> io::Result<Option<u64>> {
+        Ok(self.read(name)?.map(|bytes| bytes.len() as u64))
+    }
+
+    // every file name of the mount
+    fn files(&self) -> io::Result<Vec<String>>;
+}
+
+// an installed game or a copied cd, names match in any case like on dos
+pub struct DirectoryMount {
+    root: PathBuf
+}
+
+impl DirectoryMount {
+    pub fn new(root: &Path) -> Self {
+        DirectoryMount { root: root.to_path_buf() }
+    }
+}
+
+fn collect_files(root: &Path, directory: &Path, files: &mut Vec<String>) -> io::Result<()> {
+    for entry in fs::read_dir(directory)?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            files.push(relative.components().map(|part| part.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"));
+        }
+    }
+
+    Ok(())
+}
+
+impl Mount for DirectoryMount {
+    fn describe(&self) -> String {
+        self.root.display().to_string()
+    }
+
+    fn read(&self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        match find_path(&self.root, name) {
+            Some(path) => Ok(Some(fs::read(path)?)),
+            None => Ok(None)
+        }
+    }
+
+    fn exists(&self, name: &str) -> bool {
+        find_path(&self.root, name).is_some()
+    }
+
+    fn size(&self, name: &str) -> io::Result<Option<u64>> {
+        match find_path(&self.root, name) {
+            Some(path) => Ok(Some(fs::metadata(path)?.len())),
+            None => Ok(None)
+        }
+    }
+
+    fn files(&self) -> io::Result<Vec<String>> {
+        let mut files = Vec::new();
+        collect_files(&self.root, &self.root, &mut files)?;
+        Ok(files)
+    }
+}
+
+// every loader of original game data reads through here instead of opening paths itself.
+// mounts added later are asked first
+#[derive(Default)]
+pub struct Vfs {
+    mounts: Vec<Box<dyn Mount>>,
+    // files read ahead for the next scene, by lowercase name
+    prefetched: Mutex<HashMap<String, Arc<Vec<u8>>>>,
+    // every read with the mount it came from, for --trace-resources
+    trace: Option<Mutex<ResourceTrace>>
+}
+
+impl Vfs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn directory(root: &Path) -> Self {
+        let mut vfs = Self::new();
+        vfs.mount(Box::new(DirectoryMount::new(root)));
+        vfs
+    }
+
+    // data_path as the player gives it, a folder, an .iso or .cue image of the cd or a .zip of the game data
+    pub fn open(path: &Path) -> EngineResult<Self> {
+        let extension = match path.is_file() {
+            true => path.extension().map(|extension| extension.to_string_lossy().to_lowercase()),
+            false => None
+        };
+
+        let mount: Box<dyn Mount> = match extension.as_deref() {
+            Some("iso") | Some("cue") => Box::new(DiscImageMount::open(path)?),
+            Some("zip") => Box::new(ZipMount::open(path)?),
+            _ => return Ok(Self::directory(path))
+        };
+
+        let mut vfs = Self::new();
+        vfs.mount(mount);
+        Ok(vfs)
+    }
+
+    pub fn mount(&mut self, mount: Box<dyn Mount>) {
+        self.mounts.push(mount);
+    }
+
+    pub fn enable_trace(&mut self) {
+        self.trace = Some(Mutex::new(ResourceTrace::new()));
+    }
+
+    // None unless tracing is enabled
+    pub fn trace_summary(&self) -> Option<Vec<String>> {
+        self.trace.as_ref().map(|trace| trace.lock().unwrap().summary())
+    }
+
+    fn record(&self, name: &str, layer: Option<String>, bytes: usize, started: Instant) {
+        if let Some(trace) = self.trace.as_ref() {
+            trace.lock().unwrap().record(TraceEntry { name: name.to_string(), layer, bytes, time: started.elapsed() });
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        self.mounts.iter().map(|mount| mount.describe()).collect::<Vec<String>>().join(", ")
+    }
+
+    pub fn read(&self, name: &str) -> EngineResult<Vec<u8>> {
+        let started = Instant::now();
+        if let Some(bytes) = self.prefetched.lock().unwrap().get(&name.to_lowercase()) {
+            self.record(name, Some("prefetched".to_string()), bytes.len(), started);
+            return Ok(bytes.as_ref().clone());
+        }
+
+        self.read_mounts(name)
+    }
+
+    fn read_mounts(&self, name: &str) -> EngineResult<Vec<u8>> {
+        let started = Instant::now();
+        for mount in self.mounts.iter().rev() {
+            if let Some(bytes) = mount.read(name)? {
+                self.record(name, Some(mount.describe()), bytes.len(), started);
+                return Ok(bytes);
+            }
+        }
+
+        self.record(name, None, 0, started);
+        Err(EngineError::NotFound(format!("{} in {}", name, self.describe())))
+    }
+
+    // reads the file now so a later read does not wait on the disk or the cd
+    pub fn prefetch(&self, name: &str) -> EngineResult<()> {
+        let key = name.to_lowercase();
+        if self.is_prefetched(&key) {
+            return Ok(());
+        }
+
+        let bytes = self.read_mounts(name)?;
+        self.prefetched.lock().unwrap().insert(key, Arc::new(bytes));
+        Ok(())
+    }
+
+    pub fn is_prefetched(&self, name: &str) -> bool {
+        self.prefetched.lock().unwrap().contains_key(&name.to_lowercase())
+    }
+
+    // drops every prefetched file the next scene does not need
+    pub fn retain_prefetched(&self, names: &HashSet<String>) {
+        self.prefetched.lock().unwrap().retain(|name, _| names.contains(name));
+    }
+
+    pub fn exists(&self, name: &str) -> bool {
+        self.mounts.iter().any(|mount| mount.exists(name))
+    }
+
+    // of the file a read would return, None when no mount has it
+    pub fn size(&self, name: &str) -> EngineResult<Option<u64>> {
+        for mount in self.mounts.iter().rev() {
+            if let Some(size) = mount.size(name)? {
+                return Ok(Some(size));
+            }
+        }
+
+        Ok(None)
+    }
+
+    // every file of every mount once, sorted
+    pub fn files(&self) -> EngineResult<Vec<String>> {
+        let mut files = Vec::new();
+        for mount in self.mounts.iter() {
+            files.extend(mount.files()?);
+        }
+
+        files.sort_by_key(|name| name.to_lowercase());
+        files.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+        Ok(files)
+    }
+}
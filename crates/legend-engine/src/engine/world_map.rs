@@ -0,0 +1,120 @@
+use crate::engine::decode::{read_u16, DecodeError};
+use crate::engine::error::EngineResult;
+use crate::engine::graphics::Vector2;
+use crate::engine::movement::Direction;
+use crate::engine::vfs::Vfs;
+
+// a town, cave or any place on the world map that loads a local map
+#[derive(Clone, Debug)]
+pub struct LocationNode {
+    pub name: String,
+    pub position: Vector2<i32>,
+    pub map: String
+}
+
+pub struct WorldMap {
+    pub size: Vector2<i32>,
+    tiles: Vec<u16>,
+    locations: Vec<LocationNode>
+}
+
+impl WorldMap {
+    pub fn new(width: i32, height: i32, tiles: Vec<u16>) -> Self {
+        let mut tiles = tiles;
+        tiles.resize(width.max(0) as usize * height.max(0) as usize, 0);
+
+        WorldMap { size: Vector2::new(width, height), tiles, locations: Vec::new() }
+    }
+
+    // width and height as u16, then one u16 tile after another row by row
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let (width, height) = (read_u16(bytes, 0)? as usize, read_u16(bytes, 2)? as usize);
+        if width == 0 || height == 0 {
+            return Err(DecodeError::InvalidSize { width, height });
+        }
+
+        let needed = 4 + width * height * 2;
+        if bytes.len() < needed {
+            return Err(DecodeError::Truncated { needed, available: bytes.len() });
+        }
+
+        let tiles = bytes[4..needed].chunks_exact(2).map(|tile| u16::from_le_bytes([tile[0], tile[1]])).collect();
+        Ok(Self::new(width as i32, height as i32, tiles))
+    }
+
+    pub fn load(vfs: &Vfs, name: &str) -> EngineResult<Self> {
+        Ok(Self::from_bytes(&vfs.read(name)?)?)
+    }
+
+    pub fn add_location(&mut self, location: LocationNode) {
+        self.locations.push(location);
+    }
+
+    pub fn locations(&self) -> &[LocationNode] {
+        &self.locations
+    }
+
+    pub fn get_tile(&self, x: i32, y: i32) -> Option<u16> {
+        if x < 0 || y < 0 || x >= self.size.x || y >= self.size.y {
+            return None;
+        }
+
+        Some(self.tiles[(y * self.size.x + x) as usize])
+    }
+
+    pub fn location_at(&self, position: Vector2<i32>) -> Option<&LocationNode> {
+        self.locations.iter().find(|location| location.position.x == position.x && location.position.y == position.y)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum TravelEvent {
+    Blocked,
+    // steps taken on the world map, random encounters count these
+    Moved(u64),
+    EnterLocation(LocationNode)
+}
+
+pub struct WorldMapTravel {
+    pub position: Vector2<i32>,
+    pub facing: Direction,
+    steps: u64
+}
+
+impl WorldMapTravel {
+    pub fn new(position: Vector2<i32>, facing: Direction) -> Self {
+        WorldMapTravel { position, facing, steps: 0 }
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    // passable decides by terrain, so the same map works for walking and sailing
+    pub fn step<F>(&mut self, world_map: &WorldMap, direction: Direction, passable: F) -> TravelEvent where F: Fn(u16) -> bool {
+        self.facing = direction;
+
+        let offset = direction.offset();
+        let target = Vector2::new(self.position.x + offset.x, self.position.y + offset.y);
+
+        match world_map.get_tile(target.x, target.y) {
+            Some(tile) if passable(tile) => (),
+            _ => return TravelEvent::Blocked
+        }
+
+        self.position = target;
+        self.steps += 1;
+
+        match world_map.location_at(target) {
+            Some(location) => TravelEvent::EnterLocation(location.clone()),
+            None => TravelEvent::Moved(self.steps)
+        }
+    }
+
+    // top left tile of the view, the party stays centered until the map edge
+    pub fn view_origin(&self, world_map: &WorldMap, view_width: i32, view_height: i32) -> Vector2<i32> {
+        let x = (self.position.x - view_width / 2).clamp(0, (world_map.size.x - view_width).max(0));
+        let y = (self.position.y - view_height / 2).clamp(0, (world_map.size.y - view_height).max(0));
+        Vector2::new(x, y)
+    }
+}
@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use flate2::read::DeflateDecoder;
+use crate::engine::decode::{read_u16, read_u32, DecodeError};
+use crate::engine::error::EngineResult;
+use crate::engine::vfs::Mount;
+
+const END_SIGNATURE: u32 = 0x0605_4b50;
+const CENTRAL_SIGNATURE: u32 = 0x0201_4b50;
+const LOCAL_SIGNATURE: u32 = 0x0403_4b50;
+const END_RECORD_BYTES: u64 = 22;
+// the end record sits behind a comment of up to this many bytes
+const MAX_COMMENT_BYTES: u64 = 0xffff;
+const CENTRAL_HEADER_BYTES: usize = 46;
+const LOCAL_HEADER_BYTES: usize = 30;
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+const ENCRYPTED_FLAG: u16 = 1;
+// deflate never makes more than this many bytes of one, so no entry inflates to more than this times the archive
+const MAX_DEFLATE_RATIO: u64 = 1032;
+
+struct ZipEntry {
+    method: u16,
+    crc: u32,
+    compressed_size: u64,
+    size: u64,
+    // of the local header, the data follows it
+    header_offset: u64
+}
+
+// a zip of the game data, the usual shape of a preservation dump. files are inflated when they are read.
+// a zip holding one folder with everything in it is read from inside that folder
+pub struct ZipMount {
+    path: PathBuf,
+    file: Mutex<File>,
+    // lowercase path with / between folders
+    entries: HashMap<String, ZipEntry>,
+    names: Vec<String>
+}
+
+fn invalid(path: &Path, message: &str) -> DecodeError {
+    DecodeError::InvalidHeader(format!("{} {}", path.display(), message))
+}
+
+// the folder every name starts with, if there is exactly one
+fn common_folder(names: &[String]) -> Option<String> {
+    let (first, _) = names.first()?.split_once('/')?;
+    let prefix = format!("{}/", first);
+    names.iter().all(|name| name.starts_with(&prefix)).then_some(prefix)
+}
+
+impl ZipMount {
+    pub fn open(path: &Path) -> EngineResult<Self> {
+        let mut file = File::open(path)?;
+        let length = file.seek(SeekFrom::End(0))?;
+
+        // the end record is found by its signature, searching back from the end
+        let tail_length = length.min(END_RECORD_BYTES + MAX_COMMENT_BYTES);
+        let mut tail = vec![0; tail_length as usize];
+        file.seek(SeekFrom::Start(length - tail_length))?;
+        file.read_exact(&mut tail)?;
+
+        let end = (0..tail.len().saturating_sub(END_RECORD_BYTES as usize - 1)).rev()
+            .find(|&offset| read_u32(&tail, offset).map(|signature| signature == END_SIGNATURE).unwrap_or(false))
+            .ok_or_else(|| invalid(path, "is not a zip file"))?;
+
+        let count = read_u16(&tail, end + 10)? as usize;
+        let (central_size, central_offset) = (read_u32(&tail, end + 12)?, read_u32(&tail, end + 16)?);
+        if central_offset == u32::MAX || count == u16::MAX as usize {
+            return Err(invalid(path, "is a zip64 file, which is not supported").into());
+        }
+        if central_offset as u64 + central_size as u64 > length {
+            return Err(invalid(path, "has a central directory past its end").into());
+        }
+
+        let mut central = vec![0; central_size as usize];
+        file.seek(SeekFrom::Start(central_offset as u64))?;
+        file.read_exact(&mut central)?;
+
+        let mut entries = Vec::new();
+        let mut offset = 0;
+        for _ in 0..count {
+            if read_u32(&central, offset)? != CENTRAL_SIGNATURE {
+                return Err(invalid(path, "has a broken central directory").into());
+            }
+
+            let name_length = read_u16(&central, offset + 28)? as usize;
+            let extra_length = read_u16(&central, offset + 30)? as usize;
+            let comment_length = read_u16(&central, offset + 32)? as usize;
+            let name_start = offset + CENTRAL_HEADER_BYTES;
+            let name = central.get(name_start..name_start + name_length)
+                .ok_or(DecodeError::Truncated { needed: name_start + name_length, available: central.len() })?;
+            let name = String::from_utf8_lossy(name).replace('\\', "/");
+
+            let entry = ZipEntry {
+                method: read_u16(&central, offset + 10)?,
+                crc: read_u32(&central, offset + 16)?,
+                compressed_size: read_u32(&central, offset + 20)? as u64,
+                size: read_u32(&central, offset + 24)? as u64,
+                header_offset: read_u32(&central, offset + 42)? as u64
+            };
+
+            let flags = read_u16(&central, offset + 8)?;
+            offset = name_start + name_length + extra_length + comment_length;
+
+            // folders are only there as part of the names
+            if name.ends_with('/') {
+                continue;
+            }
+            if flags & ENCRYPTED_FLAG != 0 {
+                return Err(invalid(path, &format!("has {} encrypted", name)).into());
+            }
+            // sizes are checked here so a broken entry can not have gigabytes allocated for it when read
+            if entry.header_offset + LOCAL_HEADER_BYTES as u64 + entry.compressed_size > length {
+                return Err(invalid(path, &format!("has {} past its end", name)).into());
+            }
+            let most = match entry.method {
+                STORED => entry.compressed_size,
+                _ => entry.compressed_size * MAX_DEFLATE_RATIO
+            };
+            if entry.size > most {
+                return Err(invalid(path, &format!("has {} at {} bytes, more than its {} compressed bytes can hold", name, entry.size, entry.compressed_size)).into());
+            }
+            entries.push((name, entry));
+        }
+
+        let names: Vec<String> = entries.iter().map(|(name, _)| name.clone()).collect();
+        let prefix = common_folder(&names).unwrap_or_default();
+        let strip = |name: &str| name[prefix.len()..].to_string();
+
+        Ok(ZipMount {
+            path: path.to_path_buf(),
+            file: Mutex::new(file),
+            names: names.iter().map(|name| strip(name)).collect(),
+            entries: entries.into_iter().map(|(name, entry)| (strip(&name).to_lowercase(), entry)).collect()
+        })
+    }
+
+    fn entry(&self, name: &str) -> Option<&ZipEntry> {
+        self.entries.get(&name.replace('\\', "/").trim_start_matches('/').to_lowercase())
+    }
+
+    fn read_entry(&self, name: &str, entry: &ZipEntry) -> io::Result<Vec<u8>> {
+        let broken = |message: &str| io::Error::new(io::ErrorKind::InvalidData, format!("{} in {} {}", name, self.path.display(), message));
+
+        let mut compressed = vec![0; entry.compressed_size as usize];
+        {
+            let mut file = self.file.lock().unwrap();
+            let mut header = [0; LOCAL_HEADER_BYTES];
+            file.seek(SeekFrom::Start(entry.header_offset))?;
+            file.read_exact(&mut header)?;
+
+            // the local header repeats the name and may have an extra field of another length than the central one
+            let local = |offset: usize| read_u16(&header, offset).map(|value| value as i64).map_err(|_| broken("has a broken header"));
+            if read_u32(&header, 0).ok() != Some(LOCAL_SIGNATURE) {
+                return Err(broken("has a broken header"));
+            }
+            file.seek(SeekFrom::Current(local(26)? + local(28)?))?;
+            file.read_exact(&mut compressed)?;
+        }
+
+        let bytes = match entry.method {
+            STORED => compressed,
+            DEFLATED => {
+                // one byte more than the size is enough to tell it is wrong
+                let mut bytes = Vec::with_capacity(entry.size as usize);
+                DeflateDecoder::new(compressed.as_slice()).take(entry.size + 1).read_to_end(&mut bytes)?;
+                bytes
+            },
+            method => return Err(broken(&format!("uses compression method {}, only stored and deflated are supported", method)))
+        };
+
+        if bytes.len() as u64 != entry.size || crc32fast::hash(&bytes) != entry.crc {
+            return Err(broken("does not match its checksum"));
+        }
+
+        Ok(bytes)
+    }
+}
+
+impl Mount for ZipMount {
+    fn describe(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn read(&self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        match self.entry(name) {
+            Some(entry) => Ok(Some(self.read_entry(name, entry)?)),
+            None => Ok(None)
+        }
+    }
+
+    fn exists(&self, name: &str) -> bool {
+        self.entry(name).is_some()
+    }
+
+    fn size(&self, name: &str) -> io::Result<Option<u64>> {
+        Ok(self.entry(name).map(|entry| entry.size))
+    }
+
+    fn files(&self) -> io::Result<Vec<String>> {
+        Ok(self.names.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::io::Write;
+    use std::process;
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+
+    struct TestEntry {
+        name: &'static str,
+        method: u16,
+        data: Vec<u8>,
+        size: u32,
+        crc: u32
+    }
+
+    fn stored(name: &'static str, content: &[u8]) -> TestEntry {
+        TestEntry { name, method: STORED, data: content.to_vec(), size: content.len() as u32, crc: crc32fast::hash(content) }
+    }
+
+    fn deflated(name: &'static str, content: &[u8]) -> TestEntry {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content).unwrap();
+        TestEntry { name, method: DEFLATED, data: encoder.finish().unwrap(), size: content.len() as u32, crc: crc32fast::hash(content) }
+    }
+
+    // local headers with the data, the central directory and the end record
+    fn zip(entries: &[TestEntry]) -> Vec<u8> {
+        let (mut archive, mut central) = (Vec::new(), Vec::new());
+
+        for entry in entries {
+            let offset = archive.len() as u32;
+            let fields = |signature: u32| {
+                let mut header = signature.to_le_bytes().to_vec();
+                if signature == CENTRAL_SIGNATURE {
+                    header.extend_from_slice(&20u16.to_le_bytes());
+                }
+                header.extend_from_slice(&20u16.to_le_bytes());
+                header.extend_from_slice(&0u16.to_le_bytes());
+                header.extend_from_slice(&entry.method.to_le_bytes());
+                header.extend_from_slice(&[0; 4]);
+                header.extend_from_slice(&entry.crc.to_le_bytes());
+                header.extend_from_slice(&(entry.data.len() as u32).to_le_bytes());
+                header.extend_from_slice(&entry.size.to_le_bytes());
+                header.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+                header.extend_from_slice(&0u16.to_le_bytes());
+                header
+            };
+
+            archive.extend(fields(LOCAL_SIGNATURE));
+            archive.extend_from_slice(entry.name.as_bytes());
+            archive.extend_from_slice(&entry.data);
+
+            central.extend(fields(CENTRAL_SIGNATURE));
+            // comment, disk, attributes and the offset of the local header
+            central.extend_from_slice(&[0; 10]);
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(entry.name.as_bytes());
+        }
+
+        let central_offset = archive.len() as u32;
+        archive.extend_from_slice(&central);
+        archive.extend_from_slice(&END_SIGNATURE.to_le_bytes());
+        archive.extend_from_slice(&[0; 4]);
+        archive.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        archive.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        archive.extend_from_slice(&(central.len() as u32).to_le_bytes());
+        archive.extend_from_slice(&central_offset.to_le_bytes());
+        archive.extend_from_slice(&0u16.to_le_bytes());
+        archive
+    }
+
+    fn open(name: &str, archive: Vec<u8>) -> EngineResult<ZipMount> {
+        let path = env::temp_dir().join(format!("legend-clover-zip-{}-{}.zip", process::id(), name));
+        fs::write(&path, archive).unwrap();
+        let mount = ZipMount::open(&path);
+        fs::remove_file(&path).unwrap();
+        mount
+    }
+
+    #[test]
+    fn reads_stored_and_deflated_files() {
+        let text = b"the same line again and again and again and again".repeat(8);
+        let mount = open("game", zip(&[stored("LEGEND/README.TXT", b"hello"), deflated("LEGEND/DATA/MAP.BIN", &text)])).unwrap();
+
+        // the one folder holding everything is left out of the names
+        assert_eq!(mount.files().unwrap(), vec!["README.TXT".to_string(), "DATA/MAP.BIN".to_string()]);
+        assert_eq!(mount.read("readme.txt").unwrap(), Some(b"hello".to_vec()));
+        assert_eq!(mount.read("data\\map.bin").unwrap(), Some(text.clone()));
+        assert_eq!(mount.size("DATA/MAP.BIN").unwrap(), Some(text.len() as u64));
+        assert_eq!(mount.read("LEGEND/README.TXT").unwrap(), None);
+    }
+
+    #[test]
+    fn refuses_sizes_the_archive_can_not_hold() {
+        let mut past_the_end = stored("README.TXT", b"hello");
+        past_the_end.data = Vec::new();
+        let mut archive = zip(&[past_the_end]);
+        // the central directory claims compressed bytes that are not in the file
+        let central = archive.len() - 22 - 46 - "README.TXT".len();
+        archive[central + 20..central + 24].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(open("past", archive).is_err());
+
+        let mut stored_larger = stored("README.TXT", b"hello");
+        stored_larger.size = 1 << 30;
+        assert!(open("stored", zip(&[stored_larger])).is_err());
+
+        let mut inflated_larger = deflated("README.TXT", b"hello");
+        inflated_larger.size = u32::MAX;
+        assert!(open("deflated", zip(&[inflated_larger])).is_err());
+    }
+
+    #[test]
+    fn checks_the_crc() {
+        let mut entry = deflated("README.TXT", b"hello");
+        entry.crc ^= 1;
+        let mount = open("crc", zip(&[entry])).unwrap();
+
+        assert_eq!(mount.read("README.TXT").unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn refuses_what_is_not_a_zip() {
+        assert!(open("text", b"not a zip at all".to_vec()).is_err());
+    }
+}